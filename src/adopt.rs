@@ -0,0 +1,188 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::build_system::{self, BuildSystem};
+use crate::edit_project::project_label;
+use crate::error::HadouError;
+use crate::language::{self, Language};
+use crate::project_config::{self, ProjectConfig};
+use crate::verilog;
+
+// What adopting a directory did
+#[derive(Debug)]
+pub struct Adoption {
+    pub name: String,
+    // Verilog files the generated Justfile compiles
+    pub sources: Vec<String>,
+    // None when the directory already had a Justfile or Makefile
+    pub build_file_created: Option<PathBuf>,
+}
+
+// The base directory itself is listed as a project once it has been adopted
+pub fn is_adopted(dir: &Path) -> bool {
+    ProjectConfig::load(dir).is_ok_and(|config| config.adopted)
+}
+
+// Treat `dir`, usually the directory Hadou was started in, as a project of
+// its own: mark it in its .hadou.toml and write a Justfile compiling all of
+// its Verilog files, unless it already has a build file
+pub fn adopt_directory(dir: &Path, simulator: &str) -> Result<Adoption, HadouError> {
+    if !language::has_source_files(dir) {
+        return Err(HadouError::Other(format!("No Verilog or VHDL files found in {}", dir.display())));
+    }
+    let sources = verilog_sources(dir);
+    let name = project_label(&dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf()));
+    let mut config = ProjectConfig::load(dir)?;
+
+    let build_file_created = match build_system::find_build_file(dir) {
+        Some(_) => None,
+        None if sources.is_empty() => {
+            return Err(HadouError::Other(format!(
+                "{} has only VHDL files; add a Justfile or Makefile for them and adopt it again",
+                dir.display()
+            )));
+        }
+        None => {
+            let path = dir.join(BuildSystem::Just.file_name());
+            fs::write(&path, adopted_justfile(dir, &name, simulator, &config)?)?;
+            Some(path)
+        }
+    };
+
+    config.adopted = true;
+    config.save(dir)?;
+    Ok(Adoption { name, sources, build_file_created })
+}
+
+// Justfile compiling every Verilog file directly inside `dir`, dumping to the
+// VCD the first `$dumpfile` names
+pub fn adopted_justfile(dir: &Path, name: &str, simulator: &str, config: &ProjectConfig) -> Result<String, HadouError> {
+    let sources = verilog_sources(dir);
+    if sources.is_empty() {
+        return Err(HadouError::Other(format!("No Verilog files found in {}", dir.display())));
+    }
+    let vcd_file = sources
+        .iter()
+        .filter_map(|source| fs::read_to_string(dir.join(source)).ok())
+        .find_map(|content| verilog::dumpfile_name(&content))
+        .unwrap_or_else(|| format!("{}.vcd", name));
+    Ok(generate_adopted_justfile(name, simulator, &sources, &vcd_file, config))
+}
+
+// Verilog files directly inside `dir`, sorted by name
+fn verilog_sources(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut sources: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && Language::of_file(path) == Some(Language::Verilog))
+        .filter_map(|path| path.file_name()?.to_str().map(str::to_string))
+        .collect();
+    sources.sort();
+    sources
+}
+
+pub fn generate_adopted_justfile(project_name: &str, simulator: &str, sources: &[String], vcd_file: &str, config: &ProjectConfig) -> String {
+    format!(
+r#"# justfile for the {} Verilog files
+# Generated by Hadou when adopting the directory; list new files in SOURCES
+
+# Project configuration
+PROJECT_NAME := "{}"
+SOURCES := "{}"
+VVP_FILE := PROJECT_NAME + ".vvp"
+VCD_FILE := "{}"
+# Extra +plusargs for vvp, usually set through HADOU_PLUSARGS in .env
+PLUSARGS := env_var_or_default("HADOU_PLUSARGS", "")
+# Extra iverilog flags (-g2012, -Wall, -I, -D), set with 'f' on Hadou's compile screen
+IVERILOG_FLAGS := env_var_or_default("HADOU_IVERILOG_FLAGS", {})
+# Further sources outside this directory, set with 'i' on Hadou's compile screen
+{}
+
+# Default recipe - compile and simulate
+default: compile simulate
+
+# Compile every source file
+compile:
+    @echo "Compiling Verilog files..."
+    {} {{{{IVERILOG_FLAGS}}}} -o {{{{VVP_FILE}}}} {{{{SOURCES}}}} {{{{EXTRA_SOURCES}}}}
+    @echo "Compilation completed: {{{{VVP_FILE}}}}"
+
+# Run the simulation
+simulate: compile
+    @echo "Running simulation..."
+    vvp {{{{VVP_FILE}}}} {{{{PLUSARGS}}}}
+
+# View waveform (requires GTKWave)
+view: simulate
+    @echo "Opening waveform viewer..."
+    gtkwave {{{{VCD_FILE}}}} &
+
+# Clean generated files
+clean:
+    @echo "Cleaning generated files..."
+    -rm {{{{VVP_FILE}}}} {{{{VCD_FILE}}}}
+    @echo "Clean completed."
+
+# Show project info
+info:
+    @echo "Project: {{{{PROJECT_NAME}}}}"
+    @echo "Source files: {{{{SOURCES}}}}"
+    @echo "Output files: {{{{VVP_FILE}}}}, {{{{VCD_FILE}}}}"
+"#,
+        project_name,
+        project_name,
+        sources.join(" "),
+        vcd_file,
+        project_config::just_string(&config.compiler_flags()),
+        project_config::justfile_sources_line(&config.sources.join(" ")),
+        simulator,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("hadou-adopt-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn adopting_writes_a_justfile_for_every_source() {
+        let dir = dir("flat");
+        fs::write(dir.join("counter.v"), "module counter(input clk); endmodule\n").unwrap();
+        fs::write(dir.join("tb.v"), "module tb; initial begin $dumpfile(\"waves.vcd\"); end endmodule\n").unwrap();
+        fs::write(dir.join("notes.txt"), "not a source\n").unwrap();
+        assert!(!is_adopted(&dir));
+
+        let adoption = adopt_directory(&dir, "iverilog").unwrap();
+        assert_eq!(adoption.sources, vec!["counter.v", "tb.v"]);
+        assert!(is_adopted(&dir));
+
+        let justfile = fs::read_to_string(adoption.build_file_created.unwrap()).unwrap();
+        assert!(justfile.contains("SOURCES := \"counter.v tb.v\""));
+        assert!(justfile.contains("VCD_FILE := \"waves.vcd\""));
+        assert_eq!(crate::justfile::recipe_program(&justfile, "compile"), Some("iverilog".to_string()));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn existing_build_file_is_kept() {
+        let dir = dir("makefile");
+        fs::write(dir.join("alu.v"), "module alu; endmodule\n").unwrap();
+        fs::write(dir.join("Makefile"), "all:\n\tiverilog alu.v\n").unwrap();
+
+        assert!(adopt_directory(&dir, "iverilog").unwrap().build_file_created.is_none());
+        assert_eq!(fs::read_to_string(dir.join("Makefile")).unwrap(), "all:\n\tiverilog alu.v\n");
+        assert!(!dir.join("Justfile").exists());
+        assert!(is_adopted(&dir));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
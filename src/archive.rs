@@ -0,0 +1,302 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Datelike, Local, Timelike};
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::{Compression, Crc};
+
+use crate::error::HadouError;
+use crate::scanner::ScanOptions;
+
+// Simulation output, tool work directories and Hadou's own backups, left
+// out on top of the configured ignore patterns and .hadouignore files
+const BUILD_ARTIFACTS: [&str; 11] = [
+    "*.vvp", "*.vcd", "*.vcd.gz", "*.fst", "*.lxt", "*.o", "*.cf", "a.out", "obj_dir", ".git", "*.bak.*",
+];
+
+// How far below the project directory files are packed
+const MAX_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArchiveFormat {
+    #[default]
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    pub const ALL: [ArchiveFormat; 2] = [ArchiveFormat::TarGz, ArchiveFormat::Zip];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::TarGz => "tar.gz",
+            ArchiveFormat::Zip => "zip",
+        }
+    }
+
+    pub fn parse(extension: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.extension() == extension)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Archive {
+    pub path: PathBuf,
+    pub files: usize,
+    pub size: u64,
+}
+
+// The configured ignore patterns plus the build artifacts, for anything that
+// copies a project's files somewhere else
+pub fn excluded_patterns(ignore_patterns: &[String]) -> Vec<String> {
+    ignore_patterns.iter().cloned().chain(BUILD_ARTIFACTS.map(String::from)).collect()
+}
+
+// Files `pack` would put in the archive, relative to the project and sorted
+pub fn project_files(project: &Path, ignore_patterns: &[String]) -> Vec<PathBuf> {
+    let options = ScanOptions {
+        max_depth: MAX_DEPTH,
+        ignore_patterns: excluded_patterns(ignore_patterns),
+        follow_symlinks: false,
+    };
+    let mut files: Vec<PathBuf> = options
+        .find_files(project, |_| true)
+        .into_iter()
+        .filter_map(|path| path.strip_prefix(project).ok().map(Path::to_path_buf))
+        .collect();
+    files.sort();
+    files
+}
+
+// Pack the project's sources, build file and notes into `<project>.<ext>`
+// next to the project directory, replacing an earlier archive. Entries sit
+// in a `<project>/` folder so unpacking doesn't scatter them.
+pub fn pack(project: &Path, format: ArchiveFormat, ignore_patterns: &[String]) -> Result<Archive, HadouError> {
+    let name = project
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| HadouError::Other("Invalid project directory name".to_string()))?;
+    let files = project_files(project, ignore_patterns);
+    if files.is_empty() {
+        return Err(HadouError::Other(format!("{} has no files to pack", name)));
+    }
+
+    let path = project.with_file_name(format!("{}.{}", name, format.extension()));
+    let file = File::create(&path)?;
+    let entries = files.iter().map(|relative| {
+        let entry_name = Path::new(name)
+            .join(relative)
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        (entry_name, project.join(relative))
+    });
+
+    match format {
+        ArchiveFormat::TarGz => {
+            let mut encoder = GzEncoder::new(file, Compression::default());
+            for (entry_name, source) in entries {
+                write_tar_entry(&mut encoder, &entry_name, &source)?;
+            }
+            // Two empty blocks end a tar archive
+            encoder.write_all(&[0; 1024])?;
+            encoder.finish()?;
+        }
+        ArchiveFormat::Zip => {
+            let mut zip = ZipWriter { file, offset: 0, central: Vec::new(), count: 0 };
+            for (entry_name, source) in entries {
+                zip.add(&entry_name, &source)?;
+            }
+            zip.finish()?;
+        }
+    }
+
+    let size = fs::metadata(&path)?.len();
+    Ok(Archive { path, files: files.len(), size })
+}
+
+// POSIX ustar: a 512-byte header, then the contents padded to 512 bytes
+fn write_tar_entry(out: &mut impl Write, entry_name: &str, source: &Path) -> Result<(), HadouError> {
+    let content = fs::read(source)?;
+    let metadata = fs::metadata(source)?;
+
+    // Names over 100 bytes are split at a '/' into the prefix field
+    let (prefix, name) = if entry_name.len() <= 100 {
+        ("", entry_name)
+    } else {
+        entry_name
+            .char_indices()
+            .filter(|(index, c)| *c == '/' && *index <= 155 && entry_name.len() - index - 1 <= 100)
+            .map(|(index, _)| (&entry_name[..index], &entry_name[index + 1..]))
+            .next()
+            .ok_or_else(|| HadouError::Other(format!("Path too long to archive: {}", entry_name)))?
+    };
+
+    let mut header = [0u8; 512];
+    let mut field = |offset: usize, value: &[u8]| header[offset..offset + value.len()].copy_from_slice(value);
+    field(0, name.as_bytes());
+    field(100, format!("{:07o}\0", file_mode(&metadata)).as_bytes());
+    field(108, b"0000000\0");
+    field(116, b"0000000\0");
+    field(124, format!("{:011o}\0", content.len()).as_bytes());
+    field(136, format!("{:011o}\0", unix_time(&metadata)).as_bytes());
+    field(156, b"0");
+    field(257, b"ustar\x0000");
+    field(345, prefix.as_bytes());
+    // The checksum is taken with its own field as spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|byte| *byte as u32).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+
+    out.write_all(&header)?;
+    out.write_all(&content)?;
+    out.write_all(&vec![0; (512 - content.len() % 512) % 512])?;
+    Ok(())
+}
+
+fn unix_time(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map_or(0, |age| age.as_secs())
+}
+
+// Keeps scripts executable for whoever unpacks the archive
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o777
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &fs::Metadata) -> u32 {
+    0o644
+}
+
+// Deflated zip entries, with the central directory written at the end
+struct ZipWriter {
+    file: File,
+    offset: u32,
+    central: Vec<u8>,
+    count: u16,
+}
+
+impl ZipWriter {
+    fn add(&mut self, entry_name: &str, source: &Path) -> Result<(), HadouError> {
+        let content = fs::read(source)?;
+        let metadata = fs::metadata(source)?;
+        let mut crc = Crc::new();
+        crc.update(&content);
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&content)?;
+        let compressed = encoder.finish()?;
+
+        let too_large = || HadouError::Other(format!("{} is too large for a zip archive", entry_name));
+        let size = u32::try_from(content.len()).map_err(|_| too_large())?;
+        let compressed_size = u32::try_from(compressed.len()).map_err(|_| too_large())?;
+        let (time, date) = dos_time(metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH));
+
+        // Fields shared by the local header and the central directory entry:
+        // version needed, UTF-8 names flag, deflate, time, date, CRC, sizes
+        let mut common = Vec::new();
+        for value in [20u16, 0x0800, 8, time, date] {
+            common.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [crc.sum(), compressed_size, size] {
+            common.extend_from_slice(&value.to_le_bytes());
+        }
+        common.extend_from_slice(&(entry_name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+
+        let mut local = 0x04034b50u32.to_le_bytes().to_vec();
+        local.extend_from_slice(&common);
+        local.extend_from_slice(entry_name.as_bytes());
+        self.file.write_all(&local)?;
+        self.file.write_all(&compressed)?;
+
+        // Made by Unix (3) so the permission bits are kept
+        self.central.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        self.central.extend_from_slice(&(0x0300u16 | 20).to_le_bytes());
+        self.central.extend_from_slice(&common);
+        self.central.extend_from_slice(&[0; 6]);
+        self.central.extend_from_slice(&((0o100000 | file_mode(&metadata)) << 16).to_le_bytes());
+        self.central.extend_from_slice(&self.offset.to_le_bytes());
+        self.central.extend_from_slice(entry_name.as_bytes());
+
+        self.offset = (local.len() as u64 + compressed.len() as u64 + self.offset as u64)
+            .try_into()
+            .map_err(|_| HadouError::Other("Project is too large for a zip archive".to_string()))?;
+        self.count = self.count.checked_add(1).ok_or_else(|| HadouError::Other("Too many files for a zip archive".to_string()))?;
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        self.file.write_all(&self.central)?;
+        let mut end = 0x06054b50u32.to_le_bytes().to_vec();
+        end.extend_from_slice(&[0; 4]);
+        end.extend_from_slice(&self.count.to_le_bytes());
+        end.extend_from_slice(&self.count.to_le_bytes());
+        end.extend_from_slice(&(self.central.len() as u32).to_le_bytes());
+        end.extend_from_slice(&self.offset.to_le_bytes());
+        end.extend_from_slice(&0u16.to_le_bytes());
+        self.file.write_all(&end)?;
+        self.file.flush()
+    }
+}
+
+// MS-DOS time and date, which can't go before 1980
+fn dos_time(modified: SystemTime) -> (u16, u16) {
+    let local = DateTime::<Local>::from(modified);
+    if local.year() < 1980 {
+        return (0, (1 << 5) | 1);
+    }
+    let time = (local.hour() << 11) | (local.minute() << 5) | (local.second() / 2);
+    let date = ((local.year() as u32 - 1980) << 9) | (local.month() << 5) | local.day();
+    (time as u16, date as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn packs_sources_without_build_output() {
+        let root = std::env::temp_dir().join(format!("hadou-archive-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let project = root.join("lab3");
+        fs::create_dir_all(project.join("obj_dir")).unwrap();
+        fs::create_dir_all(project.join("rtl")).unwrap();
+        fs::write(project.join("main.v"), "module lab3; endmodule\n").unwrap();
+        fs::write(project.join("rtl/alu.v"), "module alu; endmodule\n").unwrap();
+        fs::write(project.join("Justfile"), "build:\n").unwrap();
+        fs::write(project.join("lab3.vvp"), "#! vvp").unwrap();
+        fs::write(project.join("lab3.vcd"), "$end").unwrap();
+        fs::write(project.join("obj_dir/Vlab3.cpp"), "").unwrap();
+        fs::write(project.join("notes.txt"), "").unwrap();
+
+        let files = project_files(&project, &["notes.txt".to_string()]);
+        assert_eq!(files, vec![PathBuf::from("Justfile"), PathBuf::from("main.v"), PathBuf::from("rtl/alu.v")]);
+
+        let archive = pack(&project, ArchiveFormat::TarGz, &[]).unwrap();
+        assert_eq!(archive.path, root.join("lab3.tar.gz"));
+        assert_eq!(archive.files, 4);
+        let mut tar = Vec::new();
+        GzDecoder::new(File::open(&archive.path).unwrap()).read_to_end(&mut tar).unwrap();
+        assert_eq!(&tar[..13], b"lab3/Justfile");
+        assert_eq!(&tar[257..263], b"ustar\0");
+        assert!(tar.windows(15).any(|name| name == b"lab3/rtl/alu.v\0"));
+        assert!(!tar.windows(8).any(|name| name == b"lab3.vvp"));
+
+        let archive = pack(&project, ArchiveFormat::Zip, &[]).unwrap();
+        let zip = fs::read(&archive.path).unwrap();
+        assert_eq!(&zip[..4], b"PK\x03\x04");
+        assert_eq!(u16::from_le_bytes([zip[zip.len() - 12], zip[zip.len() - 11]]), 4);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,141 @@
+use std::path::{Path, PathBuf};
+
+use crate::justfile;
+
+// Tool a project's targets are run with, told apart by its build file
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum BuildSystem {
+    #[default]
+    Just,
+    Make,
+}
+
+impl BuildSystem {
+    // In the order they are looked for, so a project with both uses just
+    pub const ALL: [BuildSystem; 2] = [BuildSystem::Just, BuildSystem::Make];
+
+    pub fn detect(project: &Path) -> Option<Self> {
+        find_build_file(project).map(|(system, _)| system)
+    }
+
+    pub fn program(&self) -> &'static str {
+        match self {
+            BuildSystem::Just => "just",
+            BuildSystem::Make => "make",
+        }
+    }
+
+    // Names the build file may have; the first is the one Hadou writes
+    pub fn file_names(&self) -> &'static [&'static str] {
+        match self {
+            BuildSystem::Just => &justfile::JUSTFILE_NAMES,
+            BuildSystem::Make => &["Makefile", "makefile"],
+        }
+    }
+
+    pub fn file_name(&self) -> &'static str {
+        self.file_names()[0]
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            BuildSystem::Just => "just (Justfile)",
+            BuildSystem::Make => "make (Makefile)",
+        }
+    }
+
+    pub fn install_hint(&self) -> &'static str {
+        match self {
+            BuildSystem::Just => "Install the 'just' command runner: cargo install just",
+            BuildSystem::Make => "Install make: sudo apt install make / xcode-select --install",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            BuildSystem::Just => BuildSystem::Make,
+            BuildSystem::Make => BuildSystem::Just,
+        }
+    }
+
+    // Recipes or targets the build file defines. None when some can't be
+    // seen from the file alone.
+    pub fn parse_targets(&self, content: &str) -> Option<Vec<String>> {
+        match self {
+            BuildSystem::Just => justfile::parse_recipes(content),
+            BuildSystem::Make => parse_make_targets(content),
+        }
+    }
+}
+
+pub fn find_build_file(project: &Path) -> Option<(BuildSystem, PathBuf)> {
+    BuildSystem::ALL.iter().find_map(|system| {
+        system.file_names()
+            .iter()
+            .map(|name| project.join(name))
+            .find(|path| path.is_file())
+            .map(|path| (*system, path))
+    })
+}
+
+// Explicit targets of a Makefile in file order: top-level `name: deps` lines,
+// skipping variable assignments, special targets such as `.PHONY` and pattern
+// rules. None when it includes other makefiles.
+fn parse_make_targets(content: &str) -> Option<Vec<String>> {
+    let mut targets = Vec::new();
+
+    for line in content.lines() {
+        if line.starts_with(char::is_whitespace) || line.starts_with('#') {
+            continue;
+        }
+
+        if ["include ", "-include ", "sinclude "].iter().any(|keyword| line.starts_with(keyword)) {
+            return None;
+        }
+
+        let Some(colon) = line.find(':') else {
+            continue;
+        };
+        let rest = &line[colon + 1..];
+        let header = &line[..colon];
+        // `:=`, `::=` and `VAR = a:b` are assignments
+        if rest.starts_with('=') || rest.starts_with(":=") || header.contains('=') {
+            continue;
+        }
+
+        for name in header.split_whitespace() {
+            if !name.starts_with('.') && !name.contains(['%', '$']) && !targets.iter().any(|target| target == name) {
+                targets.push(name.to_string());
+            }
+        }
+    }
+
+    Some(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn make_targets_skip_assignments_and_special_targets() {
+        let makefile = "CC := gcc\nVCD = a:b\n.PHONY: compile clean\n\ncompile: main.v\n\tiverilog main.v\n%.o: %.c\n\tcc $<\nclean simulate::\n\trm -f x\n";
+        assert_eq!(parse_make_targets(makefile), Some(vec!["compile".to_string(), "clean".to_string(), "simulate".to_string()]));
+        assert_eq!(parse_make_targets("include common.mk\nall:\n"), None);
+    }
+
+    #[test]
+    fn justfile_wins_over_makefile() {
+        let dir = std::env::temp_dir().join(format!("hadou-build-system-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        assert_eq!(BuildSystem::detect(&dir), None);
+        std::fs::write(dir.join("makefile"), "all:\n").unwrap();
+        assert_eq!(BuildSystem::detect(&dir), Some(BuildSystem::Make));
+        std::fs::write(dir.join("Justfile"), "all:\n").unwrap();
+        assert_eq!(BuildSystem::detect(&dir), Some(BuildSystem::Just));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
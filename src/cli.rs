@@ -0,0 +1,323 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crate::build_system;
+use crate::compile_project::{CompileAction, ProjectCompiler};
+use crate::config::Config;
+use crate::edit_project::project_label;
+use crate::error::HadouError;
+use crate::history::{self, HistoryEntry};
+use crate::justfile;
+use crate::language::Language;
+use crate::scanner::ScanOptions;
+
+// Bumped only when a field is renamed, removed or changes meaning. New
+// fields can appear without a bump, so readers should ignore unknown ones.
+pub const JSON_VERSION: u32 = 1;
+
+const USAGE: &str = "Usage:
+  hadou                                  Start the TUI
+  hadou <directory>                      Start the TUI working in another directory
+  hadou list [--json]                    List the projects below the working directory
+  hadou compile <project> [recipe] [--json]
+                                         Run a just recipe or make target (default: compile)
+                                         and wait for it
+  hadou help                             Show this message
+
+<project> is a project directory or the name of one listed by `hadou list`.";
+
+const COMMANDS: [&str; 5] = ["list", "compile", "help", "--help", "-h"];
+
+// The directory `hadou <directory>` starts the TUI in. Command names win
+// over directories of the same name.
+pub fn tui_directory(args: &[String]) -> Option<PathBuf> {
+    match args {
+        [dir] if !COMMANDS.contains(&dir.as_str()) && Path::new(dir).is_dir() => Some(PathBuf::from(dir)),
+        _ => None,
+    }
+}
+
+// Commands that run without the TUI, for scripts and editor plugins
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    List { json: bool },
+    Compile { project: String, recipe: String, json: bool },
+    Help,
+}
+
+impl CliCommand {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let json = args.iter().any(|arg| arg == "--json");
+        let mut words = args.iter().filter(|arg| *arg != "--json").map(String::as_str);
+
+        let command = match words.next() {
+            Some("list") => CliCommand::List { json },
+            Some("compile") => {
+                let project = words.next().ok_or("compile needs a project")?.to_string();
+                let recipe = words.next().unwrap_or("compile").to_string();
+                CliCommand::Compile { project, recipe, json }
+            }
+            Some("help" | "--help" | "-h") => CliCommand::Help,
+            Some(other) if other.contains(std::path::MAIN_SEPARATOR) => return Err(format!("'{}' is not a directory", other)),
+            Some(other) => return Err(format!("unknown command '{}'", other)),
+            None => return Err("missing command".to_string()),
+        };
+
+        match words.next() {
+            Some(extra) => Err(format!("unexpected argument '{}'", extra)),
+            None => Ok(command),
+        }
+    }
+}
+
+// One project as `hadou list --json` reports it
+#[derive(Debug, Serialize)]
+pub struct ProjectInfo {
+    pub name: String,
+    // Absolute where it could be resolved
+    pub path: PathBuf,
+    // "verilog" or "vhdl"
+    pub language: String,
+    // Verilog/VHDL sources in the project directory
+    pub file_count: usize,
+    pub has_justfile: bool,
+    // "just" or "make", or null without a build file
+    pub build_system: Option<String>,
+    // Program the build file's compile recipe runs, when it can be told
+    pub simulator: Option<String>,
+    pub last_build: Option<BuildStatus>,
+}
+
+// Latest entry of a project's build history
+#[derive(Debug, Serialize)]
+pub struct BuildStatus {
+    pub action: String,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    // RFC 3339 with the local offset
+    pub finished_at: String,
+    pub duration_ms: u64,
+}
+
+impl From<&HistoryEntry> for BuildStatus {
+    fn from(entry: &HistoryEntry) -> Self {
+        Self {
+            action: entry.action.clone(),
+            success: entry.success,
+            exit_code: entry.exit_code,
+            finished_at: entry.timestamp.to_rfc3339(),
+            duration_ms: entry.duration_ms,
+        }
+    }
+}
+
+impl ProjectInfo {
+    pub fn collect(compiler: &ProjectCompiler, project_path: &Path) -> Self {
+        let build_file = build_system::find_build_file(project_path);
+        let simulator = build_file
+            .as_ref()
+            .and_then(|(_, path)| fs::read_to_string(path).ok())
+            .and_then(|content| justfile::recipe_program(&content, "compile"));
+
+        Self {
+            name: project_label(project_path),
+            path: project_path.canonicalize().unwrap_or_else(|_| project_path.to_path_buf()),
+            language: Language::detect(project_path).unwrap_or_default().name().to_lowercase(),
+            file_count: compiler.get_source_files(project_path).len(),
+            has_justfile: justfile::find_justfile(project_path).is_some(),
+            build_system: build_file.map(|(system, _)| system.program().to_string()),
+            simulator,
+            last_build: history::load(project_path).last().map(BuildStatus::from),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ProjectList {
+    version: u32,
+    projects: Vec<ProjectInfo>,
+}
+
+#[derive(Debug, Serialize)]
+struct CompileReport {
+    version: u32,
+    project: Option<ProjectInfo>,
+    recipe: String,
+    success: bool,
+    exit_code: Option<i32>,
+    duration_ms: u64,
+    output: Vec<String>,
+    // Why the run failed or could not start; null on success
+    error: Option<String>,
+}
+
+// Run a command line and return the process exit code: 0 on success, 1
+// when the command failed and 2 for usage errors
+pub fn run(args: &[String], config: &Config) -> i32 {
+    let command = match CliCommand::parse(args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("hadou: {}\n\n{}", e, USAGE);
+            return 2;
+        }
+    };
+
+    let mut compiler = ProjectCompiler::new(ScanOptions::from_config(config));
+    compiler.simulation_timeout = config.simulation_timeout;
+    compiler.project_timeouts = config.project_timeouts.clone();
+
+    match command {
+        CliCommand::Help => {
+            println!("{}", USAGE);
+            0
+        }
+        CliCommand::List { json } => {
+            compiler.scan_for_projects();
+            let projects: Vec<ProjectInfo> = compiler.projects
+                .iter()
+                .map(|entry| ProjectInfo::collect(&compiler, &entry.path))
+                .collect();
+
+            if json {
+                print_json(&ProjectList { version: JSON_VERSION, projects });
+            } else {
+                for project in &projects {
+                    let status = match &project.last_build {
+                        Some(build) if build.success => "passed",
+                        Some(_) => "failed",
+                        None => "never built",
+                    };
+                    println!("{}\t{}\t{}", project.name, project.path.display(), status);
+                }
+            }
+            0
+        }
+        CliCommand::Compile { project, recipe, json } => {
+            let project_path = resolve_project(&mut compiler, &project);
+            let result = project_path.and_then(|path| {
+                compile(&mut compiler, path.clone(), &recipe)?;
+                Ok(path)
+            });
+
+            let output = compiler.compilation_output.clone();
+            let summary = compiler.last_summary.as_ref();
+            let (success, error) = match &result {
+                Ok(_) => (true, None),
+                Err(HadouError::CompileFailed { action, code, .. }) => {
+                    // The output is reported on its own, so leave it out here
+                    let ending = code.map(|code| format!("exited with code {}", code)).unwrap_or_else(|| "was terminated by a signal".to_string());
+                    (false, Some(format!("{} {}", action, ending)))
+                }
+                Err(e) => (false, Some(e.to_string())),
+            };
+
+            if json {
+                print_json(&CompileReport {
+                    version: JSON_VERSION,
+                    project: result.as_ref().ok().map(|path| ProjectInfo::collect(&compiler, path)),
+                    recipe,
+                    success,
+                    exit_code: summary.and_then(|summary| summary.exit_code),
+                    duration_ms: summary.map(|summary| summary.duration.as_millis() as u64).unwrap_or(0),
+                    output,
+                    error,
+                });
+            } else {
+                for line in &output {
+                    println!("{}", line);
+                }
+                if let Some(error) = error {
+                    eprintln!("hadou: {}", error);
+                }
+            }
+
+            if success { 0 } else { 1 }
+        }
+    }
+}
+
+fn print_json<T: Serialize>(value: &T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("hadou: {}", e),
+    }
+}
+
+// A directory, or the name of a project found below the working directory
+fn resolve_project(compiler: &mut ProjectCompiler, project: &str) -> Result<PathBuf, HadouError> {
+    let path = Path::new(project);
+    if path.is_dir() {
+        return Ok(path.to_path_buf());
+    }
+
+    compiler.scan_for_projects();
+    compiler.projects
+        .iter()
+        .find(|entry| project_label(&entry.path) == project)
+        .map(|entry| entry.path.clone())
+        .ok_or_else(|| HadouError::Other(format!("No project named '{}' below {}", project, compiler.current_directory.display())))
+}
+
+// Start the recipe and wait for it the way the compile screen does, so the
+// timeout, dump depth and build history all apply
+fn compile(compiler: &mut ProjectCompiler, project_path: PathBuf, recipe: &str) -> Result<String, HadouError> {
+    let action = match recipe {
+        "compile" => CompileAction::CompileOnly,
+        "simulate" => CompileAction::CompileAndSimulate,
+        "view" => CompileAction::CompileSimulateAndView,
+        "clean" => CompileAction::Clean,
+        "info" => CompileAction::Info,
+        other => CompileAction::Custom(other.to_string()),
+    };
+    compiler.start_action(project_path, action)?;
+
+    loop {
+        if let Some(result) = compiler.poll_compilation() {
+            return result;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliCommand, String> {
+        CliCommand::parse(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn json_flag_goes_anywhere() {
+        assert_eq!(parse(&["list", "--json"]), Ok(CliCommand::List { json: true }));
+        assert_eq!(
+            parse(&["--json", "compile", "alu", "simulate"]),
+            Ok(CliCommand::Compile { project: "alu".to_string(), recipe: "simulate".to_string(), json: true })
+        );
+    }
+
+    #[test]
+    fn compile_defaults_to_the_compile_recipe() {
+        assert_eq!(
+            parse(&["compile", "alu"]),
+            Ok(CliCommand::Compile { project: "alu".to_string(), recipe: "compile".to_string(), json: false })
+        );
+        assert!(parse(&["compile"]).is_err());
+        assert!(parse(&["list", "extra"]).is_err());
+        assert!(parse(&["frobnicate"]).is_err());
+    }
+
+    #[test]
+    fn simulator_comes_from_the_compile_recipe() {
+        let generated = crate::create_new_project::generate_justfile("alu", "iverilog", "");
+        assert_eq!(justfile::recipe_program(&generated, "compile"), Some("iverilog".to_string()));
+        assert_eq!(justfile::recipe_program("compile:\n    @echo hi\n\n    -ghdl -a main.vhd\n", "compile"), Some("ghdl".to_string()));
+        assert_eq!(justfile::recipe_program("build:\n    make\n", "compile"), None);
+
+        let makefile = crate::create_new_project::generate_makefile("alu", "iverilog", "");
+        assert_eq!(justfile::recipe_program(&makefile, "compile"), Some("iverilog".to_string()));
+    }
+}
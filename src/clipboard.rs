@@ -0,0 +1,39 @@
+use arboard::Clipboard;
+
+use crate::error::HadouError;
+
+// The system clipboard, opened on first use. It stays open because on X11
+// copied text is only served while the clipboard owner is still alive.
+#[derive(Default)]
+pub struct SystemClipboard {
+    clipboard: Option<Clipboard>,
+}
+
+impl std::fmt::Debug for SystemClipboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SystemClipboard")
+            .field("open", &self.clipboard.is_some())
+            .finish()
+    }
+}
+
+impl SystemClipboard {
+    // Fails when there is no clipboard to talk to, e.g. over SSH or on a
+    // headless machine
+    pub fn copy(&mut self, text: &str) -> Result<(), HadouError> {
+        self.open()?.set_text(text)?;
+        Ok(())
+    }
+
+    pub fn paste(&mut self) -> Result<String, HadouError> {
+        Ok(self.open()?.get_text()?)
+    }
+
+    fn open(&mut self) -> Result<&mut Clipboard, HadouError> {
+        let clipboard = match self.clipboard.take() {
+            Some(clipboard) => clipboard,
+            None => Clipboard::new()?,
+        };
+        Ok(self.clipboard.insert(clipboard))
+    }
+}
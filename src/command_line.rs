@@ -0,0 +1,36 @@
+use std::ffi::OsStr;
+use std::process::Command;
+
+// How a Command would be typed into a shell: `cd <dir> && VAR=value program
+// args`, for showing what Hadou is about to run without running it
+pub fn render(command: &Command) -> String {
+    let mut parts = Vec::new();
+
+    if let Some(dir) = command.get_current_dir() {
+        parts.push(format!("cd {} &&", quote(dir.as_os_str())));
+    }
+
+    for (key, value) in command.get_envs() {
+        match value {
+            Some(value) => parts.push(format!("{}={}", key.to_string_lossy(), quote(value))),
+            None => parts.push(format!("env -u {}", key.to_string_lossy())),
+        }
+    }
+
+    parts.push(quote(command.get_program()));
+    parts.extend(command.get_args().map(quote));
+    parts.join(" ")
+}
+
+// Single-quote anything a POSIX shell would split or expand
+fn quote(arg: &OsStr) -> String {
+    let arg = arg.to_string_lossy();
+    let plain = !arg.is_empty()
+        && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=+,@%".contains(c));
+
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
@@ -1,25 +1,59 @@
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::env;
 use std::fs;
+use std::io::{BufRead, BufReader, Read};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::adopt;
+use crate::build_system::BuildSystem;
+use crate::command_line;
+use crate::edit_project::project_label;
+use crate::env_file::{self, ProjectEnv};
+use crate::error::HadouError;
+use crate::fuzzy::{self, FuzzyMatch, ListSearch};
+use crate::history::{self, HistoryEntry};
+use crate::icons::Icon;
+use crate::justfile::{self, JustfileStatus, RecipeCache};
+use crate::language::{self, Language};
+use crate::list_window::ListWindow;
+use crate::notes;
+use crate::project_config::{self, ProjectConfig};
+use crate::safe_write;
+use crate::scanner::{self, ListEntry, ScanOptions, SortMode};
+use crate::settings::command_exists;
+use crate::testbench;
+use crate::verilog;
+use crate::watcher::SourceWatcher;
+use crate::waveform_viewer;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum CompileAction {
     CompileOnly,
     CompileAndSimulate,
     CompileSimulateAndView,
+    // Runs `simulate`, then loads the VCD it wrote into Hadou's own viewer
+    CompileSimulateAndInspect,
     Clean,
     Info,
+    // A recipe from the project's own Justfile or Makefile, run when the one
+    // an action expects is missing
+    Custom(String),
 }
 
 impl CompileAction {
-    pub fn as_just_recipe(&self) -> &'static str {
+    pub fn as_just_recipe(&self) -> &str {
         match self {
             CompileAction::CompileOnly => "compile",
             CompileAction::CompileAndSimulate => "simulate", // simulate depends on compile
             CompileAction::CompileSimulateAndView => "view", // view depends on simulate
+            CompileAction::CompileSimulateAndInspect => "simulate",
             CompileAction::Clean => "clean",
             CompileAction::Info => "info",
+            CompileAction::Custom(recipe) => recipe,
         }
     }
 
@@ -28,50 +62,331 @@ impl CompileAction {
             CompileAction::CompileOnly => "Compile Verilog files only",
             CompileAction::CompileAndSimulate => "Compile and run simulation",
             CompileAction::CompileSimulateAndView => "Compile, simulate, and open waveform",
+            CompileAction::CompileSimulateAndInspect => "Compile, simulate, and view in Hadou",
             CompileAction::Clean => "Clean generated files",
             CompileAction::Info => "Show project information",
+            CompileAction::Custom(_) => "Run build file recipe",
         }
     }
 
-    pub fn icon(&self) -> &'static str {
+    // Whether the action builds the design, as opposed to housekeeping that
+    // is left out of a project's history
+    pub fn builds(&self) -> bool {
+        !matches!(self, CompileAction::Clean | CompileAction::Info)
+    }
+
+    pub fn simulates(&self) -> bool {
+        matches!(
+            self,
+            CompileAction::CompileAndSimulate | CompileAction::CompileSimulateAndView | CompileAction::CompileSimulateAndInspect
+        )
+    }
+
+    pub fn opens_internal_viewer(&self) -> bool {
+        matches!(self, CompileAction::CompileSimulateAndInspect)
+    }
+
+    pub fn icon(&self, ascii_mode: bool) -> &'static str {
+        let icon = match self {
+            CompileAction::CompileOnly => Icon::Gear,
+            CompileAction::CompileAndSimulate => Icon::Rocket,
+            CompileAction::CompileSimulateAndView => Icon::Chart,
+            CompileAction::CompileSimulateAndInspect => Icon::Wave,
+            CompileAction::Clean => Icon::Broom,
+            CompileAction::Info => Icon::Info,
+            CompileAction::Custom(_) => Icon::Justfile,
+        };
+        icon.glyph(ascii_mode)
+    }
+}
+
+// Views on the compile screen, switched with Tab/Shift-Tab
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompileTab {
+    Projects,
+    Output,
+    Diagnostics,
+    History,
+    Environment,
+    Info,
+}
+
+impl CompileTab {
+    pub const ALL: [CompileTab; 6] = [
+        CompileTab::Projects,
+        CompileTab::Output,
+        CompileTab::Diagnostics,
+        CompileTab::History,
+        CompileTab::Environment,
+        CompileTab::Info,
+    ];
+
+    pub fn title(&self) -> &'static str {
         match self {
-            CompileAction::CompileOnly => "⚙️ ",
-            CompileAction::CompileAndSimulate => "🚀",
-            CompileAction::CompileSimulateAndView => "📊",
-            CompileAction::Clean => "🧹",
-            CompileAction::Info => "ℹ️ ",
+            CompileTab::Projects => "Projects",
+            CompileTab::Output => "Output",
+            CompileTab::Diagnostics => "Diagnostics",
+            CompileTab::History => "History",
+            CompileTab::Environment => "Environment",
+            CompileTab::Info => "Info",
         }
     }
+
+    pub fn index(&self) -> usize {
+        Self::ALL.iter().position(|tab| tab == self).unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchResult {
+    pub project: PathBuf,
+    pub success: bool,
+    pub message: String,
+}
+
+// One action run over several projects, a project at a time. Each run goes
+// through the same background path as a single one, and `poll_batch` starts
+// the next project once the previous has finished.
+#[derive(Debug)]
+pub struct BatchRun {
+    pub action: CompileAction,
+    queue: VecDeque<PathBuf>,
+    // Project running now, with when it started
+    current: Option<(PathBuf, Instant)>,
+    pub total: usize,
+    pub results: Vec<BatchResult>,
+    // Started on the marked projects rather than on every listed one
+    pub marked: bool,
+}
+
+impl BatchRun {
+    // Position of the running project, counting from 1, and its name
+    pub fn progress(&self) -> Option<(usize, String)> {
+        let (project, _) = self.current.as_ref()?;
+        Some((self.results.len() + 1, project_label(project)))
+    }
+}
+
+// A generated file or directory left in a project by a build or simulation
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    pub path: PathBuf,
+    pub size: u64,
+    pub is_dir: bool,
+    // Deleted by the generated `clean` recipe; anything else has to be
+    // removed by hand
+    pub removed_by_clean: bool,
+}
+
+// What the project list shows for a project, worked out when the list is
+// scanned rather than every frame
+#[derive(Debug, Default)]
+pub struct ProjectSummary {
+    pub language: Language,
+    pub source_files: Vec<PathBuf>,
+    pub build_system: Option<BuildSystem>,
+    // When the justfile was last changed, to tell whether a validation of
+    // it still applies
+    justfile_modified: Option<SystemTime>,
+}
+
+// What the preview shows about the selected project, read when the selection
+// changes, the list is refreshed or a run finishes rather than every frame
+#[derive(Debug, Default)]
+pub struct ProjectDetails {
+    pub artifacts: Vec<Artifact>,
+    // Port mismatches between the design and its testbench
+    pub testbench_warnings: Vec<String>,
+    // Recorded runs, oldest first
+    pub history: Vec<HistoryEntry>,
+    // .hadou.toml, or why it couldn't be read
+    pub config: Option<ProjectConfig>,
+    pub config_error: Option<String>,
+    pub extra_source_count: usize,
+    // The testbench simulations use, each testbench found with the VCD its
+    // $dumpfile names, and the $dumpvars depth written in the selected one
+    pub testbench: String,
+    pub testbenches: Vec<(String, Option<String>)>,
+    pub dump_depth: Option<u32>,
+    pub notes: Option<String>,
+}
+
+// A `just` invocation running in the background. Output lines are read on
+// separate threads and handed over through `receiver`.
+#[derive(Debug)]
+struct RunningCompilation {
+    child: Child,
+    receiver: Receiver<String>,
+    readers: Vec<JoinHandle<()>>,
+    project_path: PathBuf,
+    action: CompileAction,
+    started: Instant,
+    timeout: Option<Duration>,
+}
+
+// How the last background run ended, written into the header of saved logs
+#[derive(Debug, Clone)]
+pub struct RunSummary {
+    pub project_path: PathBuf,
+    pub action: CompileAction,
+    // None when the process was killed by a signal or could not be waited on
+    pub exit_code: Option<i32>,
+    pub duration: Duration,
+}
+
+// Which part of a run printed an output line. Everything goes through one
+// build tool process, so the switch is spotted from the lines it echoes.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutputPhase {
+    // Hadou's notes, the build tool and the compiler
+    #[default]
+    Build,
+    // Printed by the simulator: $display, $monitor, $finish and the like
+    Simulation,
+}
+
+impl OutputPhase {
+    // Phase of `line` and the phase of the lines after it, given the phase
+    // the previous line left behind
+    pub fn classify(current: OutputPhase, line: &str) -> (OutputPhase, OutputPhase) {
+        let line = line.trim();
+        let mut words = line.split_whitespace();
+        let program = words.next().map(|word| word.rsplit('/').next().unwrap_or(word)).unwrap_or_default();
+        let starts_simulation =
+            line == "Running simulation..." || program == "vvp" || (program == "ghdl" && words.next() == Some("-r"));
+        let ends_simulation = line.starts_with("────")
+            || line.starts_with("Compiling ")
+            || line.starts_with("Simulation completed")
+            || line.starts_with("Opening waveform viewer")
+            || line.starts_with("error: Recipe")
+            || line.starts_with("make:")
+            || program.starts_with("iverilog")
+            || program == "ghdl";
+
+        if starts_simulation {
+            (OutputPhase::Build, OutputPhase::Simulation)
+        } else if ends_simulation {
+            (OutputPhase::Build, OutputPhase::Build)
+        } else {
+            (current, current)
+        }
+    }
+}
+
+// Older lines are dropped once a watched project's rolling output gets this long
+const MAX_OUTPUT_LINES: usize = 5000;
+
+
+// Deepest $dumpvars depth offered by the compile screen's toggle
+const MAX_DUMP_DEPTH: u32 = 3;
+
+// Re-runs an action on a project whenever its sources change
+#[derive(Debug)]
+pub struct ProjectWatch {
+    pub project_path: PathBuf,
+    pub action: CompileAction,
+    pub runs: usize,
+    // Sources changed while a run was still going; run again once it ends
+    rerun_pending: bool,
+    watcher: SourceWatcher,
 }
 
 #[derive(Debug)]
 pub struct ProjectCompiler {
-    pub projects: Vec<PathBuf>,
+    pub projects: Vec<ListEntry>,
     pub selected_project_index: usize,
     pub selected_action_index: usize,
     pub current_directory: PathBuf,
+    pub scan_options: ScanOptions,
+    pub sort_mode: SortMode,
+    // Projects listed first whatever the sort order
+    pinned: HashSet<PathBuf>,
     pub available_actions: Vec<CompileAction>,
+    // Projects marked with Space to run an action on together
+    pub marked_projects: HashSet<PathBuf>,
     pub compilation_output: Vec<String>,
+    // Phase of each line in compilation_output, tagged as it comes in
+    output_phases: Vec<OutputPhase>,
+    output_phase: OutputPhase,
     pub is_compiling: bool,
+    pub active_tab: CompileTab,
+    pub search: ListSearch,
+    pub list_window: ListWindow,
+    // Project and action of the most recent run, repeated with F5
+    pub last_run: Option<(PathBuf, CompileAction)>,
+    pub last_summary: Option<RunSummary>,
+    pub watch: Option<ProjectWatch>,
+    pub batch: Option<BatchRun>,
+    // $dumpvars depth written into main_test.v before each simulation; None
+    // leaves the testbench as it is
+    pub dump_depth: Option<u32>,
+    // Fast mode: compile Verilog projects with -DNO_DUMP so guarded
+    // testbenches skip writing a VCD
+    pub skip_dump: bool,
+    // Seconds before a simulating run is stopped (0 never), and per-project
+    // overrides keyed by directory name, both from the config
+    pub simulation_timeout: u64,
+    pub project_timeouts: HashMap<String, u64>,
+    // .env variables of the selected project, including per-run changes
+    pub project_env: ProjectEnv,
+    env_project: Option<PathBuf>,
+    pub latest_sim_time: Option<u64>,
+    pub expected_end_time: Option<u64>,
+    running: Option<RunningCompilation>,
+    recipes: RecipeCache,
+    // Last `just --summary` verdict per project, with the justfile's
+    // modification time when it was checked
+    justfile_checks: HashMap<PathBuf, (Option<SystemTime>, JustfileStatus)>,
+    summaries: HashMap<PathBuf, ProjectSummary>,
+    pub details: ProjectDetails,
+    details_project: Option<PathBuf>,
 }
 
 impl ProjectCompiler {
-    pub fn new() -> Self {
+    pub fn new(scan_options: ScanOptions) -> Self {
         let current_dir = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
         let mut compiler = Self {
             projects: Vec::new(),
             selected_project_index: 0,
             selected_action_index: 0,
             current_directory: current_dir,
+            scan_options,
+            sort_mode: SortMode::default(),
+            pinned: HashSet::new(),
             available_actions: vec![
                 CompileAction::CompileOnly,
                 CompileAction::CompileAndSimulate,
                 CompileAction::CompileSimulateAndView,
+                CompileAction::CompileSimulateAndInspect,
                 CompileAction::Clean,
                 CompileAction::Info,
             ],
+            marked_projects: HashSet::new(),
             compilation_output: Vec::new(),
+            output_phases: Vec::new(),
+            output_phase: OutputPhase::Build,
             is_compiling: false,
+            active_tab: CompileTab::Projects,
+            search: ListSearch::default(),
+            list_window: ListWindow::default(),
+            last_run: None,
+            last_summary: None,
+            watch: None,
+            batch: None,
+            dump_depth: None,
+            skip_dump: false,
+            simulation_timeout: 60,
+            project_timeouts: HashMap::new(),
+            project_env: ProjectEnv::default(),
+            env_project: None,
+            latest_sim_time: None,
+            expected_end_time: None,
+            running: None,
+            recipes: RecipeCache::default(),
+            justfile_checks: HashMap::new(),
+            summaries: HashMap::new(),
+            details: ProjectDetails::default(),
+            details_project: None,
         };
 
         compiler.scan_for_projects();
@@ -79,183 +394,927 @@ impl ProjectCompiler {
     }
 
     pub fn scan_for_projects(&mut self) {
-        self.projects.clear();
         self.selected_project_index = 0;
 
-        if let Ok(entries) = fs::read_dir(&self.current_directory) {
+        let mut projects = self.scan_options.find_dirs(&self.current_directory, |path| self.has_source_files(path));
+        if adopt::is_adopted(&self.current_directory) {
+            projects.insert(0, self.current_directory.clone());
+        }
+        self.summaries = projects.iter().map(|project| (project.clone(), self.summarize(project))).collect();
+        self.projects = projects.into_iter().map(ListEntry::read).collect();
+        self.sort_projects();
+
+        if self.search.is_active() {
+            self.select_best_match();
+        }
+        self.details_project = None;
+        self.sync_selection();
+    }
+
+    // Re-sort the list, keeping the same project selected
+    pub fn set_sort_mode(&mut self, sort_mode: SortMode) {
+        let selected = self.get_selected_project_path().cloned();
+        self.sort_mode = sort_mode;
+        self.sort_projects();
+        if let Some(index) = selected.and_then(|path| self.projects.iter().position(|entry| entry.path == path)) {
+            self.selected_project_index = index;
+        }
+    }
+
+    // Pin or unpin projects, re-sorting the list around the same selection
+    pub fn set_pinned(&mut self, pinned: HashSet<PathBuf>) {
+        self.pinned = pinned;
+        self.set_sort_mode(self.sort_mode);
+    }
+
+    pub fn is_pinned(&self, project_path: &Path) -> bool {
+        self.pinned.contains(project_path)
+    }
+
+    fn sort_projects(&mut self) {
+        self.sort_mode.sort(&mut self.projects);
+        scanner::pin_to_top(&mut self.projects, &self.pinned);
+    }
+
+    fn summarize(&self, project_path: &Path) -> ProjectSummary {
+        ProjectSummary {
+            language: Language::detect(project_path).unwrap_or_default(),
+            source_files: self.get_source_files(project_path),
+            build_system: self.build_system(project_path),
+            justfile_modified: justfile_modified(project_path),
+        }
+    }
+
+    pub fn summary(&self, project_path: &Path) -> Option<&ProjectSummary> {
+        self.summaries.get(project_path)
+    }
+
+    // Reload what is kept about the selected project when the selection
+    // changes
+    fn sync_selection(&mut self) {
+        self.sync_project_env();
+        self.sync_details();
+    }
+
+    fn sync_details(&mut self) {
+        let selected = self.get_selected_project_path().cloned();
+        if selected == self.details_project {
+            return;
+        }
+
+        self.details = selected.as_deref().map(|project| self.load_details(project)).unwrap_or_default();
+        self.details_project = selected;
+    }
+
+    // Re-read the selected project's summary and details after something
+    // changed them on disk, such as a finished run
+    pub fn reload_details(&mut self) {
+        if let Some(project) = self.get_selected_project_path().cloned() {
+            let summary = self.summarize(&project);
+            self.summaries.insert(project, summary);
+        }
+        self.details_project = None;
+        self.sync_details();
+    }
+
+    fn load_details(&self, project_path: &Path) -> ProjectDetails {
+        let (config, config_error) = match ProjectConfig::load(project_path) {
+            Ok(config) => (Some(config), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let testbench = testbench::selected(project_path);
+
+        ProjectDetails {
+            artifacts: self.list_artifacts(project_path),
+            testbench_warnings: self.check_testbench(project_path),
+            history: history::load(project_path),
+            extra_source_count: config.as_ref().map_or(0, |config| config.extra_source_files(project_path).len()),
+            config,
+            config_error,
+            testbenches: testbench::find_testbenches(project_path)
+                .into_iter()
+                .map(|name| {
+                    let vcd = testbench::vcd_name(project_path, &name);
+                    (name, vcd)
+                })
+                .collect(),
+            dump_depth: fs::read_to_string(project_path.join(&testbench)).ok().and_then(|source| verilog::dump_depth(&source)),
+            testbench,
+            notes: notes::load(project_path),
+        }
+    }
+
+    // Reload the .env file when the selected project changes. Per-run edits
+    // are kept for as long as the same project stays selected.
+    pub fn sync_project_env(&mut self) {
+        let selected = self.get_selected_project_path().cloned();
+        if selected == self.env_project {
+            return;
+        }
+
+        self.project_env = selected.as_deref().map(ProjectEnv::load).unwrap_or_default();
+        self.env_project = selected;
+    }
+
+    pub fn reload_project_env(&mut self) {
+        self.env_project = None;
+        self.sync_project_env();
+    }
+
+    // Environment for running in `project_path`: the selected project's
+    // edited variables, or a fresh load of another project's .env
+    fn env_for(&self, project_path: &Path) -> ProjectEnv {
+        if self.env_project.as_deref() == Some(project_path) {
+            self.project_env.clone()
+        } else {
+            ProjectEnv::load(project_path)
+        }
+    }
+
+    // Verilog or VHDL sources make a directory a project
+    pub fn has_source_files(&self, dir_path: &Path) -> bool {
+        language::has_source_files(dir_path)
+    }
+
+    // just for a Justfile, make for a Makefile
+    pub fn build_system(&self, dir_path: &Path) -> Option<BuildSystem> {
+        BuildSystem::detect(dir_path)
+    }
+
+    pub fn has_build_file(&self, dir_path: &Path) -> bool {
+        self.build_system(dir_path).is_some()
+    }
+
+    pub fn get_source_files(&self, project_path: &Path) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+
+        if let Ok(entries) = fs::read_dir(project_path) {
             for entry in entries.flatten() {
                 let path = entry.path();
-                if path.is_dir() && self.has_verilog_files(&path) {
-                    self.projects.push(path);
+                if path.is_file() && Language::of_file(&path).is_some() {
+                    files.push(path);
                 }
             }
         }
 
-        // Sort projects alphabetically
-        self.projects.sort_by(|a, b| {
+        // Sort files alphabetically
+        files.sort_by(|a, b| {
             a.file_name()
                 .unwrap_or_default()
                 .cmp(b.file_name().unwrap_or_default())
         });
+
+        // Then the extra sources from .hadou.toml, wherever they live
+        let extra = ProjectConfig::load(project_path)
+            .map(|config| config.extra_source_files(project_path))
+            .unwrap_or_default();
+        for path in extra {
+            if !files.contains(&path) {
+                files.push(path);
+            }
+        }
+
+        files
     }
 
-    pub fn has_verilog_files(&self, dir_path: &Path) -> bool {
-        if let Ok(entries) = fs::read_dir(dir_path) {
+    // Build outputs that currently exist in the project: compiled `.vvp`
+    // files, GHDL's `work-obj*.cf` libraries, waveform dumps and Verilator's
+    // `obj_dir/`
+    pub fn list_artifacts(&self, project_path: &Path) -> Vec<Artifact> {
+        let mut artifacts = Vec::new();
+        let cleaned = clean_targets(project_path);
+
+        if let Ok(entries) = fs::read_dir(project_path) {
             for entry in entries.flatten() {
                 let path = entry.path();
-                if path.is_file() {
-                    if let Some(extension) = path.extension() {
-                        if extension == "v" {
-                            return true;
-                        }
-                    }
+                let name = entry.file_name().to_string_lossy().to_lowercase();
+
+                if path.is_dir() && name == "obj_dir" {
+                    artifacts.push(Artifact {
+                        size: scanner::dir_size(&path),
+                        path,
+                        is_dir: true,
+                        removed_by_clean: false,
+                    });
+                } else if path.is_file()
+                    && (name.ends_with(".vvp") || name.ends_with(".vcd") || name.ends_with(".vcd.gz") || (name.starts_with("work-obj") && name.ends_with(".cf")))
+                {
+                    artifacts.push(Artifact {
+                        size: entry.metadata().map(|metadata| metadata.len()).unwrap_or(0),
+                        removed_by_clean: cleaned.iter().any(|target| entry.file_name().to_string_lossy() == *target),
+                        path,
+                        is_dir: false,
+                    });
                 }
             }
         }
-        false
-    }
 
-    pub fn has_justfile(&self, dir_path: &Path) -> bool {
-        let justfile_path = dir_path.join("justfile");
-        let justfile_alt_path = dir_path.join("Justfile");
-        justfile_path.exists() || justfile_alt_path.exists()
+        artifacts.sort_by(|a, b| a.path.cmp(&b.path));
+        artifacts
     }
 
-    pub fn get_verilog_files(&self, project_path: &Path) -> Vec<PathBuf> {
-        let mut files = Vec::new();
+    // Compare the design's port list against how the testbench instantiates
+    // it. Purely advisory: the result is shown as warnings and never stops a run.
+    pub fn check_testbench(&self, project_path: &Path) -> Vec<String> {
+        let Ok(design) = fs::read_to_string(project_path.join("main.v")) else {
+            return Vec::new();
+        };
+        let testbench_name = testbench::selected(project_path);
+        let Ok(testbench) = fs::read_to_string(project_path.join(&testbench_name)) else {
+            return Vec::new();
+        };
 
-        if let Ok(entries) = fs::read_dir(project_path) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_file() {
-                    if let Some(extension) = path.extension() {
-                        if extension == "v" {
-                            files.push(path);
+        let modules = verilog::parse_modules(&design);
+        if modules.is_empty() {
+            return Vec::new();
+        }
+
+        let module_names: Vec<String> = modules.iter().map(|module| module.name.clone()).collect();
+        let instances = verilog::find_instances(&testbench, &module_names);
+        let mut warnings = Vec::new();
+
+        if instances.is_empty() {
+            warnings.push(format!(
+                "{} does not instantiate any module from main.v ({})",
+                testbench_name,
+                module_names.join(", ")
+            ));
+            return warnings;
+        }
+
+        for instance in &instances {
+            let Some(module) = modules.iter().find(|module| module.name == instance.module) else {
+                continue;
+            };
+
+            match &instance.connections {
+                verilog::PortConnections::Named(connections) => {
+                    for port in module.ports.iter().map(|port| &port.name) {
+                        match connections.iter().find(|(name, _)| name == port) {
+                            None => warnings.push(format!(
+                                "{}: port '{}' of '{}' is not connected",
+                                instance.name, port, module.name
+                            )),
+                            Some((_, None)) => warnings.push(format!(
+                                "{}: port '{}' of '{}' is left empty",
+                                instance.name, port, module.name
+                            )),
+                            Some(_) => {}
                         }
                     }
+                    for (name, _) in connections {
+                        if !module.has_port(name) {
+                            warnings.push(format!(
+                                "{}: '{}' has no port named '{}'",
+                                instance.name, module.name, name
+                            ));
+                        }
+                    }
+                }
+                verilog::PortConnections::Positional(count) if *count != module.ports.len() => {
+                    warnings.push(format!(
+                        "{}: {} connection(s) given but '{}' has {} port(s)",
+                        instance.name, count, module.name, module.ports.len()
+                    ));
                 }
+                _ => {}
             }
         }
 
-        // Sort files alphabetically
-        files.sort_by(|a, b| {
-            a.file_name()
-                .unwrap_or_default()
-                .cmp(b.file_name().unwrap_or_default())
-        });
-
-        files
+        warnings
     }
 
-    pub fn execute_compilation(&mut self) -> Result<String, Box<dyn std::error::Error>> {
+    // Start the selected action in the background. Progress is collected by
+    // `poll_compilation`, which the UI calls on every tick.
+    pub fn start_compilation(&mut self) -> Result<(), HadouError> {
         if self.projects.is_empty() {
-            return Err("No Verilog projects found in current directory".into());
+            return Err(HadouError::NoProjects);
         }
 
         if self.selected_project_index >= self.projects.len() {
-            return Err("Invalid project selection".into());
+            return Err(HadouError::NoSelection("project"));
         }
 
         if self.selected_action_index >= self.available_actions.len() {
-            return Err("Invalid action selection".into());
+            return Err(HadouError::NoSelection("action"));
         }
 
         // Clone the values we need to avoid borrowing conflicts
-        let project_path = self.projects[self.selected_project_index].clone();
+        let project_path = self.projects[self.selected_project_index].path.clone();
         let action = self.available_actions[self.selected_action_index].clone();
 
-        // Check if justfile exists
-        if !self.has_justfile(&project_path) {
-            return Err("No justfile found in project directory. Please create the project using Hadou first.".into());
-        }
+        self.start_compilation_for(project_path, action, None)
+    }
 
-        self.is_compiling = true;
-        self.compilation_output.clear();
+    // Run the selected action now and again after every change to the
+    // project's Verilog sources, collecting all runs in one output
+    pub fn start_watch(&mut self) -> Result<(), HadouError> {
+        let project_path = self.get_selected_project_path().cloned().ok_or(HadouError::NoSelection("project"))?;
+        let action = self.get_selected_action().cloned().ok_or(HadouError::NoSelection("action"))?;
+
+        self.watch = Some(ProjectWatch {
+            watcher: SourceWatcher::new(&project_path)?,
+            project_path,
+            action,
+            runs: 0,
+            rerun_pending: true,
+        });
+        self.clear_compilation_output();
+
+        self.poll_watch()
+    }
+
+    pub fn stop_watch(&mut self) {
+        self.watch = None;
+    }
+
+    // Project and action of the compilation running in the background
+    pub fn running_compilation(&self) -> Option<(&Path, &CompileAction)> {
+        self.running.as_ref().map(|running| (running.project_path.as_path(), &running.action))
+    }
 
-        let result = self.run_just_command(&project_path, &action);
-        
+    // Kill the running compilation along with the simulator it started
+    pub fn stop_compilation(&mut self) {
+        if let Some(mut running) = self.running.take() {
+            kill_process_group(&mut running.child);
+            let _ = running.child.wait();
+        }
+        self.batch = None;
         self.is_compiling = false;
+    }
+
+    // Start the next watched run once changes have settled and nothing else
+    // is running. Stops watching if the run cannot be started.
+    pub fn poll_watch(&mut self) -> Result<(), HadouError> {
+        let Some(watch) = self.watch.as_mut() else {
+            return Ok(());
+        };
+
+        if watch.watcher.poll() {
+            watch.rerun_pending = true;
+        }
+        if !watch.rerun_pending || self.running.is_some() {
+            return Ok(());
+        }
+
+        watch.rerun_pending = false;
+        watch.runs += 1;
+        let (project_path, action) = (watch.project_path.clone(), watch.action.clone());
+
+        let header = format!("{} #{} at {}", action.description(), watch.runs, chrono::Local::now().format("%H:%M:%S"));
+        let result = self.start_compilation_for(project_path, action, Some(header));
+        if result.is_err() {
+            self.watch = None;
+        }
         result
     }
 
-    fn run_just_command(&mut self, project_dir: &Path, action: &CompileAction) -> Result<String, Box<dyn std::error::Error>> {
-        // Check if just command exists
-        if !self.command_exists("just") {
-            return Err("'just' command not found. Please install 'just' command runner.".into());
+    // One line per finished watched run, since those don't open a dialog
+    pub fn record_watched_result(&mut self, result: &Result<String, HadouError>) {
+        // Failures carry the whole output, which is already on screen
+        let line = match result {
+            Ok(message) => message.clone(),
+            Err(e) => e.to_string().lines().next().unwrap_or_default().to_string(),
+        };
+        self.push_output_line(line);
+    }
+
+    // Repeat the last run, selecting its project again if it is still listed
+    pub fn rerun_last(&mut self) -> Result<(), HadouError> {
+        let (project_path, action) = self.last_run.clone().ok_or_else(|| HadouError::Other("Nothing has been compiled yet".to_string()))?;
+
+        self.select_project(&project_path);
+        if let Some(index) = self.available_actions.iter().position(|available| *available == action) {
+            self.selected_action_index = index;
+        }
+
+        self.start_compilation_for(project_path, action, None)
+    }
+
+    // A `header` keeps the previous output and adds the header above the new
+    // run's; without one the output starts over
+    fn start_compilation_for(&mut self, project_path: PathBuf, action: CompileAction, header: Option<String>) -> Result<(), HadouError> {
+        if self.running.is_some() {
+            return Err(HadouError::AlreadyRunning);
+        }
+
+        if !project_path.is_dir() {
+            return Err(HadouError::ProjectMissing(project_path));
         }
 
-        let mut command = Command::new("just");
-        command.current_dir(project_dir);
-        command.arg(action.as_just_recipe());
+        let Some(system) = self.build_system(&project_path) else {
+            return Err(HadouError::NoBuildFile(project_path));
+        };
+
+        if !command_exists(system.program()) {
+            return Err(HadouError::tool_not_found(system.program(), system.install_hint()));
+        }
 
-        // Capture both stdout and stderr
-        let output = command.output()?;
+        self.check_recipe(&project_path, &action)?;
+        // A broken .hadou.toml would otherwise build without its flags
+        ProjectConfig::load(&project_path)?;
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+        let testbench_notes = self.prepare_testbench(&project_path, &action)?;
 
-        // Store output for display
-        if !stdout.is_empty() {
-            self.compilation_output.extend(stdout.lines().map(String::from));
+        let mut command = self.build_command(system, &project_path, &action);
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        own_process_group(&mut command);
+
+        let mut child = command.spawn()?;
+        let timeout = self.timeout_for(&project_path, &action);
+
+        let (sender, receiver) = mpsc::channel();
+        let mut readers = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            readers.push(spawn_line_reader(stdout, sender.clone()));
         }
-        if !stderr.is_empty() {
-            self.compilation_output.extend(stderr.lines().map(String::from));
+        if let Some(stderr) = child.stderr.take() {
+            readers.push(spawn_line_reader(stderr, sender));
         }
 
-        if output.status.success() {
-            let project_name = project_dir
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy();
+        match header {
+            Some(header) => {
+                if !self.compilation_output.is_empty() {
+                    self.push_output_line(String::new());
+                }
+                self.push_output_line(format!("──── {} ────", header));
+            }
+            None => self.clear_compilation_output(),
+        }
+        for note in testbench_notes {
+            self.push_output_line(note);
+        }
+        if let Some(warning) = testbench::override_warning(&project_path, system) {
+            self.push_output_line(warning);
+        }
+        // Only worth checking when the run goes on to simulate
+        if action.simulates() {
+            for warning in self.check_testbench(&project_path) {
+                self.push_output_line(format!("warning: {}", warning));
+            }
+        }
+        self.latest_sim_time = None;
+        self.expected_end_time = fs::read_to_string(testbench::selected_path(&project_path))
+            .ok()
+            .and_then(|source| verilog::estimate_simulation_end(&source));
+        self.is_compiling = true;
+        self.last_run = Some((project_path.clone(), action.clone()));
+        self.last_summary = None;
+        self.running = Some(RunningCompilation {
+            child,
+            receiver,
+            readers,
+            project_path,
+            action,
+            started: Instant::now(),
+            timeout,
+        });
+
+        Ok(())
+    }
+
+    // Collect new output from the running compilation. Returns the outcome
+    // once the process has exited.
+    pub fn poll_compilation(&mut self) -> Option<Result<String, HadouError>> {
+        let running = self.running.as_mut()?;
+
+        let lines: Vec<String> = running.receiver.try_iter().collect();
+        let mut exit_status = running.child.try_wait();
+
+        let timed_out = matches!(exit_status, Ok(None))
+            && running.timeout.is_some_and(|timeout| running.started.elapsed() >= timeout);
+        if timed_out {
+            kill_process_group(&mut running.child);
+            exit_status = running.child.wait().map(Some);
+        }
+
+        for line in lines {
+            self.push_output_line(line);
+        }
 
-            Ok(format!(
+        let status = match exit_status {
+            Ok(Some(status)) => Ok(status),
+            Ok(None) => return None,
+            Err(e) => Err(e),
+        };
+
+        let running = self.running.take()?;
+        for reader in running.readers {
+            let _ = reader.join();
+        }
+        for line in running.receiver.try_iter() {
+            self.push_output_line(line);
+        }
+        // A batch is still going until its last project has run
+        self.is_compiling = self.batch.is_some();
+        let summary = RunSummary {
+            project_path: running.project_path.clone(),
+            action: running.action.clone(),
+            exit_code: status.as_ref().ok().and_then(|status| status.code()),
+            duration: running.started.elapsed(),
+        };
+        record_history(&summary, status.as_ref().is_ok_and(|status| status.success()));
+        self.last_summary = Some(summary);
+        self.reload_details();
+
+        let project_name = running.project_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+
+        if let Some(timeout) = running.timeout.filter(|_| timed_out) {
+            let error = HadouError::TimedOut { seconds: timeout.as_secs() };
+            self.push_output_line(error.to_string());
+            return Some(Err(error));
+        }
+
+        Some(match status {
+            Ok(status) if status.success() => Ok(format!(
                 "{} completed successfully for project '{}'",
-                action.description(),
+                running.action.description(),
                 project_name
-            ))
+            )),
+            Ok(status) => Err(HadouError::CompileFailed {
+                action: running.action.description(),
+                code: status.code(),
+                output: self.compilation_output.join("\n"),
+            }),
+            Err(e) => Err(HadouError::Io(e)),
+        })
+    }
+
+    // The VCD the testbench's $dumpfile names when the last run wrote it,
+    // otherwise the newest VCD in its project. Files written before the run
+    // started are ignored so a testbench that dumps nothing doesn't bring up
+    // stale data.
+    pub fn produced_vcd(&self) -> Option<PathBuf> {
+        let summary = self.last_summary.as_ref()?;
+        let started = SystemTime::now().checked_sub(summary.duration)?;
+
+        let expected = summary.project_path.join(testbench::vcd_file(&summary.project_path));
+        if ListEntry::read(expected.clone()).modified.is_some_and(|modified| modified >= started) {
+            return Some(expected);
+        }
+
+        self.scan_options
+            .find_files(&summary.project_path, waveform_viewer::is_vcd_file)
+            .into_iter()
+            .map(ListEntry::read)
+            .filter(|entry| entry.modified.is_some_and(|modified| modified >= started))
+            .max_by_key(|entry| entry.modified)
+            .map(|entry| entry.path)
+    }
+
+    fn push_output_line(&mut self, line: String) {
+        if let Some(time) = verilog::parse_simulation_time(&line) {
+            self.latest_sim_time = Some(time);
+        }
+        let (phase, next) = OutputPhase::classify(self.output_phase, &line);
+        self.output_phase = next;
+        self.compilation_output.push(line);
+        self.output_phases.push(phase);
+
+        if self.compilation_output.len() > MAX_OUTPUT_LINES {
+            let excess = self.compilation_output.len() - MAX_OUTPUT_LINES;
+            self.compilation_output.drain(..excess);
+            self.output_phases.drain(..excess);
+        }
+    }
+
+    pub fn next_tab(&mut self) {
+        let index = (self.active_tab.index() + 1) % CompileTab::ALL.len();
+        self.active_tab = CompileTab::ALL[index];
+    }
+
+    pub fn previous_tab(&mut self) {
+        let index = (self.active_tab.index() + CompileTab::ALL.len() - 1) % CompileTab::ALL.len();
+        self.active_tab = CompileTab::ALL[index];
+    }
+
+    // Error and warning lines from the last run's output
+    pub fn output_diagnostics(&self) -> Vec<&str> {
+        self.compilation_output
+            .iter()
+            .map(String::as_str)
+            .filter(|line| {
+                let lower = line.to_lowercase();
+                lower.contains("error") || lower.contains("warning")
+            })
+            .collect()
+    }
+
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.running.as_ref().map(|running| running.started.elapsed())
+    }
+
+    // Fraction of the expected simulation time reached so far, if both the
+    // end time and the current time are known
+    pub fn progress(&self) -> Option<f64> {
+        let expected = self.expected_end_time?;
+        let latest = self.latest_sim_time?;
+        Some((latest as f64 / expected as f64).clamp(0.0, 1.0))
+    }
+
+    // Run the selected action on every discovered project, carrying on past failures
+    pub fn execute_batch(&mut self) -> Result<(), HadouError> {
+        if self.projects.is_empty() {
+            return Err(HadouError::NoProjects);
+        }
+
+        let projects: Vec<PathBuf> = self.projects.iter().map(|entry| entry.path.clone()).collect();
+        self.start_batch(projects, false)
+    }
+
+    // Run the selected action on the marked projects only, in list order
+    pub fn execute_marked(&mut self) -> Result<(), HadouError> {
+        let projects: Vec<PathBuf> = self.projects
+            .iter()
+            .map(|entry| entry.path.clone())
+            .filter(|path| self.marked_projects.contains(path))
+            .collect();
+        if projects.is_empty() {
+            return Err(HadouError::NoSelection("project"));
+        }
+
+        self.start_batch(projects, true)
+    }
+
+    // Queue the projects; `poll_batch` starts the first on the next tick
+    fn start_batch(&mut self, projects: Vec<PathBuf>, marked: bool) -> Result<(), HadouError> {
+        if self.running.is_some() || self.batch.is_some() {
+            return Err(HadouError::AlreadyRunning);
+        }
+        if self.watch.is_some() {
+            return Err(HadouError::Other("Stop watching ('w') before running on several projects".to_string()));
+        }
+        let action = self.get_selected_action().cloned().ok_or(HadouError::NoSelection("action"))?;
+
+        self.clear_compilation_output();
+        self.batch = Some(BatchRun {
+            action,
+            total: projects.len(),
+            queue: projects.into(),
+            current: None,
+            results: Vec::new(),
+            marked,
+        });
+        self.is_compiling = true;
+        Ok(())
+    }
+
+    // Start the batch's next project once nothing is running. Projects that
+    // can't be started count as failed straight away. Returns the batch
+    // after its last project has finished.
+    pub fn poll_batch(&mut self) -> Option<BatchRun> {
+        if self.running.is_some() {
+            return None;
+        }
+
+        loop {
+            let batch = self.batch.as_mut()?;
+            let Some(project_path) = batch.queue.pop_front() else {
+                self.is_compiling = false;
+                return self.batch.take();
+            };
+
+            let header = format!("[{}/{}] {}", batch.results.len() + 1, batch.total, project_label(&project_path));
+            let action = batch.action.clone();
+            batch.current = Some((project_path.clone(), Instant::now()));
+            match self.start_compilation_for(project_path, action, Some(header)) {
+                Ok(()) => return None,
+                Err(e) => {
+                    self.push_output_line(e.to_string());
+                    self.record_batch_result(Err(e));
+                }
+            }
+        }
+    }
+
+    // Add the outcome of the batch's running project to its results
+    pub fn record_batch_result(&mut self, result: Result<String, HadouError>) {
+        let Some((project, started)) = self.batch.as_mut().and_then(|batch| batch.current.take()) else {
+            return;
+        };
+
+        let success = result.is_ok();
+        // Set apart like the headers, so the simulation summary doesn't count it
+        self.push_output_line(format!(
+            "──── {} in {:.1}s ────",
+            if success { "PASS" } else { "FAIL" },
+            started.elapsed().as_secs_f64()
+        ));
+        let message = match result {
+            Ok(message) => message,
+            Err(e) => e.to_string(),
+        };
+        if let Some(batch) = self.batch.as_mut() {
+            batch.results.push(BatchResult { project, success, message });
+        }
+    }
+
+    // Steps None (as written) → 0 → 1 → ... → MAX_DUMP_DEPTH → None
+    pub fn cycle_dump_depth(&mut self) {
+        self.dump_depth = match self.dump_depth {
+            None => Some(0),
+            Some(depth) if depth < MAX_DUMP_DEPTH => Some(depth + 1),
+            Some(_) => None,
+        };
+    }
+
+    // Before a run that simulates a Verilog project, give a picked testbench
+    // its own VCD and rewrite the $dumpvars depth. Returns lines for the
+    // output describing what happened.
+    fn prepare_testbench(&self, project_path: &Path, action: &CompileAction) -> Result<Vec<String>, HadouError> {
+        if !action.simulates() || Language::detect(project_path) != Some(Language::Verilog) {
+            return Ok(Vec::new());
+        }
+
+        let mut notes: Vec<String> = testbench::separate_dumpfile(project_path)?.into_iter().collect();
+        if self.skip_dump {
+            notes.extend(self.dump_skip_note(project_path));
         } else {
-            Err(format!(
-                "{} failed with exit code: {}\nOutput: {}{}",
-                action.description(),
-                output.status.code().unwrap_or(-1),
-                stdout,
-                if !stderr.is_empty() { format!("\nErrors: {}", stderr) } else { String::new() }
-            ).into())
+            notes.extend(self.apply_dump_depth(project_path)?);
         }
+        Ok(notes)
     }
 
-    fn command_exists(&self, command: &str) -> bool {
-        Command::new("which")
-            .arg(command)
-            .output()
-            .map(|output| output.status.success())
-            .unwrap_or_else(|_| {
-                if cfg!(target_os = "windows") {
-                    Command::new("where")
-                        .arg(command)
-                        .output()
-                        .map(|output| output.status.success())
-                        .unwrap_or(false)
-                } else {
-                    false
+    fn dump_skip_note(&self, project_path: &Path) -> Vec<String> {
+        let mut notes = vec![format!("Waveform dump off: compiling with -D{}", verilog::NO_DUMP_DEFINE)];
+        let testbench_name = testbench::selected(project_path);
+        let guarded = fs::read_to_string(project_path.join(&testbench_name)).is_ok_and(|source| verilog::has_dump_guard(&source));
+        if !guarded {
+            notes.push(format!(
+                "warning: {} has no `ifndef {} around its $dumpfile and $dumpvars, so it still writes a VCD",
+                testbench_name,
+                verilog::NO_DUMP_DEFINE
+            ));
+        }
+        notes
+    }
+
+    // Rewrite the testbench's $dumpvars depth. Returns a line for the output
+    // describing what happened.
+    fn apply_dump_depth(&self, project_path: &Path) -> Result<Option<String>, HadouError> {
+        let Some(depth) = self.dump_depth else {
+            return Ok(None);
+        };
+
+        let testbench_name = testbench::selected(project_path);
+        let testbench_path = project_path.join(&testbench_name);
+        let Ok(testbench) = fs::read_to_string(&testbench_path) else {
+            return Ok(None);
+        };
+
+        match verilog::set_dump_depth(&testbench, depth) {
+            Some(updated) if updated == testbench => Ok(None),
+            Some(updated) => {
+                let backup = safe_write::write_with_backup(&testbench_path, &updated)?;
+                let mut note = format!("Set the $dumpvars depth in {} to {}", testbench_name, verilog::describe_dump_depth(depth));
+                if let Some(backup) = backup {
+                    note.push_str(&format!(" (previous version saved to {})", backup.display()));
                 }
-            })
+                Ok(Some(note))
+            }
+            None => Ok(Some(format!("warning: {} has no $dumpvars(<depth>, ...) call to set the depth of", testbench_name))),
+        }
+    }
+
+    pub fn batch_summary(results: &[BatchResult]) -> String {
+        let passed = results.iter().filter(|result| result.success).count();
+        let failed = results.len() - passed;
+
+        let mut summary = format!("{} passed, {} failed\n", passed, failed);
+
+        for result in results {
+            let project_name = result.project
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy();
+            let status = if result.success { "PASS" } else { "FAIL" };
+            summary.push_str(&format!("\n{} {}", status, project_name));
+        }
+
+        let failing: Vec<String> = results
+            .iter()
+            .filter(|result| !result.success)
+            .map(|result| result.project.file_name().unwrap_or_default().to_string_lossy().to_string())
+            .collect();
+        if !failing.is_empty() {
+            summary.push_str(&format!("\n\nFailing projects: {}", failing.join(", ")));
+        }
+
+        summary
+    }
+
+    // Fail before starting `just` (or `make`) when the build file lacks the
+    // action's recipe, naming the recipes it does have. Build files that
+    // can't be read fully are left for the tool to judge.
+    fn check_recipe(&mut self, project_path: &Path, action: &CompileAction) -> Result<(), HadouError> {
+        let Some(recipes) = self.recipes.recipes(project_path) else {
+            return Ok(());
+        };
+
+        let recipe = action.as_just_recipe();
+        if recipes.iter().any(|available| available == recipe) {
+            return Ok(());
+        }
+
+        Err(HadouError::MissingRecipe { recipe: recipe.to_string(), available: recipes.to_vec() })
+    }
+
+    // Run a recipe picked from the Justfile in place of a missing one
+    // Run an action on a project that needn't be listed or selected, as the
+    // command line does
+    pub fn start_action(&mut self, project_path: PathBuf, action: CompileAction) -> Result<(), HadouError> {
+        self.start_compilation_for(project_path, action, None)
+    }
+
+    pub fn run_recipe(&mut self, project_path: PathBuf, recipe: String) -> Result<(), HadouError> {
+        self.start_compilation_for(project_path, CompileAction::Custom(recipe), None)
+    }
+
+    // The `just` or `make` invocation for an action, with the project's .env
+    // applied. Both kinds of run and the command preview start from this.
+    fn build_command(&self, system: BuildSystem, project_dir: &Path, action: &CompileAction) -> Command {
+        let mut command = Command::new(system.program());
+        // A picked testbench goes in as TEST_FILE=... ahead of the recipe,
+        // which both just and make read as a variable override
+        command
+            .current_dir(project_dir)
+            .args(testbench::build_overrides(project_dir))
+            .arg(action.as_just_recipe());
+        // Set ahead of the .env so a HADOU_IVERILOG_FLAGS there still wins
+        if let Some(flags) = self.iverilog_flags(project_dir) {
+            command.env(project_config::IVERILOG_FLAGS_VAR, flags);
+        }
+        if let Some(sources) = extra_sources(project_dir) {
+            command.env(project_config::EXTRA_SOURCES_VAR, sources);
+        }
+        self.env_for(project_dir).apply(&mut command);
+        command
+    }
+
+    // Flags passed to the build file through HADOU_IVERILOG_FLAGS: the
+    // project's own with its include directories, plus -DNO_DUMP in fast mode
+    fn iverilog_flags(&self, project_dir: &Path) -> Option<String> {
+        let mut flags = ProjectConfig::load(project_dir).map(|config| config.compiler_flag_list()).unwrap_or_default();
+        if self.skip_dump && Language::detect(project_dir) == Some(Language::Verilog) {
+            flags.push(format!("-D{}", verilog::NO_DUMP_DEFINE));
+        }
+        (!flags.is_empty()).then(|| flags.join(" "))
+    }
+
+    // Shell command the selected action would run, for previewing it
+    pub fn command_preview(&self) -> Option<String> {
+        let project_path = self.get_selected_project_path()?;
+        let action = self.get_selected_action()?;
+        let system = self.build_system(project_path).unwrap_or_default();
+        Some(command_line::render(&self.build_command(system, project_path, action)))
+    }
+
+    // How long a run of `action` on the project may take, if it is limited
+    fn timeout_for(&self, project_path: &Path, action: &CompileAction) -> Option<Duration> {
+        if !action.simulates() {
+            return None;
+        }
+
+        let name = project_path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let seconds = self.project_timeouts.get(&name).copied().unwrap_or(self.simulation_timeout);
+        (seconds > 0).then(|| Duration::from_secs(seconds))
+    }
+
+    // Projects matching the search query, best match first
+    pub fn visible_projects(&self) -> Vec<(usize, FuzzyMatch)> {
+        self.search.filter_projects(&self.projects)
+    }
+
+    pub fn selection_visible(&self) -> bool {
+        self.search.project_visible(&self.projects, self.selected_project_index)
+    }
+
+    pub fn select_best_match(&mut self) {
+        if let Some(index) = self.search.best_project(&self.projects) {
+            self.selected_project_index = index;
+        }
+        self.sync_selection();
     }
 
     pub fn move_project_selection_up(&mut self) {
-        if !self.projects.is_empty() {
-            self.selected_project_index = if self.selected_project_index == 0 {
-                self.projects.len() - 1
-            } else {
-                self.selected_project_index - 1
-            };
+        if let Some(index) = fuzzy::step_selection(&self.visible_projects(), self.selected_project_index, false) {
+            self.selected_project_index = index;
         }
+        self.sync_selection();
     }
 
     pub fn move_project_selection_down(&mut self) {
-        if !self.projects.is_empty() {
-            self.selected_project_index = (self.selected_project_index + 1) % self.projects.len();
+        if let Some(index) = fuzzy::step_selection(&self.visible_projects(), self.selected_project_index, true) {
+            self.selected_project_index = index;
         }
+        self.sync_selection();
     }
 
     pub fn move_action_selection_up(&mut self) {
@@ -281,6 +1340,7 @@ impl ProjectCompiler {
     pub fn get_selected_project_name(&self) -> Option<String> {
         if self.selected_project_index < self.projects.len() {
             self.projects[self.selected_project_index]
+                .path
                 .file_name()
                 .and_then(|name| name.to_str())
                 .map(|s| s.to_string())
@@ -289,9 +1349,21 @@ impl ProjectCompiler {
         }
     }
 
+    // Select the project at `path` if it is still listed
+    pub fn select_project(&mut self, path: &Path) -> bool {
+        match self.projects.iter().position(|entry| entry.path == path) {
+            Some(index) => {
+                self.selected_project_index = index;
+                self.sync_selection();
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn get_selected_project_path(&self) -> Option<&PathBuf> {
         if self.selected_project_index < self.projects.len() {
-            Some(&self.projects[self.selected_project_index])
+            Some(&self.projects[self.selected_project_index].path)
         } else {
             None
         }
@@ -305,6 +1377,21 @@ impl ProjectCompiler {
         }
     }
 
+    // Mark or unmark the selected project, then move on to the next one
+    pub fn toggle_mark(&mut self) {
+        if let Some(path) = self.get_selected_project_path().cloned()
+            && !self.marked_projects.remove(&path)
+        {
+            self.marked_projects.insert(path);
+        }
+        self.move_project_selection_down();
+    }
+
+    // Marks on projects that are still listed
+    pub fn marked_count(&self) -> usize {
+        self.projects.iter().filter(|entry| self.marked_projects.contains(&entry.path)).count()
+    }
+
     pub fn has_projects(&self) -> bool {
         !self.projects.is_empty()
     }
@@ -317,13 +1404,393 @@ impl ProjectCompiler {
         &self.compilation_output
     }
 
+    // Have just parse the selected project's justfile and list what it said
+    // in the output pane
+    pub fn validate_justfile(&mut self) -> Result<JustfileStatus, HadouError> {
+        if self.is_compiling {
+            return Err(HadouError::AlreadyRunning);
+        }
+        let project = self.get_selected_project_path().cloned().ok_or(HadouError::NoSelection("project"))?;
+        let status = justfile::validate(&project)?;
+        let summary = self.summarize(&project);
+        self.justfile_checks.insert(project.clone(), (summary.justfile_modified, status.clone()));
+        self.summaries.insert(project.clone(), summary);
+
+        self.clear_compilation_output();
+        self.push_output_line(format!(
+            "──── Validate Justfile of {} at {} ────",
+            project_label(&project),
+            chrono::Local::now().format("%H:%M:%S")
+        ));
+        match &status {
+            JustfileStatus::Valid(recipes) => self.push_output_line(format!("Justfile parses. Recipes: {}", recipes.join(", "))),
+            JustfileStatus::Invalid(message) => {
+                for line in message.lines() {
+                    self.push_output_line(line.to_string());
+                }
+            }
+        }
+        Ok(status)
+    }
+
+    // Verdict of the last validation, while the justfile is unchanged since
+    pub fn justfile_status(&self, project: &Path) -> Option<&JustfileStatus> {
+        let (checked, status) = self.justfile_checks.get(project)?;
+        (self.summary(project)?.justfile_modified == *checked).then_some(status)
+    }
+
+    // Output lines with the phase each was printed in
+    pub fn tagged_output(&self) -> impl DoubleEndedIterator<Item = (&str, OutputPhase)> + ExactSizeIterator {
+        self.compilation_output.iter().map(String::as_str).zip(self.output_phases.iter().copied())
+    }
+
+    pub fn simulation_line_count(&self) -> usize {
+        self.output_phases.iter().filter(|phase| **phase == OutputPhase::Simulation).count()
+    }
+
     pub fn clear_compilation_output(&mut self) {
         self.compilation_output.clear();
+        self.output_phases.clear();
+        self.output_phase = OutputPhase::Build;
+    }
+
+    // Write the output buffer to a timestamped log with a header describing
+    // the run. Relative log directories are resolved against the project.
+    pub fn save_output_log(&self, log_dir: Option<&str>) -> Result<PathBuf, HadouError> {
+        if self.compilation_output.is_empty() {
+            return Err(HadouError::Other("There is no compile output to save".to_string()));
+        }
+
+        let (project_path, action, exit_code, duration) = match (&self.running, &self.last_summary) {
+            (Some(running), _) => (
+                &running.project_path,
+                &running.action,
+                "still running".to_string(),
+                running.started.elapsed(),
+            ),
+            (None, Some(summary)) => (
+                &summary.project_path,
+                &summary.action,
+                summary.exit_code.map(|code| code.to_string()).unwrap_or_else(|| "unknown".to_string()),
+                summary.duration,
+            ),
+            (None, None) => return Err(HadouError::Other("The output does not belong to a finished run".to_string())),
+        };
+
+        let directory = match log_dir.filter(|dir| !dir.is_empty()) {
+            Some(dir) => project_path.join(dir),
+            None => project_path.clone(),
+        };
+        fs::create_dir_all(&directory)?;
+
+        let now = chrono::Local::now();
+        let log_path = directory.join(format!("build-{}.log", now.format("%Y-%m-%d-%H%M%S")));
+
+        let program = self.build_system(project_path).unwrap_or_default().program();
+        let mut command = format!("{} {}", program, action.as_just_recipe());
+        if let Some(plusargs) = self.env_for(project_path).plusargs() {
+            command.push_str(&format!(" ({}={})", env_file::PLUSARGS_VAR, plusargs));
+        }
+        if let Some(flags) = self.iverilog_flags(project_path) {
+            command.push_str(&format!(" ({}={})", project_config::IVERILOG_FLAGS_VAR, flags));
+        }
+        if let Some(sources) = extra_sources(project_path) {
+            command.push_str(&format!(" ({}={})", project_config::EXTRA_SOURCES_VAR, sources));
+        }
+
+        let mut content = format!(
+            "# Hadou build log\n# Project:   {}\n# Command:   {}\n# Exit code: {}\n# Duration:  {:.2}s\n# Saved:     {}\n\n",
+            project_path.display(),
+            command,
+            exit_code,
+            duration.as_secs_f64(),
+            now.format("%Y-%m-%d %H:%M:%S"),
+        );
+        for line in &self.compilation_output {
+            content.push_str(line);
+            content.push('\n');
+        }
+
+        fs::write(&log_path, content)?;
+        Ok(log_path)
+    }
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+// Files the generated `clean` recipes delete: <PROJECT_NAME>.vvp, the VCD
+// the run is told to write and GHDL's work library
+fn clean_targets(project_path: &Path) -> Vec<String> {
+    vec![
+        format!("{}.vvp", testbench::build_project_name(project_path)),
+        testbench::vcd_file(project_path),
+        "work-obj08.cf".to_string(),
+    ]
+}
+
+// Append a finished run to the project's history. Best effort: a project
+// that can't be written to just goes without one.
+// The project's extra source patterns, passed to the build file through
+// HADOU_EXTRA_SOURCES
+fn extra_sources(project_dir: &Path) -> Option<String> {
+    let config = ProjectConfig::load(project_dir).ok()?;
+    (!config.sources.is_empty()).then(|| config.sources.join(" "))
+}
+
+fn record_history(summary: &RunSummary, success: bool) {
+    if summary.action.builds() {
+        let entry = HistoryEntry::new(summary.action.as_just_recipe(), success, summary.exit_code, summary.duration);
+        let _ = history::record(&summary.project_path, entry);
+    }
+}
+
+fn spawn_line_reader<R: Read + Send + 'static>(reader: R, sender: Sender<String>) -> JoinHandle<()> {
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    })
+}
+
+// `just` starts iverilog and vvp as children of its own. Giving the run a
+// process group of its own lets a timeout stop all of them, not just `just`.
+fn own_process_group(command: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    #[cfg(not(unix))]
+    let _ = command;
+}
+
+fn kill_process_group(child: &mut Child) {
+    #[cfg(unix)]
+    {
+        // The group id is the child's pid, see `own_process_group`
+        if unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) } == 0 {
+            return;
+        }
+    }
+    #[cfg(windows)]
+    {
+        let killed = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &child.id().to_string()])
+            .output()
+            .is_ok_and(|output| output.status.success());
+        if killed {
+            return;
+        }
     }
+    let _ = child.kill();
 }
 
 impl Default for ProjectCompiler {
     fn default() -> Self {
-        Self::new()
+        Self::new(ScanOptions::default())
+    }
+}
+
+fn justfile_modified(project: &Path) -> Option<SystemTime> {
+    fs::metadata(justfile::find_justfile(project)?).ok()?.modified().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simulator_output_is_told_apart_from_the_build_log() {
+        let lines = [
+            "Compiling Verilog files...",
+            "iverilog -o main.vvp main.v main_test.v",
+            "Running simulation...",
+            "vvp main.vvp",
+            "VCD info: dumpfile main_test.vcd opened for output.",
+            "t=10 out=1",
+            "Simulation completed. VCD file: main_test.vcd",
+        ];
+
+        let mut current = OutputPhase::Build;
+        let phases: Vec<OutputPhase> = lines
+            .iter()
+            .map(|line| {
+                let (phase, next) = OutputPhase::classify(current, line);
+                current = next;
+                phase
+            })
+            .collect();
+
+        assert_eq!(
+            phases,
+            [
+                OutputPhase::Build,
+                OutputPhase::Build,
+                OutputPhase::Build,
+                OutputPhase::Build,
+                OutputPhase::Simulation,
+                OutputPhase::Simulation,
+                OutputPhase::Build,
+            ]
+        );
+    }
+
+    #[test]
+    fn marks_follow_the_list_and_skip_projects_no_longer_listed() {
+        let root = env::temp_dir().join(format!("hadou-marks-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for name in ["alu", "counter", "uart"] {
+            fs::create_dir_all(root.join(name)).unwrap();
+            fs::write(root.join(name).join("main.v"), "").unwrap();
+        }
+
+        let mut compiler = ProjectCompiler::new(ScanOptions::default());
+        compiler.current_directory = root.clone();
+        compiler.refresh_projects();
+        assert_eq!(compiler.project_count(), 3);
+
+        compiler.toggle_mark();
+        compiler.toggle_mark();
+        assert_eq!(compiler.marked_count(), 2);
+        compiler.move_project_selection_up();
+        compiler.toggle_mark();
+        assert_eq!(compiler.marked_count(), 1);
+
+        compiler.toggle_mark();
+        fs::remove_dir_all(root.join(compiler.projects[0].path.file_name().unwrap())).unwrap();
+        compiler.refresh_projects();
+        assert_eq!(compiler.marked_count(), 1);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn batches_run_one_project_per_poll_and_carry_on_past_failures() {
+        let root = env::temp_dir().join(format!("hadou-batch-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for name in ["alu", "counter", "uart"] {
+            fs::create_dir_all(root.join(name)).unwrap();
+            fs::write(root.join(name).join("main.v"), "").unwrap();
+        }
+        fs::write(root.join("alu/Makefile"), "compile:\n\t@echo built alu\n").unwrap();
+        fs::write(root.join("uart/Makefile"), "compile:\n\t@exit 3\n").unwrap();
+
+        let mut compiler = ProjectCompiler::new(ScanOptions::default());
+        compiler.current_directory = root.clone();
+        compiler.refresh_projects();
+        compiler.execute_batch().unwrap();
+        // Nothing runs until the first poll, and the key handler returns at once
+        assert!(compiler.is_compiling && compiler.running_compilation().is_none());
+        assert!(matches!(compiler.execute_batch(), Err(HadouError::AlreadyRunning)));
+
+        let started = Instant::now();
+        let batch = loop {
+            if let Some(batch) = compiler.poll_batch() {
+                break batch;
+            }
+            assert!(compiler.is_compiling);
+            if let Some(result) = compiler.poll_compilation() {
+                compiler.record_batch_result(result);
+            }
+            assert!(started.elapsed() < Duration::from_secs(20), "batch never finished");
+            thread::sleep(Duration::from_millis(10));
+        };
+
+        assert!(!compiler.is_compiling && compiler.batch.is_none());
+        let outcomes: Vec<(String, bool)> = batch.results.iter().map(|result| (project_label(&result.project), result.success)).collect();
+        assert_eq!(outcomes, vec![("alu".to_string(), true), ("counter".to_string(), false), ("uart".to_string(), false)]);
+        let output = compiler.compilation_output.join("\n");
+        assert!(output.contains("──── [1/3] alu ────\nbuilt alu"));
+        assert!(output.contains("──── [3/3] uart ────"));
+        // The selected project's history is re-read once its run has finished
+        assert_eq!(compiler.details.history.len(), 1);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn details_are_read_once_per_selection_and_artifacts_split_by_clean() {
+        let root = env::temp_dir().join(format!("hadou-artifacts-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let project = root.join("alu");
+        fs::create_dir_all(project.join("obj_dir")).unwrap();
+        fs::write(project.join("main.v"), "").unwrap();
+        fs::write(project.join("Justfile"), "PROJECT_NAME := \"alu8\"\n").unwrap();
+        for name in ["alu8.vvp", "alu8.vcd", "old_run.vcd"] {
+            fs::write(project.join(name), "x").unwrap();
+        }
+
+        let mut compiler = ProjectCompiler::new(ScanOptions::default());
+        compiler.current_directory = root.clone();
+        compiler.refresh_projects();
+        let cleaned = |compiler: &ProjectCompiler| -> Vec<(String, bool)> {
+            compiler
+                .details
+                .artifacts
+                .iter()
+                .map(|artifact| (artifact.path.file_name().unwrap().to_string_lossy().to_string(), artifact.removed_by_clean))
+                .collect()
+        };
+        let expected = vec![
+            ("alu8.vcd".to_string(), true),
+            ("alu8.vvp".to_string(), true),
+            ("obj_dir".to_string(), false),
+            ("old_run.vcd".to_string(), false),
+        ];
+        assert_eq!(cleaned(&compiler), expected);
+
+        fs::remove_file(project.join("old_run.vcd")).unwrap();
+        compiler.move_project_selection_down();
+        assert_eq!(cleaned(&compiler), expected);
+        compiler.refresh_projects();
+        assert_eq!(cleaned(&compiler).len(), 3);
+
+        // Notes and .hadou.toml are read with the rest, not on every frame
+        assert_eq!(compiler.details.notes, None);
+        fs::write(project.join(notes::NOTES_FILE), "check the carry\n").unwrap();
+        fs::write(project.join(project_config::PROJECT_CONFIG_FILE), "iverilog_flags = [\"-g2012\"]\n").unwrap();
+        assert_eq!(compiler.details.notes, None);
+        compiler.reload_details();
+        assert_eq!(compiler.details.notes.as_deref(), Some("check the carry"));
+        assert_eq!(compiler.details.config.as_ref().unwrap().iverilog_flags, vec!["-g2012"]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn project_summaries_are_worked_out_when_the_list_is_scanned() {
+        let root = env::temp_dir().join(format!("hadou-summaries-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("alu")).unwrap();
+        fs::write(root.join("alu/main.v"), "").unwrap();
+        fs::write(root.join("alu/Makefile"), "compile:\n").unwrap();
+
+        let mut compiler = ProjectCompiler::new(ScanOptions::default());
+        compiler.current_directory = root.clone();
+        compiler.refresh_projects();
+        let project = root.join("alu");
+        let summary = compiler.summary(&project).unwrap();
+        assert_eq!((summary.language, summary.source_files.len(), summary.build_system), (Language::Verilog, 1, Some(BuildSystem::Make)));
+
+        fs::write(project.join("main_test.v"), "").unwrap();
+        assert_eq!(compiler.summary(&project).unwrap().source_files.len(), 1);
+        compiler.refresh_projects();
+        assert_eq!(compiler.summary(&project).unwrap().source_files.len(), 2);
+        fs::remove_dir_all(root).unwrap();
     }
 }
@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::HadouError;
+use crate::sim_results;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    // Catppuccin flavor identifier (latte, frappe, macchiato, mocha)
+    pub flavor: Option<String>,
+    // How many directory levels below the working directory to scan
+    pub scan_depth: usize,
+    // Descend into symlinked directories while scanning; off so a link
+    // pointing back up the tree can't make a scan run away
+    pub follow_symlinks: bool,
+    // Glob patterns for directories and files skipped while scanning
+    pub ignore: Vec<String>,
+    // Replace emoji icons with plain ASCII labels
+    pub ascii_mode: bool,
+    // Command used to open projects, taking precedence over $EDITOR
+    pub editor: Option<String>,
+    // Open a project in the editor as soon as it has been created
+    pub open_after_create: bool,
+    // Start of the names F2 fills in on the create screen, before a number
+    pub name_prefix: String,
+    // "terminal" or "gui" when the editor kind can't be guessed from its name
+    pub editor_kind: Option<String>,
+    // "workspace" or "files" to override how the editor is given the project
+    pub editor_open: Option<String>,
+    // iverilog-compatible compiler written into generated Justfiles
+    pub simulator: Option<String>,
+    // External waveform viewer tried before the built-in list
+    pub viewer: Option<String>,
+    // Ask which installed viewer to open when no viewer is set above.
+    // Turned off, the first viewer found is opened.
+    pub viewer_picker: bool,
+    // Record the lines the built-in viewer's parser skips, for its parse
+    // warnings panel
+    pub vcd_parse_warnings: bool,
+    // Viewer picked most recently, highlighted first the next time
+    pub last_viewer: Option<String>,
+    // Order of the project and VCD lists: name, modified or size
+    pub sort: Option<String>,
+    // Absolute paths of projects pinned to the top of the project lists
+    pub pinned: Vec<String>,
+    // Base directories Hadou was pointed at, newest first
+    pub recent_dirs: Vec<String>,
+    // Where saved compile logs go, relative to the project unless absolute
+    pub log_dir: Option<String>,
+    // Check each project's main.v with `iverilog -t null` on the edit screen
+    pub syntax_check: bool,
+    // Re-scan the lists when files are added or removed below the working
+    // directory. Off by default since watching a large tree is not free.
+    pub watch: bool,
+    // Seconds a simulating run may take before it is stopped, so a
+    // testbench without $finish can't run forever. 0 never stops it.
+    pub simulation_timeout: u64,
+    // Per-project overrides of the above, keyed by project directory name
+    pub project_timeouts: HashMap<String, u64>,
+    // Text marking a passed or failed check in a testbench's output, counted
+    // into the summary above the simulation output
+    pub pass_markers: Vec<String>,
+    pub fail_markers: Vec<String>,
+    // Keys for the shared actions, e.g. navigate_up = ["Up", "e"]. Actions
+    // left out keep their default keys.
+    pub keys: HashMap<String, Vec<String>>,
+    // Why config.toml on disk could not be loaded. Saving is refused while
+    // set so the defaults used instead can't overwrite the user's file.
+    #[serde(skip)]
+    pub load_error: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            flavor: None,
+            scan_depth: 1,
+            follow_symlinks: false,
+            ignore: vec![
+                "**/target".to_string(),
+                "**/.git".to_string(),
+                "**/obj_dir".to_string(),
+                "**/node_modules".to_string(),
+            ],
+            ascii_mode: false,
+            editor: None,
+            open_after_create: false,
+            name_prefix: "proj_".to_string(),
+            editor_kind: None,
+            editor_open: None,
+            simulator: None,
+            viewer: None,
+            viewer_picker: true,
+            vcd_parse_warnings: false,
+            last_viewer: None,
+            sort: None,
+            pinned: Vec::new(),
+            recent_dirs: Vec::new(),
+            log_dir: None,
+            syntax_check: true,
+            watch: false,
+            simulation_timeout: 60,
+            project_timeouts: HashMap::new(),
+            pass_markers: sim_results::default_pass_markers(),
+            fail_markers: sim_results::default_fail_markers(),
+            keys: HashMap::new(),
+            load_error: None,
+        }
+    }
+}
+
+// Oldest recent directories are forgotten past this many
+const MAX_RECENT_DIRS: usize = 10;
+
+impl Config {
+    pub fn load() -> Self {
+        Self::config_path().map(|path| Self::load_from(&path)).unwrap_or_default()
+    }
+
+    // A missing file gives the defaults; one that can't be read or parsed
+    // gives the defaults with `load_error` set
+    pub fn load_from(path: &Path) -> Self {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => return Self::unloadable(path, &e.to_string()),
+        };
+
+        match toml::from_str(&content) {
+            Ok(config) => config,
+            Err(e) => Self::unloadable(path, e.message()),
+        }
+    }
+
+    fn unloadable(path: &Path, reason: &str) -> Self {
+        Self {
+            load_error: Some(format!("{} could not be loaded: {}", path.display(), reason.trim_end())),
+            ..Self::default()
+        }
+    }
+
+    pub fn save(&self) -> Result<(), HadouError> {
+        let path = Self::config_path().ok_or_else(|| HadouError::Other("Could not determine config directory".to_string()))?;
+        self.save_to(&path)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), HadouError> {
+        if let Some(reason) = &self.load_error {
+            return Err(HadouError::ConfigUnreadable(reason.clone()));
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    // Move `dir` to the front of the recent directories
+    pub fn remember_dir(&mut self, dir: &Path) {
+        let dir = dir.to_string_lossy().to_string();
+        self.recent_dirs.retain(|recent| *recent != dir);
+        self.recent_dirs.insert(0, dir);
+        self.recent_dirs.truncate(MAX_RECENT_DIRS);
+    }
+
+    pub fn config_path() -> Option<PathBuf> {
+        Self::config_dir().map(|dir| dir.join("config.toml"))
+    }
+
+    pub fn config_dir() -> Option<PathBuf> {
+        if cfg!(target_os = "windows") {
+            return env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join("hadou"));
+        }
+
+        if let Some(dir) = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+            return Some(PathBuf::from(dir).join("hadou"));
+        }
+
+        env::var_os("HOME").map(|home| PathBuf::from(home).join(".config").join("hadou"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_unparsable_file_is_never_overwritten() {
+        let dir = env::temp_dir().join(format!("hadou_config_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+
+        assert!(Config::load_from(&path).load_error.is_none());
+
+        fs::write(&path, "scan_depth = \"deep\"\n").unwrap();
+        let mut config = Config::load_from(&path);
+        assert!(config.load_error.as_deref().is_some_and(|e| e.contains("config.toml")));
+        assert_eq!(config.scan_depth, 1);
+
+        config.flavor = Some("latte".to_string());
+        assert!(matches!(config.save_to(&path), Err(HadouError::ConfigUnreadable(_))));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "scan_depth = \"deep\"\n");
+
+        fs::write(&path, "scan_depth = 3\n").unwrap();
+        let config = Config::load_from(&path);
+        assert!(config.load_error.is_none());
+        assert_eq!(config.scan_depth, 3);
+        config.save_to(&path).unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,74 +1,684 @@
+use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
+
+use crate::build_system::BuildSystem;
+use crate::edit_project;
+use crate::error::HadouError;
+use crate::file_template::FileTemplate;
+use crate::language::Language;
+use crate::project_config;
+use crate::verilog::{self, ModuleDecl, Port, PortDirection};
+
+const DEFAULT_CLOCK_PERIOD: &str = "10";
+const DEFAULT_DUMP_DEPTH: &str = "0";
+
+// Device names Windows reserves in every directory, whatever the case
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+// Output directories of other tools. Scans skip them by default, so a
+// project by one of these names would never show up in the lists.
+const BUILD_DIRECTORIES: [&str; 3] = ["obj_dir", "target", "node_modules"];
+
+// Why a project name can't be used
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NameProblem {
+    Empty,
+    // `.` or `..`, which name existing directories
+    DotName,
+    Characters,
+    Reserved,
+    Numeric,
+    BuildDirectory,
+    // Fine as a directory, but not as the Verilog module named after it
+    ModuleName,
+}
+
+impl NameProblem {
+    pub fn message(&self, name: &str) -> String {
+        match self {
+            NameProblem::Empty => "Project name cannot be empty".to_string(),
+            NameProblem::DotName => format!("'{}' refers to an existing directory and can't be a project name", name),
+            NameProblem::Characters => format!(
+                "Invalid Project name '{}'. Use only alphanumeric characters, underscores and hyphens",
+                name
+            ),
+            NameProblem::Reserved => format!("'{}' is a reserved device name on Windows", name),
+            NameProblem::Numeric => format!("'{}' is only digits, which can't be used as a Verilog module name", name),
+            NameProblem::BuildDirectory => format!(
+                "'{}' is the name of a build directory other tools use, and is skipped when scanning for projects",
+                name
+            ),
+            NameProblem::ModuleName => format!(
+                "'{}' can't be used as a Verilog module name. Start with a letter or underscore and use only letters, digits and underscores",
+                name
+            ),
+        }
+    }
+}
+
+// Input fields on the create screen, in the order Tab cycles through them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CreateField {
+    Name,
+    BuildSystem,
+    ExtraFiles,
+    TargetDirectory,
+    DumpDepth,
+    OpenInEditor,
+    Language,
+    ClockPeriod,
+    ClockEdge,
+    ResetLevel,
+}
+
+impl CreateField {
+    pub const ALL: [CreateField; 10] = [
+        CreateField::Name,
+        CreateField::BuildSystem,
+        CreateField::ExtraFiles,
+        CreateField::TargetDirectory,
+        CreateField::DumpDepth,
+        CreateField::OpenInEditor,
+        CreateField::Language,
+        CreateField::ClockPeriod,
+        CreateField::ClockEdge,
+        CreateField::ResetLevel,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            CreateField::Name => "Project Name",
+            CreateField::BuildSystem => "Build With",
+            CreateField::ExtraFiles => "Extra Files (1/2/3)",
+            CreateField::TargetDirectory => "Target Directory (optional)",
+            CreateField::DumpDepth => "$dumpvars Depth (0 = all)",
+            CreateField::OpenInEditor => "Then Open in Editor",
+            CreateField::Language => "Language",
+            CreateField::ClockPeriod => "Clock Period (ns)",
+            CreateField::ClockEdge => "Active Clock Edge",
+            CreateField::ResetLevel => "Reset",
+        }
+    }
+
+    // Toggle fields switch between fixed values instead of taking text
+    pub fn is_toggle(&self) -> bool {
+        matches!(
+            self,
+            CreateField::BuildSystem
+                | CreateField::ExtraFiles
+                | CreateField::OpenInEditor
+                | CreateField::Language
+                | CreateField::ClockEdge
+                | CreateField::ResetLevel
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClockEdge {
+    Rising,
+    Falling,
+}
+
+impl ClockEdge {
+    pub fn description(&self) -> &'static str {
+        match self {
+            ClockEdge::Rising => "rising (posedge)",
+            ClockEdge::Falling => "falling (negedge)",
+        }
+    }
+
+    fn keyword(&self) -> &'static str {
+        match self {
+            ClockEdge::Rising => "posedge",
+            ClockEdge::Falling => "negedge",
+        }
+    }
+
+    fn vhdl_function(&self) -> &'static str {
+        match self {
+            ClockEdge::Rising => "rising_edge",
+            ClockEdge::Falling => "falling_edge",
+        }
+    }
+
+    // Start the clock on the inactive level so the first transition is an active edge
+    fn initial_level(&self) -> u8 {
+        match self {
+            ClockEdge::Rising => 0,
+            ClockEdge::Falling => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResetLevel {
+    ActiveHigh,
+    ActiveLow,
+}
+
+impl ResetLevel {
+    pub fn description(&self) -> &'static str {
+        match self {
+            ResetLevel::ActiveHigh => "rst, active-high",
+            ResetLevel::ActiveLow => "rst_n, active-low",
+        }
+    }
+
+    pub fn signal_name(&self) -> &'static str {
+        match self {
+            ResetLevel::ActiveHigh => "rst",
+            ResetLevel::ActiveLow => "rst_n",
+        }
+    }
+
+    fn edge_keyword(&self) -> &'static str {
+        match self {
+            ResetLevel::ActiveHigh => "posedge",
+            ResetLevel::ActiveLow => "negedge",
+        }
+    }
+
+    fn active_value(&self) -> u8 {
+        match self {
+            ResetLevel::ActiveHigh => 1,
+            ResetLevel::ActiveLow => 0,
+        }
+    }
+
+    fn condition(&self) -> &'static str {
+        match self {
+            ResetLevel::ActiveHigh => "rst",
+            ResetLevel::ActiveLow => "!rst_n",
+        }
+    }
+}
+
+// A project described on one line, for creating it without going through
+// the form:
+//
+//   alu name=alu8 in=a:8,b:8 out=result:8,carry:1
+//
+// The first word is the project name. `name=` sets the module name, which
+// defaults to the project name. `in=`, `out=` and `inout=` list ports as
+// `port` or `port:width` and may be repeated; a missing width means 1 bit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuickSpec {
+    pub project_name: String,
+    pub module_name: String,
+    pub ports: Vec<Port>,
+}
+
+impl QuickSpec {
+    // Errors are phrased for showing under the input while it is typed
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut words = spec.split_whitespace();
+        let project_name = words.next().ok_or("Start with the project name")?.to_string();
+
+        if project_name.contains('=') {
+            return Err("Start with the project name, then the key=value parts".to_string());
+        }
+        ProjectCreator::check_project_name(&project_name).map_err(|e| e.to_string())?;
+
+        let mut module_name = None;
+        let mut ports: Vec<Port> = Vec::new();
+
+        for word in words {
+            let (key, value) = word
+                .split_once('=')
+                .ok_or_else(|| format!("Expected key=value, found '{}'", word))?;
+
+            let direction = match key {
+                "name" => {
+                    if !verilog::is_identifier(value) {
+                        return Err(format!("'{}' is not a valid module name", value));
+                    }
+                    module_name = Some(value.to_string());
+                    continue;
+                }
+                "in" => PortDirection::Input,
+                "out" => PortDirection::Output,
+                "inout" => PortDirection::Inout,
+                _ => return Err(format!("Unknown key '{}'. Use name=, in=, out= or inout=", key)),
+            };
+
+            for item in value.split(',').filter(|item| !item.is_empty()) {
+                let port = parse_spec_port(item, direction)?;
+                if ports.iter().any(|existing| existing.name == port.name) {
+                    return Err(format!("Port '{}' is listed twice", port.name));
+                }
+                ports.push(port);
+            }
+        }
+
+        let module_name = match module_name {
+            Some(name) => name,
+            None if verilog::is_identifier(&project_name) => project_name.clone(),
+            None => return Err(format!("'{}' is not a valid module name; add name=<module>", project_name)),
+        };
+        if ports.iter().any(|port| port.name == module_name) {
+            return Err(format!("Port '{}' has the same name as the module", module_name));
+        }
+
+        Ok(Self { project_name, module_name, ports })
+    }
+
+    pub fn port_count(&self, direction: PortDirection) -> usize {
+        self.ports.iter().filter(|port| port.direction == direction).count()
+    }
+}
+
+// `name` or `name:width`
+fn parse_spec_port(item: &str, direction: PortDirection) -> Result<Port, String> {
+    let (name, width) = match item.split_once(':') {
+        Some((name, width)) => {
+            let width = width
+                .parse::<usize>()
+                .ok()
+                .filter(|width| *width > 0)
+                .ok_or_else(|| format!("Width of '{}' must be a positive whole number", name))?;
+            (name, width)
+        }
+        None => (item, 1),
+    };
+
+    if !verilog::is_identifier(name) {
+        return Err(format!("'{}' is not a valid port name", name));
+    }
+
+    Ok(Port {
+        name: name.to_string(),
+        direction,
+        width: (width > 1).then(|| format!("[{}:0]", width - 1)),
+        dimensions: None,
+    })
+}
 
 #[derive(Debug)]
 pub struct ProjectCreator {
     pub project_name: String,
+    // Directory the project is created in, relative to the working directory
+    // unless absolute. Empty means the working directory itself.
+    pub target_directory: String,
+    pub language: Language,
+    pub focused_field: CreateField,
+    // First argument of the testbench's $dumpvars call
+    pub dump_depth: String,
+    pub clock_period: String,
+    pub clock_edge: ClockEdge,
+    pub reset_level: ResetLevel,
+    pub simulator: String,
+    // Kept across resets, like the simulator, since it is a preference
+    pub build_system: BuildSystem,
+    // Open the project in the editor once created; starts out as the
+    // open_after_create setting
+    pub open_in_editor: bool,
+    // Optional files written next to the design, in FileTemplate::ALL order
+    pub extra_files: Vec<FileTemplate>,
+    // Set while creating from a one-line spec, replacing the clocked template
+    quick_spec: Option<QuickSpec>,
+    // Pasted Verilog written as main.v instead of the template
+    imported: Option<ImportedModule>,
+}
+
+// Verilog source brought in from the clipboard, with its top module
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedModule {
+    pub source: String,
+    pub module: ModuleDecl,
 }
 
 impl ProjectCreator {
     pub fn new() -> Self {
         Self { 
-            project_name: String::new() 
+            project_name: String::new(),
+            target_directory: String::new(),
+            language: Language::Verilog,
+            focused_field: CreateField::Name,
+            dump_depth: DEFAULT_DUMP_DEPTH.to_string(),
+            clock_period: DEFAULT_CLOCK_PERIOD.to_string(),
+            clock_edge: ClockEdge::Rising,
+            reset_level: ResetLevel::ActiveHigh,
+            simulator: "iverilog".to_string(),
+            build_system: BuildSystem::Just,
+            open_in_editor: false,
+            extra_files: Vec::new(),
+            quick_spec: None,
+            imported: None,
         }
     }
 
     pub fn reset(&mut self) {
         self.project_name.clear();
+        self.target_directory.clear();
+        self.language = Language::Verilog;
+        self.focused_field = CreateField::Name;
+        self.dump_depth = DEFAULT_DUMP_DEPTH.to_string();
+        self.clock_period = DEFAULT_CLOCK_PERIOD.to_string();
+        self.clock_edge = ClockEdge::Rising;
+        self.reset_level = ResetLevel::ActiveHigh;
+        self.extra_files.clear();
+        self.quick_spec = None;
+        self.imported = None;
+    }
+
+    pub fn focus_next_field(&mut self) {
+        let index = CreateField::ALL.iter().position(|field| *field == self.focused_field).unwrap_or(0);
+        self.focused_field = CreateField::ALL[(index + 1) % CreateField::ALL.len()];
+    }
+
+    pub fn focus_previous_field(&mut self) {
+        let index = CreateField::ALL.iter().position(|field| *field == self.focused_field).unwrap_or(0);
+        self.focused_field = CreateField::ALL[(index + CreateField::ALL.len() - 1) % CreateField::ALL.len()];
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        match self.focused_field {
+            CreateField::Name if c.is_alphanumeric() || c == '_' || c == '-' => self.project_name.push(c),
+            CreateField::TargetDirectory if !c.is_control() => self.target_directory.push(c),
+            CreateField::DumpDepth if c.is_ascii_digit() => self.dump_depth.push(c),
+            CreateField::ClockPeriod if c.is_ascii_digit() || c == '.' => self.clock_period.push(c),
+            // 1, 2 and 3 pick the templates one by one
+            CreateField::ExtraFiles if c.is_ascii_digit() => {
+                let index = c.to_digit(10).unwrap_or(0) as usize;
+                if let Some(template) = index.checked_sub(1).and_then(|index| FileTemplate::ALL.get(index)) {
+                    self.toggle_extra_file(*template);
+                }
+            }
+            CreateField::BuildSystem | CreateField::OpenInEditor | CreateField::Language | CreateField::ClockEdge | CreateField::ResetLevel if c == ' ' => {
+                self.toggle_focused_field()
+            }
+            _ => {}
+        }
+    }
+
+    // Pasted text goes through the same per-character rules as typing.
+    // Toggles ignore it rather than flipping once per pasted space.
+    pub fn paste(&mut self, text: &str) {
+        if self.focused_field.is_toggle() {
+            return;
+        }
+        for c in text.chars() {
+            self.push_char(c);
+        }
+    }
+
+    pub fn pop_char(&mut self) {
+        match self.focused_field {
+            CreateField::Name => self.project_name.pop(),
+            CreateField::TargetDirectory => self.target_directory.pop(),
+            CreateField::DumpDepth => self.dump_depth.pop(),
+            CreateField::ClockPeriod => self.clock_period.pop(),
+            CreateField::BuildSystem
+            | CreateField::ExtraFiles
+            | CreateField::OpenInEditor
+            | CreateField::Language
+            | CreateField::ClockEdge
+            | CreateField::ResetLevel => None,
+        };
+    }
+
+    pub fn toggle_focused_field(&mut self) {
+        match self.focused_field {
+            CreateField::BuildSystem => self.build_system = self.build_system.next(),
+            CreateField::OpenInEditor => self.open_in_editor = !self.open_in_editor,
+            // All or nothing; the digit keys pick single templates
+            CreateField::ExtraFiles => {
+                self.extra_files = if self.extra_files.is_empty() { FileTemplate::ALL.to_vec() } else { Vec::new() };
+            }
+            CreateField::Language => self.language = self.language.next(),
+            CreateField::ClockEdge => {
+                self.clock_edge = match self.clock_edge {
+                    ClockEdge::Rising => ClockEdge::Falling,
+                    ClockEdge::Falling => ClockEdge::Rising,
+                };
+            }
+            CreateField::ResetLevel => {
+                self.reset_level = match self.reset_level {
+                    ResetLevel::ActiveHigh => ResetLevel::ActiveLow,
+                    ResetLevel::ActiveLow => ResetLevel::ActiveHigh,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    pub fn field_value(&self, field: CreateField) -> String {
+        match field {
+            CreateField::Name => self.project_name.clone(),
+            CreateField::BuildSystem => self.build_system.description().to_string(),
+            CreateField::TargetDirectory => self.target_directory.clone(),
+            CreateField::Language => self.language.description().to_string(),
+            CreateField::DumpDepth => self.dump_depth.clone(),
+            CreateField::OpenInEditor => if self.open_in_editor { "yes" } else { "no" }.to_string(),
+            CreateField::ExtraFiles => FileTemplate::ALL
+                .iter()
+                .map(|template| {
+                    let mark = if self.extra_files.contains(template) { "x" } else { " " };
+                    format!("[{}] {}", mark, template.label())
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+            CreateField::ClockPeriod => self.clock_period.clone(),
+            CreateField::ClockEdge => self.clock_edge.description().to_string(),
+            CreateField::ResetLevel => self.reset_level.description().to_string(),
+        }
+    }
+
+    pub fn toggle_extra_file(&mut self, template: FileTemplate) {
+        if self.extra_files.contains(&template) {
+            self.extra_files.retain(|extra| *extra != template);
+        } else {
+            self.extra_files.push(template);
+            self.extra_files.sort_by_key(|extra| FileTemplate::ALL.iter().position(|t| t == extra));
+        }
+    }
+
+    // Extra files that will be written for the chosen language
+    pub fn extra_file_names(&self) -> Vec<String> {
+        self.extra_files
+            .iter()
+            .filter(|template| template.applies_to(self.language))
+            .map(|template| template.file_name(&self.project_name))
+            .collect()
+    }
+
+    pub fn parsed_clock_period(&self) -> Option<f64> {
+        self.clock_period
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|period| period.is_finite() && *period > 0.0)
+    }
+
+    pub fn parsed_dump_depth(&self) -> Option<u32> {
+        self.dump_depth.trim().parse().ok()
+    }
+
+    // Absolute path the project will be created at
+    pub fn resolved_path(&self) -> PathBuf {
+        normalize_path(&self.resolved_target().join(&self.project_name))
+    }
+
+    fn resolved_target(&self) -> PathBuf {
+        let base = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let target = self.target_directory.trim();
+
+        let target_path = if target == "~" || target.starts_with("~/") {
+            let home = env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+            home.join(target.trim_start_matches('~').trim_start_matches('/'))
+        } else {
+            PathBuf::from(target)
+        };
+
+        normalize_path(&base.join(target_path))
+    }
+
+    // Name the project `<prefix>NNN` with the next number free in the target
+    // directory; the name can still be edited afterwards
+    pub fn auto_name(&mut self, prefix: &str) {
+        self.project_name = next_auto_name(&self.resolved_target(), prefix);
+    }
+
+    // Scaffold the project around pasted Verilog: the source becomes main.v
+    // and the testbench instantiates its top module, whose name is offered
+    // as the project name
+    pub fn import_module(&mut self, source: &str) -> Result<&ModuleDecl, HadouError> {
+        let has_end = verilog::contains_identifier(&verilog::strip_comments(source), "endmodule");
+        let module = edit_project::top_module(source)
+            .filter(|_| has_end)
+            .ok_or_else(|| HadouError::Other("The pasted text has no complete module ... endmodule".to_string()))?;
+
+        self.language = Language::Verilog;
+        self.quick_spec = None;
+        self.project_name = module.name.clone();
+        let source = if source.ends_with('\n') { source.to_string() } else { format!("{}\n", source) };
+        Ok(&self.imported.insert(ImportedModule { source, module }).module)
+    }
+
+    pub fn imported_module(&self) -> Option<&ModuleDecl> {
+        self.imported.as_ref().map(|imported| &imported.module)
     }
 
-    pub fn create_project(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
-        if self.project_name.is_empty() {
-            return Err("Project name cannot be empty".into());
+    pub fn clear_import(&mut self) {
+        self.imported = None;
+    }
+
+    pub fn create_project(&self) -> Result<PathBuf, HadouError> {
+        Self::check_project_name(&self.project_name)?;
+
+        if self.imported.is_some() && self.language == Language::Vhdl {
+            return Err(HadouError::Other("A pasted Verilog module can only start a Verilog project".to_string()));
         }
 
-        if !self.is_valid_project_name(&self.project_name) {
-            return Err("Invalid Project name. Use only alphanumeric characters, underscores and hyphens".into());
+        // Entity names can't contain hyphens or repeated underscores
+        if self.language == Language::Vhdl && !is_vhdl_identifier(&self.project_name) {
+            return Err(HadouError::Other(
+                "VHDL project names must be valid identifiers: letters, digits and single underscores, no hyphens".to_string(),
+            ));
         }
 
-        let project_path = PathBuf::from(&self.project_name);
+        // The generated design and testbench modules are named after the
+        // project; pasted and quick-spec modules bring names of their own
+        if self.language == Language::Verilog && self.imported.is_none() && self.quick_spec.is_none() {
+            Self::check_module_name(&self.project_name)?;
+        }
+
+        if self.parsed_clock_period().is_none() {
+            return Err(HadouError::Other("Clock period must be a positive number of nanoseconds".to_string()));
+        }
+
+        if self.parsed_dump_depth().is_none() {
+            return Err(HadouError::Other("$dumpvars depth must be a whole number (0 dumps all levels)".to_string()));
+        }
+
+        let project_path = self.resolved_path();
 
         if project_path.exists() {
-            return Err(format!("Directory {} already exists", self.project_name).into());
+            return Err(HadouError::ProjectExists(project_path));
         }
 
-        fs::create_dir_all(&project_path)?;
+        // Every existing ancestor must be a directory for create_dir_all to succeed
+        if let Some(blocking) = project_path.ancestors().skip(1).find(|ancestor| ancestor.exists() && !ancestor.is_dir()) {
+            return Err(HadouError::Other(format!("{} exists and is not a directory", blocking.display())));
+        }
 
-        // Create main.v file
-        let main_v_path = project_path.join("main.v");
-        let main_v_content = self.generate_main_v_content();
-        fs::write(&main_v_path, main_v_content)?;
+        fs::create_dir_all(&project_path)?;
 
-        // Create main_test.v file
-        let main_test_v_path = project_path.join("main_test.v");
-        let main_test_v_content = self.generate_testbench_content();
-        fs::write(&main_test_v_path, main_test_v_content)?;
+        let (design, testbench, build_file) = match (self.language, self.build_system) {
+            (Language::Verilog, system) => (
+                self.generate_main_v_content(),
+                self.generate_testbench_content(),
+                generate_build_file(system, &self.project_name, &self.simulator, ""),
+            ),
+            (Language::Vhdl, BuildSystem::Just) => (self.generate_vhdl_design(), self.generate_vhdl_testbench(), generate_ghdl_justfile(&self.project_name)),
+            (Language::Vhdl, BuildSystem::Make) => (self.generate_vhdl_design(), self.generate_vhdl_testbench(), self.generate_ghdl_makefile()),
+        };
 
-        // Create a simple Justfile for easy compilation
-        let justfilee_path = project_path.join("Justfile");
-        let justfile_contents = self.generate_justfile();
-        fs::write(&justfilee_path, justfile_contents)?;
+        // Design, testbench and a Justfile or Makefile for easy compilation
+        fs::write(project_path.join(self.language.design_file()), design)?;
+        fs::write(project_path.join(self.language.testbench_file()), testbench)?;
+        fs::write(project_path.join(self.build_system.file_name()), build_file)?;
+        for template in self.extra_files.iter().filter(|template| template.applies_to(self.language)) {
+            fs::write(project_path.join(template.file_name(&self.project_name)), template.content(&self.project_name))?;
+        }
 
         Ok(project_path.canonicalize()?)
     }
 
-    fn is_valid_project_name(&self, name: &str) -> bool {
-        !name.is_empty()
-        && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
-        && !name.starts_with('-')
-        && !name.starts_with('_')
+    // Create the project described by a quick-create spec in the working
+    // directory. The form is cleared either way.
+    pub fn create_from_spec(&mut self, spec: &str) -> Result<PathBuf, HadouError> {
+        let spec = QuickSpec::parse(spec).map_err(HadouError::Other)?;
+
+        self.reset();
+        self.project_name = spec.project_name.clone();
+        self.quick_spec = Some(spec);
+
+        let result = self.create_project();
+        self.reset();
+        result
+    }
+
+    pub fn check_project_name(name: &str) -> Result<(), HadouError> {
+        let problem = if name.is_empty() {
+            Some(NameProblem::Empty)
+        } else if name == "." || name == ".." {
+            Some(NameProblem::DotName)
+        } else if !name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+            || name.starts_with('-')
+            || name.starts_with('_')
+        {
+            Some(NameProblem::Characters)
+        } else if RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(name)) {
+            Some(NameProblem::Reserved)
+        } else if name.chars().all(|c| c.is_ascii_digit()) {
+            Some(NameProblem::Numeric)
+        } else if BUILD_DIRECTORIES.iter().any(|directory| directory.eq_ignore_ascii_case(name)) {
+            Some(NameProblem::BuildDirectory)
+        } else {
+            None
+        };
+
+        match problem {
+            Some(problem) => Err(HadouError::InvalidProjectName { name: name.to_string(), problem }),
+            None => Ok(()),
+        }
+    }
+
+    // For names that become a Verilog module as well as a directory
+    pub fn check_module_name(name: &str) -> Result<(), HadouError> {
+        Self::check_project_name(name)?;
+        if verilog::is_identifier(name) {
+            Ok(())
+        } else {
+            Err(HadouError::InvalidProjectName { name: name.to_string(), problem: NameProblem::ModuleName })
+        }
     }
 
     fn generate_main_v_content(&self) -> String {
+        if let Some(spec) = &self.quick_spec {
+            return generate_spec_module(spec);
+        }
+        if let Some(imported) = &self.imported {
+            return imported.source.clone();
+        }
+
+        let reset = self.reset_level;
         format!(
 r#"//////////////////////////////////////////////////////////////////////////////////
 // Company: 
 // Engineer: 
 // 
-// Create Date: {}
-// Design Name: {}
-// Module Name: {}
-// Project Name: {}
+// Create Date: {date}
+// Design Name: {name}
+// Module Name: {name}
+// Project Name: {name}
 // Target Devices: 
 // Tool Versions: 
 // Description: 
@@ -81,32 +691,71 @@ r#"/////////////////////////////////////////////////////////////////////////////
 // 
 //////////////////////////////////////////////////////////////////////////////////
 
-module {} (
+`timescale 1ns / 1ps
+
+module {name} (
+    input wire clk,
+    input wire {rst}
 );
 
+    always @({clock_edge} clk or {reset_edge} {rst}) begin
+        if ({reset_condition}) begin
+            // Reset logic
+        end else begin
+            // Sequential logic
+        end
+    end
+
 endmodule
 "#,
-            chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
-            self.project_name,
-            self.project_name,
-            self.project_name,
-            self.project_name,
+            date = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+            name = self.project_name,
+            rst = reset.signal_name(),
+            clock_edge = self.clock_edge.keyword(),
+            reset_edge = reset.edge_keyword(),
+            reset_condition = reset.condition(),
         )
     }
 
     fn generate_testbench_content(&self) -> String {
+        if let Some(imported) = &self.imported {
+            let module = &imported.module;
+            return generate_testbench(
+                &module.name,
+                &module.parameters,
+                &module.ports,
+                &format!("{}.vcd", self.project_name),
+                self.parsed_dump_depth().unwrap_or(0),
+            );
+        }
+        if let Some(spec) = &self.quick_spec {
+            return generate_testbench(
+                &spec.module_name,
+                &[],
+                &spec.ports,
+                &format!("{}.vcd", spec.project_name),
+                self.parsed_dump_depth().unwrap_or(0),
+            );
+        }
+
+        let period = self.parsed_clock_period().unwrap_or(10.0);
+        let reset = self.reset_level;
+        let ports = [
+            Port { name: "clk".to_string(), direction: PortDirection::Input, width: None, dimensions: None },
+            Port { name: reset.signal_name().to_string(), direction: PortDirection::Input, width: None, dimensions: None },
+        ];
         format!(
 r#"//////////////////////////////////////////////////////////////////////////////////
 // Company: 
 // Engineer: 
 // 
-// Create Date: {}
-// Design Name: {}_testbench
-// Module Name: {}_test
-// Project Name: {}
+// Create Date: {date}
+// Design Name: {name}_testbench
+// Module Name: {name}_test
+// Project Name: {name}
 // Target Devices: 
 // Tool Versions: 
-// Description: Testbench for {}
+// Description: Testbench for {name}
 // 
 // Dependencies: 
 // 
@@ -116,34 +765,443 @@ r#"/////////////////////////////////////////////////////////////////////////////
 // 
 //////////////////////////////////////////////////////////////////////////////////
 
-module {}_test;
-    
+`timescale 1ns / 1ps
 
-    {} uut (
+module {name}_test;
+{declarations}
+
+{instance}
+
+    // {period} ns clock, active on the {edge_description} edge
+    initial clk = {clock_start};
+    always #{half_period} clk = ~clk;
 
-    );
     initial begin
         $display("Starting simulation...");
-        $dumpfile("{}.vcd");
-        $dumpvars(0, {}_test);
+        // Compiling with -DNO_DUMP skips the waveform for quicker runs
+        `ifndef NO_DUMP
+        $dumpfile("{name}.vcd");
+        $dumpvars({dump_depth}, {name}_test);
+        `endif
+
+        // Hold reset for two clock periods
+        {rst} = {reset_active};
+        #{reset_time} {rst} = {reset_inactive};
+
+        #{run_time} $finish;
     end
 
 endmodule
 "#,
-            chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
-            self.project_name,
-            self.project_name,
-            self.project_name,
-            self.project_name,
-            self.project_name,
-            self.project_name,
-            self.project_name,
-            self.project_name,
+            date = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+            name = self.project_name,
+            declarations = testbench_declarations(&ports),
+            dump_depth = self.parsed_dump_depth().unwrap_or(0),
+            instance = testbench_instance(&self.project_name, "uut", &ports),
+            rst = reset.signal_name(),
+            period = format_delay(period),
+            edge_description = match self.clock_edge {
+                ClockEdge::Rising => "rising",
+                ClockEdge::Falling => "falling",
+            },
+            clock_start = self.clock_edge.initial_level(),
+            half_period = format_delay(period / 2.0),
+            reset_active = reset.active_value(),
+            reset_inactive = 1 - reset.active_value(),
+            reset_time = format_delay(period * 2.0),
+            run_time = format_delay(period * 20.0),
+        )
+    }
+
+    fn generate_vhdl_design(&self) -> String {
+        let reset = self.reset_level;
+        format!(
+r#"-- Design: {name}
+-- Created: {date}
+
+library ieee;
+use ieee.std_logic_1164.all;
+use ieee.numeric_std.all;
+
+entity {name} is
+    port (
+        clk : in std_logic;
+        {rst} : in std_logic
+    );
+end entity {name};
+
+architecture rtl of {name} is
+begin
+
+    process (clk, {rst})
+    begin
+        if {rst} = '{reset_active}' then
+            -- Reset logic
+        elsif {edge}(clk) then
+            -- Sequential logic
+        end if;
+    end process;
+
+end architecture rtl;
+"#,
+            date = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+            name = self.project_name,
+            rst = reset.signal_name(),
+            reset_active = reset.active_value(),
+            edge = self.clock_edge.vhdl_function(),
+        )
+    }
+
+    fn generate_vhdl_testbench(&self) -> String {
+        let period = self.parsed_clock_period().unwrap_or(10.0);
+        let reset = self.reset_level;
+        format!(
+r#"-- Testbench for {name}
+-- Created: {date}
+
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity {name}_test is
+end entity {name}_test;
+
+architecture sim of {name}_test is
+    constant CLK_PERIOD : time := {period} ns;
+
+    signal clk : std_logic := '{clock_start}';
+    signal {rst} : std_logic := '{reset_active}';
+begin
+
+    uut : entity work.{name}
+        port map (
+            clk => clk,
+            {rst} => {rst}
+        );
+
+    -- {period} ns clock, active on the {edge_description} edge
+    clk <= not clk after CLK_PERIOD / 2;
+
+    stimulus : process
+    begin
+        report "Starting simulation...";
+
+        -- Hold reset for two clock periods
+        wait for CLK_PERIOD * 2;
+        {rst} <= '{reset_inactive}';
+
+        wait for CLK_PERIOD * 20;
+        std.env.finish;
+    end process;
+
+end architecture sim;
+"#,
+            date = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+            name = self.project_name,
+            rst = reset.signal_name(),
+            period = format_delay(period),
+            edge_description = match self.clock_edge {
+                ClockEdge::Rising => "rising",
+                ClockEdge::Falling => "falling",
+            },
+            clock_start = self.clock_edge.initial_level(),
+            reset_active = reset.active_value(),
+            reset_inactive = 1 - reset.active_value(),
         )
     }
 
-    fn generate_justfile(&self) -> String {
+    // The GHDL Justfile's targets for make
+    fn generate_ghdl_makefile(&self) -> String {
         format!(
+r#"# Makefile for {name} VHDL project
+# Generated by Hadou
+
+# Project configuration
+PROJECT_NAME = {name}
+SRC_FILE = main.vhd
+TEST_FILE = main_test.vhd
+TOP = $(PROJECT_NAME)_test
+VCD_FILE = $(PROJECT_NAME).vcd
+GHDL_FLAGS = --std=08
+# Extra run options for ghdl -r (e.g. --stop-time=1us), usually set through HADOU_PLUSARGS in .env
+HADOU_PLUSARGS ?=
+
+.PHONY: default compile simulate view clean info
+
+# Default target - compile and simulate
+default: compile simulate
+
+# Analyze the design and testbench, then elaborate the testbench
+compile:
+	@echo "Compiling VHDL files..."
+	ghdl -a $(GHDL_FLAGS) $(SRC_FILE) $(TEST_FILE)
+	ghdl -e $(GHDL_FLAGS) $(TOP)
+	@echo "Compilation completed: $(TOP)"
+
+# Run the simulation
+simulate: compile
+	@echo "Running simulation..."
+	ghdl -r $(GHDL_FLAGS) $(TOP) --vcd=$(VCD_FILE) $(HADOU_PLUSARGS)
+	@echo "Simulation completed. VCD file: $(VCD_FILE)"
+
+# View waveform (requires GTKWave)
+view: simulate
+	@echo "Opening waveform viewer..."
+	gtkwave $(VCD_FILE) &
+
+# Clean generated files
+clean:
+	@echo "Cleaning generated files..."
+	-ghdl --clean $(GHDL_FLAGS)
+	-rm -f $(VCD_FILE) work-obj08.cf
+	@echo "Clean completed."
+
+# Show project info
+info:
+	@echo "Project: $(PROJECT_NAME)"
+	@echo "Source file: $(SRC_FILE)"
+	@echo "Test file: $(TEST_FILE)"
+	@echo "Top entity: $(TOP)"
+	@echo "Output file: $(VCD_FILE)"
+"#,
+            name = self.project_name,
+        )
+    }
+}
+
+// Letters, digits and underscores, starting with a letter, without a
+// trailing or doubled underscore
+fn is_vhdl_identifier(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.ends_with('_')
+        && !name.contains("__")
+}
+
+// Testbench signals for the ports of the unit under test: a `reg` to drive
+// each input and a `wire` to observe everything else
+pub fn testbench_declarations(ports: &[Port]) -> String {
+    ports
+        .iter()
+        .map(|port| {
+            let kind = if port.direction == PortDirection::Input { "reg" } else { "wire" };
+            let dimensions = port.dimensions.as_deref().map(|dimensions| format!(" {}", dimensions)).unwrap_or_default();
+            match &port.width {
+                Some(width) => format!("    {} {} {}{};", kind, width, port.name, dimensions),
+                None => format!("    {} {}{};", kind, port.name, dimensions),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// `localparam`s for the design parameters that port ranges refer to, so the
+// testbench signals get the same widths
+pub fn testbench_parameters(parameters: &[(String, String)], ports: &[Port]) -> String {
+    parameters
+        .iter()
+        .filter(|(name, _)| {
+            ports.iter().flat_map(|port| [port.width.as_deref(), port.dimensions.as_deref()]).flatten().any(|range| {
+                range
+                    .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .any(|word| word == name)
+            })
+        })
+        .map(|(name, value)| format!("    localparam {} = {};", name, value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Instantiation of `module` with every port connected to the signal of the same name
+pub fn testbench_instance(module: &str, instance: &str, ports: &[Port]) -> String {
+    format!("    {} {}", module, testbench_connections(instance, ports))
+}
+
+// The `name (...);` part of an instantiation
+pub fn testbench_connections(instance: &str, ports: &[Port]) -> String {
+    if ports.is_empty() {
+        return format!("{} ();", instance);
+    }
+
+    let connections = ports
+        .iter()
+        .map(|port| format!("        .{}({})", port.name, port.name))
+        .collect::<Vec<_>>()
+        .join(",\n");
+
+    format!("{} (\n{}\n    );", instance, connections)
+}
+
+// Instantiation template for `module`, with each parameter set to its default
+// and each port connected to a signal of the same name. Port directions and
+// ranges are passed through as written in a comment on each connection.
+pub fn instantiation_snippet(module: &ModuleDecl, instance: &str) -> String {
+    let mut snippet = module.name.clone();
+
+    if !module.parameters.is_empty() {
+        let overrides = module
+            .parameters
+            .iter()
+            .map(|(name, value)| format!("    .{}({})", name, value))
+            .collect::<Vec<_>>()
+            .join(",\n");
+        snippet.push_str(&format!(" #(\n{}\n)", overrides));
+    }
+
+    if module.ports.is_empty() {
+        snippet.push_str(&format!(" {} ();", instance));
+        return snippet;
+    }
+
+    let connections: Vec<String> = module
+        .ports
+        .iter()
+        .enumerate()
+        .map(|(i, port)| {
+            let separator = if i + 1 < module.ports.len() { "," } else { "" };
+            format!(".{}({}){}", port.name, port.name, separator)
+        })
+        .collect();
+    let column = connections.iter().map(String::len).max().unwrap_or(0);
+
+    let lines = connections
+        .iter()
+        .zip(&module.ports)
+        .map(|(connection, port)| {
+            let mut description = port.direction.keyword().to_string();
+            for range in [&port.width, &port.dimensions].into_iter().flatten() {
+                description.push(' ');
+                description.push_str(range);
+            }
+            format!("    {:<column$} // {}", connection, description)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    snippet.push_str(&format!(" {} (\n{}\n);", instance, lines));
+    snippet
+}
+
+// Minimal testbench for an existing design: signals for every port, the
+// instance, a clock if the design has one, and a dump of all signals
+pub fn generate_testbench(
+    module: &str,
+    parameters: &[(String, String)],
+    ports: &[Port],
+    vcd_file: &str,
+    dump_depth: u32,
+) -> String {
+    let mut declarations = testbench_parameters(parameters, ports);
+    if !declarations.is_empty() {
+        declarations.push('\n');
+    }
+    declarations.push_str(&testbench_declarations(ports));
+
+    let clock = ports
+        .iter()
+        .find(|port| port.direction == PortDirection::Input && port.width.is_none() && matches!(port.name.as_str(), "clk" | "clock"))
+        .map(|port| format!("\n    initial {name} = 0;\n    always #5 {name} = ~{name};\n", name = port.name))
+        .unwrap_or_default();
+
+    format!(
+r#"`timescale 1ns / 1ps
+
+module {module}_test;
+{declarations}
+
+{instance}
+{clock}
+    initial begin
+        `ifndef NO_DUMP
+        $dumpfile("{vcd_file}");
+        $dumpvars({dump_depth}, {module}_test);
+        `endif
+
+        #100 $finish;
+    end
+
+endmodule
+"#,
+        instance = testbench_instance(module, "uut", ports),
+    )
+}
+
+// GHDL analyses the sources, elaborates the testbench entity and runs it
+// with --vcd, so the rest of Hadou sees the same recipes and dump file
+// `<prefix>001`, `<prefix>002`, ...: one past the highest number already
+// taken in `dir`, so deleting an old project never brings its name back
+pub fn next_auto_name(dir: &Path, prefix: &str) -> String {
+    let highest = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.strip_prefix(prefix)?.parse::<u32>().ok())
+        .max()
+        .unwrap_or(0);
+    format!("{}{:03}", prefix, highest + 1)
+}
+
+pub fn generate_ghdl_justfile(project_name: &str) -> String {
+    format!(
+r#"# justfile for {name} VHDL project
+# Generated by Hadou
+
+# Project configuration
+PROJECT_NAME := "{name}"
+SRC_FILE := "main.vhd"
+TEST_FILE := "main_test.vhd"
+TOP := PROJECT_NAME + "_test"
+VCD_FILE := PROJECT_NAME + ".vcd"
+GHDL_FLAGS := "--std=08"
+# Extra run options for ghdl -r (e.g. --stop-time=1us), usually set through HADOU_PLUSARGS in .env
+PLUSARGS := env_var_or_default("HADOU_PLUSARGS", "")
+
+# Default recipe - compile and simulate
+default: compile simulate
+
+# Analyze the design and testbench, then elaborate the testbench
+compile:
+    @echo "Compiling VHDL files..."
+    ghdl -a {{{{GHDL_FLAGS}}}} {{{{SRC_FILE}}}} {{{{TEST_FILE}}}}
+    ghdl -e {{{{GHDL_FLAGS}}}} {{{{TOP}}}}
+    @echo "Compilation completed: {{{{TOP}}}}"
+
+# Run the simulation
+simulate: compile
+    @echo "Running simulation..."
+    ghdl -r {{{{GHDL_FLAGS}}}} {{{{TOP}}}} --vcd={{{{VCD_FILE}}}} {{{{PLUSARGS}}}}
+    @echo "Simulation completed. VCD file: {{{{VCD_FILE}}}}"
+
+# View waveform (requires GTKWave)
+view: simulate
+    @echo "Opening waveform viewer..."
+    gtkwave {{{{VCD_FILE}}}} &
+
+# Clean generated files
+clean:
+    @echo "Cleaning generated files..."
+    -ghdl --clean {{{{GHDL_FLAGS}}}}
+    -rm -f {{{{VCD_FILE}}}} work-obj08.cf
+    @echo "Clean completed."
+
+# Show project info
+info:
+    @echo "Project: {{{{PROJECT_NAME}}}}"
+    @echo "Source file: {{{{SRC_FILE}}}}"
+    @echo "Test file: {{{{TEST_FILE}}}}"
+    @echo "Top entity: {{{{TOP}}}}"
+    @echo "Output file: {{{{VCD_FILE}}}}"
+
+# List all available recipes
+list:
+    @just --list
+"#,
+        name = project_name,
+    )
+}
+
+// Justfile driving iverilog and vvp for a Verilog project
+pub fn generate_justfile(project_name: &str, simulator: &str, iverilog_flags: &str) -> String {
+    format!(
 r#"# justfile for {} Verilog project
 # Generated by Hadou
 
@@ -153,6 +1211,12 @@ SRC_FILE := "main.v"
 TEST_FILE := "main_test.v"
 VVP_FILE := PROJECT_NAME + ".vvp"
 VCD_FILE := PROJECT_NAME + ".vcd"
+# Extra +plusargs for vvp, usually set through HADOU_PLUSARGS in .env
+PLUSARGS := env_var_or_default("HADOU_PLUSARGS", "")
+# Extra iverilog flags (-g2012, -Wall, -I, -D), set with 'f' on Hadou's compile screen
+IVERILOG_FLAGS := env_var_or_default("HADOU_IVERILOG_FLAGS", {})
+# Further sources compiled with main.v, set with 'i' on Hadou's compile screen
+{}
 
 # Default recipe - compile and simulate
 default: compile simulate
@@ -160,13 +1224,13 @@ default: compile simulate
 # Compile the design and testbench
 compile:
     @echo "Compiling Verilog files..."
-    iverilog -o {{{{VVP_FILE}}}} {{{{SRC_FILE}}}} {{{{TEST_FILE}}}}
+    {} {{{{IVERILOG_FLAGS}}}} -o {{{{VVP_FILE}}}} {{{{SRC_FILE}}}} {{{{EXTRA_SOURCES}}}} {{{{TEST_FILE}}}}
     @echo "Compilation completed: {{{{VVP_FILE}}}}"
 
 # Run the simulation
 simulate: compile
     @echo "Running simulation..."
-    vvp {{{{VVP_FILE}}}}
+    vvp {{{{VVP_FILE}}}} {{{{PLUSARGS}}}}
     @echo "Simulation completed. VCD file: {{{{VCD_FILE}}}}"
 
 # View waveform (requires GTKWave)
@@ -203,10 +1267,146 @@ help:
     @echo "  just list      - List all available recipes"
     @echo "  just help      - Show this help message"
 "#,
-            self.project_name,
-            self.project_name,
-        )
+        project_name,
+        project_name,
+        project_config::just_string(iverilog_flags),
+        project_config::justfile_sources_line(""),
+        simulator,
+    )
+}
+
+// Build file driving iverilog and vvp with either build system
+pub fn generate_build_file(system: BuildSystem, project_name: &str, simulator: &str, iverilog_flags: &str) -> String {
+    match system {
+        BuildSystem::Just => generate_justfile(project_name, simulator, iverilog_flags),
+        BuildSystem::Make => generate_makefile(project_name, simulator, iverilog_flags),
+    }
+}
+
+// The Justfile's targets for make. HADOU_* variables set in the environment
+// take precedence over the `?=` defaults, as they do in the Justfile.
+pub fn generate_makefile(project_name: &str, simulator: &str, iverilog_flags: &str) -> String {
+    format!(
+r#"# Makefile for {name} Verilog project
+# Generated by Hadou
+
+# Project configuration
+PROJECT_NAME = {name}
+SRC_FILE = main.v
+TEST_FILE = main_test.v
+VVP_FILE = $(PROJECT_NAME).vvp
+VCD_FILE = $(PROJECT_NAME).vcd
+# Extra +plusargs for vvp, usually set through HADOU_PLUSARGS in .env
+HADOU_PLUSARGS ?=
+# Extra iverilog flags (-g2012, -Wall, -I, -D), set with 'f' on Hadou's compile screen
+{flags_line}
+# Further sources compiled with main.v, set with 'i' on Hadou's compile screen
+{sources_line}
+
+.PHONY: default compile simulate view clean info help
+
+# Default target - compile and simulate
+default: compile simulate
+
+# Compile the design and testbench
+compile:
+	@echo "Compiling Verilog files..."
+	{simulator} $({flags_var}) -o $(VVP_FILE) $(SRC_FILE) $({sources_var}) $(TEST_FILE)
+	@echo "Compilation completed: $(VVP_FILE)"
+
+# Run the simulation
+simulate: compile
+	@echo "Running simulation..."
+	vvp $(VVP_FILE) $(HADOU_PLUSARGS)
+	@echo "Simulation completed. VCD file: $(VCD_FILE)"
+
+# View waveform (requires GTKWave)
+view: simulate
+	@echo "Opening waveform viewer..."
+	gtkwave $(VCD_FILE) &
+
+# Clean generated files
+clean:
+	@echo "Cleaning generated files..."
+	-rm -f $(VVP_FILE) $(VCD_FILE)
+	@echo "Clean completed."
+
+# Show project info
+info:
+	@echo "Project: $(PROJECT_NAME)"
+	@echo "Source file: $(SRC_FILE)"
+	@echo "Test file: $(TEST_FILE)"
+	@echo "Output files: $(VVP_FILE), $(VCD_FILE)"
+
+# Help - show available commands
+help:
+	@echo "Available commands:"
+	@echo "  make           - Compile and simulate (default)"
+	@echo "  make compile   - Compile Verilog files"
+	@echo "  make simulate  - Run simulation (generates VCD)"
+	@echo "  make view      - Open GTKWave to view waveform"
+	@echo "  make clean     - Remove generated files"
+	@echo "  make info      - Show project information"
+	@echo "  make help      - Show this help message"
+"#,
+        name = project_name,
+        flags_line = project_config::makefile_flags_line(iverilog_flags),
+        flags_var = project_config::IVERILOG_FLAGS_VAR,
+        sources_line = project_config::makefile_sources_line(""),
+        sources_var = project_config::EXTRA_SOURCES_VAR,
+    )
+}
+
+// Empty module with the spec's ports, left for the user to fill in
+fn generate_spec_module(spec: &QuickSpec) -> String {
+    let ports = spec.ports
+        .iter()
+        .map(|port| match &port.width {
+            Some(width) => format!("    {} wire {} {}", port.direction.keyword(), width, port.name),
+            None => format!("    {} wire {}", port.direction.keyword(), port.name),
+        })
+        .collect::<Vec<_>>()
+        .join(",\n");
+    let header = if ports.is_empty() {
+        format!("module {};", spec.module_name)
+    } else {
+        format!("module {} (\n{}\n);", spec.module_name, ports)
+    };
+
+    format!(
+r#"`timescale 1ns / 1ps
+
+{header}
+
+    // Logic
+
+endmodule
+"#,
+    )
+}
+
+// Delays are rounded to a few decimals so floating point noise (0.1 * 20
+// being 2.0000000000000004) stays out of the testbench, and written without
+// trailing zeros or a fractional part when they are whole numbers
+fn format_delay(value: f64) -> String {
+    let formatted = format!("{:.6}", value);
+    formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+// Resolve `.` and `..` lexically so the preview shows a clean path even
+// before the directories exist
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
     }
+    normalized
 }
 
 impl Default for ProjectCreator {
@@ -214,3 +1414,131 @@ impl Default for ProjectCreator {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problem(name: &str) -> Option<NameProblem> {
+        match ProjectCreator::check_project_name(name) {
+            Ok(()) => None,
+            Err(HadouError::InvalidProjectName { problem, .. }) => Some(problem),
+            Err(e) => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn ordinary_names_are_accepted() {
+        for name in ["counter", "alu-8bit", "uart_tx", "Console", "com10", "4bit_adder", "targets"] {
+            assert_eq!(problem(name), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn verilog_projects_need_names_that_are_module_names() {
+        let dir = std::env::temp_dir().join(format!("hadou-module-names-{}", std::process::id()));
+        let mut creator = ProjectCreator::new();
+        creator.target_directory = dir.display().to_string();
+
+        for name in ["alu-8bit", "4bit_adder"] {
+            let result = ProjectCreator::check_module_name(name);
+            assert!(matches!(result, Err(HadouError::InvalidProjectName { problem: NameProblem::ModuleName, .. })), "{}", name);
+
+            creator.project_name = name.to_string();
+            assert!(matches!(creator.create_project(), Err(HadouError::InvalidProjectName { .. })), "{}", name);
+        }
+        assert!(ProjectCreator::check_module_name("alu_8bit").is_ok());
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn windows_device_names_are_rejected_in_any_case() {
+        for name in ["CON", "con", "Prn", "aux", "NUL", "com1", "COM9", "lpt3"] {
+            assert_eq!(problem(name), Some(NameProblem::Reserved), "{}", name);
+        }
+    }
+
+    #[test]
+    fn dot_names_and_odd_characters_are_rejected() {
+        assert_eq!(problem("."), Some(NameProblem::DotName));
+        assert_eq!(problem(".."), Some(NameProblem::DotName));
+        assert_eq!(problem(""), Some(NameProblem::Empty));
+        for name in ["my project", "a/b", "-flag", "_hidden", ".git", "con.txt"] {
+            assert_eq!(problem(name), Some(NameProblem::Characters), "{}", name);
+        }
+    }
+
+    #[test]
+    fn numeric_and_build_directory_names_are_rejected() {
+        assert_eq!(problem("123"), Some(NameProblem::Numeric));
+        assert_eq!(problem("0"), Some(NameProblem::Numeric));
+        for name in ["obj_dir", "target", "node_modules", "Target"] {
+            assert_eq!(problem(name), Some(NameProblem::BuildDirectory), "{}", name);
+        }
+    }
+
+    #[test]
+    fn each_problem_explains_itself() {
+        let message = ProjectCreator::check_project_name("nul").unwrap_err().to_string();
+        assert!(message.contains("reserved device name"), "{}", message);
+
+        let quick = QuickSpec::parse("target in=a").unwrap_err();
+        assert!(quick.contains("build directory"), "{}", quick);
+    }
+
+    #[test]
+    fn generated_testbenches_guard_the_dump() {
+        let testbench = generate_testbench("alu", &[], &[], "alu.vcd", 2);
+        assert!(verilog::has_dump_guard(&testbench));
+        assert_eq!(verilog::dumpfile_name(&testbench).as_deref(), Some("alu.vcd"));
+        assert_eq!(verilog::dump_depth(&testbench), Some(2));
+        assert!(!verilog::has_dump_guard("`ifdef NO_DUMP\n$dumpvars(0, tb);\n`endif\n"));
+    }
+
+    #[test]
+    fn auto_names_count_up_from_the_highest_number_taken() {
+        let dir = env::temp_dir().join(format!("hadou-auto-name-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        assert_eq!(next_auto_name(&dir, "proj_"), "proj_001");
+
+        for name in ["proj_001", "proj_007", "proj_x", "scratch_042"] {
+            fs::create_dir(dir.join(name)).unwrap();
+        }
+        assert_eq!(next_auto_name(&dir, "proj_"), "proj_008");
+        assert_eq!(next_auto_name(&dir, "scratch_"), "scratch_043");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn pasted_module_becomes_the_design_with_a_testbench_around_it() {
+        let dir = env::temp_dir().join(format!("hadou-import-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut creator = ProjectCreator::new();
+        assert!(creator.import_module("module half (input a);").is_err());
+
+        let source = "// from a forum post\nmodule adder #(parameter W = 4) (input [W-1:0] a, b, output [W:0] sum);\n  assign sum = a + b;\nendmodule";
+        assert_eq!(creator.import_module(source).unwrap().ports.len(), 3);
+        assert_eq!(creator.project_name, "adder");
+
+        creator.project_name = "adder4".to_string();
+        creator.target_directory = dir.display().to_string();
+        let project = creator.create_project().unwrap();
+        assert_eq!(fs::read_to_string(project.join("main.v")).unwrap(), format!("{}\n", source));
+        let testbench = fs::read_to_string(project.join("main_test.v")).unwrap();
+        assert!(testbench.contains("adder uut (") && testbench.contains(".sum(sum)"));
+        assert_eq!(verilog::dumpfile_name(&testbench).as_deref(), Some("adder4.vcd"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn delays_are_written_without_floating_point_noise() {
+        assert_eq!(format_delay(10.0), "10");
+        assert_eq!(format_delay(0.1 * 20.0), "2");
+        assert_eq!(format_delay(2.5), "2.5");
+        assert_eq!(format_delay(0.1 + 0.2), "0.3");
+        assert_eq!(format_delay(0.125 / 2.0), "0.0625");
+    }
+}
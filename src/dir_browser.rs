@@ -0,0 +1,120 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::HadouError;
+use crate::language::Language;
+use crate::list_window::ListWindow;
+
+// Walks the directory tree to pick the base directory Hadou scans
+#[derive(Debug, Default)]
+pub struct DirectoryBrowser {
+    // Directory whose subdirectories are listed
+    pub path: PathBuf,
+    pub entries: Vec<PathBuf>,
+    pub selected: usize,
+    pub show_hidden: bool,
+    pub list_window: ListWindow,
+}
+
+impl DirectoryBrowser {
+    pub fn open(&mut self, path: &Path) -> Result<(), HadouError> {
+        let path = path.canonicalize()?;
+        let mut entries: Vec<PathBuf> = fs::read_dir(&path)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|entry| entry.is_dir())
+            .filter(|entry| self.show_hidden || !is_hidden(entry))
+            .collect();
+        entries.sort_by_key(|entry| entry.file_name().map(|name| name.to_string_lossy().to_lowercase()));
+
+        self.path = path;
+        self.entries = entries;
+        self.selected = 0;
+        Ok(())
+    }
+
+    pub fn selected_path(&self) -> Option<&PathBuf> {
+        self.entries.get(self.selected)
+    }
+
+    pub fn move_up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        if self.selected + 1 < self.entries.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn enter_selected(&mut self) -> Result<(), HadouError> {
+        match self.selected_path().cloned() {
+            Some(path) => self.open(&path),
+            None => Ok(()),
+        }
+    }
+
+    // Go to the parent directory with the one just left selected
+    pub fn go_up(&mut self) -> Result<(), HadouError> {
+        let Some(parent) = self.path.parent().map(Path::to_path_buf) else {
+            return Ok(());
+        };
+        let child = self.path.clone();
+        self.open(&parent)?;
+        if let Some(index) = self.entries.iter().position(|entry| *entry == child) {
+            self.selected = index;
+        }
+        Ok(())
+    }
+
+    pub fn toggle_hidden(&mut self) -> Result<(), HadouError> {
+        self.show_hidden = !self.show_hidden;
+        let selected = self.selected_path().cloned();
+        let path = self.path.clone();
+        self.open(&path)?;
+        if let Some(index) = selected.and_then(|selected| self.entries.iter().position(|entry| *entry == selected)) {
+            self.selected = index;
+        }
+        Ok(())
+    }
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+// Whether the directory holds a design file the project lists would pick up
+pub fn is_project(path: &Path) -> bool {
+    Language::ALL.iter().any(|language| path.join(language.design_file()).is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_subdirectories_and_returns_to_the_one_left() {
+        let root = std::env::temp_dir().join(format!("hadou-browse-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for dir in ["b_uart", "A_alu", ".git", "b_uart/rtl"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        fs::write(root.join("notes.txt"), "").unwrap();
+        let root = root.canonicalize().unwrap();
+
+        let mut browser = DirectoryBrowser::default();
+        browser.open(&root).unwrap();
+        assert_eq!(browser.entries, vec![root.join("A_alu"), root.join("b_uart")]);
+
+        browser.move_down();
+        browser.enter_selected().unwrap();
+        assert_eq!(browser.path, root.join("b_uart"));
+        browser.go_up().unwrap();
+        assert_eq!(browser.selected_path(), Some(&root.join("b_uart")));
+
+        browser.toggle_hidden().unwrap();
+        assert_eq!(browser.entries.len(), 3);
+        assert_eq!(browser.selected_path(), Some(&root.join("b_uart")));
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -1,22 +1,255 @@
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::SystemTime;
+
+use crate::adopt;
+use crate::archive;
+use crate::build_system::{self, BuildSystem};
+use crate::command_line;
+use crate::create_new_project::{self, ProjectCreator};
+use crate::error::HadouError;
+use crate::fuzzy::{self, FuzzyMatch, ListSearch};
+use crate::language::Language;
+use crate::list_window::ListWindow;
+use crate::notes;
+use crate::project_config::{self, ProjectConfig};
+use crate::safe_write;
+use crate::scanner::{self, IgnoreRules, ListEntry, ScanOptions, SortMode};
+use crate::settings::command_exists;
+use crate::syntax_check::SyntaxChecker;
+use crate::verilog::{self, ModuleDecl};
+
+// GUI editors open their own window and are left running in the background.
+// Terminal editors take over the terminal, so Hadou has to step aside first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditorKind {
+    Terminal,
+    Gui,
+}
+
+impl EditorKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "terminal" | "tui" => Some(EditorKind::Terminal),
+            "gui" => Some(EditorKind::Gui),
+            _ => None,
+        }
+    }
+
+    // Guess from the editor's executable name. Anything unknown is treated as
+    // a terminal editor, which is the safe choice since Hadou waits for it.
+    pub fn detect(editor: &str) -> Self {
+        let name = editor_name(editor);
+
+        const GUI_EDITORS: [&str; 16] = [
+            "code", "code-insiders", "codium", "subl", "sublime_text", "gedit", "gnome-text-editor", "kate",
+            "kwrite", "mousepad", "xed", "pluma", "geany", "zed", "gvim", "notepad++",
+        ];
+
+        if GUI_EDITORS.contains(&name.as_str()) || name == "notepad" {
+            return EditorKind::Gui;
+        }
+
+        // Emacs runs either way; go by whether there is a display to open a window on
+        if name == "emacs" && (env::var_os("DISPLAY").is_some() || env::var_os("WAYLAND_DISPLAY").is_some()) {
+            return EditorKind::Gui;
+        }
+
+        EditorKind::Terminal
+    }
+}
+
+// Whether the editor gets the project directory as a workspace or the list of
+// project files. Editors with a folder/workspace view default to the former.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OpenMode {
+    Workspace,
+    Files,
+}
+
+impl OpenMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "workspace" | "directory" | "dir" => Some(OpenMode::Workspace),
+            "files" => Some(OpenMode::Files),
+            _ => None,
+        }
+    }
+
+    pub fn detect(editor: &str) -> Self {
+        const WORKSPACE_EDITORS: [&str; 10] = [
+            "code", "code-insiders", "codium", "vscodium", "zed", "zeditor", "hx", "helix", "subl", "notepad++",
+        ];
+
+        if WORKSPACE_EDITORS.contains(&editor_name(editor).as_str()) {
+            OpenMode::Workspace
+        } else {
+            OpenMode::Files
+        }
+    }
+}
+
+fn editor_name(editor: &str) -> String {
+    Path::new(editor)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+// On Windows `code` and `codium` are `.cmd` shims, which Command won't find
+// by bare name since it only tries `.exe`. Ask `where` for the full path.
+fn windows_program(editor: &str) -> Option<PathBuf> {
+    if !cfg!(target_os = "windows") || Path::new(editor).extension().is_some() {
+        return None;
+    }
+
+    let output = Command::new("where").arg(editor).output().ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| PathBuf::from(line.trim()))
+        .find(|path| {
+            path.extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| ["exe", "cmd", "bat"].contains(&extension.to_lowercase().as_str()))
+        })
+}
+
+// A prepared editor command. Terminal editors must be run with the TUI
+// suspended; see `run`.
+#[derive(Debug)]
+pub struct EditorLaunch {
+    pub editor: String,
+    pub kind: EditorKind,
+    command: Command,
+}
+
+impl EditorLaunch {
+    pub fn command_line(&self) -> String {
+        command_line::render(&self.command)
+    }
+
+    pub fn run(mut self) -> Result<(), HadouError> {
+        match self.kind {
+            EditorKind::Gui => {
+                let mut child = self.command
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()?;
+
+                // Reap the process when it exits so it doesn't linger as a zombie
+                thread::spawn(move || {
+                    let _ = child.wait();
+                });
+                Ok(())
+            }
+            EditorKind::Terminal => {
+                let status = self.command.status()?;
+
+                if !status.success() {
+                    return Err(HadouError::EditorFailed { editor: self.editor, code: status.code() });
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+// Outcome of regenerating a project's testbench
+#[derive(Debug)]
+pub struct TestbenchUpdate {
+    pub module: String,
+    pub backup: Option<PathBuf>,
+    // The old file was patched in place rather than replaced wholesale
+    pub preserved_stimulus: bool,
+}
+
+// Outcome of generating a testbench for a hand-written design
+#[derive(Debug)]
+pub struct TestbenchCreation {
+    pub module: String,
+    pub backup: Option<PathBuf>,
+    // Build file written next to it; None when the project already had a
+    // Justfile or Makefile, which is left alone
+    pub build_file_created: Option<BuildSystem>,
+}
+
+// Outcome of renaming a project in place
+#[derive(Debug)]
+pub struct ProjectRename {
+    pub path: PathBuf,
+    // Files whose module, dump file or project name references changed
+    pub updated_files: Vec<PathBuf>,
+    // Their previous versions, now inside the renamed directory
+    pub backups: Vec<PathBuf>,
+}
+
+// Instantiation template for one module found in a project
+#[derive(Debug, Clone)]
+pub struct ModuleSnippet {
+    pub file: PathBuf,
+    pub module: String,
+    pub snippet: String,
+}
+
+// A line of a project file that mentions a searched-for name
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub file: PathBuf,
+    // 1-based, as editors count
+    pub line: usize,
+    pub text: String,
+}
 
 #[derive(Debug)]
 pub struct ProjectEditor {
-    pub projects: Vec<PathBuf>,
+    pub projects: Vec<ListEntry>,
     pub selected_project_index: usize,
-    pub current_directory: PathBuf
+    pub current_directory: PathBuf,
+    pub scan_options: ScanOptions,
+    pub sort_mode: SortMode,
+    // Projects listed first whatever the sort order
+    pinned: HashSet<PathBuf>,
+    // Editor from the config, used before $EDITOR and auto-detection
+    pub preferred_editor: Option<String>,
+    // Overrides GUI/terminal detection for editors that could be either
+    pub editor_kind: Option<EditorKind>,
+    // Overrides workspace/file-list detection
+    pub open_mode: Option<OpenMode>,
+    // List every regular file in a project instead of just its sources
+    pub show_all_files: bool,
+    pub search: ListSearch,
+    pub list_window: ListWindow,
+    pub syntax_checker: SyntaxChecker,
+    // NOTES.md of the selected project, read when the selection changes
+    pub notes: Option<String>,
+    notes_project: Option<PathBuf>,
 }
 
 impl ProjectEditor {
-    pub fn new() -> Self {
+    pub fn new(scan_options: ScanOptions) -> Self {
         let current_dir = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
         let mut editor = Self {
             projects: Vec::new(),
             selected_project_index: 0,
             current_directory: current_dir,
+            scan_options,
+            sort_mode: SortMode::default(),
+            pinned: HashSet::new(),
+            preferred_editor: None,
+            editor_kind: None,
+            open_mode: None,
+            show_all_files: false,
+            search: ListSearch::default(),
+            list_window: ListWindow::default(),
+            syntax_checker: SyntaxChecker::default(),
+            notes: None,
+            notes_project: None,
         };
 
         editor.scan_for_projects();
@@ -25,34 +258,73 @@ impl ProjectEditor {
     }
 
     pub fn scan_for_projects(&mut self) {
-        self.projects.clear();
         self.selected_project_index = 0;
 
-        if let Ok(entries) = fs::read_dir(&self.current_directory) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_dir() && self.is_valid_project(&path) {
-                    self.projects.push(path);
-                }
-            }
+        let mut projects = self.scan_options.find_dirs(&self.current_directory, |path| self.is_valid_project(path));
+        if adopt::is_adopted(&self.current_directory) {
+            projects.insert(0, self.current_directory.clone());
         }
+        self.projects = projects.into_iter().map(ListEntry::read).collect();
+        self.sort_projects();
 
-        self.projects.sort_by(|a, b| {
-            a.file_name()
-                .unwrap_or_default()
-                .cmp(b.file_name().unwrap_or_default())
-        });
+        if self.search.is_active() {
+            self.select_best_match();
+        }
+        self.reload_notes();
+    }
+
+    fn sync_notes(&mut self) {
+        let selected = self.get_selected_project_path().cloned();
+        if selected == self.notes_project {
+            return;
+        }
+
+        self.notes = selected.as_deref().and_then(notes::load);
+        self.notes_project = selected;
+    }
+
+    pub fn reload_notes(&mut self) {
+        self.notes_project = None;
+        self.sync_notes();
     }
 
+    // Re-sort the list, keeping the same project selected
+    pub fn set_sort_mode(&mut self, sort_mode: SortMode) {
+        let selected = self.get_selected_project_path().cloned();
+        self.sort_mode = sort_mode;
+        self.sort_projects();
+        if let Some(index) = selected.and_then(|path| self.projects.iter().position(|entry| entry.path == path)) {
+            self.selected_project_index = index;
+        }
+    }
+
+    // Pin or unpin projects, re-sorting the list around the same selection
+    pub fn set_pinned(&mut self, pinned: HashSet<PathBuf>) {
+        self.pinned = pinned;
+        self.set_sort_mode(self.sort_mode);
+    }
+
+    pub fn is_pinned(&self, project_path: &Path) -> bool {
+        self.pinned.contains(project_path)
+    }
+
+    fn sort_projects(&mut self) {
+        self.sort_mode.sort(&mut self.projects);
+        scanner::pin_to_top(&mut self.projects, &self.pinned);
+    }
+
+    // A project has a main design file in one of the supported languages
     pub fn is_valid_project(&self, dir_path: &Path) -> bool {
-        let main_v_path = dir_path.join("main.v");
-        main_v_path.exists() && main_v_path.is_file()
+        Language::ALL
+            .iter()
+            .any(|language| dir_path.join(language.design_file()).is_file())
     }
 
     pub fn get_project_files(&self, project_path: &Path) -> Vec<PathBuf> {
         let mut files = Vec::new();
 
-        let essential_files = ["main.v", "main_test.v", "Justfile", "justfile"];
+        let language = Language::detect(project_path).unwrap_or_default();
+        let essential_files = [language.design_file(), language.testbench_file()];
 
         for file_name in &essential_files {
             let file_path = project_path.join(file_name);
@@ -60,109 +332,447 @@ impl ProjectEditor {
                 files.push(file_path);
             }
         }
+        if let Some((_, build_file)) = build_system::find_build_file(project_path) {
+            files.push(build_file);
+        }
+        let notes_file = notes::notes_path(project_path);
+        if notes_file.is_file() {
+            files.push(notes_file);
+        }
 
-        // Add any other .v files in the directory
+        // Add any other source files in the directory, or with
+        // `show_all_files` everything else too (constraints, scripts,
+        // memory init files, dotfiles)
+        let mut others = Vec::new();
         if let Ok(entries) = fs::read_dir(project_path) {
             for entry in entries.flatten() {
                 let path = entry.path();
-                if path.is_file() {
-                    if let Some(extension) = path.extension() {
-                        if extension == "v" {
-                            let file_name = path.file_name().unwrap().to_string_lossy();
-                            if file_name != "main.v" && file_name != "main_test.v" {
-                                files.push(path);
-                            }
-                        }
-                    }
+                let wanted = self.show_all_files || Language::of_file(&path).is_some();
+                if path.is_file() && wanted && !files.contains(&path) {
+                    others.push(path);
                 }
             }
         }
+        others.sort();
+        files.extend(others);
 
         files
     }
 
-    pub fn open_project_in_editor(&self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn open_project_in_editor(&self) -> Result<EditorLaunch, HadouError> {
         if self.projects.is_empty() {
-            return Err("No Verilog projects found in current directory".into());
+            return Err(HadouError::NoProjects);
         }
 
         if self.selected_project_index >= self.projects.len() {
-            return Err("Invalid Project Selection".into());
+            return Err(HadouError::NoSelection("project"));
         }
 
-        let project_path = &self.projects[self.selected_project_index];
+        self.open_path_in_editor(&self.projects[self.selected_project_index].path)
+    }
+
+    // Open the project at `project_path`, listed or not (a project that was
+    // just created may sit outside the scanned directories)
+    pub fn open_path_in_editor(&self, project_path: &Path) -> Result<EditorLaunch, HadouError> {
         let files_to_edit = self.get_project_files(project_path);
 
         if files_to_edit.is_empty() {
-            return Err("No Editable files found".into());
+            return Err(HadouError::Other("No Editable files found".to_string()));
         } 
 
-        self.launch_editor(&files_to_edit, project_path)?;
-        Ok(())
+        self.build_editor_command(&files_to_edit, project_path)
     }
 
-    fn launch_editor(&self, files: &[PathBuf], project_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
-        let editor = self.detect_editor()?;
+    pub fn duplicate_selected_project(&self, new_name: &str) -> Result<PathBuf, HadouError> {
+        let source = self.get_selected_project_path().ok_or(HadouError::NoSelection("project"))?;
+        // Copying it would copy every project below it as well
+        if *source == self.current_directory {
+            return Err(HadouError::Other("An adopted base directory can't be duplicated".to_string()));
+        }
 
-        let mut command = Command::new(&editor);
+        ProjectCreator::check_project_name(new_name)?;
 
-        command.current_dir(project_dir);   // Change to project directory
+        let old_name = source
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| HadouError::Other("Invalid project directory name".to_string()))?
+            .to_string();
+        let target = source.with_file_name(new_name);
+
+        if target.exists() {
+            return Err(HadouError::ProjectExists(target));
+        }
+
+        let rules = IgnoreRules::new(source, archive::excluded_patterns(&self.scan_options.ignore_patterns));
+        copy_project_dir(source, &target, &[rules])?;
+        for (path, content) in renamed_project_files(&target, &old_name, new_name)? {
+            fs::write(path, content)?;
+        }
+
+        Ok(target)
+    }
+
+    // Rename the selected project's directory along with its design and
+    // testbench modules, the dump file they write and the build file's
+    // PROJECT_NAME. Rewritten files are backed up first.
+    pub fn rename_selected_project(&self, new_name: &str) -> Result<ProjectRename, HadouError> {
+        let source = self.get_selected_project_path().ok_or(HadouError::NoSelection("project"))?;
+        if *source == self.current_directory {
+            return Err(HadouError::Other("An adopted base directory can't be renamed from inside Hadou".to_string()));
+        }
+
+        ProjectCreator::check_project_name(new_name)?;
+
+        let old_name = source
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| HadouError::Other("Invalid project directory name".to_string()))?
+            .to_string();
+        if old_name == new_name {
+            return Err(HadouError::Other(format!("The project is already called '{}'", new_name)));
+        }
+        let target = source.with_file_name(new_name);
+
+        // A name differing only in case is the same directory on
+        // case-insensitive file systems
+        if target.exists() && !old_name.eq_ignore_ascii_case(new_name) {
+            return Err(HadouError::ProjectExists(target));
+        }
+
+        // Work out every change before touching anything, so a file that
+        // can't be read leaves the project as it was
+        let updates = renamed_project_files(source, &old_name, new_name)?;
+        fs::rename(source, &target)?;
+
+        let mut rename = ProjectRename {
+            path: target.clone(),
+            updated_files: Vec::new(),
+            backups: Vec::new(),
+        };
+        for (path, content) in updates {
+            let path = target.join(path.strip_prefix(source).unwrap_or(&path));
+            rename.backups.extend(safe_write::write_with_backup(&path, &content)?);
+            rename.updated_files.push(path);
+        }
+
+        Ok(rename)
+    }
+
+    // Rebuild the top module's instantiation in main_test.v from the ports
+    // currently declared in main.v. Stimulus is kept when the old testbench
+    // already instantiates the module; otherwise a fresh one is written. The
+    // previous file is backed up first.
+    pub fn regenerate_testbench(&self) -> Result<TestbenchUpdate, HadouError> {
+        let project_path = self.get_selected_project_path().ok_or(HadouError::NoSelection("project"))?;
+        require_verilog(project_path, "Regenerating the testbench")?;
+        let top = design_top_module(project_path)?;
+
+        let testbench_path = project_path.join("main_test.v");
+        let old_testbench = fs::read_to_string(&testbench_path).ok();
+
+        let existing_instance = old_testbench.as_deref().and_then(|content| {
+            verilog::find_instances(content, std::slice::from_ref(&top.name))
+                .into_iter()
+                .next()
+                .map(|instance| (content, instance))
+        });
+
+        let (content, preserved_stimulus) = match existing_instance {
+            Some((content, instance)) => {
+                let declared = verilog::declared_signals(content);
+                let missing: Vec<_> = top.ports
+                    .iter()
+                    .filter(|port| !declared.contains(&port.name))
+                    .cloned()
+                    .collect();
+                let missing_parameters: Vec<_> = top.parameters
+                    .iter()
+                    .filter(|(name, _)| !declared.contains(name))
+                    .cloned()
+                    .collect();
+
+                // New declarations go on the lines just above the instance,
+                // whose parameter overrides are left as they are
+                let line_start = content[..instance.span.0].rfind('\n').map(|i| i + 1).unwrap_or(0);
+
+                let mut additions = create_new_project::testbench_parameters(&missing_parameters, &missing);
+                if !missing.is_empty() {
+                    if !additions.is_empty() {
+                        additions.push('\n');
+                    }
+                    additions.push_str(&create_new_project::testbench_declarations(&missing));
+                }
+
+                let mut updated = String::with_capacity(content.len());
+                updated.push_str(&content[..line_start]);
+                if !additions.is_empty() {
+                    updated.push_str(&additions);
+                    updated.push_str("\n\n");
+                }
+                updated.push_str(&content[line_start..instance.name_offset]);
+                updated.push_str(&create_new_project::testbench_connections(&instance.name, &top.ports));
+                updated.push_str(&content[instance.span.1..]);
+                (updated, true)
+            }
+            None => {
+                let project_name = build_system::find_build_file(project_path)
+                    .and_then(|(_, path)| fs::read_to_string(path).ok())
+                    .and_then(|build_file| justfile_project_name(&build_file))
+                    .unwrap_or_else(|| project_label(project_path));
+                let vcd_file = format!("{}.vcd", project_name);
+                // Keep the dump depth the old testbench used
+                let dump_depth = old_testbench.as_deref().and_then(verilog::dump_depth).unwrap_or(0);
+                (create_new_project::generate_testbench(&top.name, &top.parameters, &top.ports, &vcd_file, dump_depth), false)
+            }
+        };
+
+        let backup = safe_write::write_with_backup(&testbench_path, &content)?;
+
+        Ok(TestbenchUpdate {
+            module: top.name.clone(),
+            backup,
+            preserved_stimulus,
+        })
+    }
+
+    // Generate main_test.v, and a `build_system` build file when there is
+    // none, around the selected project's hand-written main.v, which is left
+    // untouched. An existing testbench is only replaced (after a backup) with
+    // `overwrite`.
+    pub fn create_testbench(&self, simulator: &str, build_system: BuildSystem, overwrite: bool) -> Result<TestbenchCreation, HadouError> {
+        let project_path = self.get_selected_project_path().ok_or(HadouError::NoSelection("project"))?;
+        require_verilog(project_path, "Generating a testbench")?;
+        let top = design_top_module(project_path)?;
+
+        let testbench_path = project_path.join("main_test.v");
+        if testbench_path.exists() && !overwrite {
+            return Err(HadouError::Other("main_test.v already exists".to_string()));
+        }
+
+        // The testbench's dump file has to match the VCD name the build file expects
+        let existing_build_file = build_system::find_build_file(project_path);
+        let project_name = existing_build_file
+            .as_ref()
+            .and_then(|(_, path)| fs::read_to_string(path).ok())
+            .and_then(|content| justfile_project_name(&content))
+            .unwrap_or_else(|| project_label(project_path));
+
+        let testbench = create_new_project::generate_testbench(
+            &top.name,
+            &top.parameters,
+            &top.ports,
+            &format!("{}.vcd", project_name),
+            0,
+        );
+        let backup = safe_write::write_with_backup(&testbench_path, &testbench)?;
+
+        let build_file_created = existing_build_file.is_none().then_some(build_system);
+        if let Some(system) = build_file_created {
+            let config = ProjectConfig::load(project_path)?;
+            let content = create_new_project::generate_build_file(system, &project_name, simulator, &config.compiler_flags());
+            let content = project_config::with_build_settings(&content, system, &config).unwrap_or(content);
+            fs::write(project_path.join(system.file_name()), content)?;
+        }
+
+        Ok(TestbenchCreation {
+            module: top.name,
+            backup,
+            build_file_created,
+        })
+    }
 
-        // Add files to command
+    // Instantiation templates for every module in the selected project's
+    // design files. The testbench is left out since nothing instantiates it.
+    pub fn module_snippets(&self) -> Result<Vec<ModuleSnippet>, HadouError> {
+        let project_path = self.get_selected_project_path().ok_or(HadouError::NoSelection("project"))?;
+        require_verilog(project_path, "Generating instantiation templates")?;
+        let mut snippets = Vec::new();
+
+        for file in self.get_project_files(project_path) {
+            let is_design = file.extension().is_some_and(|extension| extension == "v")
+                && file.file_name().is_some_and(|name| name != "main_test.v");
+            if !is_design {
+                continue;
+            }
+
+            let source = fs::read_to_string(&file)?;
+            for module in verilog::parse_modules(&source) {
+                snippets.push(ModuleSnippet {
+                    file: file.clone(),
+                    snippet: create_new_project::instantiation_snippet(&module, &format!("u_{}", module.name)),
+                    module: module.name,
+                });
+            }
+        }
+
+        if snippets.is_empty() {
+            return Err(HadouError::Other("No modules found in the project's design files".to_string()));
+        }
+
+        Ok(snippets)
+    }
+
+    // Every line of the selected project's Verilog files where `name` appears
+    // as a whole identifier, so `clk` doesn't turn up `clk_div`. Files are
+    // read here rather than with grep so it works the same everywhere.
+    pub fn find_references(&self, name: &str) -> Result<Vec<Reference>, HadouError> {
+        let project_path = self.get_selected_project_path().ok_or(HadouError::NoSelection("project"))?;
+        require_verilog(project_path, "Finding references")?;
+
+        let mut files = self.scan_options.find_files(project_path, |path| {
+            path.extension().is_some_and(|extension| extension == "v" || extension == "sv")
+        });
+        files.sort();
+
+        let mut references = Vec::new();
         for file in files {
-            if let Ok(relative_path) = file.strip_prefix(project_dir) {
-                command.arg(relative_path);
-            } else {
-                command.arg(file);
+            // Binary or unreadable files are skipped rather than failing the search
+            let Ok(source) = fs::read_to_string(&file) else {
+                continue;
+            };
+            for (index, text) in source.lines().enumerate() {
+                if verilog::contains_identifier(text, name) {
+                    references.push(Reference {
+                        file: file.clone(),
+                        line: index + 1,
+                        text: text.trim().to_string(),
+                    });
+                }
             }
         }
 
-        match editor.to_lowercase().as_str() {
-            editor_name if editor_name.contains("code") => {
-                // Clear previous args and set new ones for VS Code
-                command = Command::new(&editor);
-                command.current_dir(project_dir);
-                command.args(&[".", "--goto", "main.v:1:1"]);
+        Ok(references)
+    }
+
+    // Open one file of the selected project with the cursor on `line`
+    pub fn open_file_at_line(&self, file: &Path, line: usize) -> Result<EditorLaunch, HadouError> {
+        let project_dir = self.get_selected_project_path().ok_or(HadouError::NoSelection("project"))?;
+        let editor = self.detect_editor()?;
+
+        let relative = file.strip_prefix(project_dir).unwrap_or(file);
+        let location = format!("{}:{}", relative.display(), line);
+
+        let mut command = Command::new(windows_program(&editor).unwrap_or_else(|| PathBuf::from(&editor)));
+        command.current_dir(project_dir);
+        match editor_name(&editor).as_str() {
+            "code" | "code-insiders" | "codium" | "vscodium" => {
+                command.args([".", "--goto", &location]);
             }
-            editor_name if editor_name.contains("nvim") || editor_name.contains("vim") => {
-                command.arg("-p"); // Open in tabs
+            "hx" | "helix" | "zed" | "zeditor" | "subl" | "sublime_text" => {
+                command.arg(&location);
             }
-            editor_name if editor_name.contains("emacs") => {
-                command.arg("--no-wait");
+            "kate" | "geany" => {
+                command.args(["--line", &line.to_string()]).arg(relative);
             }
-            editor_name if editor_name.contains("codium") => {
-                // Clear previous args and set new ones for VSCodium
-                command = Command::new(&editor);
-                command.current_dir(project_dir);
-                command.args(&[".", "--goto", "main.v:1:1"]);
+            "notepad++" => {
+                command.arg(format!("-n{}", line)).arg(relative);
             }
-            editor_name if editor_name.contains("edit") => {
-                // For editors that can only edit one file at a time
-                command = Command::new(&editor);
-                command.current_dir(project_dir);
-                command.arg(files.iter().find(|f| f.file_name().unwrap() == "main.v")
-                    .unwrap_or(&files[0]));
+            "vi" | "vim" | "nvim" | "gvim" | "nano" | "micro" | "emacs" | "gedit" | "mousepad" | "xed" | "pluma" | "kak" => {
+                command.arg(format!("+{}", line)).arg(relative);
             }
+            // No known way to pass a line, so at least open the file
             _ => {
-                // Default: keep all files as arguments
+                command.arg(relative);
             }
         }
 
-        let status = command.status()?;
+        let kind = self.editor_kind.unwrap_or_else(|| EditorKind::detect(&editor));
+        Ok(EditorLaunch { editor, kind, command })
+    }
 
-        if !status.success() {
-            return Err(format!("Editor {} exited with error code: {}", editor, status.code().unwrap_or(-1)).into());
+    fn build_editor_command(&self, files: &[PathBuf], project_dir: &Path) -> Result<EditorLaunch, HadouError> {
+        let editor = self.detect_editor()?;
+
+        let open_mode = self.open_mode.unwrap_or_else(|| OpenMode::detect(&editor));
+
+        let program = windows_program(&editor).unwrap_or_else(|| PathBuf::from(&editor));
+        let mut command = Command::new(&program);
+
+        command.current_dir(project_dir);   // Change to project directory
+
+        let design_file = Language::detect(project_dir).unwrap_or_default().design_file();
+
+        // Notepad opens a single file and nothing else. Relative paths keep
+        // spaces in the directories above the project out of its command line.
+        if editor_name(&editor) == "notepad" {
+            command.arg(design_file);
+        } else if open_mode == OpenMode::Workspace {
+            match editor_name(&editor).as_str() {
+                "code" | "code-insiders" | "codium" | "vscodium" => {
+                    command.args([".", "--goto", &format!("{}:1:1", design_file)]);
+                }
+                // Open the folder and focus the design file
+                "zed" | "zeditor" | "subl" => {
+                    command.args([".", design_file]);
+                }
+                "notepad++" => {
+                    command.args(["-openFoldersAsWorkspace", ".", design_file]);
+                }
+                // Helix and anything else that accepts a directory
+                _ => {
+                    command.arg(".");
+                }
+            }
+        } else {
+            // Add files to command
+            for file in files {
+                if let Ok(relative_path) = file.strip_prefix(project_dir) {
+                    command.arg(relative_path);
+                } else {
+                    command.arg(file);
+                }
+            }
+
+            match editor.to_lowercase().as_str() {
+                editor_name if editor_name.contains("nvim") || editor_name.contains("vim") => {
+                    command.arg("-p"); // Open in tabs
+                }
+                editor_name if editor_name.contains("emacs") => {
+                    command.arg("--no-wait");
+                }
+                _ if matches!(editor_name(&editor).as_str(), "hx" | "helix") => {
+                    command.arg("--vsplit");
+                }
+                editor_name if editor_name.contains("edit") => {
+                    // For editors that can only edit one file at a time
+                    command = Command::new(&program);
+                    command.current_dir(project_dir);
+                    command.arg(files.iter().find(|f| f.file_name().unwrap() == design_file)
+                        .unwrap_or(&files[0]));
+                }
+                _ => {
+                    // Default: keep all files as arguments
+                }
+            }
+        }
+
+        let mut kind = self.editor_kind.unwrap_or_else(|| EditorKind::detect(&editor));
+
+        // Inside tmux a terminal editor gets its own window, so the project
+        // opens as a workspace next to Hadou instead of replacing it
+        if kind == EditorKind::Terminal && open_mode == OpenMode::Workspace && env::var_os("TMUX").is_some() {
+            let window_name = project_dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let mut tmux = Command::new("tmux");
+            tmux.arg("new-window")
+                .arg("-c")
+                .arg(project_dir)
+                .args(["-n", &window_name, &editor])
+                .args(command.get_args());
+            command = tmux;
+            kind = EditorKind::Gui;
         }
 
-        Ok(())
+        Ok(EditorLaunch { editor, kind, command })
     }
 
-    fn detect_editor(&self) -> Result<String, Box<dyn std::error::Error>> {
-        if let Ok(editor) = env::var("EDITOR") {
-             if !editor.is_empty() {
-                return  Ok(editor);
-            }
-         } 
+    fn detect_editor(&self) -> Result<String, HadouError> {
+        if let Some(editor) = self.preferred_editor.as_ref().filter(|editor| !editor.is_empty()) {
+            return Ok(editor.clone());
+        }
+
+        if let Ok(editor) = env::var("EDITOR") && !editor.is_empty() {
+            return Ok(editor);
+        }
 
         if cfg!(target_os = "windows") {
             let windows_editors = [
@@ -173,77 +783,82 @@ impl ProjectEditor {
             ];
 
             for editor in &windows_editors {
-                if self.command_exists(editor) {
+                if command_exists(editor) {
                     return  Ok(editor.to_string());
                 }
             }
 
-            return  Ok("notepad".to_string());   // Default
+            Ok("notepad".to_string())   // Default
         } else {
             let unix_editors = [
                 "nvim",
                 "vim",
+                "hx",
                 "emacs",
                 "code",
                 "codium",
+                "zed",
                 "nano",
                 "gedit",
                 "kate"
             ];
 
             for editor in &unix_editors {
-                if self.command_exists(editor) {
+                if command_exists(editor) {
                     return Ok(editor.to_string());
                 }
             }
 
-            return Err("No suitable editor found. Please set the EDITOR environment variable".into());
+            Err(HadouError::NoEditor)
         }
     }
 
-    fn command_exists(&self, command: &str) -> bool {
-        Command::new("which")
-            .arg(command)
-            .output()
-            .map(|output| output.status.success())
-            .unwrap_or_else(|_| {
-                if cfg!(target_os = "windows") {
-                    Command::new("where")
-                        .arg(command)
-                        .output()
-                        .map(|output| output.status.success())
-                        .unwrap_or(false)
-                } else {
-                    false
-                }
-            })
+    // Projects matching the search query, best match first
+    pub fn visible_projects(&self) -> Vec<(usize, FuzzyMatch)> {
+        self.search.filter_projects(&self.projects)
+    }
+
+    pub fn selection_visible(&self) -> bool {
+        self.search.project_visible(&self.projects, self.selected_project_index)
+    }
+
+    pub fn select_best_match(&mut self) {
+        if let Some(index) = self.search.best_project(&self.projects) {
+            self.selected_project_index = index;
+        }
+        self.sync_notes();
     }
 
     pub fn move_selection_up(&mut self) {
-        if !self.projects.is_empty() {
-            self.selected_project_index = if self.selected_project_index == 0 {
-                self.projects.len() - 1
-            } else {
-                    self.selected_project_index - 1
-            };
+        if let Some(index) = fuzzy::step_selection(&self.visible_projects(), self.selected_project_index, false) {
+            self.selected_project_index = index;
         }
+        self.sync_notes();
     }
 
     // Fixed typo: move_sleection_down -> move_selection_down
     pub fn move_selection_down(&mut self) {
-        if !self.projects.is_empty() {
-            self.selected_project_index = (self.selected_project_index + 1) % self.projects.len();
+        if let Some(index) = fuzzy::step_selection(&self.visible_projects(), self.selected_project_index, true) {
+            self.selected_project_index = index;
         }
+        self.sync_notes();
     }
 
     pub fn refresh_projects(&mut self) {
         self.scan_for_projects();
     }
 
+    // Check every listed project's main.v in the background
+    pub fn check_syntax(&mut self, compiler: &str) {
+        let projects: Vec<PathBuf> = self.projects.iter().map(|entry| entry.path.clone()).collect();
+        self.syntax_checker.start(&projects, compiler);
+    }
+
     // Fixed method name: selected_project_name -> get_selected_project_name
     pub fn get_selected_project_name(&self) -> Option<String> {
         if self.selected_project_index < self.projects.len() {
             self.projects[self.selected_project_index]
+                .path
                 .file_name()
                 .and_then(|name| name.to_str())
                 .map(|s| s.to_string())
@@ -253,9 +868,21 @@ impl ProjectEditor {
     }
 
     // Fixed method name: get_selected_project_files -> get_selected_project_path
+    // Select the project at `path` if it is still listed
+    pub fn select_project(&mut self, path: &Path) -> bool {
+        match self.projects.iter().position(|entry| entry.path == path) {
+            Some(index) => {
+                self.selected_project_index = index;
+                self.sync_notes();
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn get_selected_project_path(&self) -> Option<&PathBuf> {
         if self.selected_project_index < self.projects.len() {
-            Some(&self.projects[self.selected_project_index])
+            Some(&self.projects[self.selected_project_index].path)
         } else {
             None
         }
@@ -270,8 +897,289 @@ impl ProjectEditor {
     }
 }
 
+pub fn project_label(path: &Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().to_string()
+}
+
+// The module no other module in the design instantiates, or the first one
+pub fn top_module(design: &str) -> Option<ModuleDecl> {
+    let modules = verilog::parse_modules(design);
+    let module_names: Vec<String> = modules.iter().map(|module| module.name.clone()).collect();
+    let instantiated: Vec<String> = verilog::find_instances(design, &module_names)
+        .into_iter()
+        .map(|instance| instance.module)
+        .collect();
+
+    let top = modules.iter().position(|module| !instantiated.contains(&module.name)).unwrap_or(0);
+    modules.into_iter().nth(top)
+}
+
+// Top module of a Verilog project's main.v. Generated projects name it after
+// the directory, but adopted and renamed ones often keep a name of their own.
+pub fn design_top_module(project: &Path) -> Result<ModuleDecl, HadouError> {
+    let design = fs::read_to_string(project.join(Language::Verilog.design_file()))?;
+    top_module(&design).ok_or_else(|| HadouError::Other("No module found in main.v".to_string()))
+}
+
+// Top module names per project for the previews, which are drawn on every
+// frame. A project's main.v is only parsed again once it has changed.
+#[derive(Debug, Default)]
+pub struct TopModuleCache {
+    entries: HashMap<PathBuf, (Option<SystemTime>, Option<String>)>,
+}
+
+impl TopModuleCache {
+    pub fn update(&mut self, project: &Path) {
+        let design = project.join(Language::Verilog.design_file());
+        let modified = fs::metadata(&design).and_then(|metadata| metadata.modified()).ok();
+        if self.entries.get(project).is_none_or(|(cached, _)| *cached != modified) {
+            let name = design_top_module(project).ok().map(|top| top.name);
+            self.entries.insert(project.to_path_buf(), (modified, name));
+        }
+    }
+
+    pub fn get(&self, project: &Path) -> Option<&str> {
+        self.entries.get(project).and_then(|(_, name)| name.as_deref())
+    }
+}
+
+// The testbench and snippet generators only understand Verilog
+fn require_verilog(project: &Path, feature: &str) -> Result<(), HadouError> {
+    match Language::detect(project) {
+        Some(Language::Vhdl) => Err(HadouError::Other(format!("{} is only available for Verilog projects", feature))),
+        _ => Ok(()),
+    }
+}
+
+// New contents of the files in `project` that refer to it by `old_name`: the
+// design and testbench modules (token-aware, so comments and longer names
+// containing it are left alone), the dump file and the build file's
+// PROJECT_NAME. Files that wouldn't change are left out.
+fn renamed_project_files(project: &Path, old_name: &str, new_name: &str) -> Result<Vec<(PathBuf, String)>, HadouError> {
+    // A top module with a name of its own keeps it, and so does its testbench
+    let named_after_project = design_top_module(project).ok().is_none_or(|top| top.name == old_name);
+    if named_after_project && Language::detect(project) == Some(Language::Verilog) {
+        ProjectCreator::check_module_name(new_name)?;
+    }
+    let renames = if named_after_project {
+        vec![
+            (old_name.to_string(), new_name.to_string()),
+            (format!("{}_test", old_name), format!("{}_test", new_name)),
+        ]
+    } else {
+        Vec::new()
+    };
+    let string_renames = [(format!("{}.vcd", old_name), format!("{}.vcd", new_name))];
+    let mut updates = Vec::new();
+
+    let sources = Language::ALL.iter().flat_map(|language| [language.design_file(), language.testbench_file()]);
+    for file_name in sources {
+        let path = project.join(file_name);
+        if path.is_file() {
+            let content = fs::read_to_string(&path)?;
+            updates.push((path, verilog::rename_identifiers(&content, &renames, &string_renames), content));
+        }
+    }
+
+    for file_name in BuildSystem::ALL.iter().flat_map(|system| system.file_names()) {
+        let path = project.join(file_name);
+        if path.is_file() {
+            let content = fs::read_to_string(&path)?;
+            updates.push((path, set_justfile_project_name(&content, new_name), content));
+        }
+    }
+
+    Ok(updates
+        .into_iter()
+        .filter(|(_, updated, content)| updated != content)
+        .map(|(path, updated, _)| (path, updated))
+        .collect())
+}
+
+// Copy a project for Duplicate, leaving out what packing it would leave out
+// (build output, ignored files). Symlinks are copied as links instead of
+// being followed, so one pointing back up the tree can't make the copy
+// recurse forever.
+fn copy_project_dir(source: &Path, target: &Path, rules: &[IgnoreRules]) -> std::io::Result<()> {
+    fs::create_dir_all(target)?;
+
+    let mut rules = rules.to_vec();
+    if let Some(local_rules) = IgnoreRules::from_ignore_file(source) {
+        rules.push(local_rules);
+    }
+
+    for entry in fs::read_dir(source)?.flatten() {
+        let path = entry.path();
+        if rules.iter().any(|rule| rule.is_ignored(&path)) {
+            continue;
+        }
+        let target_path = target.join(entry.file_name());
+
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            copy_symlink(&path, &target_path)?;
+        } else if file_type.is_dir() {
+            copy_project_dir(&path, &target_path, &rules)?;
+        } else {
+            fs::copy(&path, &target_path)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(link: &Path, target: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(link)?, target)
+}
+
+// Creating links needs extra privileges on Windows, so they are left out
+#[cfg(not(unix))]
+fn copy_symlink(_link: &Path, _target: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+// `PROJECT_NAME := "name"` in a generated Justfile, `PROJECT_NAME = name` in
+// a generated Makefile
+pub fn justfile_project_name(content: &str) -> Option<String> {
+    content
+        .lines()
+        .find_map(project_name_value)
+        .map(|value| value.trim().trim_matches('"').to_string())
+        .filter(|name| !name.is_empty())
+}
+
+fn project_name_value(line: &str) -> Option<&str> {
+    let rest = line.trim_start().strip_prefix("PROJECT_NAME")?.trim_start();
+    rest.strip_prefix(":=").or_else(|| rest.strip_prefix('='))
+}
+
+fn set_justfile_project_name(content: &str, project_name: &str) -> String {
+    let mut updated: Vec<String> = content
+        .lines()
+        .map(|line| match project_name_value(line) {
+            Some(value) if value.trim_start().starts_with('"') => format!("PROJECT_NAME := \"{}\"", project_name),
+            Some(_) => format!("PROJECT_NAME = {}", project_name),
+            None => line.to_string(),
+        })
+        .collect();
+
+    if content.ends_with('\n') {
+        updated.push(String::new());
+    }
+
+    updated.join("\n")
+}
+
 impl Default for ProjectEditor {
     fn default() -> Self {
-        Self::new()
+        Self::new(ScanOptions::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renaming_a_project_rewrites_its_files_and_backs_them_up() {
+        let root = std::env::temp_dir().join(format!("hadou-rename-project-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let dir = root.join("alu");
+        fs::create_dir_all(&dir).unwrap();
+        fs::create_dir_all(root.join("taken")).unwrap();
+        fs::write(root.join("taken/main.v"), "module taken;\nendmodule\n").unwrap();
+        let design = "// alu: adds a and b\nmodule alu(input a, b, output y);\n  wire alu_carry;\n  assign y = a ^ b;\nendmodule\n";
+        fs::write(dir.join("main.v"), design).unwrap();
+        fs::write(
+            dir.join("main_test.v"),
+            "module alu_test;\n  reg a, b; wire y;\n  alu uut (.a(a), .b(b), .y(y));\n  initial $dumpfile(\"alu.vcd\");\nendmodule\n",
+        )
+        .unwrap();
+        fs::write(dir.join("Justfile"), "PROJECT_NAME := \"alu\"\nVCD_FILE := PROJECT_NAME + \".vcd\"\n").unwrap();
+
+        let mut editor = ProjectEditor::new(ScanOptions::default());
+        editor.current_directory = root.clone();
+        editor.refresh_projects();
+        assert!(editor.select_project(&dir));
+
+        // An existing project's name is refused before anything is touched
+        assert!(matches!(editor.rename_selected_project("taken"), Err(HadouError::ProjectExists(_))));
+        assert!(matches!(editor.rename_selected_project("alu-2"), Err(HadouError::InvalidProjectName { .. })));
+        assert_eq!(fs::read_to_string(dir.join("main.v")).unwrap(), design);
+
+        let rename = editor.rename_selected_project("adder").unwrap();
+        let target = root.join("adder");
+        assert_eq!(rename.path, target);
+        assert!(!dir.exists());
+
+        let main = fs::read_to_string(target.join("main.v")).unwrap();
+        assert!(main.starts_with("// alu: adds a and b\nmodule adder(") && main.contains("wire alu_carry;"));
+        let testbench = fs::read_to_string(target.join("main_test.v")).unwrap();
+        assert!(testbench.contains("module adder_test;") && testbench.contains("adder uut (") && testbench.contains("$dumpfile(\"adder.vcd\")"));
+        assert!(fs::read_to_string(target.join("Justfile")).unwrap().starts_with("PROJECT_NAME := \"adder\"\n"));
+
+        assert_eq!(rename.updated_files.len(), 3);
+        assert_eq!(rename.backups.len(), 3);
+        let backup = rename.backups.iter().find(|backup| backup.to_string_lossy().contains("main.v.bak")).unwrap();
+        assert!(backup.starts_with(&target));
+        assert_eq!(fs::read_to_string(backup).unwrap(), design);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn renaming_leaves_a_differently_named_top_module_alone() {
+        let dir = std::env::temp_dir().join(format!("hadou-edit-rename-{}", std::process::id())).join("my_alu");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("main.v"), "module alu(input a, output y);\n  assign y = a;\nendmodule\n").unwrap();
+        fs::write(
+            dir.join("main_test.v"),
+            "module alu_test;\n  reg a; wire y;\n  alu uut (.a(a), .y(y));\n  initial $dumpfile(\"my_alu.vcd\");\nendmodule\n",
+        )
+        .unwrap();
+        fs::write(dir.join("Justfile"), "PROJECT_NAME := \"my_alu\"\n").unwrap();
+        assert_eq!(design_top_module(&dir).unwrap().name, "alu");
+        let mut top_modules = TopModuleCache::default();
+        top_modules.update(&dir);
+        assert_eq!(top_modules.get(&dir), Some("alu"));
+        assert_eq!(top_modules.get(&dir.join("missing")), None);
+
+        let updates = renamed_project_files(&dir, "my_alu", "alu2").unwrap();
+        let updated = |file: &str| updates.iter().find(|(path, _)| path.ends_with(file)).map(|(_, content)| content.clone());
+        assert_eq!(updated("main.v"), None);
+        let testbench = updated("main_test.v").unwrap();
+        assert!(testbench.contains("module alu_test;") && testbench.contains("alu uut") && testbench.contains("\"alu2.vcd\""));
+        assert_eq!(updated("Justfile").unwrap(), "PROJECT_NAME := \"alu2\"\n");
+
+        // A module named after the project is renamed with it
+        fs::write(dir.join("main.v"), "module my_alu(input a, output y);\n  assign y = a;\nendmodule\n").unwrap();
+        let updates = renamed_project_files(&dir, "my_alu", "alu2").unwrap();
+        assert!(updates.iter().any(|(path, content)| path.ends_with("main.v") && content.starts_with("module alu2(")));
+        fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn duplicating_copies_links_and_skips_build_output() {
+        let root = std::env::temp_dir().join(format!("hadou-edit-copy-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let source = root.join("lab1");
+        fs::create_dir_all(source.join("rtl")).unwrap();
+        fs::write(source.join("main.v"), "module lab1; endmodule\n").unwrap();
+        fs::write(source.join("rtl/alu.v"), "module alu; endmodule\n").unwrap();
+        fs::write(source.join("lab1.vcd"), "$end").unwrap();
+        fs::write(source.join("lab1.vvp"), "#! vvp").unwrap();
+        // Would recurse until the stack overflows if followed
+        std::os::unix::fs::symlink("..", source.join("rtl/up")).unwrap();
+
+        let target = root.join("lab2");
+        let rules = IgnoreRules::new(&source, archive::excluded_patterns(&[]));
+        copy_project_dir(&source, &target, &[rules]).unwrap();
+
+        assert!(target.join("main.v").is_file() && target.join("rtl/alu.v").is_file());
+        assert!(!target.join("lab1.vcd").exists() && !target.join("lab1.vvp").exists());
+        assert_eq!(fs::read_link(target.join("rtl/up")).unwrap(), PathBuf::from(".."));
+        fs::remove_dir_all(root).unwrap();
     }
 }
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+pub const ENV_FILE_NAME: &str = ".env";
+
+// Generated Justfiles pass this variable's value to `vvp` as plusargs
+pub const PLUSARGS_VAR: &str = "HADOU_PLUSARGS";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvVar {
+    pub key: String,
+    pub value: String,
+    // Disabled variables are not passed on, leaving the process value (if any)
+    pub enabled: bool,
+    // Value was changed for this session and differs from the .env file
+    pub overridden: bool,
+}
+
+// Variables from a project's .env file plus any per-run changes made in the UI.
+// Precedence when running: process environment < .env < per-run overrides.
+#[derive(Debug, Clone, Default)]
+pub struct ProjectEnv {
+    pub vars: Vec<EnvVar>,
+    pub selected_index: usize,
+}
+
+impl ProjectEnv {
+    pub fn load(project_path: &Path) -> Self {
+        let vars = fs::read_to_string(project_path.join(ENV_FILE_NAME))
+            .map(|content| parse_env_file(&content))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(key, value)| EnvVar {
+                key,
+                value,
+                enabled: true,
+                overridden: false,
+            })
+            .collect();
+
+        Self {
+            vars,
+            selected_index: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    pub fn selected(&self) -> Option<&EnvVar> {
+        self.vars.get(self.selected_index)
+    }
+
+    pub fn move_selection_up(&mut self) {
+        if !self.vars.is_empty() {
+            self.selected_index = if self.selected_index == 0 {
+                self.vars.len() - 1
+            } else {
+                self.selected_index - 1
+            };
+        }
+    }
+
+    pub fn move_selection_down(&mut self) {
+        if !self.vars.is_empty() {
+            self.selected_index = (self.selected_index + 1) % self.vars.len();
+        }
+    }
+
+    pub fn toggle_selected(&mut self) {
+        if let Some(var) = self.vars.get_mut(self.selected_index) {
+            var.enabled = !var.enabled;
+        }
+    }
+
+    pub fn set_selected_value(&mut self, value: String) {
+        if let Some(var) = self.vars.get_mut(self.selected_index) {
+            var.value = value;
+            var.overridden = true;
+            var.enabled = true;
+        }
+    }
+
+    pub fn plusargs(&self) -> Option<&str> {
+        self.vars
+            .iter()
+            .find(|var| var.enabled && var.key == PLUSARGS_VAR)
+            .map(|var| var.value.as_str())
+    }
+
+    // Layer the enabled variables on top of the inherited process environment
+    pub fn apply(&self, command: &mut Command) {
+        for var in self.vars.iter().filter(|var| var.enabled) {
+            command.env(&var.key, &var.value);
+        }
+    }
+}
+
+// Parse `KEY=VALUE` lines. Supports `export` prefixes, comments, single
+// quotes (taken literally) and double quotes or bare values with `$VAR` /
+// `${VAR}` references to earlier entries or the process environment.
+pub fn parse_env_file(content: &str) -> Vec<(String, String)> {
+    let mut vars: Vec<(String, String)> = Vec::new();
+    let mut known: HashMap<String, String> = HashMap::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line).trim_start();
+        let Some((key, raw_value)) = line.split_once('=') else {
+            continue;
+        };
+
+        let key = key.trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            continue;
+        }
+
+        let raw_value = raw_value.trim();
+        let value = if let Some(inner) = raw_value.strip_prefix('\'').and_then(|rest| rest.strip_suffix('\'')) {
+            inner.to_string()
+        } else if let Some(inner) = raw_value.strip_prefix('"').and_then(|rest| rest.strip_suffix('"')) {
+            interpolate(&inner.replace("\\n", "\n").replace("\\\"", "\""), &known)
+        } else {
+            // Bare values end at an inline comment
+            let bare = raw_value.split(" #").next().unwrap_or("").trim_end();
+            interpolate(bare, &known)
+        };
+
+        known.insert(key.to_string(), value.clone());
+        match vars.iter_mut().find(|(existing, _)| existing == key) {
+            Some(entry) => entry.1 = value,
+            None => vars.push((key.to_string(), value)),
+        }
+    }
+
+    vars
+}
+
+fn interpolate(value: &str, known: &HashMap<String, String>) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    let mut output = String::with_capacity(value.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] != '$' {
+            output.push(chars[i]);
+            i += 1;
+            continue;
+        }
+
+        let (name, next) = if chars.get(i + 1) == Some(&'{') {
+            match chars[i + 2..].iter().position(|c| *c == '}') {
+                Some(length) => (chars[i + 2..i + 2 + length].iter().collect::<String>(), i + 3 + length),
+                None => (String::new(), i + 1),
+            }
+        } else {
+            let length = chars[i + 1..]
+                .iter()
+                .take_while(|c| c.is_ascii_alphanumeric() || **c == '_')
+                .count();
+            (chars[i + 1..i + 1 + length].iter().collect::<String>(), i + 1 + length)
+        };
+
+        if name.is_empty() {
+            output.push('$');
+            i += 1;
+            continue;
+        }
+
+        let resolved = known
+            .get(&name)
+            .cloned()
+            .or_else(|| env::var(&name).ok())
+            .unwrap_or_default();
+        output.push_str(&resolved);
+        i = next;
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+
+    #[test]
+    fn parses_quotes_comments_and_references() {
+        let content = r#"
+# comment
+export SEED=42
+NAME='literal $SEED # kept'
+MSG="line one\nsaid \"hi\" with ${SEED}"
+BARE=$SEED-run # trailing comment
+OPEN=${SEED
+EMPTY=
+not a line
+BAD-KEY=1
+SEED=7
+AFTER=$SEED
+"#;
+        let vars = parse_env_file(content);
+        let value = |key: &str| vars.iter().find(|(name, _)| name == key).map(|(_, value)| value.as_str());
+
+        // A redefined key keeps its first position and takes the last value
+        assert_eq!(vars.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>(), vec![
+            "SEED", "NAME", "MSG", "BARE", "OPEN", "EMPTY", "AFTER"
+        ]);
+        assert_eq!(value("SEED"), Some("7"));
+        assert_eq!(value("NAME"), Some("literal $SEED # kept"));
+        assert_eq!(value("MSG"), Some("line one\nsaid \"hi\" with 42"));
+        assert_eq!(value("BARE"), Some("42-run"));
+        // Without a closing brace the text is kept as written
+        assert_eq!(value("OPEN"), Some("${SEED"));
+        assert_eq!(value("EMPTY"), Some(""));
+        assert_eq!(value("AFTER"), Some("7"));
+    }
+
+    #[test]
+    fn overrides_win_over_the_env_file_and_the_process() {
+        let mut project_env = ProjectEnv {
+            vars: parse_env_file("PATH=/from/env/file\nSEED=1\nHADOU_PLUSARGS=+fast\n")
+                .into_iter()
+                .map(|(key, value)| EnvVar { key, value, enabled: true, overridden: false })
+                .collect(),
+            selected_index: 0,
+        };
+        let passed = |project_env: &ProjectEnv| {
+            let mut command = Command::new("true");
+            project_env.apply(&mut command);
+            command
+                .get_envs()
+                .map(|(key, value)| (key.to_owned(), value.map(OsStr::to_owned)))
+                .collect::<HashMap<_, _>>()
+        };
+
+        // The .env value replaces the inherited PATH
+        assert_eq!(passed(&project_env)[OsStr::new("PATH")].as_deref(), Some(OsStr::new("/from/env/file")));
+
+        project_env.move_selection_down();
+        project_env.set_selected_value("2".to_string());
+        assert!(project_env.selected().unwrap().overridden);
+        assert_eq!(passed(&project_env)[OsStr::new("SEED")].as_deref(), Some(OsStr::new("2")));
+
+        // A disabled variable leaves the process value alone
+        project_env.move_selection_up();
+        project_env.toggle_selected();
+        assert!(!passed(&project_env).contains_key(OsStr::new("PATH")));
+        assert_eq!(project_env.plusargs(), Some("+fast"));
+    }
+}
@@ -0,0 +1,163 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::create_new_project::NameProblem;
+
+#[derive(Debug)]
+pub enum HadouError {
+    // An external program Hadou runs is not on the PATH
+    ToolNotFound { tool: String, install_hint: &'static str },
+    // Neither a Justfile nor a Makefile
+    NoBuildFile(PathBuf),
+    // The Justfile has no recipe of that name; `available` lists those it has
+    MissingRecipe { recipe: String, available: Vec<String> },
+    // A just recipe ran but exited unsuccessfully. `code` is None when the
+    // process was killed by a signal.
+    CompileFailed { action: &'static str, code: Option<i32>, output: String },
+    // A simulation ran past its timeout and was stopped
+    TimedOut { seconds: u64 },
+    InvalidProjectName { name: String, problem: NameProblem },
+    ProjectExists(PathBuf),
+    ProjectMissing(PathBuf),
+    NoProjects,
+    NoVcdFiles,
+    // What was supposed to be selected: "project", "action", "file"
+    NoSelection(&'static str),
+    AlreadyRunning,
+    NoEditor,
+    EditorFailed { editor: String, code: Option<i32> },
+    VcdParse(String),
+    // A [keys] entry in config.toml that can't be used
+    Keymap(String),
+    // config.toml exists but couldn't be loaded, so it isn't saved over
+    ConfigUnreadable(String),
+    // Work the user stopped before it finished
+    Cancelled,
+    // Failures the UI has no special handling for
+    Other(String),
+    Io(io::Error),
+    Json(serde_json::Error),
+    Toml(toml::ser::Error),
+    Watch(notify::Error),
+    Clipboard(arboard::Error),
+}
+
+impl HadouError {
+    pub fn tool_not_found(tool: &str, install_hint: &'static str) -> Self {
+        HadouError::ToolNotFound { tool: tool.to_string(), install_hint }
+    }
+
+    // What the user can do about it, shown below the error
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            HadouError::ToolNotFound { install_hint, .. } => Some(install_hint),
+            HadouError::NoBuildFile(_) => Some("Please create the project using Hadou first."),
+            HadouError::NoEditor => Some("Please set the EDITOR environment variable or choose an editor in Settings."),
+            HadouError::TimedOut { .. } => Some("Make sure the testbench calls $finish, or raise the timeout in Settings."),
+            HadouError::Keymap(_) => Some("Fix the [keys] table in config.toml; the default keys are used until then."),
+            HadouError::ConfigUnreadable(_) => Some("Fix or remove config.toml; the defaults are used and nothing is saved until Hadou is restarted."),
+            _ => None,
+        }
+    }
+
+    // Everything a failed run printed, for reading in full
+    pub fn output(&self) -> Option<&str> {
+        match self {
+            HadouError::CompileFailed { output, .. } if !output.trim().is_empty() => Some(output),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for HadouError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HadouError::ToolNotFound { tool, .. } => write!(f, "'{}' command not found", tool),
+            HadouError::NoBuildFile(project) => write!(f, "No Justfile or Makefile found in {}", project.display()),
+            HadouError::MissingRecipe { recipe, available } if available.is_empty() => {
+                write!(f, "The build file has no '{}' recipe, or any other recipes", recipe)
+            }
+            HadouError::MissingRecipe { recipe, available } => write!(
+                f,
+                "The build file has no '{}' recipe. Recipes it does have: {}",
+                recipe,
+                available.join(", ")
+            ),
+            HadouError::CompileFailed { action, code: Some(code), output } => {
+                write!(f, "{} failed with exit code: {}\nOutput: {}", action, code, output)
+            }
+            HadouError::CompileFailed { action, code: None, output } => {
+                write!(f, "{} was terminated by a signal\nOutput: {}", action, output)
+            }
+            HadouError::TimedOut { seconds } => write!(f, "simulation timed out after {}s", seconds),
+            HadouError::InvalidProjectName { name, problem } => write!(f, "{}", problem.message(name)),
+            HadouError::ProjectExists(path) => write!(f, "Directory {} already exists", path.display()),
+            HadouError::ProjectMissing(path) => write!(f, "Project directory {} no longer exists", path.display()),
+            HadouError::NoProjects => write!(f, "No Verilog or VHDL projects found in current directory"),
+            HadouError::NoVcdFiles => write!(f, "No VCD files found"),
+            HadouError::NoSelection(what) => write!(f, "No {} selected", what),
+            HadouError::AlreadyRunning => write!(f, "A compilation is already running"),
+            HadouError::NoEditor => write!(f, "No suitable editor found"),
+            HadouError::EditorFailed { editor, code } => write!(
+                f,
+                "Editor {} exited with error code: {}",
+                editor,
+                code.map(|code| code.to_string()).unwrap_or_else(|| "none (killed by a signal)".to_string())
+            ),
+            HadouError::Cancelled => write!(f, "Cancelled"),
+            HadouError::VcdParse(message)
+            | HadouError::Keymap(message)
+            | HadouError::ConfigUnreadable(message)
+            | HadouError::Other(message) => write!(f, "{}", message),
+            HadouError::Io(e) => write!(f, "{}", e),
+            HadouError::Json(e) => write!(f, "{}", e),
+            HadouError::Toml(e) => write!(f, "{}", e),
+            HadouError::Watch(e) => write!(f, "{}", e),
+            HadouError::Clipboard(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for HadouError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HadouError::Io(e) => Some(e),
+            HadouError::Json(e) => Some(e),
+            HadouError::Toml(e) => Some(e),
+            HadouError::Watch(e) => Some(e),
+            HadouError::Clipboard(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for HadouError {
+    fn from(e: io::Error) -> Self {
+        HadouError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for HadouError {
+    fn from(e: serde_json::Error) -> Self {
+        HadouError::Json(e)
+    }
+}
+
+impl From<toml::ser::Error> for HadouError {
+    fn from(e: toml::ser::Error) -> Self {
+        HadouError::Toml(e)
+    }
+}
+
+impl From<notify::Error> for HadouError {
+    fn from(e: notify::Error) -> Self {
+        HadouError::Watch(e)
+    }
+}
+
+impl From<arboard::Error> for HadouError {
+    fn from(e: arboard::Error) -> Self {
+        HadouError::Clipboard(e)
+    }
+}
@@ -0,0 +1,164 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::HadouError;
+use crate::language::Language;
+
+// Optional files a project can be given besides its design, testbench and
+// build file, when it's created or later from the edit screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileTemplate {
+    Package,
+    Header,
+    Constraints,
+}
+
+impl FileTemplate {
+    pub const ALL: [FileTemplate; 3] = [FileTemplate::Package, FileTemplate::Header, FileTemplate::Constraints];
+
+    pub fn description(self) -> &'static str {
+        match self {
+            FileTemplate::Package => "SystemVerilog package",
+            FileTemplate::Header => "`define header",
+            FileTemplate::Constraints => "Pin constraints (Vivado .xdc)",
+        }
+    }
+
+    // Short name for the create form
+    pub fn label(self) -> &'static str {
+        match self {
+            FileTemplate::Package => "pkg",
+            FileTemplate::Header => "vh",
+            FileTemplate::Constraints => "xdc",
+        }
+    }
+
+    pub fn parse(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|template| template.label() == label)
+    }
+
+    pub fn file_name(self, project_name: &str) -> String {
+        match self {
+            FileTemplate::Package => format!("{}_pkg.sv", project_name),
+            FileTemplate::Header => "defines.vh".to_string(),
+            FileTemplate::Constraints => format!("{}.xdc", project_name),
+        }
+    }
+
+    // Packages and headers are only any use to Verilog sources
+    pub fn applies_to(self, language: Language) -> bool {
+        match self {
+            FileTemplate::Package | FileTemplate::Header => language == Language::Verilog,
+            FileTemplate::Constraints => true,
+        }
+    }
+
+    // Filled in the way the design and testbench templates are: the project
+    // name for {name} and the creation time in the header comment
+    pub fn content(self, project_name: &str) -> String {
+        let date = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC");
+        match self {
+            FileTemplate::Package => format!(
+r#"//////////////////////////////////////////////////////////////////////////////////
+// Create Date: {date}
+// Package Name: {name}_pkg
+// Project Name: {name}
+// Description: Types and constants shared by the design and testbench.
+//              Compile with -g2012 and list this file before the sources
+//              that `import {name}_pkg::*;`.
+//////////////////////////////////////////////////////////////////////////////////
+
+package {name}_pkg;
+
+    localparam int DATA_WIDTH = 8;
+
+    typedef logic [DATA_WIDTH-1:0] data_t;
+
+    typedef enum logic [1:0] {{
+        IDLE,
+        BUSY,
+        DONE
+    }} state_t;
+
+endpackage
+"#,
+                date = date,
+                name = project_name,
+            ),
+            FileTemplate::Header => format!(
+r#"//////////////////////////////////////////////////////////////////////////////////
+// Create Date: {date}
+// Project Name: {name}
+// Description: Macros shared between files; pull them in with
+//              `include "defines.vh"
+//////////////////////////////////////////////////////////////////////////////////
+
+`ifndef {guard}_DEFINES_VH
+`define {guard}_DEFINES_VH
+
+`define DATA_WIDTH 8
+`define CLK_PERIOD 10
+
+`endif
+"#,
+                date = date,
+                name = project_name,
+                guard = project_name.to_uppercase().replace('-', "_"),
+            ),
+            FileTemplate::Constraints => format!(
+r#"## Create Date: {date}
+## Project Name: {name}
+## Pin constraints for {name}. Uncomment and set the pins of your board.
+
+## Clock
+# set_property -dict {{ PACKAGE_PIN E3 IOSTANDARD LVCMOS33 }} [get_ports clk]
+# create_clock -add -name sys_clk_pin -period 10.00 -waveform {{0 5}} [get_ports clk]
+
+## Reset
+# set_property -dict {{ PACKAGE_PIN C12 IOSTANDARD LVCMOS33 }} [get_ports rst]
+"#,
+                date = date,
+                name = project_name,
+            ),
+        }
+    }
+}
+
+// Write `template` into `project`, leaving an existing file of that name alone
+pub fn add_to_project(project: &Path, template: FileTemplate) -> Result<PathBuf, HadouError> {
+    let project_name = project
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| HadouError::Other("Invalid project directory name".to_string()))?;
+    let path = project.join(template.file_name(project_name));
+    if path.exists() {
+        return Err(HadouError::Other(format!("{} already exists", path.display())));
+    }
+
+    fs::write(&path, template.content(project_name))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_are_named_after_the_project_and_never_overwrite() {
+        let dir = std::env::temp_dir().join(format!("hadou-template-{}", std::process::id())).join("uart-rx");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let package = add_to_project(&dir, FileTemplate::Package).unwrap();
+        assert_eq!(package.file_name().unwrap(), "uart-rx_pkg.sv");
+        assert!(fs::read_to_string(&package).unwrap().contains("endpackage"));
+
+        let header = fs::read_to_string(add_to_project(&dir, FileTemplate::Header).unwrap()).unwrap();
+        assert!(header.contains("`ifndef UART_RX_DEFINES_VH"));
+
+        assert!(add_to_project(&dir, FileTemplate::Header).is_err());
+        assert!(!FileTemplate::Package.applies_to(Language::Vhdl));
+        assert_eq!(FileTemplate::parse("xdc"), Some(FileTemplate::Constraints));
+        fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,198 @@
+// Subsequence matching in the spirit of fzf, used to filter the project and
+// VCD lists while typing
+
+use crate::edit_project::project_label;
+use crate::scanner::ListEntry;
+
+const MATCH_SCORE: i64 = 16;
+const CONSECUTIVE_BONUS: i64 = 8;
+const WORD_START_BONUS: i64 = 10;
+const GAP_PENALTY: i64 = 1;
+const PREFIX_BONUS: i64 = 1000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    // Character (not byte) positions of the matched characters in the candidate
+    pub positions: Vec<usize>,
+}
+
+// Case-insensitive match of `query` as a subsequence of `candidate`.
+// Consecutive characters and characters at word starts (after `_`, `-`, `.`,
+// `/`, spaces or a lower-to-upper case change) score higher, and a candidate
+// that starts with the query always outranks one that only contains it.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    let chars: Vec<char> = candidate.chars().collect();
+
+    if query.is_empty() {
+        return Some(FuzzyMatch { score: 0, positions: Vec::new() });
+    }
+
+    // Matching greedily from the first occurrence can miss a tighter match
+    // further along, so try every place the first character appears
+    (0..chars.len())
+        .filter(|start| chars[*start].to_lowercase().eq(std::iter::once(query[0])))
+        .filter_map(|start| match_from(&query, &chars, start))
+        .max_by_key(|found| found.score)
+}
+
+fn match_from(query: &[char], chars: &[char], start: usize) -> Option<FuzzyMatch> {
+    let mut positions = Vec::with_capacity(query.len());
+    let mut score = 0;
+    let mut next = start;
+
+    for wanted in query {
+        let offset = chars[next..]
+            .iter()
+            .position(|c| c.to_lowercase().eq(std::iter::once(*wanted)))?;
+        let position = next + offset;
+        let gap = if positions.is_empty() { position } else { offset };
+
+        score += MATCH_SCORE - gap as i64 * GAP_PENALTY;
+        if positions.last().is_some_and(|last| last + 1 == position) {
+            score += CONSECUTIVE_BONUS;
+        }
+        if is_word_start(chars, position) {
+            score += WORD_START_BONUS;
+        }
+
+        positions.push(position);
+        next = position + 1;
+    }
+
+    let prefix_length = positions.iter().enumerate().take_while(|(i, position)| *i == **position).count();
+    if prefix_length == query.len() {
+        score += PREFIX_BONUS;
+    }
+
+    // Prefer shorter candidates when everything else is equal
+    score -= (chars.len() - query.len()) as i64 / 4;
+
+    Some(FuzzyMatch { score, positions })
+}
+
+fn is_word_start(chars: &[char], position: usize) -> bool {
+    if position == 0 {
+        return true;
+    }
+
+    let previous = chars[position - 1];
+    matches!(previous, '_' | '-' | '.' | '/' | ' ')
+        || (previous.is_lowercase() && chars[position].is_uppercase())
+}
+
+// Search query for one list. While `typing` the screen sends printable keys
+// here instead of treating them as shortcuts.
+#[derive(Debug, Clone, Default)]
+pub struct ListSearch {
+    pub query: String,
+    pub typing: bool,
+}
+
+impl ListSearch {
+    pub fn start(&mut self) {
+        self.typing = true;
+    }
+
+    pub fn clear(&mut self) {
+        self.query.clear();
+        self.typing = false;
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.query.is_empty()
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        fuzzy_match(&self.query, name).is_some()
+    }
+
+    // Indices of the names matching the query, best match first. With no
+    // query every name is returned in its original order.
+    pub fn filter<S: AsRef<str>>(&self, names: impl IntoIterator<Item = S>) -> Vec<(usize, FuzzyMatch)> {
+        let mut results: Vec<(usize, FuzzyMatch)> = names
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, name)| fuzzy_match(&self.query, name.as_ref()).map(|found| (i, found)))
+            .collect();
+
+        // Stable sort keeps the original order between equal scores
+        results.sort_by_key(|(_, found)| std::cmp::Reverse(found.score));
+        results
+    }
+
+    // Projects matching the query by their directory name, best match first
+    pub fn filter_projects(&self, projects: &[ListEntry]) -> Vec<(usize, FuzzyMatch)> {
+        self.filter(projects.iter().map(|entry| project_label(&entry.path)))
+    }
+
+    // Whether the project at `selected` is still listed under the query
+    pub fn project_visible(&self, projects: &[ListEntry], selected: usize) -> bool {
+        projects.get(selected).is_some_and(|entry| self.matches(&project_label(&entry.path)))
+    }
+
+    pub fn best_project(&self, projects: &[ListEntry]) -> Option<usize> {
+        self.filter_projects(projects).first().map(|(index, _)| *index)
+    }
+}
+
+// Index of the entry before or after `current` in a filtered list, wrapping
+// around. Falls back to the best match when `current` is filtered out.
+pub fn step_selection(visible: &[(usize, FuzzyMatch)], current: usize, forward: bool) -> Option<usize> {
+    if visible.is_empty() {
+        return None;
+    }
+
+    let next = match visible.iter().position(|(i, _)| *i == current) {
+        Some(position) if forward => (position + 1) % visible.len(),
+        Some(0) => visible.len() - 1,
+        Some(position) => position - 1,
+        None => 0,
+    };
+
+    Some(visible[next].0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn prefixes_rank_first_and_word_starts_match() {
+        let found = fuzzy_match("mtst", "main_test").unwrap();
+        assert_eq!(found.positions, vec![0, 5, 7, 8]);
+        assert!(fuzzy_match("MTST", "main_test").is_some());
+        assert!(fuzzy_match("tsm", "main_test").is_none());
+
+        // A long name starting with the query beats a tight match further in
+        let search = ListSearch { query: "alu".to_string(), typing: false };
+        let names = ["my_alu", "alu_with_a_long_name_and_pipeline", "a_l_u"];
+        let order: Vec<usize> = search.filter(names).into_iter().map(|(i, _)| i).collect();
+        assert_eq!(order[0], 1);
+        assert_eq!(order.len(), 3);
+    }
+
+    #[test]
+    fn selection_steps_through_matches_and_wraps() {
+        let projects: Vec<ListEntry> = ["counter", "alu", "adder", "uart"]
+            .iter()
+            .map(|name| ListEntry { path: PathBuf::from("/work").join(name), modified: None, size: 0 })
+            .collect();
+        let search = ListSearch { query: "a".to_string(), typing: true };
+        let visible = search.filter_projects(&projects);
+        // "alu" and "adder" start with the query, so they come before "uart"
+        assert_eq!(visible.iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(search.best_project(&projects), Some(1));
+        assert!(!search.project_visible(&projects, 0));
+        assert!(!search.project_visible(&projects, 9));
+
+        assert_eq!(step_selection(&visible, 1, true), Some(2));
+        assert_eq!(step_selection(&visible, 3, true), Some(1));
+        assert_eq!(step_selection(&visible, 1, false), Some(3));
+        // A filtered-out selection falls back to the best match
+        assert_eq!(step_selection(&visible, 0, true), Some(1));
+        assert_eq!(step_selection(&[], 0, true), None);
+    }
+}
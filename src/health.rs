@@ -0,0 +1,91 @@
+use crate::build_system::BuildSystem;
+use crate::compile_project::ProjectCompiler;
+use crate::config::Config;
+use crate::language::Language;
+use crate::settings::command_exists;
+use crate::waveform_viewer;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolStatus {
+    pub label: &'static str,
+    // Command that was found, or the one that was looked for
+    pub command: String,
+    pub installed: bool,
+    pub install_hint: &'static str,
+    // Compiling or simulating fails without it; the compile screen warns
+    // about these up front
+    pub needed_to_compile: bool,
+}
+
+// Readiness summary shown on the main menu. Computed at startup and when the
+// menu is refreshed rather than every frame, since it scans and runs `which`.
+#[derive(Debug, Clone, Default)]
+pub struct ProjectHealth {
+    pub projects: usize,
+    // Projects with a Justfile or Makefile
+    pub projects_with_build_file: usize,
+    pub vcd_files: usize,
+    pub tools: Vec<ToolStatus>,
+}
+
+impl ProjectHealth {
+    pub fn collect(compiler: &ProjectCompiler, vcd_files: usize, config: &Config) -> Self {
+        let simulator = config.simulator.clone().unwrap_or_else(|| "iverilog".to_string());
+
+        let mut viewers: Vec<String> = config.viewer.iter().cloned().collect();
+        viewers.extend(waveform_viewer::EXTERNAL_VIEWERS.iter().map(|viewer| viewer.to_string()));
+        let viewer = viewers.iter().find(|viewer| command_exists(viewer)).cloned();
+
+        let mut tools = vec![
+            tool("Compiler", simulator, "install Icarus Verilog: sudo apt install iverilog / brew install icarus-verilog"),
+            tool("Simulator", "vvp".to_string(), "vvp ships with Icarus Verilog: sudo apt install iverilog / brew install icarus-verilog"),
+            tool("Runner", "just".to_string(), "cargo install just / brew install just / sudo apt install just"),
+            ToolStatus {
+                label: "Viewer",
+                installed: viewer.is_some(),
+                command: viewer.unwrap_or_else(|| viewers.join("/")),
+                install_hint: "cargo install dwfv (or press 'v' on a VCD file for the built-in viewer)",
+                needed_to_compile: false,
+            },
+        ];
+
+        // make only matters once a project is built with it
+        if compiler.projects.iter().any(|entry| compiler.build_system(&entry.path) == Some(BuildSystem::Make)) {
+            tools.push(tool("Make", "make".to_string(), BuildSystem::Make.install_hint()));
+        }
+
+        // GHDL only matters once there are VHDL projects
+        if compiler.projects.iter().any(|entry| Language::detect(&entry.path) == Some(Language::Vhdl)) {
+            tools.push(tool("VHDL", "ghdl".to_string(), "install GHDL: sudo apt install ghdl / brew install ghdl"));
+        }
+
+        Self {
+            projects: compiler.projects.len(),
+            projects_with_build_file: compiler
+                .projects
+                .iter()
+                .filter(|entry| compiler.has_build_file(&entry.path))
+                .count(),
+            vcd_files,
+            tools,
+        }
+    }
+
+    pub fn missing_tools(&self) -> usize {
+        self.tools.iter().filter(|tool| !tool.installed).count()
+    }
+
+    pub fn missing_compile_tools(&self) -> Vec<&ToolStatus> {
+        self.tools.iter().filter(|tool| tool.needed_to_compile && !tool.installed).collect()
+    }
+}
+
+fn tool(label: &'static str, command: String, install_hint: &'static str) -> ToolStatus {
+    ToolStatus {
+        label,
+        installed: command_exists(&command),
+        command,
+        install_hint,
+        needed_to_compile: true,
+    }
+}
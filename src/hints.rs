@@ -0,0 +1,288 @@
+// Key bindings each screen lists in its help line and in the '?' overlay.
+// The help line shows all of them when they fit on one row and only the
+// essential ones, plus a pointer to the overlay, when they don't.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hint {
+    pub keys: &'static str,
+    pub action: &'static str,
+    // Kept in the abbreviated help line on narrow terminals
+    pub essential: bool,
+}
+
+const fn key(keys: &'static str, action: &'static str) -> Hint {
+    Hint { keys, action, essential: true }
+}
+
+const fn extra(keys: &'static str, action: &'static str) -> Hint {
+    Hint { keys, action, essential: false }
+}
+
+// Appended to every help line that '?' works on
+pub const SHOW_ALL: Hint = key("'?'", "all keys");
+
+// Listed with every screen's keys, but only shown when the line has room
+pub const PALETTE: Hint = extra("':' or Ctrl-P", "command palette");
+
+pub const MAIN_MENU: &[Hint] = &[
+    key("↑/↓ or j/k", "navigate"),
+    key("Enter", "select"),
+    extra("'n'", "quick-create a project from a spec"),
+    extra("'a'", "adopt the current directory as a project"),
+    extra("'r'", "refresh the status"),
+    extra("F5", "re-run the last compilation"),
+    key("'q' or Esc", "quit"),
+];
+
+pub const CREATE: &[Hint] = &[
+    key("Tab", "switch field"),
+    extra("F2", "fill in a free name"),
+    extra("F3", "import a module from the clipboard"),
+    extra("Space/←/→ (h/l)", "toggle options"),
+    extra("1/2/3", "pick extra files"),
+    key("Enter", "create the project"),
+    key("Esc", "return to main menu"),
+];
+
+// Typing into a list's search box
+pub const SEARCH: &[Hint] = &[
+    key("Type", "filter the list"),
+    key("↑/↓", "navigate matches"),
+    key("Enter", "keep the filter"),
+    key("Esc", "clear the filter"),
+];
+
+pub const NO_PROJECTS: &[Hint] = &[
+    key("'r'", "refresh"),
+    key("Esc", "return to main menu"),
+    extra("'a' on the main menu", "adopt loose sources in this directory"),
+];
+
+pub const COMPILE_PROJECTS: &[Hint] = &[
+    key("Tab/Shift-Tab", "switch view"),
+    key("↑/↓ or j/k", "select project"),
+    key("←/→ or h/l", "select action"),
+    key("Enter", "execute"),
+    extra("Space", "mark (Enter then runs on the marked)"),
+    key("'/'", "search"),
+    extra("'s'", "sort"),
+    extra("'p'", "pin"),
+    extra("'y'", "copy path"),
+    extra("'w'", "watch"),
+    extra("'d'", "dump depth"),
+    extra("'D'", "skip the VCD dump"),
+    extra("'f'", "iverilog flags"),
+    extra("'i'", "extra sources"),
+    extra("'J'", "validate Justfile"),
+    extra("'t'", "testbench"),
+    extra("'N'", "add a note"),
+    extra("'!'", "show command"),
+    extra("'a'", "run on all"),
+    extra("PgUp/PgDn", "scroll preview"),
+    extra("'r'", "refresh"),
+    key("Esc", "return"),
+];
+
+pub const COMPILE_ENVIRONMENT: &[Hint] = &[
+    key("Tab/Shift-Tab", "switch view"),
+    key("↑/↓ or j/k", "select variable"),
+    key("Space", "toggle"),
+    extra("'e'", "override value"),
+    key("Enter", "execute"),
+    extra("'r'", "reload .env"),
+    key("Esc", "return"),
+];
+
+pub const COMPILE_WATCHING: &[Hint] = &[
+    key("'w'", "stop watching"),
+    extra("'c'", "clear output"),
+    extra("'l'", "save log"),
+    key("Tab/Shift-Tab", "switch view"),
+    key("Esc", "return"),
+];
+
+pub const COMPILE_OUTPUT: &[Hint] = &[
+    key("Tab/Shift-Tab", "switch view"),
+    key("Enter", "execute"),
+    extra("F5", "re-run last"),
+    extra("'c'", "clear output"),
+    extra("'l'", "save log"),
+    extra("'r'", "refresh"),
+    key("Esc", "return"),
+];
+
+pub const EDIT: &[Hint] = &[
+    key("↑/↓ or j/k", "navigate"),
+    key("Enter", "edit project"),
+    key("'/'", "search"),
+    extra("'s'", "sort"),
+    extra("'p'", "pin to the top"),
+    extra("'y'", "copy path"),
+    extra("'i'", "instantiation templates"),
+    extra("'f'", "find references"),
+    extra("'d'", "duplicate"),
+    extra("'n'", "rename"),
+    extra("'N'", "add a note"),
+    extra("'t'", "regenerate testbench"),
+    extra("'g'", "generate a testbench and build file for a hand-written main.v"),
+    extra("'A'", "add a package, header or constraints file"),
+    extra("'z'", "pack the project into a .tar.gz or .zip"),
+    extra("'a'", "show all files"),
+    extra("'!'", "show the editor command"),
+    extra("PgUp/PgDn", "scroll the preview"),
+    extra("'r'", "refresh"),
+    key("Esc", "return to main menu"),
+];
+
+pub const MODULE_SNIPPETS: &[Hint] = &[
+    key("↑/↓ or j/k", "select module"),
+    key("Enter or 'y'", "copy to clipboard"),
+    key("Esc", "return"),
+];
+
+pub const REFERENCES: &[Hint] = &[
+    key("↑/↓ or j/k", "select"),
+    key("Enter", "open the editor at that line"),
+    key("Esc", "return"),
+];
+
+pub const VCD_FILES: &[Hint] = &[
+    key("↑/↓ or j/k", "select VCD file"),
+    key("Enter", "launch viewer"),
+    extra("'o'", "open with"),
+    extra("'!'", "show command"),
+    key("'v'", "built-in viewer"),
+    extra("'w'", "web viewer"),
+    extra("'c'", "compare"),
+    extra("'/'", "search"),
+    extra("'s'", "sort"),
+    extra("'y'", "copy path"),
+    extra("'r'", "refresh"),
+    extra("'i'", "install info"),
+    key("Esc", "return"),
+];
+
+pub const NO_VCD_FILES: &[Hint] = &[
+    key("'r'", "refresh files"),
+    key("'i'", "install viewer info"),
+    key("Esc", "return to main menu"),
+];
+
+pub const WAVEFORM: &[Hint] = &[
+    key("↑/↓ or j/k", "signal"),
+    key("←/→ or h/l", "scroll"),
+    key("+/-", "zoom"),
+    extra("'c'", "cursor A"),
+    extra("','/'.'", "previous/next edge"),
+    extra("'t'", "time relative to A"),
+    extra("'e'", "expand/collapse bus bits"),
+    extra("'v'", "value table"),
+    extra("'f'", "find value"),
+    extra("'g'/'G'", "next/previous match"),
+    extra("'W'", "parse warnings"),
+    extra("'r'", "radix"),
+    extra("'a'", "add/remove from view"),
+    extra("'['/']'", "reorder"),
+    extra("'s'", "save view"),
+    extra("'n'", "next view"),
+    extra("'x'", "clear view"),
+    key("Esc", "return"),
+];
+
+pub const SETTINGS: &[Hint] = &[
+    key("↑/↓ or j/k", "select"),
+    key("Enter", "change"),
+    key("'s'", "save"),
+    key("Esc", "return"),
+];
+
+pub const SETTINGS_TEXT: &[Hint] = &[
+    key("↑/↓ or j/k", "select"),
+    key("Enter", "edit"),
+    key("'s'", "save"),
+    key("Esc", "return"),
+];
+
+pub const SETTINGS_EDITING: &[Hint] = &[
+    key("Type", "edit"),
+    key("Enter", "confirm"),
+    key("Esc", "cancel"),
+];
+
+// Full output of a failed run, opened from its error message
+pub const ERROR_DETAIL: &[Hint] = &[
+    key("↑/↓ or j/k", "scroll"),
+    extra("PgUp/PgDn", "page"),
+    extra("Home/End", "jump"),
+    key("'y'", "copy it all to the clipboard"),
+    key("Esc", "back to the message"),
+];
+
+pub const COMMAND_PALETTE: &[Hint] = &[
+    key("Type", "search"),
+    key("↑/↓", "select"),
+    key("Enter", "run"),
+    key("Esc", "close"),
+];
+
+pub const BROWSE_DIRECTORY: &[Hint] = &[
+    key("↑/↓ or j/k", "select"),
+    key("Enter or →", "open"),
+    key("← or Backspace", "go up"),
+    key("Space", "work in the directory shown above"),
+    extra("1-9", "jump to a recent directory"),
+    extra("'.'", "show hidden"),
+    extra("'~'", "home"),
+    key("Esc", "return"),
+];
+
+// Every hint, for screens where '?' can't be pressed (the key is typed)
+pub fn join(hints: &[Hint]) -> String {
+    hints
+        .iter()
+        .map(|hint| format!("{} {}", hint.keys, hint.action))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Help line fitting `width` columns: all of `hints` when they fit, otherwise
+// as many of the essential ones as fit, in order. Both end with the '?'
+// pointer.
+pub fn help_line(hints: &[Hint], width: u16) -> String {
+    let all: Vec<Hint> = hints.iter().copied().chain([PALETTE, SHOW_ALL]).collect();
+    let full = join(&all);
+    if full.chars().count() <= width as usize {
+        return full;
+    }
+
+    let mut shown = vec![SHOW_ALL];
+    for hint in hints.iter().filter(|hint| hint.essential) {
+        shown.insert(shown.len() - 1, *hint);
+        if join(&shown).chars().count() > width as usize {
+            shown.remove(shown.len() - 2);
+        }
+    }
+    join(&shown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn narrow_help_keeps_the_essential_keys() {
+        let wide = help_line(MAIN_MENU, 500);
+        assert!(wide.contains("'a' adopt the current directory as a project"));
+        assert!(wide.ends_with("'q' or Esc quit, ':' or Ctrl-P command palette, '?' all keys"));
+
+        let narrow = help_line(MAIN_MENU, 70);
+        assert_eq!(narrow, "↑/↓ or j/k navigate, Enter select, 'q' or Esc quit, '?' all keys");
+
+        // Essential keys that don't fit are left to the overlay
+        let compile = help_line(COMPILE_PROJECTS, 58);
+        assert_eq!(compile, "Tab/Shift-Tab switch view, Enter execute, '?' all keys");
+        for hints in [CREATE, EDIT, VCD_FILES, WAVEFORM, BROWSE_DIRECTORY, MODULE_SNIPPETS, REFERENCES, ERROR_DETAIL] {
+            assert!(help_line(hints, 58).chars().count() <= 58);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::error::HadouError;
+use crate::scanner;
+
+pub const HISTORY_FILE: &str = ".hadou-history.json";
+
+// Older runs are pruned once a project has recorded this many
+const MAX_ENTRIES: usize = 100;
+
+// Runs the pass rate in the summary is taken over
+const TREND_RUNS: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub timestamp: DateTime<Local>,
+    // just recipe that was run
+    pub action: String,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+}
+
+impl HistoryEntry {
+    pub fn new(action: &str, success: bool, exit_code: Option<i32>, duration: Duration) -> Self {
+        Self {
+            timestamp: Local::now(),
+            action: action.to_string(),
+            success,
+            exit_code,
+            duration_ms: duration.as_millis() as u64,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.duration_ms)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct HistoryFile {
+    entries: Vec<HistoryEntry>,
+}
+
+pub fn history_path(project: &Path) -> PathBuf {
+    project.join(HISTORY_FILE)
+}
+
+// Recorded runs, oldest first. A missing or unreadable file is an empty history.
+pub fn load(project: &Path) -> Vec<HistoryEntry> {
+    fs::read_to_string(history_path(project))
+        .ok()
+        .and_then(|content| serde_json::from_str::<HistoryFile>(&content).ok())
+        .map(|history| history.entries)
+        .unwrap_or_default()
+}
+
+pub fn record(project: &Path, entry: HistoryEntry) -> Result<(), HadouError> {
+    let mut entries = load(project);
+    entries.push(entry);
+    if entries.len() > MAX_ENTRIES {
+        let excess = entries.len() - MAX_ENTRIES;
+        entries.drain(..excess);
+    }
+
+    fs::write(history_path(project), serde_json::to_string_pretty(&HistoryFile { entries })?)?;
+    Ok(())
+}
+
+// e.g. "last built 5m ago, passed · 8 of the last 10 runs passed"
+pub fn summary(entries: &[HistoryEntry]) -> Option<String> {
+    let last = entries.last()?;
+    let outcome = if last.success { "passed" } else { "failed" };
+    let mut summary = format!("last built {}, {}", scanner::format_age(last.timestamp.into()), outcome);
+
+    let recent = &entries[entries.len().saturating_sub(TREND_RUNS)..];
+    if recent.len() > 1 {
+        let passed = recent.iter().filter(|entry| entry.success).count();
+        summary.push_str(&format!(" · {} of the last {} runs passed", passed, recent.len()));
+    }
+
+    Some(summary)
+}
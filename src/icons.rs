@@ -0,0 +1,81 @@
+use std::env;
+
+use crate::config::Config;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Icon {
+    Wave,
+    Folder,
+    Edit,
+    Gear,
+    Chart,
+    File,
+    Test,
+    Justfile,
+    Found,
+    Missing,
+    Rocket,
+    Broom,
+    Info,
+    Monitor,
+    Wrench,
+    Star,
+}
+
+impl Icon {
+    // Emoji glyphs that render narrower than two cells carry a trailing space
+    // so both variants line up the same way in lists
+    pub fn glyph(self, ascii_mode: bool) -> &'static str {
+        if ascii_mode {
+            match self {
+                Icon::Wave => "~~",
+                Icon::Folder => "[D]",
+                Icon::Edit => "[E]",
+                Icon::Gear => "[*]",
+                Icon::Chart => "[~]",
+                Icon::File => "[F]",
+                Icon::Test => "[T]",
+                Icon::Justfile => "[J]",
+                Icon::Found => "[+]",
+                Icon::Missing => "[-]",
+                Icon::Rocket => "[>]",
+                Icon::Broom => "[C]",
+                Icon::Info => "[i]",
+                Icon::Monitor => "[G]",
+                Icon::Wrench => "[S]",
+                Icon::Star => "[P]",
+            }
+        } else {
+            match self {
+                Icon::Wave => "🌊",
+                Icon::Folder => "📁",
+                Icon::Edit => "✏️ ",
+                Icon::Gear => "⚙️ ",
+                Icon::Chart => "📊",
+                Icon::File => "📄",
+                Icon::Test => "🧪",
+                Icon::Justfile => "⚡",
+                Icon::Found => "✅",
+                Icon::Missing => "❌",
+                Icon::Rocket => "🚀",
+                Icon::Broom => "🧹",
+                Icon::Info => "ℹ️ ",
+                Icon::Monitor => "🖥️ ",
+                Icon::Wrench => "🔧",
+                Icon::Star => "⭐",
+            }
+        }
+    }
+}
+
+// ASCII mode is on when enabled in the config or requested through the
+// NO_EMOJI / HADOU_ASCII environment variables
+pub fn ascii_mode_enabled(config: &Config) -> bool {
+    config.ascii_mode || env_flag_set("NO_EMOJI") || env_flag_set("HADOU_ASCII")
+}
+
+fn env_flag_set(name: &str) -> bool {
+    env::var(name)
+        .map(|value| !value.is_empty() && value != "0" && !value.eq_ignore_ascii_case("false"))
+        .unwrap_or(false)
+}
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::SystemTime;
+
+use crate::adopt;
+use crate::build_system::{self, BuildSystem};
+use crate::create_new_project;
+use crate::edit_project::{self, project_label};
+use crate::error::HadouError;
+use crate::language::Language;
+use crate::project_config::{self, ProjectConfig};
+use crate::safe_write;
+use crate::testbench;
+
+pub const JUSTFILE_NAMES: [&str; 2] = ["Justfile", "justfile"];
+
+pub fn find_justfile(project: &Path) -> Option<PathBuf> {
+    JUSTFILE_NAMES.iter().map(|name| project.join(name)).find(|path| path.is_file())
+}
+
+// Names of the recipes (and aliases) a justfile defines, in file order.
+// Only top-level lines are looked at: `name param: deps`, `@name:` and
+// `alias name := target`. Settings, assignments, attributes and recipe
+// bodies are skipped. None when the justfile imports others, since their
+// recipes can't be seen from here.
+pub fn parse_recipes(content: &str) -> Option<Vec<String>> {
+    let mut recipes = Vec::new();
+
+    for line in content.lines() {
+        if line.starts_with(char::is_whitespace) || line.starts_with('#') || line.starts_with('[') {
+            continue;
+        }
+
+        if ["import ", "import? ", "mod ", "mod? "].iter().any(|keyword| line.starts_with(keyword)) {
+            return None;
+        }
+
+        if let Some(alias) = line.strip_prefix("alias ") {
+            if let Some((name, _)) = alias.split_once(":=") {
+                recipes.push(name.trim().to_string());
+            }
+            continue;
+        }
+
+        // The header's colon, as opposed to the one in `:=`
+        let Some(colon) = line.find(':').filter(|&colon| !line[colon + 1..].starts_with('=')) else {
+            continue;
+        };
+        let header = line[..colon].trim_start_matches('@');
+        let Some(name) = header.split_whitespace().next() else {
+            continue;
+        };
+
+        if name != "set" && name != "export" && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            recipes.push(name.to_string());
+        }
+    }
+
+    Some(recipes)
+}
+
+// Program the first command of `recipe` runs, passing over `echo` lines,
+// e.g. "iverilog" for a generated `compile` recipe. Makefile targets are laid
+// out the same way, so this reads those too.
+pub fn recipe_program(content: &str, recipe: &str) -> Option<String> {
+    let mut in_recipe = false;
+
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if !line.starts_with(char::is_whitespace) {
+            let header = line.split(':').next().unwrap_or_default().trim_start_matches('@');
+            in_recipe = !line.contains(":=") && header.split_whitespace().next() == Some(recipe);
+            continue;
+        }
+
+        if !in_recipe {
+            continue;
+        }
+        let command = line.trim().trim_start_matches(['@', '-']);
+        match command.split_whitespace().next() {
+            Some(program) if program != "echo" && !program.starts_with('#') => return Some(program.to_string()),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+// What `just --summary` made of a project's justfile
+#[derive(Debug, Clone, PartialEq)]
+pub enum JustfileStatus {
+    // The recipes just listed
+    Valid(Vec<String>),
+    // just's error, with the line and column it points at
+    Invalid(String),
+}
+
+// Have just itself parse the project's justfile. --summary parses the whole
+// file without running anything, so errors show up here rather than halfway
+// through a run.
+pub fn validate(project: &Path) -> Result<JustfileStatus, HadouError> {
+    let path = find_justfile(project).ok_or_else(|| HadouError::NoBuildFile(project.to_path_buf()))?;
+    let output = Command::new(BuildSystem::Just.program())
+        .arg("--justfile")
+        .arg(&path)
+        .arg("--working-directory")
+        .arg(project)
+        .arg("--summary")
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => HadouError::tool_not_found("just", BuildSystem::Just.install_hint()),
+            _ => HadouError::Io(e),
+        })?;
+
+    if output.status.success() {
+        let summary = String::from_utf8_lossy(&output.stdout);
+        return Ok(JustfileStatus::Valid(summary.split_whitespace().map(str::to_string).collect()));
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let message = stderr.trim_end();
+    Ok(JustfileStatus::Invalid(if message.is_empty() {
+        format!("just exited with code {}", output.status.code().unwrap_or(-1))
+    } else {
+        message.to_string()
+    }))
+}
+
+// Replace the project's justfile with a freshly generated one, backing up the
+// old file. The PROJECT_NAME it set is kept when it can still be read, so the
+// VCD keeps its name.
+pub fn regenerate(project: &Path, simulator: &str) -> Result<Option<PathBuf>, HadouError> {
+    let path = find_justfile(project).unwrap_or_else(|| project.join(BuildSystem::Just.file_name()));
+    let name = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| edit_project::justfile_project_name(&content))
+        .unwrap_or_else(|| project_label(project));
+    let config = ProjectConfig::load(project)?;
+
+    let content = if config.adopted {
+        adopt::adopted_justfile(project, &name, simulator, &config)?
+    } else if Language::detect(project) == Some(Language::Vhdl) {
+        create_new_project::generate_ghdl_justfile(&name)
+    } else {
+        let content = create_new_project::generate_justfile(&name, simulator, &config.compiler_flags());
+        let content = project_config::with_build_settings(&content, BuildSystem::Just, &config).unwrap_or(content);
+        match testbench::vcd_name(project, Language::Verilog.testbench_file()) {
+            Some(vcd) if vcd != format!("{}.vcd", name) => testbench::with_vcd_file(&content, &vcd),
+            _ => content,
+        }
+    };
+    safe_write::write_with_backup(&path, &content)
+}
+
+// Recipe (or make target) lists per project, re-read only when the build
+// file changes
+#[derive(Debug, Default)]
+pub struct RecipeCache {
+    entries: HashMap<PathBuf, (Option<SystemTime>, Option<Vec<String>>)>,
+}
+
+impl RecipeCache {
+    // None when the project has no readable build file or one whose recipes
+    // can't all be seen, so nothing can be said about which recipes exist
+    pub fn recipes(&mut self, project: &Path) -> Option<&[String]> {
+        let (system, path) = build_system::find_build_file(project)?;
+        let modified = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok();
+
+        let stale = self.entries.get(project).is_none_or(|(cached, _)| *cached != modified);
+        if stale {
+            let recipes = fs::read_to_string(&path).ok().and_then(|content| system.parse_targets(&content));
+            self.entries.insert(project.to_path_buf(), (modified, recipes));
+        }
+
+        self.entries.get(project).and_then(|(_, recipes)| recipes.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regenerating_keeps_the_project_name_and_backs_up() {
+        let project = std::env::temp_dir().join(format!("hadou-justfile-{}", std::process::id())).join("alu");
+        let _ = fs::remove_dir_all(&project);
+        fs::create_dir_all(&project).unwrap();
+        fs::write(project.join("main.v"), "module alu8; endmodule\n").unwrap();
+        let broken = "PROJECT_NAME := \"alu8\"\ncompile:\n    iverilog -o {{VVP_FILE main.v\n";
+        fs::write(project.join("Justfile"), broken).unwrap();
+
+        let backup = regenerate(&project, "iverilog").unwrap().unwrap();
+        assert_eq!(fs::read_to_string(backup).unwrap(), broken);
+        let content = fs::read_to_string(project.join("Justfile")).unwrap();
+        assert!(content.contains("PROJECT_NAME := \"alu8\""));
+        assert!(parse_recipes(&content).unwrap().contains(&"simulate".to_string()));
+        fs::remove_dir_all(project.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,357 @@
+use crossterm::event::KeyCode;
+use std::collections::HashMap;
+
+use crate::error::HadouError;
+
+// Commands shared by several screens that can be bound to other keys under
+// [keys] in config.toml. Keys only one screen uses stay fixed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    NavigateUp,
+    NavigateDown,
+    NavigateLeft,
+    NavigateRight,
+    Select,
+    Back,
+    Quit,
+    Refresh,
+    Search,
+    Sort,
+    CopyPath,
+    Pin,
+    ShowCommand,
+    NextTab,
+    PreviousTab,
+}
+
+impl Action {
+    pub const ALL: [Action; 15] = [
+        Action::NavigateUp,
+        Action::NavigateDown,
+        Action::NavigateLeft,
+        Action::NavigateRight,
+        Action::Select,
+        Action::Back,
+        Action::Quit,
+        Action::Refresh,
+        Action::Search,
+        Action::Sort,
+        Action::CopyPath,
+        Action::Pin,
+        Action::ShowCommand,
+        Action::NextTab,
+        Action::PreviousTab,
+    ];
+
+    // Name used in config.toml
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::NavigateUp => "navigate_up",
+            Action::NavigateDown => "navigate_down",
+            Action::NavigateLeft => "navigate_left",
+            Action::NavigateRight => "navigate_right",
+            Action::Select => "select",
+            Action::Back => "back",
+            Action::Quit => "quit",
+            Action::Refresh => "refresh",
+            Action::Search => "search",
+            Action::Sort => "sort",
+            Action::CopyPath => "copy_path",
+            Action::Pin => "pin",
+            Action::ShowCommand => "show_command",
+            Action::NextTab => "next_tab",
+            Action::PreviousTab => "previous_tab",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    // The keys Hadou has always used, h/j/k/l included
+    fn default_keys(self) -> Vec<KeyCode> {
+        match self {
+            Action::NavigateUp => vec![KeyCode::Up, KeyCode::Char('k')],
+            Action::NavigateDown => vec![KeyCode::Down, KeyCode::Char('j')],
+            Action::NavigateLeft => vec![KeyCode::Left, KeyCode::Char('h')],
+            Action::NavigateRight => vec![KeyCode::Right, KeyCode::Char('l')],
+            Action::Select => vec![KeyCode::Enter],
+            Action::Back => vec![KeyCode::Esc],
+            Action::Quit => vec![KeyCode::Char('q')],
+            Action::Refresh => vec![KeyCode::Char('r')],
+            Action::Search => vec![KeyCode::Char('/')],
+            Action::Sort => vec![KeyCode::Char('s')],
+            Action::CopyPath => vec![KeyCode::Char('y')],
+            Action::Pin => vec![KeyCode::Char('p')],
+            Action::ShowCommand => vec![KeyCode::Char('!')],
+            Action::NextTab => vec![KeyCode::Tab],
+            Action::PreviousTab => vec![KeyCode::BackTab],
+        }
+    }
+}
+
+// Keys screens handle themselves, which an action can't be moved onto
+// without hiding the screen's own command. The actions' default keys are
+// exempt: screens that use one of those don't handle that action.
+const SCREEN_KEYS: [(&str, &[KeyCode]); 14] = [
+    ("every screen", &[KeyCode::Char('?'), KeyCode::Char(':')]),
+    ("the main menu", &[KeyCode::Char('a'), KeyCode::Char('n')]),
+    ("the create screen", &[KeyCode::F(2), KeyCode::F(3), KeyCode::Backspace]),
+    (
+        "the compile screen",
+        &[
+            KeyCode::Char(' '),
+            KeyCode::Char('a'),
+            KeyCode::Char('c'),
+            KeyCode::Char('d'),
+            KeyCode::Char('D'),
+            KeyCode::Char('e'),
+            KeyCode::Char('f'),
+            KeyCode::Char('i'),
+            KeyCode::Char('J'),
+            KeyCode::Char('l'),
+            KeyCode::Char('N'),
+            KeyCode::Char('t'),
+            KeyCode::Char('w'),
+        ],
+    ),
+    (
+        "the edit screen",
+        &[
+            KeyCode::Char('a'),
+            KeyCode::Char('A'),
+            KeyCode::Char('d'),
+            KeyCode::Char('f'),
+            KeyCode::Char('g'),
+            KeyCode::Char('i'),
+            KeyCode::Char('n'),
+            KeyCode::Char('N'),
+            KeyCode::Char('t'),
+            KeyCode::Char('z'),
+        ],
+    ),
+    ("the directory browser", &[KeyCode::Char(' '), KeyCode::Char('.'), KeyCode::Char('~'), KeyCode::Backspace]),
+    (
+        "the waveform list",
+        &[KeyCode::Char('c'), KeyCode::Char('i'), KeyCode::Char('o'), KeyCode::Char('v'), KeyCode::Char('w')],
+    ),
+    (
+        "the waveform viewer",
+        &[
+            KeyCode::Char('+'),
+            KeyCode::Char('='),
+            KeyCode::Char('-'),
+            KeyCode::Char('a'),
+            KeyCode::Char('['),
+            KeyCode::Char(']'),
+            KeyCode::Char('x'),
+            KeyCode::Char('n'),
+            KeyCode::Char('c'),
+            KeyCode::Char('.'),
+            KeyCode::Char('>'),
+            KeyCode::Char(','),
+            KeyCode::Char('<'),
+            KeyCode::Char('e'),
+            KeyCode::Char('t'),
+            KeyCode::Char('r'),
+            KeyCode::Char('v'),
+            KeyCode::Char('W'),
+            KeyCode::Char('f'),
+            KeyCode::Char('g'),
+            KeyCode::Char('G'),
+            KeyCode::Char('s'),
+        ],
+    ),
+    ("the web viewer screen", &[KeyCode::Char('o')]),
+    ("pick dialogs", &[KeyCode::Char('d'), KeyCode::Char('m')]),
+    ("confirm dialogs", &[KeyCode::Char('y'), KeyCode::Char('n')]),
+    ("message dialogs", &[KeyCode::Char('d'), KeyCode::PageUp, KeyCode::PageDown, KeyCode::Home, KeyCode::End]),
+    ("text input", &[KeyCode::Backspace]),
+    ("the settings screen", &[KeyCode::Char('s')]),
+];
+
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyCode, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .into_iter()
+            .flat_map(|action| action.default_keys().into_iter().map(move |key| (key, action)))
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    // Build the keymap from the [keys] table, e.g. `navigate_up = ["Up", "e"]`.
+    // Listing an action replaces its default keys; the rest keep theirs.
+    pub fn from_config(keys: &HashMap<String, Vec<String>>) -> Result<Self, HadouError> {
+        let mut names: Vec<&String> = keys.keys().collect();
+        names.sort();
+
+        let mut overrides = HashMap::new();
+        for name in names {
+            let action = Action::parse(name)
+                .ok_or_else(|| HadouError::Keymap(format!("'{}' is not an action that can be bound", name)))?;
+            if keys[name].is_empty() {
+                return Err(HadouError::Keymap(format!("{} has no keys", name)));
+            }
+            let parsed = keys[name]
+                .iter()
+                .map(|key| parse_key(key).ok_or_else(|| HadouError::Keymap(format!("'{}' for {} is not a key", key, name))))
+                .collect::<Result<Vec<_>, _>>()?;
+            for key in parsed.iter().filter(|key| !action.default_keys().contains(key)) {
+                if let Some((screen, _)) = SCREEN_KEYS.iter().find(|(_, keys)| keys.contains(key)) {
+                    return Err(HadouError::Keymap(format!("{} for {} is already used by {}", key_label(*key), name, screen)));
+                }
+            }
+            overrides.insert(action, parsed);
+        }
+
+        let mut bindings: HashMap<KeyCode, Action> = HashMap::new();
+        for action in Action::ALL {
+            let keys = overrides.remove(&action).unwrap_or_else(|| action.default_keys());
+            for key in keys {
+                // F5 re-runs the last compilation on every screen
+                if key == KeyCode::F(5) {
+                    return Err(HadouError::Keymap(format!("F5 for {} is taken by re-running the last compilation", action.name())));
+                }
+                match bindings.insert(key, action) {
+                    Some(other) if other != action => {
+                        return Err(HadouError::Keymap(format!(
+                            "{} is bound to both {} and {}",
+                            key_label(key),
+                            other.name(),
+                            action.name()
+                        )));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(Self { bindings })
+    }
+
+    // The action `key` is bound to. Screens pass the letters they use as
+    // commands of their own in `reserved` so those keep working.
+    pub fn action(&self, key: KeyCode, reserved: &[char]) -> Option<Action> {
+        match key {
+            KeyCode::Char(c) if reserved.contains(&c) => None,
+            _ => self.bindings.get(&key).copied(),
+        }
+    }
+
+    // Every key bound to `action`, for telling the user which to press
+    pub fn keys_for(&self, action: Action) -> String {
+        let mut keys: Vec<String> = self.bindings
+            .iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(key, _)| key_label(*key))
+            .collect();
+        keys.sort();
+        keys.join(" or ")
+    }
+
+    // Like `action`, for screens where letters are typed as text
+    pub fn text_action(&self, key: KeyCode) -> Option<Action> {
+        match key {
+            KeyCode::Char(_) => None,
+            _ => self.bindings.get(&key).copied(),
+        }
+    }
+}
+
+// "Up", "PageDown", "Esc", "F2", "Space" or a single character
+fn parse_key(key: &str) -> Option<KeyCode> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+
+    let named = match key.to_ascii_lowercase().as_str() {
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backtab" | "shift+tab" => KeyCode::BackTab,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "space" => KeyCode::Char(' '),
+        other => {
+            let number = other.strip_prefix('f')?.parse().ok().filter(|n| (1..=12).contains(n))?;
+            KeyCode::F(number)
+        }
+    };
+    Some(named)
+}
+
+fn key_label(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => format!("'{}'", c),
+        KeyCode::F(n) => format!("F{}", n),
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(entries: &[(&str, &[&str])]) -> HashMap<String, Vec<String>> {
+        entries
+            .iter()
+            .map(|(action, keys)| (action.to_string(), keys.iter().map(|key| key.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn overrides_replace_the_default_keys() {
+        let keymap = Keymap::from_config(&keys(&[("navigate_up", &["Up", "u"]), ("navigate_down", &["Down", "b"])])).unwrap();
+        assert_eq!(keymap.action(KeyCode::Char('u'), &[]), Some(Action::NavigateUp));
+        assert_eq!(keymap.action(KeyCode::Char('b'), &[]), Some(Action::NavigateDown));
+        assert_eq!(keymap.action(KeyCode::Char('k'), &[]), None);
+        assert_eq!(keymap.action(KeyCode::Char('h'), &[]), Some(Action::NavigateLeft));
+        assert_eq!(keymap.action(KeyCode::Char('h'), &['h']), None);
+        assert_eq!(keymap.text_action(KeyCode::Char('u')), None);
+        assert_eq!(keymap.text_action(KeyCode::Up), Some(Action::NavigateUp));
+        assert_eq!(keymap.keys_for(Action::NavigateUp), "'u' or Up");
+        assert_eq!(keymap.keys_for(Action::Quit), "'q'");
+    }
+
+    #[test]
+    fn bad_bindings_are_rejected() {
+        let error = Keymap::from_config(&keys(&[("refresh", &["k"])])).unwrap_err();
+        assert_eq!(error.to_string(), "'k' is bound to both navigate_up and refresh");
+        assert!(Keymap::from_config(&keys(&[("jump", &["j"])])).is_err());
+        assert!(Keymap::from_config(&keys(&[("quit", &["Ctrl+Q"])])).is_err());
+        assert!(Keymap::from_config(&keys(&[("back", &[])])).is_err());
+        assert!(Keymap::from_config(&keys(&[("refresh", &["F5"])])).is_err());
+        assert!(Keymap::from_config(&keys(&[("refresh", &["F6"]), ("select", &["Enter", "Insert"])])).is_ok());
+    }
+
+    #[test]
+    fn screen_keys_cannot_be_taken() {
+        let error = Keymap::from_config(&keys(&[("navigate_up", &["Up", "a"])])).unwrap_err();
+        assert_eq!(error.to_string(), "'a' for navigate_up is already used by the main menu");
+        let error = Keymap::from_config(&keys(&[("refresh", &["d"])])).unwrap_err();
+        assert_eq!(error.to_string(), "'d' for refresh is already used by the compile screen");
+        assert!(Keymap::from_config(&keys(&[("select", &["Enter", "Space"])])).is_err());
+        assert!(Keymap::from_config(&keys(&[("search", &["F2"])])).is_err());
+
+        // Listing an action's own default keys again is fine
+        assert!(Keymap::from_config(&keys(&[("refresh", &["r", "F6"]), ("sort", &["s"])])).is_ok());
+    }
+}
@@ -0,0 +1,90 @@
+use std::fs;
+use std::path::Path;
+
+// Hardware description language a project is written in. Verilog projects
+// are built with Icarus Verilog, VHDL projects with GHDL.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Language {
+    #[default]
+    Verilog,
+    Vhdl,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::Verilog, Language::Vhdl];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Verilog => "Verilog",
+            Language::Vhdl => "VHDL",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Language::Verilog => "Verilog (Icarus Verilog)",
+            Language::Vhdl => "VHDL (GHDL)",
+        }
+    }
+
+    pub fn design_file(&self) -> &'static str {
+        match self {
+            Language::Verilog => "main.v",
+            Language::Vhdl => "main.vhd",
+        }
+    }
+
+    pub fn testbench_file(&self) -> &'static str {
+        match self {
+            Language::Verilog => "main_test.v",
+            Language::Vhdl => "main_test.vhd",
+        }
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            Language::Verilog => &["v", "sv"],
+            Language::Vhdl => &["vhd", "vhdl"],
+        }
+    }
+
+    // Language of a source file, by its extension
+    pub fn of_file(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|language| language.extensions().contains(&extension.as_str()))
+    }
+
+    // Language of the project in `dir`, going by its main design file or
+    // else by whichever sources it has
+    pub fn detect(dir: &Path) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|language| dir.join(language.design_file()).is_file())
+            .or_else(|| {
+                fs::read_dir(dir)
+                    .ok()?
+                    .flatten()
+                    .find_map(|entry| Self::of_file(&entry.path()))
+            })
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Language::Verilog => Language::Vhdl,
+            Language::Vhdl => Language::Verilog,
+        }
+    }
+}
+
+// Whether `dir` directly contains any Verilog or VHDL source
+pub fn has_source_files(dir: &Path) -> bool {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .any(|entry| entry.path().is_file() && Language::of_file(&entry.path()).is_some())
+        })
+        .unwrap_or(false)
+}
@@ -0,0 +1,79 @@
+use std::cell::Cell;
+use std::ops::Range;
+
+// Scroll position of a list that only builds the rows it can show, so a VCD
+// with thousands of signals costs as much to draw as one with twenty.
+// Rendering only sees `&App`, so the offset is kept in a Cell and moved
+// while drawing.
+#[derive(Debug, Clone, Default)]
+pub struct ListWindow {
+    offset: Cell<usize>,
+}
+
+impl ListWindow {
+    // Rows of a `len` long list that fit in `height`, scrolling as little as
+    // possible to keep `selected` (a row position, not an entry index) on screen
+    pub fn rows(&self, len: usize, selected: Option<usize>, height: u16) -> Range<usize> {
+        let height = (height as usize).max(1);
+        let mut offset = self.offset.get().min(len.saturating_sub(height));
+
+        if let Some(selected) = selected {
+            if selected < offset {
+                offset = selected;
+            } else if selected >= offset + height {
+                offset = selected + 1 - height;
+            }
+        }
+
+        self.offset.set(offset);
+        offset..len.min(offset + height)
+    }
+}
+
+// Scroll position of a block of text such as a preview or a message. Like
+// `ListWindow` it is clamped while drawing, which is also when it learns how
+// many rows a page is.
+#[derive(Debug, Clone, Default)]
+pub struct TextScroll {
+    offset: Cell<usize>,
+    height: Cell<usize>,
+}
+
+impl TextScroll {
+    pub fn scroll_up(&self, lines: usize) {
+        self.offset.set(self.offset.get().saturating_sub(lines));
+    }
+
+    pub fn scroll_down(&self, lines: usize) {
+        self.offset.set(self.offset.get().saturating_add(lines));
+    }
+
+    // Rows shown at a time, less one so a line of context stays on screen
+    pub fn page(&self) -> usize {
+        self.height.get().saturating_sub(1).max(1)
+    }
+
+    pub fn reset(&self) {
+        self.offset.set(0);
+    }
+
+    // First line to draw of `lines` in `height` rows
+    pub fn offset(&self, lines: usize, height: u16) -> u16 {
+        let height = height as usize;
+        let offset = self.offset.get().min(lines.saturating_sub(height));
+        self.offset.set(offset);
+        self.height.set(height);
+        offset.min(u16::MAX as usize) as u16
+    }
+
+    // Lines of `lines` on screen as of the last draw
+    pub fn visible(&self, lines: usize) -> Range<usize> {
+        let offset = self.offset.get().min(lines);
+        offset..lines.min(offset + self.height.get())
+    }
+
+    // Whether there is more text than fits, as of the last draw
+    pub fn overflows(&self, lines: usize) -> bool {
+        lines > self.height.get()
+    }
+}
@@ -1,5 +1,8 @@
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event, KeyCode,
+        KeyEventKind, KeyModifiers,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -9,22 +12,87 @@ use ratatui::{
     layout::{Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    symbols::Marker,
+    widgets::{canvas::{self, Canvas}, Block, Borders, Cell, Clear, Gauge, List, ListItem, Paragraph, Row, Table, Tabs, Wrap},
     Frame, Terminal
 };
 
-use catppuccin::PALETTE;
+use catppuccin::{FlavorColors, FlavorName};
 
+use std::collections::{HashMap, HashSet};
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+mod adopt;
+mod archive;
+mod build_system;
+mod cli;
+mod clipboard;
+mod command_line;
+mod config;
 mod create_new_project;
+mod dir_browser;
 mod edit_project;
+mod env_file;
+mod error;
+mod file_template;
+mod fuzzy;
+mod health;
+mod hints;
+mod history;
+mod icons;
+mod justfile;
+mod keymap;
+mod language;
+mod list_window;
+mod notes;
+mod palette;
 mod compile_project;
+mod project_config;
+mod safe_write;
+mod scanner;
+mod settings;
+mod sim_results;
+mod syntax_check;
+mod testbench;
+mod theme;
+mod vcd_diff;
+mod verilog;
+mod waveform_viewer;
+mod watcher;
+mod web_viewer;
 
-use create_new_project::ProjectCreator;
-use edit_project::ProjectEditor;
-use compile_project::ProjectCompiler;
+use archive::ArchiveFormat;
+use build_system::BuildSystem;
+use clipboard::SystemClipboard;
+use create_new_project::{CreateField, ProjectCreator, QuickSpec};
+use dir_browser::DirectoryBrowser;
+use error::HadouError;
+use file_template::FileTemplate;
+use edit_project::{project_label, EditorKind, EditorLaunch, ModuleSnippet, OpenMode, ProjectEditor, Reference, TopModuleCache};
+use compile_project::{BatchRun, CompileTab, OutputPhase, ProjectCompiler};
+use config::Config;
+use fuzzy::{FuzzyMatch, ListSearch};
+use health::{ProjectHealth, ToolStatus};
+use hints::Hint;
+use icons::Icon;
+use justfile::JustfileStatus;
+use keymap::{Action, Keymap};
+use language::Language;
+use list_window::{ListWindow, TextScroll};
+use palette::{Command, CommandPalette};
+use project_config::ProjectConfig;
+use scanner::{ListEntry, ScanOptions, SortMode};
+use settings::{command_exists, SettingField, SettingsEditor};
+use syntax_check::SyntaxStatus;
+use vcd_diff::DiffMode;
+use verilog::PortDirection;
+use waveform_viewer::{Radix, Signal, ValueTarget, VcdSummary, WaveformViewer};
+use watcher::{DirectoryWatcher, WatchChange};
+use web_viewer::WebServer;
+
+const EVENT_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum AppMode {
@@ -33,400 +101,4414 @@ pub enum AppMode {
     CompileProject,
     EditProject,
     ViewWaveform,
+    InternalWaveform,
+    Settings,
+    ModuleSnippets,
+    References,
+    // Picking another base directory to scan
+    BrowseDirectory,
+    PickDialog,
     InputDialog,
-    MessageDialog
+    ConfirmDialog,
+    // A VCD file is loading into the built-in viewer
+    LoadingVcd,
+    // A VCD file is being served to the browser
+    WebViewer,
+    MessageDialog,
+    // Full output of the failed run a message reports, opened from it
+    ErrorDetail,
+    // Every key of the screen underneath, opened with '?'
+    KeyHelp,
+    // Actions of every screen, searched by name; opened with ':' or Ctrl-P
+    CommandPalette,
+}
+
+#[derive(Debug, Clone)]
+pub struct MenuItem {
+    pub icon: Icon,
+    pub label: &'static str,
+    pub target: AppMode,
+}
+
+impl MenuItem {
+    pub fn new(icon: Icon, label: &'static str, target: AppMode) -> Self {
+        Self { icon, label, target }
+    }
+}
+
+// Entries shown on the main menu, in display order
+fn main_menu_items() -> Vec<MenuItem> {
+    vec![
+        MenuItem::new(Icon::Folder, "Create New Project", AppMode::CreateProject),
+        MenuItem::new(Icon::Edit, "Edit Project", AppMode::EditProject),
+        MenuItem::new(Icon::Gear, "Compile Project", AppMode::CompileProject),
+        MenuItem::new(Icon::Chart, "View Waveform", AppMode::ViewWaveform),
+        MenuItem::new(Icon::Folder, "Change Directory", AppMode::BrowseDirectory),
+        MenuItem::new(Icon::Wrench, "Settings", AppMode::Settings),
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputAction {
+    DuplicateProject,
+    RenameProject,
+    SaveSignalView,
+    EditEnvValue,
+    QuickCreate,
+    EditIverilogFlags,
+    EditSources,
+    FindReferences,
+    // Value of the selected signal to jump to, read in the viewer's radix
+    FindValue { radix: Radix },
+    // Appends to a project's notes from the compile or edit screen
+    AddNote { project: PathBuf, from: AppMode },
+}
+
+impl InputAction {
+    pub fn title(&self) -> &'static str {
+        match self {
+            InputAction::DuplicateProject => "Duplicate Project",
+            InputAction::RenameProject => "Rename Project",
+            InputAction::SaveSignalView => "Save Signal View",
+            InputAction::EditEnvValue => "Override Variable",
+            InputAction::QuickCreate => "Quick Create",
+            InputAction::EditIverilogFlags => "iverilog Flags",
+            InputAction::EditSources => "Extra Sources",
+            InputAction::FindReferences => "Find References",
+            InputAction::FindValue { .. } => "Find Value",
+            InputAction::AddNote { .. } => "Add Note",
+        }
+    }
+
+    pub fn prompt(&self) -> &'static str {
+        match self {
+            InputAction::DuplicateProject | InputAction::RenameProject => "New project name (alphanumeric, _ and - allowed):",
+            InputAction::SaveSignalView => "View name:",
+            InputAction::EditEnvValue => "Value for this run:",
+            InputAction::QuickCreate => "project [name=module] [in=a:8,b:8] [out=y:8] [inout=io]",
+            InputAction::EditIverilogFlags => "Flags for this project, e.g. -g2012 -Wall -Iinclude -DSIM:",
+            InputAction::EditSources => "Extra sources and include dirs, e.g. rtl/*.v common/fifo.v -I include:",
+            InputAction::FindReferences => "Module or signal name:",
+            InputAction::FindValue { .. } => "Value of the selected signal (0x, 0d, 0b or 8'h.. override the radix):",
+            InputAction::AddNote { .. } => "Note (a TODO, pin number or observation):",
+        }
+    }
+
+    // Screen the dialog was opened from and returns to when cancelled
+    pub fn return_mode(&self) -> AppMode {
+        match self {
+            InputAction::DuplicateProject | InputAction::RenameProject | InputAction::FindReferences => AppMode::EditProject,
+            InputAction::SaveSignalView | InputAction::FindValue { .. } => AppMode::InternalWaveform,
+            InputAction::EditEnvValue | InputAction::EditIverilogFlags | InputAction::EditSources => AppMode::CompileProject,
+            InputAction::QuickCreate => AppMode::MainMenu,
+            InputAction::AddNote { from, .. } => from.clone(),
+        }
+    }
+
+    pub fn accepts(&self, c: char) -> bool {
+        match self {
+            InputAction::DuplicateProject | InputAction::RenameProject => c.is_alphanumeric() || c == '_' || c == '-',
+            InputAction::FindReferences => c.is_ascii_alphanumeric() || c == '_' || c == '$',
+            InputAction::SaveSignalView => c.is_alphanumeric() || c == '_' || c == '-' || c == ' ',
+            InputAction::FindValue { .. } => c.is_ascii_alphanumeric() || matches!(c, '_' | '\'' | '.'),
+            InputAction::EditEnvValue
+            | InputAction::QuickCreate
+            | InputAction::EditIverilogFlags
+            | InputAction::EditSources
+            | InputAction::AddNote { .. } => {
+                !c.is_control()
+            }
+        }
+    }
+
+    // Feedback shown under the input while typing; Enter is refused while
+    // this is an error
+    pub fn check(&self, input: &str) -> Option<Result<String, String>> {
+        match self {
+            InputAction::QuickCreate if !input.trim().is_empty() => Some(QuickSpec::parse(input).map(|spec| {
+                format!(
+                    "module {} with {} input(s), {} output(s), {} inout(s)",
+                    spec.module_name,
+                    spec.port_count(PortDirection::Input),
+                    spec.port_count(PortDirection::Output),
+                    spec.port_count(PortDirection::Inout),
+                )
+            })),
+            InputAction::FindValue { radix } if !input.trim().is_empty() => Some(
+                ValueTarget::parse(input, *radix)
+                    .map(|target| target.describe())
+                    .ok_or_else(|| format!("Not a {} value", radix.name())),
+            ),
+            _ => None,
+        }
+    }
+}
+
+// Actions that overwrite something and ask for a yes/no first
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfirmAction {
+    RegenerateTestbench,
+    ReplaceTestbench,
+    // Loading a dump this big into the built-in viewer stalls the UI for a while
+    OpenLargeVcd(PathBuf),
+    // Treat the base directory's loose sources as a project
+    AdoptDirectory(PathBuf),
+    // Replace a justfile just can't parse with a generated one
+    RegenerateJustfile(PathBuf),
+    // Quit while the listed work is still going
+    Quit(Vec<String>),
+}
+
+impl ConfirmAction {
+    pub fn title(&self) -> &'static str {
+        match self {
+            ConfirmAction::RegenerateTestbench => "Regenerate Testbench",
+            ConfirmAction::ReplaceTestbench => "Replace Testbench",
+            ConfirmAction::OpenLargeVcd(_) => "Large VCD File",
+            ConfirmAction::AdoptDirectory(_) => "Adopt Current Directory",
+            ConfirmAction::RegenerateJustfile(_) => "Regenerate Justfile",
+            ConfirmAction::Quit(_) => "Quit Hadou",
+        }
+    }
+
+    // Shown in red above the prompt
+    pub fn warning(&self) -> &'static str {
+        match self {
+            ConfirmAction::RegenerateTestbench | ConfirmAction::ReplaceTestbench => "Overwrites existing files",
+            ConfirmAction::OpenLargeVcd(_) => "Slow to load",
+            ConfirmAction::AdoptDirectory(_) => "Writes a Justfile and .hadou.toml",
+            ConfirmAction::RegenerateJustfile(_) => "Overwrites the Justfile",
+            ConfirmAction::Quit(_) => "Still running in the background",
+        }
+    }
+
+    pub fn prompt(&self) -> String {
+        match self {
+            ConfirmAction::RegenerateTestbench => "This overwrites main_test.v with an instantiation matching the current ports of main.v. Existing stimulus is kept where the module is already instantiated. The old file is backed up as main_test.v.bak.<timestamp>.",
+            ConfirmAction::ReplaceTestbench => "main_test.v already exists. Replace it with a fresh testbench for the top module of main.v? Its stimulus is lost; the old file is backed up as main_test.v.bak.<timestamp>.",
+            ConfirmAction::OpenLargeVcd(path) => {
+                return format!(
+                    "{} holds {} of value changes. The built-in viewer takes a while and a lot of memory to load it (Esc cancels the load); an external viewer ('o' on the waveform screen) copes better. Load it anyway?",
+                    vcd_label(path),
+                    compile_project::format_size(waveform_viewer::loaded_size(path))
+                );
+            }
+            ConfirmAction::AdoptDirectory(dir) => {
+                return format!(
+                    "List {} itself as a project? A Justfile compiling all of its Verilog files is written unless it already has a Justfile or Makefile, and .hadou.toml records the adoption so it stays listed.",
+                    dir.display()
+                );
+            }
+            ConfirmAction::RegenerateJustfile(project) => {
+                return format!(
+                    "just can't parse the Justfile of {} (its errors are in the output pane). Replace it with a freshly generated one? The broken file is backed up as Justfile.bak.<timestamp>, so recipes added by hand can be copied back from it.",
+                    project_label(project)
+                );
+            }
+            ConfirmAction::Quit(running) => {
+                let list: Vec<String> = running.iter().map(|work| format!("- {}", work)).collect();
+                return format!("{}\n\nQuitting stops all of this. Quit anyway?", list.join("\n"));
+            }
+        }
+        .to_string()
+    }
+
+    pub fn return_mode(&self) -> AppMode {
+        match self {
+            ConfirmAction::RegenerateTestbench | ConfirmAction::ReplaceTestbench => AppMode::EditProject,
+            ConfirmAction::OpenLargeVcd(_) => AppMode::ViewWaveform,
+            ConfirmAction::AdoptDirectory(_) | ConfirmAction::Quit(_) => AppMode::MainMenu,
+            ConfirmAction::RegenerateJustfile(_) => AppMode::CompileProject,
+        }
+    }
+}
+
+// Lists the pick dialog offers one entry from
+#[derive(Debug, Clone, PartialEq)]
+pub enum PickAction {
+    // External viewer for the selected VCD file
+    Viewer,
+    // Recipe to run in place of the one `project`'s build file lacks
+    Recipe { project: PathBuf, missing: String },
+    // Testbench `project`'s runs simulate
+    Testbench { project: PathBuf },
+    // VCD file to compare `first` against
+    CompareVcd { first: PathBuf, mode: DiffMode },
+    // Template to add to `project`
+    AddFile { project: PathBuf },
+    // Archive format to pack `project` into
+    Archive { project: PathBuf },
+}
+
+impl PickAction {
+    pub fn title(&self) -> String {
+        match self {
+            PickAction::Viewer => "Open with".to_string(),
+            PickAction::Recipe { missing, .. } => format!("No '{}' recipe, run instead", missing),
+            PickAction::Testbench { project } => format!("Testbench for {}", project_label(project)),
+            PickAction::CompareVcd { first, mode } => format!("Compare {}, {}", vcd_label(first), mode.label()),
+            PickAction::AddFile { project } => format!("Add to {}", project_label(project)),
+            PickAction::Archive { project } => format!("Pack {}", project_label(project)),
+        }
+    }
+
+    pub fn help(&self) -> &'static str {
+        match self {
+            PickAction::Viewer => "Enter open, 'd' open and make default, Esc cancel",
+            PickAction::Recipe { .. } => "Enter run, Esc cancel",
+            PickAction::Testbench { .. } => "Enter simulate this one from now on, Esc cancel",
+            PickAction::CompareVcd { .. } => "Enter compare, 'm' timing mode, Esc cancel",
+            PickAction::AddFile { .. } => "Enter add, Esc cancel",
+            PickAction::Archive { .. } => "Enter pack, Esc cancel",
+        }
+    }
+
+    pub fn return_mode(&self) -> AppMode {
+        match self {
+            PickAction::Viewer | PickAction::CompareVcd { .. } => AppMode::ViewWaveform,
+            PickAction::Recipe { .. } | PickAction::Testbench { .. } => AppMode::CompileProject,
+            PickAction::AddFile { .. } | PickAction::Archive { .. } => AppMode::EditProject,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct App {
     pub mode: AppMode,
+    pub menu_items: Vec<MenuItem>,
     pub selected_index: usize,
     pub project_creator: ProjectCreator,
     pub project_editor: ProjectEditor,
     pub project_compiler: ProjectCompiler,
-    pub vcd_files: Vec<PathBuf>,
+    pub waveform_viewer: WaveformViewer,
+    pub settings_editor: SettingsEditor,
+    pub dir_browser: DirectoryBrowser,
+    pub vcd_files: Vec<ListEntry>,
+    // Header-only details of each VCD, read when the list is scanned
+    pub vcd_summaries: HashMap<PathBuf, VcdSummary>,
     pub selected_vcd_index: usize,
+    pub vcd_search: ListSearch,
+    pub vcd_window: ListWindow,
+    pub sort_mode: SortMode,
+    pub health: ProjectHealth,
+    // Only present while auto refresh is enabled
+    pub watcher: Option<DirectoryWatcher>,
+    pub clipboard: SystemClipboard,
+    // Instantiation templates for the project picked on the edit screen
+    pub module_snippets: Vec<ModuleSnippet>,
+    pub selected_snippet_index: usize,
+    // Results of the last find references search on the edit screen
+    pub reference_query: String,
+    pub references: Vec<Reference>,
+    pub selected_reference_index: usize,
+    pub reference_window: ListWindow,
+    // Entries of the pick dialog. For viewers, None opens the first one
+    // found, like pressing Enter without a picker used to.
+    pub pick_action: Option<PickAction>,
+    pub pick_choices: Vec<Option<String>>,
+    pub selected_pick_index: usize,
     pub input_buffer: String,
+    pub input_action: Option<InputAction>,
+    pub confirm_action: Option<ConfirmAction>,
     pub message: String,
+    // Screen the message dialog goes back to when dismissed
+    pub message_return_mode: AppMode,
+    // Screen a cancelled or failed VCD load goes back to
+    pub loading_return_mode: AppMode,
+    // Local HTTP server for the VCD shown in the browser, stopped on Esc
+    pub web_server: Option<WebServer>,
+    // Whether a browser was opened for it, or why not
+    pub web_status: String,
+    pub message_scroll: TextScroll,
+    // Complete output of the failed run the message dialog reports, kept raw
+    // so it can be read and copied exactly
+    pub error_detail: Option<String>,
+    pub error_scroll: TextScroll,
+    // Result of copying the error output, shown in its title
+    pub error_status: Option<String>,
+    // Screen the '?' overlay lists the keys of and closes back to
+    pub key_help_return: AppMode,
+    pub key_help_scroll: TextScroll,
+    pub palette: CommandPalette,
+    // Screen the palette was opened over, where its commands start from
+    pub palette_return: AppMode,
+    // Shared by the compile and edit screens' project previews
+    pub preview_scroll: TextScroll,
+    // Top module of the selected projects' main.v, shown in the previews
+    pub top_modules: TopModuleCache,
+    pub config: Config,
+    // Keys for the actions shared between screens, from [keys] in the config
+    pub keymap: Keymap,
+    pub flavor: FlavorName,
+    pub ascii_mode: bool,
+    pub tick_count: u64,
+    // Terminal editor waiting for `run_app` to hand over the terminal
+    pub pending_editor: Option<EditorLaunch>,
     pub should_quit: bool
 }
 
 impl App {
-    pub fn new() -> Self {
+    pub fn new(config: Config, flavor: FlavorName) -> Self {
+        let scan_options = ScanOptions::from_config(&config);
         let mut app = Self {
             mode: AppMode::MainMenu,
+            menu_items: main_menu_items(),
             selected_index: 0,
             project_creator: ProjectCreator::new(),
-            project_editor: ProjectEditor::new(),
-            project_compiler: ProjectCompiler::new(),
+            project_editor: ProjectEditor::new(scan_options.clone()),
+            project_compiler: ProjectCompiler::new(scan_options.clone()),
+            waveform_viewer: WaveformViewer::new(scan_options),
+            settings_editor: SettingsEditor::new(&config),
+            dir_browser: DirectoryBrowser::default(),
             vcd_files: Vec::new(),
+            vcd_summaries: HashMap::new(),
             selected_vcd_index: 0,
+            vcd_search: ListSearch::default(),
+            vcd_window: ListWindow::default(),
+            sort_mode: SortMode::from_config(&config),
+            health: ProjectHealth::default(),
+            watcher: None,
+            clipboard: SystemClipboard::default(),
+            module_snippets: Vec::new(),
+            selected_snippet_index: 0,
+            reference_query: String::new(),
+            references: Vec::new(),
+            selected_reference_index: 0,
+            reference_window: ListWindow::default(),
+            pick_action: None,
+            pick_choices: Vec::new(),
+            selected_pick_index: 0,
             input_buffer: String::new(),
+            input_action: None,
+            confirm_action: None,
             message: String::new(),
+            message_return_mode: AppMode::MainMenu,
+            loading_return_mode: AppMode::MainMenu,
+            web_server: None,
+            web_status: String::new(),
+            message_scroll: TextScroll::default(),
+            error_detail: None,
+            error_scroll: TextScroll::default(),
+            error_status: None,
+            key_help_return: AppMode::MainMenu,
+            key_help_scroll: TextScroll::default(),
+            palette: CommandPalette::default(),
+            palette_return: AppMode::MainMenu,
+            preview_scroll: TextScroll::default(),
+            top_modules: TopModuleCache::default(),
+            ascii_mode: icons::ascii_mode_enabled(&config),
+            config,
+            keymap: Keymap::default(),
+            flavor,
+            tick_count: 0,
+            pending_editor: None,
             should_quit: false
         };
+        app.apply_config();
         app.scan_vcd_files();
+        app.refresh_health();
+        if let Some(reason) = app.config.load_error.clone() {
+            app.message = error_message("Could not load the settings", &HadouError::ConfigUnreadable(reason));
+            app.mode = AppMode::MessageDialog;
+        }
         app
     }
 
-    fn scan_vcd_files(&mut self) {
-        self.vcd_files.clear();
-        self.selected_vcd_index = 0;
+    fn refresh_health(&mut self) {
+        self.health = ProjectHealth::collect(&self.project_compiler, self.vcd_files.len(), &self.config);
+    }
 
-        // Scan current directory for VCD files
-        if let Ok(entries) = std::fs::read_dir(".") {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("vcd") {
-                    self.vcd_files.push(path);
-                }
+    // Push config values that affect behaviour out to the individual screens
+    fn apply_config(&mut self) {
+        let scan_options = ScanOptions::from_config(&self.config);
+        self.project_editor.scan_options = scan_options.clone();
+        self.project_editor.preferred_editor = self.config.editor.clone();
+        self.project_editor.editor_kind = self.config.editor_kind.as_deref().and_then(EditorKind::parse);
+        self.project_editor.open_mode = self.config.editor_open.as_deref().and_then(OpenMode::parse);
+        self.project_compiler.scan_options = scan_options.clone();
+        self.project_compiler.simulation_timeout = self.config.simulation_timeout;
+        self.project_compiler.project_timeouts = self.config.project_timeouts.clone();
+        self.apply_pinned();
+        self.set_sort_mode(SortMode::from_config(&self.config));
+        self.waveform_viewer.scan_options = scan_options.clone();
+        self.waveform_viewer.collect_warnings = self.config.vcd_parse_warnings;
+        self.apply_watch_config(scan_options);
+        self.project_creator.simulator = self.config.simulator.clone().unwrap_or_else(|| "iverilog".to_string());
+        self.project_creator.open_in_editor = self.config.open_after_create;
+        self.ascii_mode = icons::ascii_mode_enabled(&self.config);
+        self.apply_keymap_config();
+        if let Some(flavor) = self.config.flavor.as_deref().and_then(theme::parse_flavor) {
+            self.flavor = flavor;
+        }
+    }
+
+    fn apply_keymap_config(&mut self) {
+        match Keymap::from_config(&self.config.keys) {
+            Ok(keymap) => self.keymap = keymap,
+            Err(e) => {
+                self.keymap = Keymap::default();
+                self.message = error_message("Could not use the configured keys", &e);
+                self.message_return_mode = self.mode.clone();
+                self.mode = AppMode::MessageDialog;
             }
         }
+    }
 
-        // Also scan subdirectories
-        if let Ok(entries) = std::fs::read_dir(".") {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_dir() {
-                    if let Ok(sub_entries) = std::fs::read_dir(&path) {
-                        for sub_entry in sub_entries.flatten() {
-                            let sub_path = sub_entry.path();
-                            if sub_path.is_file() && sub_path.extension().and_then(|s| s.to_str()) == Some("vcd") {
-                                self.vcd_files.push(sub_path);
-                            }
-                        }
-                    }
-                }
+    fn apply_watch_config(&mut self, scan_options: ScanOptions) {
+        if !self.config.watch {
+            self.watcher = None;
+            return;
+        }
+
+        if let Some(watcher) = self.watcher.as_mut() {
+            watcher.scan_options = scan_options;
+            return;
+        }
+
+        match DirectoryWatcher::new(Path::new("."), scan_options) {
+            Ok(watcher) => self.watcher = Some(watcher),
+            Err(e) => {
+                self.message = format!("Could not watch the working directory: {}
+Use 'r' to refresh lists instead.", e);
+                self.message_return_mode = self.mode.clone();
+                self.mode = AppMode::MessageDialog;
             }
         }
+    }
+
+    // Point every list at another directory. The process moves there too so
+    // relative paths (new project targets, the VCD scan, the directory
+    // watcher) follow it.
+    fn set_base_dir(&mut self, dir: &Path) -> Result<(), HadouError> {
+        let dir = dir.canonicalize()?;
+        std::env::set_current_dir(&dir)?;
+
+        self.project_editor.current_directory = dir.clone();
+        self.project_editor.refresh_projects();
+        self.project_compiler.current_directory = dir.clone();
+        self.project_compiler.refresh_projects();
+        self.waveform_viewer.current_directory = dir;
+        self.waveform_viewer.scan_for_vcd_files();
+        self.scan_vcd_files();
+        self.refresh_health();
+        self.watcher = None;
+        self.apply_watch_config(ScanOptions::from_config(&self.config));
 
-        // Sort VCD files alphabetically
-        self.vcd_files.sort();
+        self.remember_base_dir()
     }
 
-    fn launch_waveform_viewer(&mut self) {
-        if self.vcd_files.is_empty() {
-            self.message = "No VCD files found. Run a simulation first!".to_string();
-            self.mode = AppMode::MessageDialog;
-            return;
+    fn remember_base_dir(&mut self) -> Result<(), HadouError> {
+        self.config.remember_dir(&self.project_compiler.current_directory);
+        self.config.save()
+    }
+
+    // Copy the absolute form of `path`, showing it instead when there is no
+    // clipboard so it can still be copied from the terminal
+    fn copy_path(&mut self, path: &Path) {
+        let absolute = path
+            .canonicalize()
+            .or_else(|_| std::env::current_dir().map(|dir| dir.join(path)))
+            .unwrap_or_else(|_| path.to_path_buf());
+        let text = absolute.display().to_string();
+
+        self.message = match self.clipboard.copy(&text) {
+            Ok(()) => format!("Copied to clipboard:\n{}", text),
+            Err(e) => format!("Clipboard not available ({}). Path:\n{}", e, text),
+        };
+        self.message_return_mode = self.mode.clone();
+        self.mode = AppMode::MessageDialog;
+    }
+
+    // Re-scan the lists a watched change affects, keeping each selection
+    fn apply_watch_change(&mut self, change: WatchChange) {
+        if change.projects {
+            let editor_selection = self.project_editor.get_selected_project_path().cloned();
+            self.project_editor.refresh_projects();
+            if let Some(path) = editor_selection {
+                self.project_editor.select_project(&path);
+            }
+
+            let compiler_selection = self.project_compiler.get_selected_project_path().cloned();
+            self.project_compiler.refresh_projects();
+            if let Some(path) = compiler_selection {
+                self.project_compiler.select_project(&path);
+            }
+
+            if self.mode == AppMode::EditProject {
+                self.start_syntax_check();
+            }
         }
 
-        let vcd_file = &self.vcd_files[self.selected_vcd_index];
+        if change.vcd_files {
+            let selection = self.vcd_files.get(self.selected_vcd_index).map(|entry| entry.path.clone());
+            self.scan_vcd_files();
+            if let Some(index) = selection.and_then(|path| self.vcd_files.iter().position(|entry| entry.path == path)) {
+                self.selected_vcd_index = index;
+            }
+        }
 
-        // Try different waveform viewers in order of preference
-        let viewers = [
-            ("dwfv", vec![vcd_file.to_string_lossy().to_string()]),
-            ("digisurf", vec!["-f".to_string(), vcd_file.to_string_lossy().to_string()]),
-            ("gtkwave", vec![vcd_file.to_string_lossy().to_string()]),
-        ];
+        self.refresh_health();
+    }
 
-        for (viewer, args) in &viewers {
-            match std::process::Command::new(viewer).args(args).spawn() {
-                Ok(mut child) => {
-                    // Show a message that the viewer is launching
-                    self.message = format!("Launching {} with {}\n\nHadou will exit when you close the waveform viewer.", viewer, vcd_file.display());
-                    self.mode = AppMode::MessageDialog;
+    pub fn colors(&self) -> &'static FlavorColors {
+        theme::colors(self.flavor)
+    }
 
-                    // Wait for the external viewer to exit, then quit Hadou
-                    std::thread::spawn(move || {
-                        let _ = child.wait();
-                        std::process::exit(0);
-                    });
+    pub fn icon(&self, icon: Icon) -> &'static str {
+        icon.glyph(self.ascii_mode)
+    }
 
-                    return;
-                }
-                Err(_) => continue,
+    fn scan_vcd_files(&mut self) {
+        self.selected_vcd_index = 0;
+
+        // Scan the current directory and its subdirectories for VCD files
+        let scan_options = ScanOptions::from_config(&self.config);
+        self.vcd_files = scan_options
+            .find_files(Path::new("."), waveform_viewer::is_vcd_file)
+            .into_iter()
+            .map(ListEntry::read)
+            .collect();
+        self.sort_mode.sort(&mut self.vcd_files);
+        self.vcd_summaries = self.vcd_files
+            .iter()
+            .map(|entry| (entry.path.clone(), VcdSummary::read(&entry.path)))
+            .collect();
+
+        self.select_best_vcd_match();
+    }
+
+    // Apply a sort order to every list, keeping their selections
+    fn set_sort_mode(&mut self, sort_mode: SortMode) {
+        self.sort_mode = sort_mode;
+        self.project_editor.set_sort_mode(sort_mode);
+        self.project_compiler.set_sort_mode(sort_mode);
+
+        let selected = self.vcd_files.get(self.selected_vcd_index).map(|entry| entry.path.clone());
+        sort_mode.sort(&mut self.vcd_files);
+        if let Some(index) = selected.and_then(|path| self.vcd_files.iter().position(|entry| entry.path == path)) {
+            self.selected_vcd_index = index;
+        }
+    }
+
+    // Switch to the next sort order and remember it in the config
+    fn cycle_sort_mode(&mut self) {
+        let sort_mode = self.sort_mode.next();
+        self.set_sort_mode(sort_mode);
+        self.config.sort = Some(sort_mode.identifier().to_string());
+
+        if let Err(e) = self.config.save() {
+            self.message = format!("Could not save sort order: {}", e);
+            self.message_return_mode = self.mode.clone();
+            self.mode = AppMode::MessageDialog;
+        }
+    }
+
+    fn apply_pinned(&mut self) {
+        let pinned: HashSet<PathBuf> = self.config.pinned.iter().map(PathBuf::from).collect();
+        self.project_editor.set_pinned(pinned.clone());
+        self.project_compiler.set_pinned(pinned);
+    }
+
+    // Pin a project to the top of both project lists, or unpin it, and
+    // remember the choice in the config
+    fn toggle_pin(&mut self, project: &Path) {
+        let key = project.to_string_lossy().to_string();
+        match self.config.pinned.iter().position(|pinned| *pinned == key) {
+            Some(index) => {
+                self.config.pinned.remove(index);
             }
+            None => self.config.pinned.push(key),
         }
+        self.apply_pinned();
 
-        self.message = "No waveform viewers found!\n\nInstall options:\n• cargo install dwfv (recommended)\n• cargo install digisurf\n• sudo apt install gtkwave".to_string();
-        self.mode = AppMode::MessageDialog;
+        if let Err(e) = self.config.save() {
+            self.message = format!("Could not save pinned projects: {}", e);
+            self.message_return_mode = self.mode.clone();
+            self.mode = AppMode::MessageDialog;
+        }
     }
 
-    pub fn on_key(&mut self, key: KeyCode) {
-        match self.mode {
-            AppMode::MainMenu => self.handle_main_menu_key(key),
-            AppMode::CreateProject => self.handle_create_project_key(key),
-            AppMode::CompileProject => self.handle_compile_project_key(key),
-            AppMode::EditProject => self.handle_edit_project_key(key),
-            AppMode::ViewWaveform => self.handle_view_waveform_key(key),
-            AppMode::InputDialog => self.handle_input_dialog_key(key),
-            AppMode::MessageDialog => self.handle_message_dialog_key(key),
+    // VCD files matching the search query, best match first
+    fn visible_vcd_files(&self) -> Vec<(usize, FuzzyMatch)> {
+        self.vcd_search.filter(self.vcd_files.iter().map(|entry| vcd_label(&entry.path)))
+    }
+
+    fn select_best_vcd_match(&mut self) {
+        if let Some((index, _)) = self.visible_vcd_files().first() {
+            self.selected_vcd_index = *index;
         }
     }
 
-    fn handle_main_menu_key(&mut self, key: KeyCode) {
-        match key {
-            KeyCode::Char('q') | KeyCode::Esc => self.should_quit = true,
-            KeyCode::Down => {
-                self.selected_index = (self.selected_index + 1) % 4;
-            },
-            KeyCode::Up => {
-                self.selected_index = if self.selected_index == 0 {
-                    3
-                } else {
-                        self.selected_index - 1
-                    };
-            },
-            KeyCode::Enter => {
-                match self.selected_index {
-                    0 => self.mode = AppMode::CreateProject,
-                    1 => {
-                        // Refresh project list when entering edit mode
-                        self.project_editor.refresh_projects();
-                        self.mode = AppMode::EditProject;
-                    }
-                    2 => {
-                        // Refresh project list when entering compile mode
-                        self.project_compiler.refresh_projects();
-                        self.mode = AppMode::CompileProject;
-                    }
-                    3 => {
-                        // Refresh VCD files and enter waveform mode
-                        self.scan_vcd_files();
-                        self.mode = AppMode::ViewWaveform;
-                    }
-                    _ => {}
-                }
+    fn vcd_selection_visible(&self) -> bool {
+        self.vcd_files
+            .get(self.selected_vcd_index)
+            .is_some_and(|entry| self.vcd_search.matches(&vcd_label(&entry.path)))
+    }
+
+    // Enter on a VCD file opens the default viewer straight away when one is
+    // configured, and otherwise asks which installed viewer to use
+    fn open_external_viewer(&mut self) {
+        if self.config.viewer.is_some() || !self.config.viewer_picker {
+            self.launch_waveform_viewer(None);
+        } else {
+            self.open_viewer_picker();
+        }
+    }
+
+    fn open_viewer_picker(&mut self) {
+        let mut installed: Vec<String> = self.config.viewer.iter().cloned().collect();
+        for viewer in waveform_viewer::EXTERNAL_VIEWERS {
+            if !installed.iter().any(|known| known == viewer) {
+                installed.push(viewer.to_string());
             }
-            _ => {}
         }
+        installed.retain(|viewer| command_exists(viewer));
+
+        // Nothing to choose between; the launch explains how to install one
+        if installed.is_empty() || self.vcd_files.is_empty() {
+            self.launch_waveform_viewer(None);
+            return;
+        }
+
+        let choices = std::iter::once(None).chain(installed.into_iter().map(Some)).collect();
+        let last = self.config.last_viewer.clone();
+        self.open_pick_dialog(PickAction::Viewer, choices, last);
     }
 
-    fn handle_create_project_key(&mut self, key: KeyCode) {
-        match key {
-            KeyCode::Esc => self.mode = AppMode::MainMenu,
-            KeyCode::Enter => {
-                if !self.project_creator.project_name.is_empty() {
-                    match self.project_creator.create_project() {
-                        Ok(path) => {
-                            self.message = format!("Project Created successfully at: {}", path.display());
-                            self.project_creator.reset();
-                            // Refresh both editor and compiler lists since we created a new project
-                            self.project_editor.refresh_projects();
-                            self.project_compiler.refresh_projects();
-                            self.mode = AppMode::MessageDialog;
-                        }
-                        Err(e) => {
-                            self.message = format!("Error creating project: {:?}", e);
-                            self.mode = AppMode::MessageDialog;
-                        }
-                    }
+    // Offer the file templates the selected project doesn't have yet
+    fn open_file_template_picker(&mut self) {
+        let Some(project) = self.project_editor.get_selected_project_path().cloned() else {
+            return;
+        };
+        let name = project_label(&project);
+        let language = Language::detect(&project).unwrap_or_default();
+        let choices: Vec<Option<String>> = FileTemplate::ALL
+            .into_iter()
+            .filter(|template| template.applies_to(language) && !project.join(template.file_name(&name)).exists())
+            .map(|template| Some(template.label().to_string()))
+            .collect();
+        if choices.is_empty() {
+            self.message = format!("{} already has every file template that suits it", name);
+            self.message_return_mode = AppMode::EditProject;
+            self.mode = AppMode::MessageDialog;
+            return;
+        }
+
+        self.open_pick_dialog(PickAction::AddFile { project }, choices, None);
+    }
+
+    fn open_archive_picker(&mut self) {
+        let Some(project) = self.project_editor.get_selected_project_path().cloned() else {
+            return;
+        };
+        let choices = ArchiveFormat::ALL.into_iter().map(|format| Some(format.extension().to_string())).collect();
+        self.open_pick_dialog(PickAction::Archive { project }, choices, None);
+    }
+
+    // Pick a second VCD file to compare the selected one with
+    fn open_vcd_compare(&mut self) {
+        let Some(first) = self.vcd_files.get(self.selected_vcd_index).map(|entry| entry.path.clone()) else {
+            return;
+        };
+        let choices: Vec<Option<String>> = self.vcd_files
+            .iter()
+            .filter(|entry| entry.path != first)
+            .map(|entry| Some(entry.path.to_string_lossy().into_owned()))
+            .collect();
+        if choices.is_empty() {
+            self.message = "There is no other VCD file to compare with. Simulate again with a changed design or testbench first.".to_string();
+            self.message_return_mode = AppMode::ViewWaveform;
+            self.mode = AppMode::MessageDialog;
+            return;
+        }
+
+        self.open_pick_dialog(PickAction::CompareVcd { first, mode: DiffMode::default() }, choices, None);
+    }
+
+    // Offer the recipes the build file does have when the selected action's
+    // recipe is missing from it
+    fn open_recipe_picker(&mut self, missing: String, available: Vec<String>) {
+        let Some(project) = self.project_compiler.projects
+            .get(self.project_compiler.selected_project_index)
+            .map(|entry| entry.path.clone())
+        else {
+            return;
+        };
+
+        let choices = available.into_iter().map(Some).collect();
+        self.open_pick_dialog(PickAction::Recipe { project, missing }, choices, None);
+    }
+
+    fn open_pick_dialog(&mut self, action: PickAction, choices: Vec<Option<String>>, preselect: Option<String>) {
+        self.selected_pick_index = preselect
+            .and_then(|preselect| choices.iter().position(|choice| choice.as_ref() == Some(&preselect)))
+            .unwrap_or(0);
+        self.pick_choices = choices;
+        self.pick_action = Some(action);
+        self.mode = AppMode::PickDialog;
+    }
+
+    fn handle_pick_dialog_key(&mut self, key: KeyCode) {
+        let Some(action) = self.pick_action.clone() else {
+            self.mode = AppMode::MainMenu;
+            return;
+        };
+
+        match (self.keymap.action(key, &[]), key) {
+            (Some(Action::Back), _) => self.mode = action.return_mode(),
+            (Some(Action::NavigateUp), _) if self.selected_pick_index > 0 => self.selected_pick_index -= 1,
+            (Some(Action::NavigateDown), _) if self.selected_pick_index + 1 < self.pick_choices.len() => self.selected_pick_index += 1,
+            (Some(Action::Select), _) => {
+                if let Some(choice) = self.pick_choices.get(self.selected_pick_index).cloned() {
+                    self.confirm_pick(action, choice, false);
                 }
             }
-            KeyCode::Backspace => {
-                self.project_creator.project_name.pop();
+            (_, KeyCode::Char('d')) if action == PickAction::Viewer => {
+                if let Some(choice) = self.pick_choices.get(self.selected_pick_index).cloned() {
+                    self.confirm_pick(action, choice, true);
+                }
             }
-            KeyCode::Char(c) => {
-                if c.is_alphanumeric() || c == '_' || c == '-' {
-                    self.project_creator.project_name.push(c);
+            (_, KeyCode::Char('m')) => {
+                if let Some(PickAction::CompareVcd { mode, .. }) = &mut self.pick_action {
+                    *mode = mode.next();
                 }
             }
             _ => {}
         }
     }
 
-    fn handle_compile_project_key(&mut self, key: KeyCode) {
-        match key {
-            KeyCode::Esc => self.mode = AppMode::MainMenu,
-            KeyCode::Up => {
-                self.project_compiler.move_project_selection_up();
-            }
-            KeyCode::Down => {
-                self.project_compiler.move_project_selection_down();
-            }
-            KeyCode::Left => {
-                self.project_compiler.move_action_selection_up();
-            }
-            KeyCode::Right => {
-                self.project_compiler.move_action_selection_down();
-            }
-            KeyCode::Enter => {
-                if self.project_compiler.has_projects() && !self.project_compiler.is_compiling {
-                    match self.project_compiler.execute_compilation() {
-                        Ok(success_msg) => {
-                            self.message = success_msg;
-                            // Refresh VCD files since compilation might have generated new ones
-                            self.scan_vcd_files();
-                            self.mode = AppMode::MessageDialog;
-                        }
-                        Err(e) => {
-                            self.message = format!("Compilation failed: {}", e);
-                            self.mode = AppMode::MessageDialog;
-                        }
+    fn confirm_pick(&mut self, action: PickAction, choice: Option<String>, make_default: bool) {
+        self.mode = action.return_mode();
+
+        match action {
+            PickAction::Recipe { project, .. } => {
+                let Some(recipe) = choice else {
+                    return;
+                };
+                match self.project_compiler.run_recipe(project, recipe) {
+                    Ok(()) => self.project_compiler.active_tab = CompileTab::Output,
+                    Err(e) => {
+                        self.message = error_message("Compilation failed", &e);
+                        self.message_return_mode = AppMode::CompileProject;
+                        self.mode = AppMode::MessageDialog;
                     }
                 }
             }
-            KeyCode::Char('r') => {
-                // Refresh project list
-                self.project_compiler.refresh_projects();
-                self.message = format!("Refreshed project list. Found {} projects", 
-                    self.project_compiler.project_count());
+            PickAction::CompareVcd { first, mode } => {
+                let Some(second) = choice.map(PathBuf::from) else {
+                    return;
+                };
+                self.message = match vcd_diff::compare(&first, &second, mode) {
+                    Ok(report) => report.describe((&vcd_label(&first), &vcd_label(&second))),
+                    Err(e) => error_message("Could not compare the VCD files", &e),
+                };
+                self.message_return_mode = AppMode::ViewWaveform;
                 self.mode = AppMode::MessageDialog;
             }
-            KeyCode::Char('c') => {
-                // Clear compilation output
-                self.project_compiler.clear_compilation_output();
+            PickAction::AddFile { project } => {
+                let Some(template) = choice.as_deref().and_then(FileTemplate::parse) else {
+                    return;
+                };
+                self.message = match file_template::add_to_project(&project, template) {
+                    Ok(path) => format!("Added {} to {}", path.display(), project_label(&project)),
+                    Err(e) => error_message("Could not add the file", &e),
+                };
+                self.message_return_mode = AppMode::EditProject;
+                self.mode = AppMode::MessageDialog;
             }
-            _ => {}
-        }
-    }
-
-    fn handle_edit_project_key(&mut self, key: KeyCode) {
-        match key {
-            KeyCode::Esc => self.mode = AppMode::MainMenu,
-            KeyCode::Up => {
-                self.project_editor.move_selection_up();
+            PickAction::Archive { project } => {
+                let Some(format) = choice.as_deref().and_then(ArchiveFormat::parse) else {
+                    return;
+                };
+                self.message = match archive::pack(&project, format, &self.config.ignore) {
+                    Ok(archive) => format!(
+                        "Packed {} files into {} ({})",
+                        archive.files,
+                        archive.path.display(),
+                        compile_project::format_size(archive.size)
+                    ),
+                    Err(e) => error_message("Could not pack the project", &e),
+                };
+                self.message_return_mode = AppMode::EditProject;
+                self.mode = AppMode::MessageDialog;
             }
-            KeyCode::Down => {
-                self.project_editor.move_selection_down();
+            PickAction::Testbench { project } => {
+                let Some(testbench) = choice else {
+                    return;
+                };
+                if let Err(e) = testbench::select(&project, &testbench) {
+                    self.message = error_message("Could not save the testbench choice", &e);
+                    self.message_return_mode = AppMode::CompileProject;
+                    self.mode = AppMode::MessageDialog;
+                }
+                self.project_compiler.reload_details();
+            }
+            PickAction::Viewer => {
+                // 'd' also makes the choice the default, skipping the picker
+                // from now on. For "just open it" that means always opening
+                // the first viewer found.
+                if make_default {
+                    self.config.viewer = choice.clone();
+                    self.config.viewer_picker = false;
+                }
+                if choice.is_some() {
+                    self.config.last_viewer = choice.clone();
+                }
+                let saved = self.config.save();
+
+                self.launch_waveform_viewer(choice);
+                if let Err(e) = saved {
+                    self.message = format!("Could not save the viewer choice: {}", e);
+                    self.message_return_mode = AppMode::ViewWaveform;
+                    self.mode = AppMode::MessageDialog;
+                }
+            }
+        }
+    }
+
+    // Viewers to try in order: just `viewer` when one was picked, otherwise
+    // every known viewer starting with the one from the config
+    fn viewer_candidates(&self, viewer: Option<String>) -> Vec<String> {
+        if let Some(viewer) = viewer {
+            return vec![viewer];
+        }
+
+        let mut viewers: Vec<String> = waveform_viewer::EXTERNAL_VIEWERS.iter().map(|viewer| viewer.to_string()).collect();
+        if let Some(preferred) = self.config.viewer.clone() {
+            viewers.retain(|viewer| *viewer != preferred);
+            viewers.insert(0, preferred);
+        }
+        viewers
+    }
+
+    // '!' on the compile, edit and waveform screens: the command the
+    // selected entry would run, shown without running it
+    fn show_command_preview(&mut self) {
+        let preview = match self.mode {
+            AppMode::CompileProject => self.project_compiler.command_preview().ok_or(HadouError::NoSelection("project")),
+            AppMode::EditProject => self.project_editor.open_project_in_editor().map(|launch| launch.command_line()),
+            AppMode::ViewWaveform => self.viewer_command_preview(),
+            _ => return,
+        };
+
+        self.message = match preview {
+            Ok(command) => format!("Dry run, nothing was started:\n\n{}", command),
+            Err(e) => error_message("No command to show", &e),
+        };
+        self.message_return_mode = self.mode.clone();
+        self.mode = AppMode::MessageDialog;
+    }
+
+    fn viewer_command_preview(&self) -> Result<String, HadouError> {
+        let vcd_file = &self.vcd_files.get(self.selected_vcd_index).ok_or(HadouError::NoVcdFiles)?.path;
+        let viewer = self.viewer_candidates(None)
+            .into_iter()
+            .find(|viewer| command_exists(viewer))
+            .ok_or_else(|| HadouError::tool_not_found("dwfv", "cargo install dwfv"))?;
+
+        let mut preview = command_line::render(&waveform_viewer::external_viewer_command(&viewer, vcd_file));
+        if waveform_viewer::is_compressed(vcd_file) {
+            preview.push_str("\n\nThe viewer is given a decompressed copy in the temp directory instead.");
+        }
+        Ok(preview)
+    }
+
+    // Open the selected VCD file in `viewer`, or in the first viewer that
+    // starts (the configured default first) when it is None
+    fn launch_waveform_viewer(&mut self, viewer: Option<String>) {
+        if self.vcd_files.is_empty() {
+            self.message = "No VCD files found. Run a simulation first!".to_string();
+            self.mode = AppMode::MessageDialog;
+            return;
+        }
+
+        let vcd_file = self.vcd_files[self.selected_vcd_index].path.clone();
+
+        // Compressed files are decompressed to a temporary copy first
+        let (viewer_file, temp_file) = if waveform_viewer::is_compressed(&vcd_file) {
+            match waveform_viewer::decompress_to_temp(&vcd_file) {
+                Ok(path) => (path.clone(), Some(path)),
+                Err(e) => {
+                    self.message = format!("Error opening {}: {}", vcd_file.display(), e);
+                    self.mode = AppMode::MessageDialog;
+                    return;
+                }
             }
-            KeyCode::Enter => {
-                if self.project_editor.has_projects() {
-                    match self.project_editor.open_project_in_editor() {
-                        Ok(()) => {
-                            if let Some(project_name) = self.project_editor.get_selected_project_name() {
-                                self.message = format!("Opened project '{}' in editor", project_name);
-                            } else {
-                                self.message = "Project opened in editor".to_string();
-                            }
-                            self.mode = AppMode::MessageDialog;
+        } else {
+            (vcd_file.clone(), None)
+        };
+
+        for viewer in &self.viewer_candidates(viewer) {
+            match waveform_viewer::external_viewer_command(viewer, &viewer_file).spawn() {
+                Ok(mut child) => {
+                    // Show a message that the viewer is launching
+                    self.message = format!("Launching {} with {}\n\nHadou will exit when you close the waveform viewer.", viewer, vcd_file.display());
+                    self.mode = AppMode::MessageDialog;
+
+                    // Wait for the external viewer to exit, then quit Hadou
+                    let temp_file = temp_file.clone();
+                    std::thread::spawn(move || {
+                        let _ = child.wait();
+                        if let Some(temp_file) = temp_file {
+                            let _ = std::fs::remove_file(temp_file);
                         }
-                        Err(e) => {
-                            self.message = format!("Error opening project in editor: {}", e);
-                            self.mode = AppMode::MessageDialog;
+                        std::process::exit(0);
+                    });
+
+                    return;
+                }
+                Err(_) => continue,
+            }
+        }
+
+        if let Some(temp_file) = temp_file {
+            let _ = std::fs::remove_file(temp_file);
+        }
+
+        self.message = "No waveform viewers found!\n\nInstall options:\n• cargo install dwfv (recommended)\n• cargo install digisurf\n• sudo apt install gtkwave".to_string();
+        self.mode = AppMode::MessageDialog;
+    }
+
+    // Opens the flags ('f') or extra sources ('i') dialog filled in with
+    // the project's current settings
+    fn edit_project_config(&mut self, action: InputAction) {
+        let Some(project_path) = self.project_compiler.get_selected_project_path().cloned() else {
+            return;
+        };
+        match ProjectConfig::load(&project_path) {
+            Ok(project_config) => {
+                let current = match action {
+                    InputAction::EditSources => project_config.sources_input(),
+                    _ => project_config.flags(),
+                };
+                self.open_input_dialog(action);
+                self.input_buffer = current;
+            }
+            Err(e) => {
+                self.message = error_message("Could not read project settings", &e);
+                self.message_return_mode = AppMode::CompileProject;
+                self.mode = AppMode::MessageDialog;
+            }
+        }
+    }
+
+    // Pick which of the selected project's testbenches runs simulate
+    fn open_testbench_picker(&mut self) {
+        let Some(project) = self.project_compiler.get_selected_project_path().cloned() else {
+            return;
+        };
+
+        let testbenches = testbench::find_testbenches(&project);
+        if testbenches.len() < 2 || Language::detect(&project) != Some(Language::Verilog) {
+            self.message = format!(
+                "{} has no other testbench to pick. Files named *_test.v or tb_*.v next to main_test.v are listed here.",
+                project_label(&project)
+            );
+            self.message_return_mode = AppMode::CompileProject;
+            self.mode = AppMode::MessageDialog;
+            return;
+        }
+
+        let current = testbench::selected(&project);
+        let choices = testbenches.into_iter().map(Some).collect();
+        self.open_pick_dialog(PickAction::Testbench { project }, choices, Some(current));
+    }
+
+    fn open_input_dialog(&mut self, action: InputAction) {
+        self.input_buffer.clear();
+        self.input_action = Some(action);
+        self.mode = AppMode::InputDialog;
+    }
+
+    fn submit_input(&mut self, action: InputAction, input: String) {
+        match action {
+            InputAction::DuplicateProject => {
+                match self.project_editor.duplicate_selected_project(&input) {
+                    Ok(path) => {
+                        self.message = format!("Project duplicated to: {}", path.display());
+                        self.project_editor.refresh_projects();
+                        self.project_compiler.refresh_projects();
+                    }
+                    Err(e) => {
+                        self.message = format!("Error duplicating project: {}", e);
+                    }
+                }
+                self.mode = AppMode::MessageDialog;
+            }
+            InputAction::RenameProject => self.rename_project(&input),
+            InputAction::SaveSignalView => {
+                if let Err(e) = self.waveform_viewer.save_view(&input) {
+                    self.waveform_viewer.status_message = Some(format!("Error saving view: {}", e));
+                }
+                self.mode = AppMode::InternalWaveform;
+            }
+            InputAction::FindValue { .. } => {
+                self.waveform_viewer.find_value(&input);
+                self.mode = AppMode::InternalWaveform;
+            }
+            InputAction::EditEnvValue => {
+                self.project_compiler.project_env.set_selected_value(input);
+                self.mode = AppMode::CompileProject;
+            }
+            InputAction::FindReferences => {
+                let name = input.trim().to_string();
+                if name.is_empty() {
+                    self.mode = AppMode::EditProject;
+                    return;
+                }
+                match self.project_editor.find_references(&name) {
+                    Ok(references) if references.is_empty() => {
+                        self.message = format!("No references to '{}' in the project's Verilog files", name);
+                        self.message_return_mode = AppMode::EditProject;
+                        self.mode = AppMode::MessageDialog;
+                    }
+                    Ok(references) => {
+                        self.references = references;
+                        self.selected_reference_index = 0;
+                        self.mode = AppMode::References;
+                    }
+                    Err(e) => {
+                        self.message = error_message("Could not search the project", &e);
+                        self.message_return_mode = AppMode::EditProject;
+                        self.mode = AppMode::MessageDialog;
+                    }
+                }
+                self.reference_query = name;
+            }
+            InputAction::EditIverilogFlags => {
+                let Some(project_path) = self.project_compiler.get_selected_project_path().cloned() else {
+                    self.mode = AppMode::CompileProject;
+                    return;
+                };
+                self.message = match project_config::set_iverilog_flags(&project_path, project_config::parse_flags(&input)) {
+                    Ok(update) => settings_saved_message(&project_path, "iverilog flags", project_config::IVERILOG_FLAGS_VAR, update),
+                    Err(e) => error_message("Could not save iverilog flags", &e),
+                };
+                self.project_compiler.reload_details();
+                self.message_return_mode = AppMode::CompileProject;
+                self.mode = AppMode::MessageDialog;
+            }
+            InputAction::EditSources => {
+                let Some(project_path) = self.project_compiler.get_selected_project_path().cloned() else {
+                    self.mode = AppMode::CompileProject;
+                    return;
+                };
+                let (sources, include_dirs) = project_config::parse_sources(&input);
+                self.message = match project_config::set_sources(&project_path, sources, include_dirs) {
+                    Ok(update) => {
+                        let mut message = settings_saved_message(&project_path, "extra sources", project_config::EXTRA_SOURCES_VAR, update);
+                        if let Ok(project_config) = ProjectConfig::load(&project_path)
+                            && !project_config.sources.is_empty()
+                        {
+                            let count = project_config.extra_source_files(&project_path).len();
+                            message.push_str(&format!("\n\n{} file(s) match the extra sources", count));
                         }
+                        message
+                    }
+                    Err(e) => error_message("Could not save extra sources", &e),
+                };
+                self.project_compiler.reload_details();
+                self.message_return_mode = AppMode::CompileProject;
+                self.mode = AppMode::MessageDialog;
+            }
+            InputAction::AddNote { project, from } => {
+                match notes::append(&project, &input) {
+                    Ok(_) => self.mode = from,
+                    Err(e) => {
+                        self.message = error_message("Could not save the note", &e);
+                        self.message_return_mode = from;
+                        self.mode = AppMode::MessageDialog;
                     }
                 }
+                self.project_compiler.reload_details();
+                self.project_editor.reload_notes();
             }
-            KeyCode::Char('r') => {
-                // Refresh project list
-                self.project_editor.refresh_projects();
-                self.message = format!("Refreshed project list. Found {} projects", 
-                    self.project_editor.project_count());
+            InputAction::QuickCreate => {
+                match self.project_creator.create_from_spec(&input) {
+                    Ok(path) => {
+                        self.project_editor.refresh_projects();
+                        self.project_compiler.refresh_projects();
+                        self.finish_project_creation(path);
+                    }
+                    Err(e) => {
+                        self.message = format!("Error creating project: {}", e);
+                        self.mode = AppMode::MessageDialog;
+                    }
+                }
+            }
+        }
+    }
+
+    fn open_internal_viewer(&mut self) {
+        let Some(vcd_file) = self.vcd_files.get(self.selected_vcd_index).map(|entry| entry.path.clone()) else {
+            self.message = "No VCD files found. Run a simulation first!".to_string();
+            self.mode = AppMode::MessageDialog;
+            return;
+        };
+
+        self.open_in_internal_viewer(vcd_file);
+    }
+
+    // Asks first when the file is big enough to freeze the UI while loading
+    fn open_in_internal_viewer(&mut self, vcd_file: PathBuf) {
+        if VcdSummary::read(&vcd_file).is_large() {
+            self.confirm_action = Some(ConfirmAction::OpenLargeVcd(vcd_file));
+            self.mode = AppMode::ConfirmDialog;
+        } else {
+            self.load_internal_viewer(&vcd_file, self.mode.clone());
+        }
+    }
+
+    // The viewer opens once the file has been parsed in the background
+    fn load_internal_viewer(&mut self, vcd_file: &Path, return_mode: AppMode) {
+        self.waveform_viewer.start_loading(vcd_file);
+        self.loading_return_mode = return_mode;
+        self.mode = AppMode::LoadingVcd;
+    }
+
+    fn finish_vcd_load(&mut self, vcd_file: &Path, result: Result<(), HadouError>) {
+        match result {
+            Ok(()) => self.mode = AppMode::InternalWaveform,
+            Err(e) => {
+                self.message = format!("Error loading {}: {}", vcd_file.display(), e);
+                self.message_return_mode = self.loading_return_mode.clone();
+                self.mode = AppMode::MessageDialog;
+            }
+        }
+    }
+
+    // Serve the selected VCD on localhost and open it in a browser, unless
+    // Hadou runs over SSH where the URL has to be forwarded first
+    fn start_web_viewer(&mut self) {
+        let Some(vcd_file) = self.vcd_files.get(self.selected_vcd_index).map(|entry| entry.path.clone()) else {
+            return;
+        };
+
+        match WebServer::start(&vcd_file) {
+            Ok(server) => {
+                self.web_status = if web_viewer::is_remote_session() {
+                    "Running over SSH: forward the port from your machine, then open the URL there.".to_string()
+                } else {
+                    match web_viewer::open_in_browser(&server.url()) {
+                        Ok(()) => "Opened in your browser.".to_string(),
+                        Err(e) => format!("{}; open the URL yourself.", e),
+                    }
+                };
+                self.web_server = Some(server);
+                self.mode = AppMode::WebViewer;
+            }
+            Err(e) => {
+                self.message = error_message("Could not serve the waveform", &e);
+                self.message_return_mode = AppMode::ViewWaveform;
                 self.mode = AppMode::MessageDialog;
             }
+        }
+    }
+
+    fn handle_web_viewer_key(&mut self, key: KeyCode) {
+        let Some(server) = &self.web_server else {
+            self.mode = AppMode::ViewWaveform;
+            return;
+        };
+
+        match (self.keymap.action(key, &[]), key) {
+            (Some(Action::Back), _) => {
+                // Dropping the server stops it
+                self.web_server = None;
+                self.mode = AppMode::ViewWaveform;
+            }
+            (_, KeyCode::Char('o')) => {
+                self.web_status = match web_viewer::open_in_browser(&server.url()) {
+                    Ok(()) => "Opened in your browser.".to_string(),
+                    Err(e) => e.to_string(),
+                };
+            }
+            (Some(Action::CopyPath), _) => {
+                let url = server.url();
+                self.web_status = match self.clipboard.copy(&url) {
+                    Ok(()) => "Copied the URL to the clipboard.".to_string(),
+                    Err(e) => format!("Clipboard not available ({}).", e),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_loading_vcd_key(&mut self, key: KeyCode) {
+        if self.keymap.action(key, &[]) == Some(Action::Back) {
+            self.waveform_viewer.cancel_loading();
+            self.mode = self.loading_return_mode.clone();
+        }
+    }
+
+    // Show the VCD a simulation just wrote in the built-in viewer, or explain
+    // why there is nothing to show
+    fn open_produced_vcd(&mut self, success_msg: String) {
+        self.message_return_mode = AppMode::CompileProject;
+        let Some(vcd_file) = self.project_compiler.produced_vcd() else {
+            self.message = if self.project_compiler.skip_dump {
+                format!("{}, with the waveform dump off. Press 'D' to dump a VCD again.", success_msg)
+            } else if let Some(project) = self.project_compiler.last_summary.as_ref().map(|summary| summary.project_path.clone()) {
+                format!(
+                    "{}, but it wrote no VCD file ({} was expected). Check the $dumpfile call in {}.",
+                    success_msg,
+                    testbench::vcd_file(&project),
+                    testbench::selected(&project)
+                )
+            } else {
+                format!("{}, but it wrote no VCD file. Check the $dumpfile call in main_test.v.", success_msg)
+            };
+            self.mode = AppMode::MessageDialog;
+            return;
+        };
+
+        let canonical = vcd_file.canonicalize().ok();
+        if let Some(index) = self.vcd_files.iter().position(|entry| entry.path.canonicalize().ok() == canonical) {
+            self.selected_vcd_index = index;
+        }
+        self.open_in_internal_viewer(vcd_file);
+    }
+
+    // Called on every pass of the event loop, whether or not a key was pressed
+    pub fn tick(&mut self) {
+        self.tick_count = self.tick_count.wrapping_add(1);
+        self.project_editor.syntax_checker.poll();
+        // Keeps the previews' top modules in step with the selected projects
+        // and their main.v without parsing it on every frame
+        let selected = [self.project_editor.get_selected_project_path(), self.project_compiler.get_selected_project_path()];
+        for project in selected.into_iter().flatten() {
+            self.top_modules.update(project);
+        }
+
+        if let Some((vcd_file, result)) = self.waveform_viewer.poll_loading() {
+            self.finish_vcd_load(&vcd_file, result);
+        }
+
+        if let Some(change) = self.watcher.as_mut().and_then(DirectoryWatcher::poll) {
+            self.apply_watch_change(change);
+        }
+
+        if let Err(e) = self.project_compiler.poll_watch() {
+            self.message = error_message("Stopped watching", &e);
+            if self.mode != AppMode::MessageDialog {
+                self.message_return_mode = self.mode.clone();
+            }
+            self.mode = AppMode::MessageDialog;
+        }
+
+        if let Some(batch) = self.project_compiler.poll_batch() {
+            self.finish_batch(batch);
+        }
+
+        if let Some(result) = self.project_compiler.poll_compilation() {
+            // Batch runs are summed up once the last project has finished
+            if self.project_compiler.batch.is_some() {
+                self.project_compiler.record_batch_result(result);
+                return;
+            }
+
+            // Watched runs report in the output so saving a file never
+            // interrupts with a dialog
+            if self.project_compiler.watch.is_some() {
+                self.project_compiler.record_watched_result(&result);
+                if result.is_ok() {
+                    self.scan_vcd_files();
+                }
+                return;
+            }
+
+            match result {
+                Ok(success_msg) => {
+                    // Refresh VCD files since compilation might have generated new ones
+                    self.scan_vcd_files();
+                    let opens_viewer = self.project_compiler.last_summary
+                        .as_ref()
+                        .is_some_and(|summary| summary.action.opens_internal_viewer());
+                    if opens_viewer {
+                        self.open_produced_vcd(success_msg);
+                        return;
+                    }
+                    self.message = success_msg;
+                }
+                Err(e) => {
+                    self.message = error_message("Compilation failed", &e);
+                    self.error_detail = e.output().map(str::to_string);
+                }
+            }
+            // Come back to the compile screen so the output can be reviewed
+            self.message_return_mode = AppMode::CompileProject;
+            self.mode = AppMode::MessageDialog;
+        }
+    }
+
+    pub fn on_key(&mut self, key: KeyCode) {
+        // F5 repeats the last compilation from anywhere except text dialogs
+        if key == KeyCode::F(5) && !matches!(self.mode, AppMode::InputDialog | AppMode::ConfirmDialog) {
+            self.rerun_last_compilation();
+            return;
+        }
+
+        // '?' lists every key of the screen and ':' searches every command,
+        // except while they would be typed
+        if matches!(key, KeyCode::Char('?' | ':')) && !typing(self, &self.mode) && screen_hints(self, &self.mode).is_some() {
+            if key == KeyCode::Char('?') {
+                self.open_key_help();
+            } else {
+                self.open_command_palette();
+            }
+            return;
+        }
+
+        match self.mode {
+            AppMode::MainMenu => self.handle_main_menu_key(key),
+            AppMode::CreateProject => self.handle_create_project_key(key),
+            AppMode::CompileProject => self.handle_compile_project_key(key),
+            AppMode::EditProject => self.handle_edit_project_key(key),
+            AppMode::ViewWaveform => self.handle_view_waveform_key(key),
+            AppMode::InternalWaveform => self.handle_internal_waveform_key(key),
+            AppMode::Settings => self.handle_settings_key(key),
+            AppMode::ModuleSnippets => self.handle_module_snippets_key(key),
+            AppMode::References => self.handle_references_key(key),
+            AppMode::BrowseDirectory => self.handle_browse_directory_key(key),
+            AppMode::PickDialog => self.handle_pick_dialog_key(key),
+            AppMode::InputDialog => self.handle_input_dialog_key(key),
+            AppMode::ConfirmDialog => self.handle_confirm_dialog_key(key),
+            AppMode::LoadingVcd => self.handle_loading_vcd_key(key),
+            AppMode::WebViewer => self.handle_web_viewer_key(key),
+            AppMode::MessageDialog => self.handle_message_dialog_key(key),
+            AppMode::ErrorDetail => self.handle_error_detail_key(key),
+            AppMode::KeyHelp => self.handle_key_help_key(key),
+            AppMode::CommandPalette => self.handle_command_palette_key(key),
+        }
+    }
+
+    fn open_key_help(&mut self) {
+        self.key_help_return = self.mode.clone();
+        self.key_help_scroll.reset();
+        self.mode = AppMode::KeyHelp;
+    }
+
+    // Ctrl-P reaches this from text fields too, as it can't be typed
+    pub fn open_command_palette(&mut self) {
+        if self.mode == AppMode::CommandPalette || screen_hints(self, &self.mode).is_none() {
+            return;
+        }
+        self.palette.open();
+        self.palette_return = self.mode.clone();
+        self.mode = AppMode::CommandPalette;
+    }
+
+    fn handle_command_palette_key(&mut self, key: KeyCode) {
+        match (self.keymap.text_action(key), key) {
+            (Some(Action::Back), _) => self.mode = self.palette_return.clone(),
+            (Some(Action::Select), _) => {
+                self.mode = self.palette_return.clone();
+                if let Some(command) = self.palette.selected_command() {
+                    self.run_command(command);
+                }
+            }
+            (Some(Action::NavigateUp | Action::PreviousTab), _) => self.palette.move_selection(false),
+            (Some(Action::NavigateDown | Action::NextTab), _) => self.palette.move_selection(true),
+            (_, KeyCode::Backspace) => self.palette.pop(),
+            (_, KeyCode::Char(c)) => self.palette.push(c),
+            _ => {}
+        }
+    }
+
+    // Run a palette command from the screen it was opened over
+    fn run_command(&mut self, command: Command) {
+        match command {
+            Command::CreateProject => self.enter_mode(AppMode::CreateProject),
+            Command::QuickCreate => self.open_input_dialog(InputAction::QuickCreate),
+            Command::AdoptDirectory => {
+                self.confirm_action = Some(ConfirmAction::AdoptDirectory(self.project_compiler.current_directory.clone()));
+                self.mode = AppMode::ConfirmDialog;
+            }
+            Command::EditProjects => self.enter_mode(AppMode::EditProject),
+            Command::OpenInEditor => {
+                self.enter_mode(AppMode::EditProject);
+                if self.project_editor.selection_visible() {
+                    self.open_selected_in_editor();
+                }
+            }
+            Command::CompileProjects => self.enter_mode(AppMode::CompileProject),
+            Command::Run(action) => {
+                self.enter_mode(AppMode::CompileProject);
+                self.project_compiler.active_tab = CompileTab::Projects;
+                if let Some(index) = self.project_compiler.available_actions.iter().position(|available| *available == action) {
+                    self.project_compiler.selected_action_index = index;
+                }
+                if self.project_compiler.selection_visible() && !self.project_compiler.is_compiling {
+                    self.start_selected_compilation();
+                }
+            }
+            Command::RerunLast => self.rerun_last_compilation(),
+            Command::ViewWaveforms => self.enter_mode(AppMode::ViewWaveform),
+            Command::ChangeDirectory => self.enter_mode(AppMode::BrowseDirectory),
+            Command::Settings => self.enter_mode(AppMode::Settings),
+            Command::Refresh => self.refresh_all(),
+            Command::ShowKeys => self.open_key_help(),
+            Command::Quit => self.quit(),
+        }
+    }
+
+    // Bracketed paste delivers the whole clipboard at once; it goes to
+    // whichever text input currently has focus
+    pub fn on_paste(&mut self, text: &str) {
+        match self.mode {
+            // A whole module pasted in starts a project around it
+            AppMode::CreateProject if text.contains("endmodule") => self.import_module(text),
+            AppMode::CreateProject => self.project_creator.paste(text),
+            AppMode::InputDialog => {
+                let accepted = text
+                    .chars()
+                    .filter(|&c| self.input_action.as_ref().is_none_or(|action| action.accepts(c)));
+                self.input_buffer.extend(accepted);
+            }
+            AppMode::Settings if self.settings_editor.editing => {
+                self.settings_editor.edit_buffer.extend(text.chars().filter(|c| !c.is_control()));
+            }
+            AppMode::CompileProject
+                if self.project_compiler.active_tab == CompileTab::Projects && self.project_compiler.search.typing =>
+            {
+                paste_into_search(&mut self.project_compiler.search, text);
+                self.project_compiler.select_best_match();
+            }
+            AppMode::EditProject if self.project_editor.search.typing => {
+                paste_into_search(&mut self.project_editor.search, text);
+                self.project_editor.select_best_match();
+            }
+            AppMode::ViewWaveform if self.vcd_search.typing => {
+                paste_into_search(&mut self.vcd_search, text);
+                self.select_best_vcd_match();
+            }
             _ => {}
         }
     }
 
-    fn handle_view_waveform_key(&mut self, key: KeyCode) {
-        match key {
-            KeyCode::Esc => self.mode = AppMode::MainMenu,
-            KeyCode::Up => {
-                if !self.vcd_files.is_empty() {
-                    self.selected_vcd_index = if self.selected_vcd_index == 0 {
-                        self.vcd_files.len() - 1
-                    } else {
-                            self.selected_vcd_index - 1
+    fn rerun_last_compilation(&mut self) {
+        match self.project_compiler.rerun_last() {
+            Ok(()) => {
+                self.message.clear();
+                self.project_compiler.active_tab = CompileTab::Output;
+                self.mode = AppMode::CompileProject;
+            }
+            Err(e) => {
+                self.message = error_message("Could not re-run", &e);
+                if self.mode != AppMode::MessageDialog {
+                    self.message_return_mode = self.mode.clone();
+                }
+                self.mode = AppMode::MessageDialog;
+            }
+        }
+    }
+
+    // Quit straight away unless something would be cut off, in which case
+    // ask first
+    fn quit(&mut self) {
+        let running = self.background_work();
+        if running.is_empty() {
+            self.should_quit = true;
+        } else {
+            self.confirm_action = Some(ConfirmAction::Quit(running));
+            self.mode = AppMode::ConfirmDialog;
+        }
+    }
+
+    // What quitting would stop, one line each
+    fn background_work(&self) -> Vec<String> {
+        let mut running = Vec::new();
+        if let Some((project, action)) = self.project_compiler.running_compilation() {
+            running.push(format!("{} for {}", action.description(), project_label(project)));
+        }
+        if let Some(watch) = &self.project_compiler.watch {
+            running.push(format!("Watch mode on {} ({} runs so far)", project_label(&watch.project_path), watch.runs));
+        }
+        if let Some(server) = &self.web_server {
+            running.push(format!("Web viewer serving {} at {}", vcd_label(&server.vcd_path), server.url()));
+        }
+        running
+    }
+
+    fn handle_main_menu_key(&mut self, key: KeyCode) {
+        match (self.keymap.action(key, &[]), key) {
+            (Some(Action::Quit | Action::Back), _) => self.quit(),
+            (Some(Action::NavigateDown), _) if !self.menu_items.is_empty() => {
+                self.selected_index = (self.selected_index + 1) % self.menu_items.len();
+            },
+            (Some(Action::NavigateUp), _) if !self.menu_items.is_empty() => {
+                self.selected_index = if self.selected_index == 0 {
+                    self.menu_items.len() - 1
+                } else {
+                    self.selected_index - 1
+                };
+            },
+            (Some(Action::Select), _) => {
+                if let Some(item) = self.menu_items.get(self.selected_index) {
+                    let target = item.target.clone();
+                    self.enter_mode(target);
+                }
+            }
+            (_, KeyCode::Char('n')) => self.open_input_dialog(InputAction::QuickCreate),
+            (_, KeyCode::Char('a')) => {
+                self.confirm_action = Some(ConfirmAction::AdoptDirectory(self.project_compiler.current_directory.clone()));
+                self.mode = AppMode::ConfirmDialog;
+            }
+            (Some(Action::Refresh), _) => self.refresh_all(),
+            _ => {}
+        }
+    }
+
+    fn refresh_all(&mut self) {
+        self.project_editor.refresh_projects();
+        self.project_compiler.refresh_projects();
+        self.scan_vcd_files();
+        self.refresh_health();
+    }
+
+    // Switch screens, refreshing whatever the target screen lists
+    fn enter_mode(&mut self, mode: AppMode) {
+        match mode {
+            AppMode::EditProject => {
+                self.project_editor.refresh_projects();
+                self.start_syntax_check();
+            }
+            AppMode::CompileProject => self.project_compiler.refresh_projects(),
+            AppMode::ViewWaveform => self.scan_vcd_files(),
+            AppMode::Settings => self.settings_editor.reset(&self.config),
+            AppMode::BrowseDirectory => {
+                if let Err(e) = self.dir_browser.open(&self.project_compiler.current_directory) {
+                    self.message = error_message("Could not list the directory", &e);
+                    self.message_return_mode = AppMode::MainMenu;
+                    self.mode = AppMode::MessageDialog;
+                    return;
+                }
+            }
+            _ => {}
+        }
+        self.mode = mode;
+    }
+
+    fn start_syntax_check(&mut self) {
+        if self.config.syntax_check {
+            let compiler = self.config.simulator.clone().unwrap_or_else(|| "iverilog".to_string());
+            self.project_editor.check_syntax(&compiler);
+        }
+    }
+
+    fn handle_create_project_key(&mut self, key: KeyCode) {
+        // Text fields take every letter, so keys bound to letters only move
+        // between fields or toggle while a toggle field is focused
+        let action = if self.project_creator.focused_field.is_toggle() {
+            self.keymap.action(key, &[])
+        } else {
+            self.keymap.text_action(key)
+        };
+
+        match (action, key) {
+            (_, KeyCode::F(2)) => self.project_creator.auto_name(&self.config.name_prefix),
+            (_, KeyCode::F(3)) if self.project_creator.imported_module().is_some() => self.project_creator.clear_import(),
+            (_, KeyCode::F(3)) => match self.clipboard.paste() {
+                Ok(text) => self.import_module(&text),
+                Err(e) => {
+                    self.message = error_message("Clipboard not available; paste the module into the terminal instead", &e);
+                    self.message_return_mode = AppMode::CreateProject;
+                    self.mode = AppMode::MessageDialog;
+                }
+            },
+            (Some(Action::Back), _) => self.mode = AppMode::MainMenu,
+            (Some(Action::Select), _) if !self.project_creator.project_name.is_empty() => {
+                match self.project_creator.create_project() {
+                    Ok(path) => {
+                        // Refresh both editor and compiler lists since we created a new project
+                        self.project_editor.refresh_projects();
+                        self.project_compiler.refresh_projects();
+                        self.finish_project_creation(path);
+                        self.project_creator.reset();
+                        self.project_creator.open_in_editor = self.config.open_after_create;
+                    }
+                    Err(e) => {
+                        self.message = format!("Error creating project: {}", e);
+                        self.mode = AppMode::MessageDialog;
+                    }
+                }
+            }
+            (Some(Action::NavigateLeft | Action::NavigateRight), _) if self.project_creator.focused_field.is_toggle() => {
+                self.project_creator.toggle_focused_field();
+            }
+            (Some(Action::NextTab | Action::NavigateDown), _) => self.project_creator.focus_next_field(),
+            (Some(Action::PreviousTab | Action::NavigateUp), _) => self.project_creator.focus_previous_field(),
+            (_, KeyCode::Backspace) => self.project_creator.pop_char(),
+            (_, KeyCode::Char(c)) => self.project_creator.push_char(c),
+            _ => {}
+        }
+    }
+
+    fn open_selected_in_editor(&mut self) {
+        match self.project_editor.open_project_in_editor() {
+            // Terminal editors run once `run_app` has suspended the TUI
+            Ok(launch) if launch.kind == EditorKind::Terminal => self.pending_editor = Some(launch),
+            Ok(launch) => {
+                let result = launch.run();
+                self.finish_editor_launch(result);
+            }
+            Err(e) => {
+                self.message = error_message("Error opening project in editor", &e);
+                self.mode = AppMode::MessageDialog;
+            }
+        }
+    }
+
+    // Output streams in while the screen stays responsive; the result is
+    // picked up in `tick`
+    // Follow a batch in the output tab as its projects run
+    fn show_batch_output(&mut self, started: Result<(), HadouError>) {
+        match started {
+            Ok(()) => self.project_compiler.active_tab = CompileTab::Output,
+            Err(e) => {
+                self.message = error_message("Batch run failed", &e);
+                self.mode = AppMode::MessageDialog;
+            }
+        }
+    }
+
+    fn finish_batch(&mut self, batch: BatchRun) {
+        let summary = ProjectCompiler::batch_summary(&batch.results);
+        self.message = if batch.marked {
+            self.project_compiler.marked_projects.clear();
+            format!("Ran {} on {} marked project(s)\n\n{}", batch.action.description(), batch.total, summary)
+        } else {
+            summary
+        };
+        self.scan_vcd_files();
+        self.message_return_mode = AppMode::CompileProject;
+        self.mode = AppMode::MessageDialog;
+    }
+
+    fn start_selected_compilation(&mut self) {
+        match self.project_compiler.start_compilation() {
+            Ok(()) => self.project_compiler.active_tab = CompileTab::Output,
+            Err(HadouError::MissingRecipe { recipe, available }) if !available.is_empty() => {
+                self.open_recipe_picker(recipe, available);
+            }
+            Err(e) => {
+                self.message = error_message("Compilation failed", &e);
+                self.mode = AppMode::MessageDialog;
+            }
+        }
+    }
+
+    fn import_module(&mut self, source: &str) {
+        if let Err(e) = self.project_creator.import_module(source) {
+            self.message = error_message("Could not import the module", &e);
+            self.message_return_mode = AppMode::CreateProject;
+            self.mode = AppMode::MessageDialog;
+        }
+    }
+
+    // Report the new project, or go straight to the editor when the create
+    // form asked for that
+    fn finish_project_creation(&mut self, path: PathBuf) {
+        if !self.project_creator.open_in_editor {
+            self.message = format!("Project Created successfully at: {}", path.display());
+            self.mode = AppMode::MessageDialog;
+            return;
+        }
+
+        self.project_editor.select_project(&path);
+        self.message_return_mode = AppMode::EditProject;
+        match self.project_editor.open_path_in_editor(&path) {
+            // Terminal editors run once `run_app` has suspended the TUI
+            Ok(launch) if launch.kind == EditorKind::Terminal => {
+                self.mode = AppMode::EditProject;
+                self.pending_editor = Some(launch);
+            }
+            Ok(launch) => {
+                let result = launch.run();
+                self.finish_editor_launch(result);
+            }
+            Err(e) => {
+                self.message = error_message(&format!("Project created at {}, but could not open it in the editor", path.display()), &e);
+                self.mode = AppMode::MessageDialog;
+            }
+        }
+    }
+
+    fn handle_compile_project_key(&mut self, key: KeyCode) {
+        let on_projects_tab = self.project_compiler.active_tab == CompileTab::Projects;
+        if on_projects_tab && self.project_compiler.search.typing && handle_search_key(&mut self.project_compiler.search, &self.keymap, key) {
+            self.project_compiler.select_best_match();
+            return;
+        }
+
+        if on_projects_tab && self.scroll_preview(key) {
+            return;
+        }
+
+        // 'l' saves the log outside the projects tab, where ←/→ have no use
+        let reserved: &[char] = if on_projects_tab { &[] } else { &['h', 'l'] };
+
+        match (self.keymap.action(key, reserved), key) {
+            (Some(Action::Back), _) if self.project_compiler.search.is_active() => self.project_compiler.search.clear(),
+            (Some(Action::Back), _) if on_projects_tab && self.project_compiler.marked_count() > 0 => {
+                self.project_compiler.marked_projects.clear();
+            }
+            (Some(Action::Back), _) => self.mode = AppMode::MainMenu,
+            (Some(Action::Search), _) if on_projects_tab => self.project_compiler.search.start(),
+            (_, KeyCode::Char(' ')) if on_projects_tab && self.project_compiler.selection_visible() => {
+                self.project_compiler.toggle_mark();
+            }
+            (Some(Action::Sort), _) if on_projects_tab => self.cycle_sort_mode(),
+            (Some(Action::Pin), _) if on_projects_tab && self.project_compiler.selection_visible() => {
+                if let Some(path) = self.project_compiler.get_selected_project_path().cloned() {
+                    self.toggle_pin(&path);
+                }
+            }
+            (Some(Action::CopyPath), _) if on_projects_tab && self.project_compiler.selection_visible() => {
+                if let Some(path) = self.project_compiler.get_selected_project_path().cloned() {
+                    self.copy_path(&path);
+                }
+            }
+            (Some(Action::NextTab), _) => self.project_compiler.next_tab(),
+            (Some(Action::PreviousTab), _) => self.project_compiler.previous_tab(),
+            (Some(Action::NavigateUp), _) if self.project_compiler.active_tab == CompileTab::Environment => {
+                self.project_compiler.project_env.move_selection_up();
+            }
+            (Some(Action::NavigateDown), _) if self.project_compiler.active_tab == CompileTab::Environment => {
+                self.project_compiler.project_env.move_selection_down();
+            }
+            (_, KeyCode::Char(' ')) if self.project_compiler.active_tab == CompileTab::Environment => {
+                self.project_compiler.project_env.toggle_selected();
+            }
+            (_, KeyCode::Char('e')) if self.project_compiler.active_tab == CompileTab::Environment => {
+                if let Some(value) = self.project_compiler.project_env.selected().map(|var| var.value.clone()) {
+                    self.open_input_dialog(InputAction::EditEnvValue);
+                    self.input_buffer = value;
+                }
+            }
+            (Some(Action::NavigateUp), _) => {
+                self.project_compiler.move_project_selection_up();
+            }
+            (Some(Action::NavigateDown), _) => {
+                self.project_compiler.move_project_selection_down();
+            }
+            (Some(Action::NavigateLeft), _) => {
+                self.project_compiler.move_action_selection_up();
+            }
+            (Some(Action::NavigateRight), _) => {
+                self.project_compiler.move_action_selection_down();
+            }
+            (Some(Action::Select), _) if on_projects_tab && self.project_compiler.marked_count() > 0 && !self.project_compiler.is_compiling => {
+                // Run the selected action on the marked projects one after another
+                let started = self.project_compiler.execute_marked();
+                self.show_batch_output(started);
+            }
+            (Some(Action::Select), _) if self.project_compiler.selection_visible() && !self.project_compiler.is_compiling => {
+                self.start_selected_compilation();
+            }
+            (_, KeyCode::Char('a')) if self.project_compiler.has_projects() && !self.project_compiler.is_compiling => {
+                // Run the selected action on every project
+                let started = self.project_compiler.execute_batch();
+                self.show_batch_output(started);
+            }
+            (Some(Action::Refresh), _) => {
+                // Refresh project list, re-read .env files and look for
+                // tools installed since the last check
+                self.project_compiler.refresh_projects();
+                self.project_compiler.reload_project_env();
+                self.refresh_health();
+                self.message = format!("Refreshed project list. Found {} projects", 
+                    self.project_compiler.project_count());
+                self.mode = AppMode::MessageDialog;
+            }
+            (_, KeyCode::Char('c')) => {
+                // Clear compilation output
+                self.project_compiler.clear_compilation_output();
+            }
+            (Some(Action::ShowCommand), _) if self.project_compiler.selection_visible() => self.show_command_preview(),
+            (_, KeyCode::Char('N')) if on_projects_tab && self.project_compiler.selection_visible() => {
+                if let Some(project) = self.project_compiler.get_selected_project_path().cloned() {
+                    self.open_input_dialog(InputAction::AddNote { project, from: AppMode::CompileProject });
+                }
+            }
+            (_, KeyCode::Char('d')) if self.project_compiler.active_tab != CompileTab::Environment => {
+                self.project_compiler.cycle_dump_depth();
+            }
+            (_, KeyCode::Char('D')) if self.project_compiler.active_tab != CompileTab::Environment => {
+                self.project_compiler.skip_dump = !self.project_compiler.skip_dump;
+            }
+            (_, KeyCode::Char('w')) if self.project_compiler.watch.is_some() => self.project_compiler.stop_watch(),
+            (_, KeyCode::Char('w')) if on_projects_tab && self.project_compiler.selection_visible() && !self.project_compiler.is_compiling => {
+                match self.project_compiler.start_watch() {
+                    Ok(()) => self.project_compiler.active_tab = CompileTab::Output,
+                    Err(e) => {
+                        self.message = error_message("Could not watch project", &e);
+                        self.message_return_mode = AppMode::CompileProject;
+                        self.mode = AppMode::MessageDialog;
+                    }
+                }
+            }
+            (_, KeyCode::Char('f')) if on_projects_tab && self.project_compiler.selection_visible() => {
+                self.edit_project_config(InputAction::EditIverilogFlags);
+            }
+            (_, KeyCode::Char('i')) if on_projects_tab && self.project_compiler.selection_visible() => {
+                self.edit_project_config(InputAction::EditSources);
+            }
+            (_, KeyCode::Char('J')) if on_projects_tab && self.project_compiler.selection_visible() => self.validate_justfile(),
+            (_, KeyCode::Char('t')) if on_projects_tab && self.project_compiler.selection_visible() && !self.project_compiler.is_compiling => {
+                self.open_testbench_picker();
+            }
+            (_, KeyCode::Char('l')) if !on_projects_tab => {
+                self.message = match self.project_compiler.save_output_log(self.config.log_dir.as_deref()) {
+                    Ok(path) => format!("Saved compile log to {}", path.display()),
+                    Err(e) => format!("Could not save log: {}", e),
+                };
+                self.message_return_mode = AppMode::CompileProject;
+                self.mode = AppMode::MessageDialog;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_edit_project_key(&mut self, key: KeyCode) {
+        if self.project_editor.search.typing && handle_search_key(&mut self.project_editor.search, &self.keymap, key) {
+            self.project_editor.select_best_match();
+            return;
+        }
+
+        if self.scroll_preview(key) {
+            return;
+        }
+
+        match (self.keymap.action(key, &[]), key) {
+            (Some(Action::Back), _) if self.project_editor.search.is_active() => self.project_editor.search.clear(),
+            (Some(Action::Back), _) => {
+                self.project_editor.syntax_checker.cancel();
+                self.mode = AppMode::MainMenu;
+            }
+            (Some(Action::Search), _) => self.project_editor.search.start(),
+            (Some(Action::Sort), _) => self.cycle_sort_mode(),
+            (Some(Action::Pin), _) if self.project_editor.selection_visible() => {
+                if let Some(path) = self.project_editor.get_selected_project_path().cloned() {
+                    self.toggle_pin(&path);
+                }
+            }
+            (Some(Action::CopyPath), _) if self.project_editor.selection_visible() => {
+                if let Some(path) = self.project_editor.get_selected_project_path().cloned() {
+                    self.copy_path(&path);
+                }
+            }
+            (Some(Action::ShowCommand), _) if self.project_editor.selection_visible() => self.show_command_preview(),
+            (_, KeyCode::Char('a')) => self.project_editor.show_all_files = !self.project_editor.show_all_files,
+            (_, KeyCode::Char('i')) if self.project_editor.selection_visible() => {
+                match self.project_editor.module_snippets() {
+                    Ok(snippets) => {
+                        self.module_snippets = snippets;
+                        self.selected_snippet_index = 0;
+                        self.mode = AppMode::ModuleSnippets;
+                    }
+                    Err(e) => {
+                        self.message = format!("Could not generate instantiation templates: {}", e);
+                        self.message_return_mode = AppMode::EditProject;
+                        self.mode = AppMode::MessageDialog;
+                    }
+                }
+            }
+            (Some(Action::NavigateUp), _) => {
+                self.project_editor.move_selection_up();
+            }
+            (Some(Action::NavigateDown), _) => {
+                self.project_editor.move_selection_down();
+            }
+            (Some(Action::Select), _) if self.project_editor.selection_visible() => self.open_selected_in_editor(),
+            (_, KeyCode::Char('d')) if self.project_editor.selection_visible() => {
+                self.open_input_dialog(InputAction::DuplicateProject);
+            }
+            (_, KeyCode::Char('A')) if self.project_editor.selection_visible() => self.open_file_template_picker(),
+            (_, KeyCode::Char('z')) if self.project_editor.selection_visible() => self.open_archive_picker(),
+            (_, KeyCode::Char('N')) if self.project_editor.selection_visible() => {
+                if let Some(project) = self.project_editor.get_selected_project_path().cloned() {
+                    self.open_input_dialog(InputAction::AddNote { project, from: AppMode::EditProject });
+                }
+            }
+            (_, KeyCode::Char('n')) if self.project_editor.selection_visible() => {
+                let current = self.project_editor.get_selected_project_name().unwrap_or_default();
+                self.open_input_dialog(InputAction::RenameProject);
+                self.input_buffer = current;
+            }
+            (_, KeyCode::Char('f')) if self.project_editor.selection_visible() => {
+                self.open_input_dialog(InputAction::FindReferences);
+                self.input_buffer = self.reference_query.clone();
+            }
+            (_, KeyCode::Char('t')) if self.project_editor.selection_visible() => {
+                self.confirm_action = Some(ConfirmAction::RegenerateTestbench);
+                self.mode = AppMode::ConfirmDialog;
+            }
+            (_, KeyCode::Char('g')) if self.project_editor.selection_visible() => {
+                let has_testbench = self.project_editor
+                    .get_selected_project_path()
+                    .is_some_and(|path| path.join("main_test.v").exists());
+                if has_testbench {
+                    self.confirm_action = Some(ConfirmAction::ReplaceTestbench);
+                    self.mode = AppMode::ConfirmDialog;
+                } else {
+                    self.create_testbench(false);
+                }
+            }
+            (Some(Action::Refresh), _) => {
+                // Refresh project list
+                self.project_editor.refresh_projects();
+                self.start_syntax_check();
+                self.message = format!("Refreshed project list. Found {} projects", 
+                    self.project_editor.project_count());
+                self.mode = AppMode::MessageDialog;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn finish_editor_launch(&mut self, result: Result<(), HadouError>) {
+        // A terminal editor has just closed, so main.v may have changed, and
+        // so may the notes, testbench or .hadou.toml the previews show
+        self.start_syntax_check();
+        self.project_editor.reload_notes();
+        self.project_compiler.reload_details();
+
+        match result {
+            Ok(()) => {
+                if let Some(project_name) = self.project_editor.get_selected_project_name() {
+                    self.message = format!("Opened project '{}' in editor", project_name);
+                } else {
+                    self.message = "Project opened in editor".to_string();
+                }
+            }
+            Err(e) => {
+                self.message = error_message("Error opening project in editor", &e);
+            }
+        }
+        self.mode = AppMode::MessageDialog;
+    }
+
+    fn handle_browse_directory_key(&mut self, key: KeyCode) {
+        let result = match (self.keymap.action(key, &[]), key) {
+            (Some(Action::Back), _) => {
+                self.mode = AppMode::MainMenu;
+                Ok(())
+            }
+            (Some(Action::NavigateUp), _) => {
+                self.dir_browser.move_up();
+                Ok(())
+            }
+            (Some(Action::NavigateDown), _) => {
+                self.dir_browser.move_down();
+                Ok(())
+            }
+            (Some(Action::Select | Action::NavigateRight), _) => self.dir_browser.enter_selected(),
+            (Some(Action::NavigateLeft), _) | (_, KeyCode::Backspace) => self.dir_browser.go_up(),
+            (_, KeyCode::Char('.')) => self.dir_browser.toggle_hidden(),
+            (_, KeyCode::Char('~')) => match std::env::var_os("HOME") {
+                Some(home) => self.dir_browser.open(Path::new(&home)),
+                None => Ok(()),
+            },
+            (_, KeyCode::Char(c @ '1'..='9')) => {
+                let index = c as usize - '1' as usize;
+                match self.config.recent_dirs.get(index).cloned() {
+                    Some(recent) => self.dir_browser.open(Path::new(&recent)),
+                    None => Ok(()),
+                }
+            }
+            (_, KeyCode::Char(' ')) => {
+                let dir = self.dir_browser.path.clone();
+                match self.set_base_dir(&dir) {
+                    Ok(()) => {
+                        self.message = format!(
+                            "Now working in {}: {} project(s), {} VCD file(s)",
+                            dir.display(),
+                            self.project_compiler.project_count(),
+                            self.vcd_files.len()
+                        );
+                        self.message_return_mode = AppMode::MainMenu;
+                        self.mode = AppMode::MessageDialog;
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            _ => Ok(()),
+        };
+
+        if let Err(e) = result {
+            self.message = error_message("Could not open directory", &e);
+            self.message_return_mode = AppMode::BrowseDirectory;
+            self.mode = AppMode::MessageDialog;
+        }
+    }
+
+    fn handle_module_snippets_key(&mut self, key: KeyCode) {
+        let count = self.module_snippets.len();
+        match (self.keymap.action(key, &[]), key) {
+            (Some(Action::Back), _) => self.mode = AppMode::EditProject,
+            (Some(Action::NavigateUp), _) if count > 0 => {
+                self.selected_snippet_index = (self.selected_snippet_index + count - 1) % count;
+            }
+            (Some(Action::NavigateDown), _) if count > 0 => {
+                self.selected_snippet_index = (self.selected_snippet_index + 1) % count;
+            }
+            (Some(Action::Select | Action::CopyPath), _) => {
+                let Some(snippet) = self.module_snippets.get(self.selected_snippet_index) else {
+                    return;
+                };
+                self.message = match self.clipboard.copy(&snippet.snippet) {
+                    Ok(()) => format!("Copied the {} instantiation to the clipboard", snippet.module),
+                    Err(e) => format!("Clipboard not available ({}). Select the snippet in the preview to copy it instead.", e),
+                };
+                self.message_return_mode = AppMode::ModuleSnippets;
+                self.mode = AppMode::MessageDialog;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_references_key(&mut self, key: KeyCode) {
+        let count = self.references.len();
+        match (self.keymap.action(key, &[]), key) {
+            (Some(Action::Back), _) => self.mode = AppMode::EditProject,
+            (Some(Action::NavigateUp), _) if count > 0 => {
+                self.selected_reference_index = (self.selected_reference_index + count - 1) % count;
+            }
+            (Some(Action::NavigateDown), _) if count > 0 => {
+                self.selected_reference_index = (self.selected_reference_index + 1) % count;
+            }
+            (Some(Action::Select), _) => {
+                let Some(reference) = self.references.get(self.selected_reference_index) else {
+                    return;
+                };
+                self.message_return_mode = AppMode::References;
+                match self.project_editor.open_file_at_line(&reference.file, reference.line) {
+                    // Terminal editors run once `run_app` has suspended the TUI
+                    Ok(launch) if launch.kind == EditorKind::Terminal => self.pending_editor = Some(launch),
+                    Ok(launch) => {
+                        let result = launch.run();
+                        self.finish_editor_launch(result);
+                    }
+                    Err(e) => {
+                        self.message = error_message("Error opening file in editor", &e);
+                        self.mode = AppMode::MessageDialog;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_view_waveform_key(&mut self, key: KeyCode) {
+        if self.vcd_search.typing && handle_search_key(&mut self.vcd_search, &self.keymap, key) {
+            self.select_best_vcd_match();
+            return;
+        }
+
+        match (self.keymap.action(key, &[]), key) {
+            (Some(Action::Back), _) if self.vcd_search.is_active() => self.vcd_search.clear(),
+            (Some(Action::Back), _) => self.mode = AppMode::MainMenu,
+            (Some(Action::Search), _) => self.vcd_search.start(),
+            (Some(Action::Sort), _) => self.cycle_sort_mode(),
+            (Some(Action::CopyPath), _) if self.vcd_selection_visible() => {
+                if let Some(path) = self.vcd_files.get(self.selected_vcd_index).map(|entry| entry.path.clone()) {
+                    self.copy_path(&path);
+                }
+            }
+            (Some(Action::NavigateUp), _) => {
+                if let Some(index) = fuzzy::step_selection(&self.visible_vcd_files(), self.selected_vcd_index, false) {
+                    self.selected_vcd_index = index;
+                }
+            }
+            (Some(Action::NavigateDown), _) => {
+                if let Some(index) = fuzzy::step_selection(&self.visible_vcd_files(), self.selected_vcd_index, true) {
+                    self.selected_vcd_index = index;
+                }
+            }
+            (Some(Action::Select), _) if self.vcd_selection_visible() || self.vcd_files.is_empty() => {
+                self.open_external_viewer();
+            }
+            (_, KeyCode::Char('o')) if self.vcd_selection_visible() => self.open_viewer_picker(),
+            (_, KeyCode::Char('w')) if self.vcd_selection_visible() => self.start_web_viewer(),
+            (_, KeyCode::Char('c')) if self.vcd_selection_visible() => self.open_vcd_compare(),
+            (Some(Action::ShowCommand), _) if self.vcd_selection_visible() => self.show_command_preview(),
+            (_, KeyCode::Char('v')) if self.vcd_selection_visible() || self.vcd_files.is_empty() => {
+                self.open_internal_viewer();
+            }
+            (Some(Action::Refresh), _) => {
+                // Refresh VCD files
+                self.scan_vcd_files();
+                self.message = format!("Refreshed VCD files. Found {} files", self.vcd_files.len());
+                self.mode = AppMode::MessageDialog;
+            }
+            (_, KeyCode::Char('i')) => {
+                // Show install instructions
+                self.message = "Waveform Viewer Installation:\n\n• DWFV (recommended): cargo install dwfv\n• DigiSurf: cargo install digisurf\n• GTKWave: sudo apt install gtkwave\n\nDWFV provides the best terminal experience with vi-like keybindings!".to_string();
+                self.mode = AppMode::MessageDialog;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_internal_waveform_key(&mut self, key: KeyCode) {
+        let viewer = &mut self.waveform_viewer;
+        match (self.keymap.action(key, &[]), key) {
+            (Some(Action::Back), _) => self.mode = AppMode::ViewWaveform,
+            (Some(Action::NavigateUp), _) => viewer.move_signal_selection_up(),
+            (Some(Action::NavigateDown), _) => viewer.move_signal_selection_down(),
+            (Some(Action::NavigateLeft), _) => viewer.scroll_left(),
+            (Some(Action::NavigateRight), _) => viewer.scroll_right(),
+            (_, KeyCode::Char('+') | KeyCode::Char('=')) => viewer.zoom_in(),
+            (_, KeyCode::Char('-')) => viewer.zoom_out(),
+            (_, KeyCode::Char('a')) => viewer.toggle_selected_in_view(),
+            (_, KeyCode::Char('[')) => viewer.move_selected_in_view(true),
+            (_, KeyCode::Char(']')) => viewer.move_selected_in_view(false),
+            (_, KeyCode::Char('x')) => viewer.clear_view(),
+            (_, KeyCode::Char('n')) => viewer.next_saved_view(),
+            (_, KeyCode::Char('c')) => viewer.toggle_cursor_a(),
+            (_, KeyCode::Char('.') | KeyCode::Char('>')) => viewer.jump_to_edge(true),
+            (_, KeyCode::Char(',') | KeyCode::Char('<')) => viewer.jump_to_edge(false),
+            (_, KeyCode::Char('e')) => viewer.toggle_bit_split(),
+            (_, KeyCode::Char('t')) => viewer.toggle_relative_time(),
+            (_, KeyCode::Char('r')) => viewer.cycle_radix(),
+            (_, KeyCode::Char('v')) => viewer.show_value_table = !viewer.show_value_table,
+            (_, KeyCode::Char('W')) => viewer.toggle_parse_warnings(),
+            (_, KeyCode::Char('g')) if viewer.value_search.is_some() => viewer.find_next_value(true),
+            (_, KeyCode::Char('G')) if viewer.value_search.is_some() => viewer.find_next_value(false),
+            (_, KeyCode::Char('f' | 'g')) => {
+                let last = viewer.value_search.as_ref().map(|(text, _)| text.clone()).unwrap_or_default();
+                let radix = viewer.radix;
+                self.open_input_dialog(InputAction::FindValue { radix });
+                self.input_buffer = last;
+            }
+            (_, KeyCode::Char('s')) => {
+                let name = if viewer.view.name.is_empty() {
+                    "default".to_string()
+                } else {
+                    viewer.view.name.clone()
+                };
+                self.open_input_dialog(InputAction::SaveSignalView);
+                self.input_buffer = name;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_settings_key(&mut self, key: KeyCode) {
+        if self.settings_editor.editing {
+            match (self.keymap.text_action(key), key) {
+                (Some(Action::Back), _) => self.settings_editor.cancel_edit(),
+                (Some(Action::Select), _) => self.settings_editor.commit_edit(),
+                (_, KeyCode::Backspace) => {
+                    self.settings_editor.edit_buffer.pop();
+                }
+                (_, KeyCode::Char(c)) => {
+                    self.settings_editor.edit_buffer.push(c);
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        match (self.keymap.action(key, &[]), key) {
+            (Some(Action::Back), _) => self.mode = AppMode::MainMenu,
+            (Some(Action::NavigateUp), _) => self.settings_editor.move_selection_up(),
+            (Some(Action::NavigateDown), _) => self.settings_editor.move_selection_down(),
+            (Some(Action::Select), _) => self.settings_editor.activate(),
+            (_, KeyCode::Char('s')) => {
+                match self.settings_editor.save() {
+                    Ok(()) => {
+                        self.config = self.settings_editor.draft.clone();
+                        self.apply_config();
+                        self.project_editor.refresh_projects();
+                        self.project_compiler.refresh_projects();
+                        self.scan_vcd_files();
+                        self.refresh_health();
+                        self.settings_editor.status = Some("Settings saved".to_string());
+                    }
+                    Err(e) => {
+                        self.settings_editor.status = Some(format!("Error saving settings: {}", e));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_input_dialog_key(&mut self, key: KeyCode) {
+        match (self.keymap.text_action(key), key) {
+            (Some(Action::Back), _) => {
+                self.input_buffer.clear();
+                self.mode = self.input_action
+                    .take()
+                    .map(|action| action.return_mode())
+                    .unwrap_or(AppMode::MainMenu);
+            }
+            (Some(Action::Select), _) if !self.input_buffer.is_empty() && self.input_is_acceptable() => {
+                let input = std::mem::take(&mut self.input_buffer);
+                match self.input_action.take() {
+                    Some(action) => self.submit_input(action, input),
+                    None => self.mode = AppMode::MainMenu,
+                }
+            }
+            (_, KeyCode::Backspace) => {
+                self.input_buffer.pop();
+            }
+            (_, KeyCode::Char(c)) if self.input_action.as_ref().is_none_or(|action| action.accepts(c)) => {
+                self.input_buffer.push(c);
+            }
+            _ => {}
+        }
+    }
+
+    fn input_is_acceptable(&self) -> bool {
+        let check = self.input_action.as_ref().and_then(|action| action.check(&self.input_buffer));
+        !matches!(check, Some(Err(_)))
+    }
+
+    fn handle_confirm_dialog_key(&mut self, key: KeyCode) {
+        let Some(action) = self.confirm_action.clone() else {
+            self.mode = AppMode::MainMenu;
+            return;
+        };
+
+        match (self.keymap.text_action(key), key) {
+            (Some(Action::Select), _) | (_, KeyCode::Char('y')) => {
+                self.confirm_action = None;
+                self.run_confirmed(action);
+            }
+            (Some(Action::Back), _) | (_, KeyCode::Char('n')) => {
+                self.confirm_action = None;
+                self.mode = action.return_mode();
+            }
+            _ => {}
+        }
+    }
+
+    fn run_confirmed(&mut self, action: ConfirmAction) {
+        match action {
+            ConfirmAction::RegenerateTestbench => {
+                self.message = match self.project_editor.regenerate_testbench() {
+                    Ok(update) => {
+                        let mut message = if update.preserved_stimulus {
+                            format!("Updated the '{}' instantiation in main_test.v; the rest of the testbench was kept", update.module)
+                        } else {
+                            format!("Generated a new main_test.v for '{}'", update.module)
+                        };
+                        if let Some(backup) = update.backup {
+                            message.push_str(&format!("\n\nPrevious version saved to {}", backup.display()));
+                        }
+                        message
+                    }
+                    Err(e) => format!("Error regenerating testbench: {}", e),
+                };
+                self.message_return_mode = action.return_mode();
+                self.mode = AppMode::MessageDialog;
+            }
+            ConfirmAction::ReplaceTestbench => self.create_testbench(true),
+            ConfirmAction::OpenLargeVcd(path) => self.load_internal_viewer(&path, AppMode::ViewWaveform),
+            ConfirmAction::AdoptDirectory(dir) => self.adopt_directory(&dir),
+            ConfirmAction::RegenerateJustfile(project) => self.regenerate_justfile(&project),
+            ConfirmAction::Quit(_) => {
+                self.project_compiler.stop_watch();
+                self.project_compiler.stop_compilation();
+                if let Some(mut server) = self.web_server.take() {
+                    server.stop();
+                }
+                self.should_quit = true;
+            }
+        }
+    }
+
+    fn adopt_directory(&mut self, dir: &Path) {
+        self.message = match adopt::adopt_directory(dir, &self.project_creator.simulator) {
+            Ok(adoption) => {
+                self.project_editor.refresh_projects();
+                self.project_compiler.refresh_projects();
+                self.refresh_health();
+                let build_file = match adoption.build_file_created {
+                    Some(path) => format!("Wrote {} to compile {}.", path.display(), adoption.sources.join(", ")),
+                    None => "Its existing build file is used as it is.".to_string(),
+                };
+                format!(
+                    "Adopted '{}' as a project. {}\n\nIt is listed on the compile and edit screens alongside any projects below it.",
+                    adoption.name, build_file
+                )
+            }
+            Err(e) => error_message("Could not adopt the directory", &e),
+        };
+        self.message_return_mode = AppMode::MainMenu;
+        self.mode = AppMode::MessageDialog;
+    }
+
+    // Check the selected project's justfile with just itself, offering a
+    // fresh one when it doesn't parse
+    fn validate_justfile(&mut self) {
+        match self.project_compiler.validate_justfile() {
+            Ok(JustfileStatus::Valid(recipes)) => {
+                self.message = format!("The Justfile parses. Recipes: {}", recipes.join(", "));
+            }
+            Ok(JustfileStatus::Invalid(_)) => {
+                self.project_compiler.active_tab = CompileTab::Output;
+                if let Some(project) = self.project_compiler.get_selected_project_path().cloned() {
+                    self.confirm_action = Some(ConfirmAction::RegenerateJustfile(project));
+                    self.mode = AppMode::ConfirmDialog;
+                }
+                return;
+            }
+            Err(e) => self.message = error_message("Could not validate the Justfile", &e),
+        }
+        self.message_return_mode = AppMode::CompileProject;
+        self.mode = AppMode::MessageDialog;
+    }
+
+    fn regenerate_justfile(&mut self, project: &Path) {
+        self.message = match justfile::regenerate(project, &self.project_creator.simulator) {
+            Ok(backup) => {
+                let mut message = format!("Wrote a fresh Justfile for {}", project_label(project));
+                if let Some(backup) = backup {
+                    message.push_str(&format!("\n\nThe broken one was saved to {}", backup.display()));
+                }
+                // Check the new file too, which also clears the invalid marker
+                if let Ok(JustfileStatus::Valid(_)) = self.project_compiler.validate_justfile() {
+                    message.push_str("\n\njust parses the new file.");
+                }
+                message
+            }
+            Err(e) => error_message("Could not regenerate the Justfile", &e),
+        };
+        self.message_return_mode = AppMode::CompileProject;
+        self.mode = AppMode::MessageDialog;
+    }
+
+    fn rename_project(&mut self, new_name: &str) {
+        let old_path = self.project_editor.get_selected_project_path().cloned();
+        self.message = match self.project_editor.rename_selected_project(new_name) {
+            Ok(rename) => {
+                // Whatever pointed at the old directory follows the project
+                if let Some(old_path) = old_path {
+                    if self.project_compiler.watch.as_ref().is_some_and(|watch| watch.project_path == old_path) {
+                        self.project_compiler.stop_watch();
+                    }
+                    if let Some((path, _)) = self.project_compiler.last_run.as_mut().filter(|(path, _)| *path == old_path) {
+                        *path = rename.path.clone();
+                    }
+                }
+                self.project_editor.refresh_projects();
+                self.project_compiler.refresh_projects();
+                self.project_editor.select_project(&rename.path);
+
+                let mut message = format!("Project renamed to: {}", rename.path.display());
+                if !rename.updated_files.is_empty() {
+                    let files: Vec<String> = rename.updated_files.iter().map(|file| project_label(file)).collect();
+                    message.push_str(&format!("\n\nUpdated {}", files.join(", ")));
+                }
+                if !rename.backups.is_empty() {
+                    let backups: Vec<String> = rename.backups.iter().map(|backup| project_label(backup)).collect();
+                    message.push_str(&format!("\n\nPrevious versions saved as {}", backups.join(", ")));
+                }
+                message
+            }
+            Err(e) => error_message("Error renaming project", &e),
+        };
+        self.message_return_mode = AppMode::EditProject;
+        self.mode = AppMode::MessageDialog;
+    }
+
+    // Testbench (and build file) for a design written outside Hadou
+    fn create_testbench(&mut self, overwrite: bool) {
+        let build_system = self.project_creator.build_system;
+        self.message = match self.project_editor.create_testbench(&self.project_creator.simulator, build_system, overwrite) {
+            Ok(creation) => {
+                let mut message = format!("Generated main_test.v for '{}'", creation.module);
+                if let Some(system) = creation.build_file_created {
+                    message.push_str(&format!(" and a {} to build it", system.file_name()));
+                }
+                if let Some(backup) = creation.backup {
+                    message.push_str(&format!("\n\nPrevious version saved to {}", backup.display()));
+                }
+                message
+            }
+            Err(e) => format!("Error generating testbench: {}", e),
+        };
+        self.message_return_mode = AppMode::EditProject;
+        self.mode = AppMode::MessageDialog;
+    }
+
+    fn handle_message_dialog_key(&mut self, key: KeyCode) {
+        match (self.keymap.action(key, &[]), key) {
+            (Some(Action::Select | Action::Back), _) => {
+                self.message.clear();
+                self.message_scroll.reset();
+                self.error_detail = None;
+                self.mode = std::mem::replace(&mut self.message_return_mode, AppMode::MainMenu);
+            }
+            (_, KeyCode::Char('d')) if self.error_detail.is_some() => {
+                self.error_scroll.reset();
+                self.error_status = None;
+                self.mode = AppMode::ErrorDetail;
+            }
+            (Some(Action::NavigateUp), _) => self.message_scroll.scroll_up(1),
+            (Some(Action::NavigateDown), _) => self.message_scroll.scroll_down(1),
+            (_, KeyCode::PageUp) => self.message_scroll.scroll_up(self.message_scroll.page()),
+            (_, KeyCode::PageDown) => self.message_scroll.scroll_down(self.message_scroll.page()),
+            (_, KeyCode::Home) => self.message_scroll.reset(),
+            _ => {}
+        }
+    }
+
+    fn handle_error_detail_key(&mut self, key: KeyCode) {
+        match (self.keymap.action(key, &[]), key) {
+            (Some(Action::Select | Action::Back), _) => self.mode = AppMode::MessageDialog,
+            (Some(Action::NavigateUp), _) => self.error_scroll.scroll_up(1),
+            (Some(Action::NavigateDown), _) => self.error_scroll.scroll_down(1),
+            (_, KeyCode::PageUp) => self.error_scroll.scroll_up(self.error_scroll.page()),
+            (_, KeyCode::PageDown) => self.error_scroll.scroll_down(self.error_scroll.page()),
+            (_, KeyCode::Home) => self.error_scroll.reset(),
+            (_, KeyCode::End) => self.error_scroll.scroll_down(usize::MAX),
+            (Some(Action::CopyPath), _) => {
+                let text = self.error_detail.clone().unwrap_or_default();
+                self.error_status = Some(match self.clipboard.copy(&text) {
+                    Ok(()) => "copied to clipboard".to_string(),
+                    Err(e) => format!("clipboard not available: {}", e),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_key_help_key(&mut self, key: KeyCode) {
+        match (self.keymap.action(key, &[]), key) {
+            (Some(Action::Select | Action::Back), _) | (_, KeyCode::Char('?')) => self.mode = self.key_help_return.clone(),
+            (Some(Action::NavigateUp), _) => self.key_help_scroll.scroll_up(1),
+            (Some(Action::NavigateDown), _) => self.key_help_scroll.scroll_down(1),
+            (_, KeyCode::PageUp) => self.key_help_scroll.scroll_up(self.key_help_scroll.page()),
+            (_, KeyCode::PageDown) => self.key_help_scroll.scroll_down(self.key_help_scroll.page()),
+            _ => {}
+        }
+    }
+
+    // PageUp/PageDown scroll the project preview; anything else puts it back
+    // at the top, since most keys change what it shows. Returns true when the
+    // key was used.
+    fn scroll_preview(&mut self, key: KeyCode) -> bool {
+        match key {
+            KeyCode::PageUp => self.preview_scroll.scroll_up(self.preview_scroll.page()),
+            KeyCode::PageDown => self.preview_scroll.scroll_down(self.preview_scroll.page()),
+            _ => {
+                self.preview_scroll.reset();
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// "context: error", followed by what to do about it when the error says
+// Confirmation after saving project settings that generated build files read
+// through `var`
+fn settings_saved_message(project_path: &Path, what: &str, var: &str, update: project_config::FlagsUpdate) -> String {
+    let build_file = BuildSystem::detect(project_path).unwrap_or_default().file_name();
+    let mut message = if update.build_file_updated {
+        format!("Saved {} to {} and the {}", what, project_config::PROJECT_CONFIG_FILE, build_file)
+    } else {
+        format!(
+            "Saved {} to {}. The {} wasn't generated with them; reference {} in its iverilog command to use them.",
+            what,
+            project_config::PROJECT_CONFIG_FILE,
+            build_file,
+            var
+        )
+    };
+    for backup in update.backups {
+        message.push_str(&format!("\n\nPrevious version saved to {}", backup.display()));
+    }
+    message
+}
+
+fn error_message(context: &str, error: &HadouError) -> String {
+    match error.hint() {
+        Some(hint) => format!("{}: {}\n\n{}", context, error, hint),
+        None => format!("{}: {}", context, error),
+    }
+}
+
+// Keys typed into a list search. Returns false for keys the search does not
+// use so the screen can still handle them (e.g. ↑/↓ to move through matches).
+fn handle_search_key(search: &mut ListSearch, keymap: &Keymap, key: KeyCode) -> bool {
+    match (keymap.text_action(key), key) {
+        (Some(Action::Back), _) => search.clear(),
+        (Some(Action::Select), _) => search.typing = false,
+        (_, KeyCode::Backspace) => {
+            search.query.pop();
+        }
+        (_, KeyCode::Char(c)) => search.query.push(c),
+        _ => return false,
+    }
+    true
+}
+
+fn paste_into_search(search: &mut ListSearch, text: &str) {
+    search.query.extend(text.chars().filter(|c| !c.is_control()));
+}
+
+fn vcd_label(path: &Path) -> String {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    match path.parent().and_then(|p| p.file_name()).and_then(|n| n.to_str()) {
+        Some(parent_dir) if parent_dir != "." => format!("{}/{}", parent_dir, file_name),
+        _ => file_name.to_string(),
+    }
+}
+
+// Smallest terminal the fixed-height layouts fit in without collapsing
+const MIN_WIDTH: u16 = 60;
+const MIN_HEIGHT: u16 = 20;
+
+// Width of each time column in the waveform value table
+const VALUE_COLUMN_WIDTH: u16 = 10;
+
+fn ui(f: &mut Frame, app: &App) {
+    let area = f.area();
+    if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        render_too_small(f, app, area);
+        return;
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+        .split(f.area());
+
+    render_status_bar(f, app, chunks[1]);
+
+    match app.mode {
+        AppMode::InputDialog => {
+            let underlying = app.input_action.as_ref().map(InputAction::return_mode).unwrap_or(AppMode::MainMenu);
+            render_screen(f, app, &underlying, chunks[0]);
+            render_input_dialog(f, app);
+        }
+        AppMode::ConfirmDialog => {
+            let underlying = app.confirm_action.as_ref().map(ConfirmAction::return_mode).unwrap_or(AppMode::MainMenu);
+            render_screen(f, app, &underlying, chunks[0]);
+            render_confirm_dialog(f, app);
+        }
+        AppMode::MessageDialog => {
+            render_screen(f, app, &app.message_return_mode, chunks[0]);
+            render_message_dialog(f, app);
+        }
+        AppMode::LoadingVcd => {
+            render_screen(f, app, &app.loading_return_mode, chunks[0]);
+            render_loading_dialog(f, app);
+        }
+        AppMode::WebViewer => {
+            render_screen(f, app, &AppMode::ViewWaveform, chunks[0]);
+            render_web_viewer_dialog(f, app);
+        }
+        AppMode::PickDialog => {
+            let return_mode = app.pick_action.as_ref().map(PickAction::return_mode).unwrap_or(AppMode::MainMenu);
+            render_screen(f, app, &return_mode, chunks[0]);
+            render_pick_dialog(f, app);
+        }
+        AppMode::KeyHelp => {
+            render_screen(f, app, &app.key_help_return, chunks[0]);
+            render_key_help(f, app);
+        }
+        AppMode::CommandPalette => {
+            render_screen(f, app, &app.palette_return, chunks[0]);
+            render_command_palette(f, app);
+        }
+        ref mode => render_screen(f, app, mode, chunks[0]),
+    }
+}
+
+fn render_too_small(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let text = vec![
+        Line::from(Span::styled(
+            "Terminal too small",
+            Style::default().fg(app.colors().red.into()).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(format!("need at least {}x{}, have {}x{}", MIN_WIDTH, MIN_HEIGHT, area.width, area.height)),
+    ];
+    let top = area.height.saturating_sub(text.len() as u16) / 2;
+    let message_area = ratatui::layout::Rect {
+        x: area.x,
+        y: area.y + top,
+        width: area.width,
+        height: area.height - top,
+    };
+
+    let message = Paragraph::new(text)
+        .wrap(Wrap { trim: true })
+        .alignment(ratatui::layout::Alignment::Center);
+    f.render_widget(message, message_area);
+}
+
+// Footer shared by every screen: where Hadou is looking, what it found
+// there, the colour flavor and whether the external tools are installed
+fn render_status_bar(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let separator = Span::styled("  │  ", Style::default().fg(Color::DarkGray));
+    let highlight = Style::default().fg(app.colors().yellow.into());
+
+    let missing: Vec<&str> = app.health.tools
+        .iter()
+        .filter(|tool| !tool.installed)
+        .map(|tool| tool.command.as_str())
+        .collect();
+    let toolchain = if missing.is_empty() {
+        Span::styled(format!("{} toolchain ready", app.icon(Icon::Found)), Style::default().fg(app.colors().green.into()))
+    } else {
+        Span::styled(format!("{} missing: {}", app.icon(Icon::Missing), missing.join(", ")), Style::default().fg(app.colors().red.into()))
+    };
+
+    let status = Line::from(vec![
+        Span::raw(format!(" {} ", app.icon(Icon::Folder))),
+        Span::styled(app.project_compiler.current_directory.display().to_string(), highlight),
+        separator.clone(),
+        Span::styled(app.project_compiler.project_count().to_string(), highlight),
+        Span::raw(" projects · "),
+        Span::styled(app.vcd_files.len().to_string(), highlight),
+        Span::raw(" VCD files"),
+        separator.clone(),
+        Span::raw(app.flavor.to_string()),
+        separator,
+        toolchain,
+    ]);
+
+    f.render_widget(Paragraph::new(status).style(Style::default().fg(Color::Gray)), area);
+}
+
+// Full-screen views; dialogs are drawn on top of one of these
+fn render_screen(f: &mut Frame, app: &App, mode: &AppMode, area: ratatui::layout::Rect) {
+    match mode {
+        AppMode::CreateProject => render_create_project(f, app, area),
+        AppMode::CompileProject => render_compile_project(f, app, area),
+        AppMode::EditProject => render_edit_project(f, app, area),
+        AppMode::ViewWaveform => render_view_waveform(f, app, area),
+        AppMode::InternalWaveform => render_internal_waveform(f, app, area),
+        AppMode::Settings => render_settings(f, app, area),
+        AppMode::ModuleSnippets => render_module_snippets(f, app, area),
+        AppMode::References => render_references(f, app, area),
+        AppMode::BrowseDirectory => render_browse_directory(f, app, area),
+        AppMode::ErrorDetail => render_error_detail(f, app, area),
+        AppMode::MainMenu
+        | AppMode::InputDialog
+        | AppMode::ConfirmDialog
+        | AppMode::LoadingVcd
+        | AppMode::WebViewer
+        | AppMode::MessageDialog
+        | AppMode::PickDialog
+        | AppMode::KeyHelp
+        | AppMode::CommandPalette => {
+            render_main_menu(f, app, area)
+        }
+    }
+}
+
+// Keys of a screen in its current state, or None for screens without a help
+// line of their own
+fn screen_hints(app: &App, mode: &AppMode) -> Option<&'static [Hint]> {
+    let hints = match mode {
+        AppMode::MainMenu => hints::MAIN_MENU,
+        AppMode::CreateProject => hints::CREATE,
+        AppMode::CompileProject => match app.project_compiler.active_tab {
+            _ if !app.project_compiler.has_projects() => hints::NO_PROJECTS,
+            CompileTab::Projects if app.project_compiler.search.typing => hints::SEARCH,
+            CompileTab::Projects => hints::COMPILE_PROJECTS,
+            CompileTab::Environment => hints::COMPILE_ENVIRONMENT,
+            _ if app.project_compiler.watch.is_some() => hints::COMPILE_WATCHING,
+            _ => hints::COMPILE_OUTPUT,
+        },
+        AppMode::EditProject if app.project_editor.search.typing => hints::SEARCH,
+        AppMode::EditProject if app.project_editor.has_projects() => hints::EDIT,
+        AppMode::EditProject => hints::NO_PROJECTS,
+        AppMode::ViewWaveform if app.vcd_search.typing => hints::SEARCH,
+        AppMode::ViewWaveform if !app.vcd_files.is_empty() => hints::VCD_FILES,
+        AppMode::ViewWaveform => hints::NO_VCD_FILES,
+        AppMode::InternalWaveform => hints::WAVEFORM,
+        AppMode::Settings if app.settings_editor.editing => hints::SETTINGS_EDITING,
+        AppMode::Settings if app.settings_editor.selected_field().is_text() => hints::SETTINGS_TEXT,
+        AppMode::Settings => hints::SETTINGS,
+        AppMode::BrowseDirectory => hints::BROWSE_DIRECTORY,
+        AppMode::ModuleSnippets => hints::MODULE_SNIPPETS,
+        AppMode::References => hints::REFERENCES,
+        AppMode::ErrorDetail => hints::ERROR_DETAIL,
+        AppMode::CommandPalette => hints::COMMAND_PALETTE,
+        _ => return None,
+    };
+    Some(hints)
+}
+
+// Whether keys on the screen go into a text field, '?' included
+fn typing(app: &App, mode: &AppMode) -> bool {
+    match mode {
+        AppMode::CreateProject => app.project_creator.focused_field == CreateField::TargetDirectory,
+        AppMode::CompileProject => app.project_compiler.active_tab == CompileTab::Projects && app.project_compiler.search.typing,
+        AppMode::EditProject => app.project_editor.search.typing,
+        AppMode::ViewWaveform => app.vcd_search.typing,
+        AppMode::Settings => app.settings_editor.editing,
+        AppMode::CommandPalette => true,
+        _ => false,
+    }
+}
+
+// The screen's help line, shortened to fit between the borders of a help box
+// as wide as `area`
+fn help_text(app: &App, mode: &AppMode, area: ratatui::layout::Rect) -> String {
+    let screen = screen_hints(app, mode).unwrap_or_default();
+    if typing(app, mode) {
+        hints::join(screen)
+    } else {
+        hints::help_line(screen, area.width.saturating_sub(2))
+    }
+}
+
+fn render_main_menu(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let title = Paragraph::new(format!("{} Hadou - Verilog Project Manager", app.icon(Icon::Wave)))
+        .style(Style::default().fg(app.colors().teal.into()).add_modifier(Modifier::BOLD))
+        .block(Block::default().borders(Borders::ALL));
+
+    let items: Vec<ListItem> = app.menu_items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let style = if i == app.selected_index {
+                Style::default().bg(app.colors().yellow.into()).fg(Color::Black)
+            } else {
+                Style::default()
+            };
+            ListItem::new(format!("{} {}", app.icon(item.icon), item.label)).style(style)
+        })
+        .collect();
+
+    let menu = List::new(items)
+        .block(Block::default().title("Menu").borders(Borders::ALL))
+        .highlight_style(Style::default().bg(app.colors().yellow.into()).fg(Color::Black));
+
+    let help = Paragraph::new(help_text(app, &AppMode::MainMenu, area))
+        .style(Style::default().fg(Color::Gray))
+        .block(Block::default().borders(Borders::ALL).title("Help"));
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(8),
+            Constraint::Length(app.health.tools.len() as u16 + 4),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    f.render_widget(title, layout[0]);
+    f.render_widget(menu, layout[1]);
+    render_health(f, app, layout[2]);
+    f.render_widget(help, layout[3]);
+}
+
+fn render_health(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let health = &app.health;
+    let count_style = Style::default().fg(app.colors().yellow.into()).add_modifier(Modifier::BOLD);
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled(health.projects.to_string(), count_style),
+            Span::raw(" projects, "),
+            Span::styled(health.projects_with_build_file.to_string(), count_style),
+            Span::raw(" with build files, "),
+            Span::styled(health.vcd_files.to_string(), count_style),
+            Span::raw(" VCD files"),
+        ]),
+        Line::from(""),
+    ];
+
+    for tool in &health.tools {
+        let line = if tool.installed {
+            Line::from(vec![
+                Span::styled(format!("{} ", app.icon(Icon::Found)), Style::default().fg(app.colors().green.into())),
+                Span::raw(format!("{:<10}", tool.label)),
+                Span::styled(tool.command.clone(), Style::default().fg(Color::Gray)),
+            ])
+        } else {
+            let missing = Style::default().fg(app.colors().red.into());
+            Line::from(vec![
+                Span::styled(format!("{} ", app.icon(Icon::Missing)), missing),
+                Span::styled(format!("{:<10}", tool.label), missing),
+                Span::styled(format!("{} not found: ", tool.command), missing),
+                Span::styled(tool.install_hint, Style::default().fg(Color::Gray)),
+            ])
+        };
+        lines.push(line);
+    }
+
+    let title = match health.missing_tools() {
+        0 => "Status".to_string(),
+        missing => format!("Status ({} missing)", missing),
+    };
+    let border_color: Color = if health.missing_tools() == 0 { Color::Gray } else { app.colors().red.into() };
+
+    let panel = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title).border_style(Style::default().fg(border_color)));
+    f.render_widget(panel, area);
+}
+
+fn render_create_project(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let title = Paragraph::new(format!("{} Create New Verilog Project", app.icon(Icon::Folder)))
+        .style(Style::default().fg(app.colors().green.into()).add_modifier(Modifier::BOLD))
+        .block(Block::default().borders(Borders::ALL));
+
+    let info_text = match app.project_creator.imported_module() {
+        Some(module) => vec![
+            Line::from(Span::styled(
+                format!("Importing module '{}' with {} port(s): it becomes main.v with a testbench around it", module.name, module.ports.len()),
+                Style::default().fg(app.colors().teal.into()),
+            )),
+            Line::from("The project name can still be changed; F3 drops the module"),
+        ],
+        None => vec![
+            Line::from("Enter Project name (letters, digits and _, starting with a letter; it also names the top module)"),
+            Line::from("and optionally a directory to create it in (F3 imports a module from the clipboard):"),
+        ],
+    };
+
+    let info = Paragraph::new(info_text)
+        .block(Block::default().borders(Borders::ALL).title("Project Info"));
+
+    let inputs: Vec<Paragraph> = CreateField::ALL
+        .iter()
+        .map(|field| {
+            let value = app.project_creator.field_value(*field);
+            let border_style = if *field == app.project_creator.focused_field {
+                Style::default().fg(app.colors().yellow.into())
+            } else {
+                Style::default().fg(Color::Gray)
+            };
+            Paragraph::new(value)
+                .style(Style::default().fg(app.colors().yellow.into()))
+                .block(Block::default().borders(Borders::ALL).border_style(border_style).title(field.title()))
+        })
+        .collect();
+
+    let preview_text = if app.project_creator.project_name.is_empty() {
+        "Enter a Project Name to see preview".to_string()
+    } else {
+        let clock = match app.project_creator.parsed_clock_period() {
+            Some(_) => format!("{} ns period, {} edge", app.project_creator.clock_period.trim(), app.project_creator.clock_edge.description()),
+            None => "invalid period".to_string(),
+        };
+        let language = app.project_creator.language;
+        let dump_depth = match (language, app.project_creator.parsed_dump_depth()) {
+            (Language::Vhdl, _) => "not used, GHDL dumps every signal".to_string(),
+            (Language::Verilog, Some(depth)) => verilog::describe_dump_depth(depth),
+            (Language::Verilog, None) => "invalid depth".to_string(),
+        };
+        let extras: String = app.project_creator
+            .extra_file_names()
+            .iter()
+            .map(|name| format!("\n {} {}", app.icon(Icon::File), name))
+            .collect();
+        // A pasted module brings its own ports in place of the clock and reset
+        let (design, signals) = match app.project_creator.imported_module() {
+            Some(module) => {
+                let ports: Vec<&str> = module.ports.iter().map(|port| port.name.as_str()).collect();
+                (format!("pasted module {}", module.name), format!("Ports: {}", ports.join(", ")))
+            }
+            None => (
+                "main module".to_string(),
+                format!("Clock: {}\nReset: {}", clock, app.project_creator.reset_level.description()),
+            ),
+        };
+        format!(
+            "Will Create:\n{} {}/\n {} {} ({})\n {} {} (testbench)\n {} {} (build automation){}\n\n{}\nDump depth: {}",
+            app.icon(Icon::Folder),
+            app.project_creator.resolved_path().display(),
+            app.icon(Icon::File),
+            language.design_file(),
+            design,
+            app.icon(Icon::Test),
+            language.testbench_file(),
+            app.icon(Icon::Justfile),
+            app.project_creator.build_system.file_name(),
+            extras,
+            signals,
+            dump_depth
+        )
+    };
+
+    let preview = Paragraph::new(preview_text)
+        .style(Style::default().fg(Color::Gray))
+        .block(Block::default().borders(Borders::ALL).title("Preview"));
+
+    let help = Paragraph::new(help_text(app, &AppMode::CreateProject, area))
+        .style(Style::default().fg(Color::Gray))
+        .block(Block::default().borders(Borders::ALL).title("Help"));
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(4),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(4),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    let name_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(25), Constraint::Percentage(25)])
+        .split(layout[2]);
+
+    let directory_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(25), Constraint::Percentage(25)])
+        .split(layout[3]);
+
+    // Language, clock and reset options share a row
+    let options_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(25),
+            Constraint::Percentage(20),
+            Constraint::Percentage(27),
+            Constraint::Percentage(28),
+        ])
+        .split(layout[4]);
+
+    let input_chunks = [name_layout[0], name_layout[1], name_layout[2], directory_layout[0], directory_layout[1], directory_layout[2], options_layout[0], options_layout[1], options_layout[2], options_layout[3]];
+
+    f.render_widget(title, layout[0]);
+    f.render_widget(info, layout[1]);
+    for (input, chunk) in inputs.into_iter().zip(input_chunks) {
+        f.render_widget(input, chunk);
+    }
+    f.render_widget(preview, layout[5]);
+    f.render_widget(help, layout[6]);
+}
+
+fn render_compile_project(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let mut title_spans = vec![Span::styled(
+        format!("{} Compile Verilog Project", app.icon(Icon::Gear)),
+        Style::default().fg(app.colors().red.into()).add_modifier(Modifier::BOLD),
+    )];
+    if let Some(watch) = &app.project_compiler.watch {
+        title_spans.push(Span::styled(
+            format!(
+                "  ● watching {} ({})",
+                watch.project_path.file_name().unwrap_or_default().to_string_lossy(),
+                watch.action.as_just_recipe()
+            ),
+            Style::default().fg(app.colors().green.into()).add_modifier(Modifier::BOLD),
+        ));
+    }
+    let title = Paragraph::new(Line::from(title_spans))
+        .block(Block::default().borders(Borders::ALL));
+
+    let tabs = Tabs::new(CompileTab::ALL.iter().map(|tab| tab.title()))
+        .select(app.project_compiler.active_tab.index())
+        .style(Style::default().fg(Color::Gray))
+        .highlight_style(Style::default().fg(app.colors().yellow.into()).add_modifier(Modifier::BOLD))
+        .block(Block::default().borders(Borders::ALL));
+
+    // Missing tools get a banner above the tabs instead of surfacing only
+    // as a failed run
+    let missing = app.health.missing_compile_tools();
+    let banner_height = if missing.is_empty() { 0 } else { missing.len() as u16 + 2 };
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(banner_height),
+            Constraint::Min(8),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    let header_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(layout[0]);
+
+    let help = Paragraph::new(help_text(app, &AppMode::CompileProject, area))
+        .style(Style::default().fg(Color::Gray))
+        .block(Block::default().borders(Borders::ALL).title("Help"));
+
+    f.render_widget(title, header_layout[0]);
+    f.render_widget(tabs, header_layout[1]);
+    if !missing.is_empty() {
+        render_missing_tools_banner(f, app, &missing, layout[1]);
+    }
+
+    match app.project_compiler.active_tab {
+        CompileTab::Projects => render_compile_projects_tab(f, app, layout[2]),
+        CompileTab::Output => render_compile_output_tab(f, app, layout[2]),
+        CompileTab::Diagnostics => render_compile_diagnostics_tab(f, app, layout[2]),
+        CompileTab::History => render_compile_history_tab(f, app, layout[2]),
+        CompileTab::Environment => render_compile_environment_tab(f, app, layout[2]),
+        CompileTab::Info => render_compile_info_tab(f, app, layout[2]),
+    }
+
+    f.render_widget(help, layout[3]);
+}
+
+fn render_missing_tools_banner(f: &mut Frame, app: &App, missing: &[&ToolStatus], area: ratatui::layout::Rect) {
+    let red = Style::default().fg(app.colors().red.into());
+    let lines: Vec<Line> = missing
+        .iter()
+        .map(|tool| {
+            Line::from(vec![
+                Span::styled(format!("{} {} ", app.icon(Icon::Missing), tool.command), red.add_modifier(Modifier::BOLD)),
+                Span::styled(format!("({}) not found: ", tool.label.to_lowercase()), red),
+                Span::styled(tool.install_hint, Style::default().fg(Color::Gray)),
+            ])
+        })
+        .collect();
+
+    let banner = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(red)
+            .title(Span::styled(" Runs will fail until these are installed ('r' checks again) ", red.add_modifier(Modifier::BOLD))),
+    );
+    f.render_widget(banner, area);
+}
+
+fn render_compile_projects_tab(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    // Create horizontal layout for projects and actions
+    let main_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    let right_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(7),
+            Constraint::Min(3),
+        ])
+        .split(main_layout[1]);
+
+    // Projects list
+    let visible_projects = app.project_compiler.visible_projects();
+    let marked = app.project_compiler.marked_count();
+    let mut projects_title = list_title("Projects", app.sort_mode, &app.project_compiler.search);
+    if marked > 0 {
+        projects_title.push_str(&format!(" - {} marked", marked));
+    }
+    let projects_widget = if !visible_projects.is_empty() {
+        let selected_row = visible_projects.iter().position(|(i, _)| *i == app.project_compiler.selected_project_index);
+        let rows = app.project_compiler.list_window.rows(visible_projects.len(), selected_row, main_layout[0].height.saturating_sub(2));
+        let project_items: Vec<ListItem> = visible_projects[rows]
+            .iter()
+            .map(|(i, found)| {
+                let entry = &app.project_compiler.projects[*i];
+                let project_path = &entry.path;
+                let selected = *i == app.project_compiler.selected_project_index;
+
+                let style = if selected {
+                    Style::default().bg(app.colors().yellow.into()).fg(Color::Black)
+                } else {
+                    Style::default()
+                };
+
+                // Show project name with source file count and build system
+                let summary = app.project_compiler.summary(project_path);
+                let source_count = summary.map_or(0, |summary| summary.source_files.len());
+                let language = summary.map(|summary| summary.language).unwrap_or_default();
+                let validity = match app.project_compiler.justfile_status(project_path) {
+                    Some(JustfileStatus::Valid(_)) => format!(" {}", app.icon(Icon::Found)),
+                    Some(JustfileStatus::Invalid(_)) => format!(" {} invalid", app.icon(Icon::Missing)),
+                    None => String::new(),
+                };
+                let build_indicator = match summary.and_then(|summary| summary.build_system) {
+                    Some(system) => format!("{} {}{}", app.icon(Icon::Justfile), system.program(), validity),
+                    None => app.icon(Icon::Missing).to_string(),
+                };
+
+                let mut spans = Vec::new();
+                if marked > 0 {
+                    spans.push(if app.project_compiler.marked_projects.contains(project_path) {
+                        Span::raw(format!("{} ", app.icon(Icon::Found)))
+                    } else {
+                        Span::raw(" ".repeat(Span::raw(app.icon(Icon::Found)).width() + 1))
+                    });
+                }
+                spans.push(project_icon(app, app.project_compiler.is_pinned(project_path), selected));
+                spans.extend(highlight_matches(app, &project_label(project_path), &found.positions, selected));
+                spans.push(Span::raw(format!(" ({} {} files) {}{}", source_count, language.name(), build_indicator, list_entry_details(entry))));
+                ListItem::new(Line::from(spans)).style(style)
+            })
+            .collect();
+
+        List::new(project_items)
+            .block(Block::default().title(projects_title).borders(Borders::ALL))
+            .highlight_style(Style::default().bg(app.colors().yellow.into()).fg(Color::Black))
+    } else if app.project_compiler.has_projects() {
+        List::new(vec![ListItem::new(format!("No projects match '{}'", app.project_compiler.search.query))])
+            .block(Block::default().title(projects_title).borders(Borders::ALL))
+            .style(Style::default().fg(Color::Gray))
+    } else {
+        List::new(vec![ListItem::new("No Verilog or VHDL projects found in current directory")])
+            .block(Block::default().title("Projects").borders(Borders::ALL))
+            .style(Style::default().fg(Color::Gray))
+    };
+
+    // Actions list
+    let action_items: Vec<ListItem> = app.project_compiler.available_actions
+        .iter()
+        .enumerate()
+        .map(|(i, action)| {
+            let style = if i == app.project_compiler.selected_action_index {
+                Style::default().bg(app.colors().blue.into()).fg(Color::White)
+            } else {
+                Style::default()
+            };
+
+            let display_text = format!("{} {}", action.icon(app.ascii_mode), action.description());
+            ListItem::new(display_text).style(style)
+        })
+        .collect();
+
+    let actions_widget = List::new(action_items)
+        .block(Block::default().title("Actions").borders(Borders::ALL))
+        .highlight_style(Style::default().bg(app.colors().blue.into()).fg(Color::White));
+
+    // Preview of selected project
+    let preview_text = if let Some(selected_path) = app.project_compiler.get_selected_project_path() {
+        let summary = app.project_compiler.summary(selected_path);
+        let source_files = summary.map(|summary| summary.source_files.as_slice()).unwrap_or_default();
+        let build_system = summary.and_then(|summary| summary.build_system);
+        let language = summary.map(|summary| summary.language).unwrap_or_default();
+
+        if !source_files.is_empty() {
+            let mut preview = format!("Selected Project:\n{} {}\n", 
+                app.icon(Icon::Folder), selected_path.file_name().unwrap().to_string_lossy());
+
+            preview.push_str(&format!("\nBuild file: {}\n", match build_system {
+                Some(system) => format!("{} {} ({})", app.icon(Icon::Found), system.file_name(), system.program()),
+                None => format!("{} Missing (no Justfile or Makefile)", app.icon(Icon::Missing)),
+            }));
+
+            preview.push_str(&format!("\n{} files:\n", language.name()));
+            for file in source_files {
+                let relative = file.strip_prefix(selected_path).unwrap_or(file);
+                preview.push_str(&format!(" {} {}\n", app.icon(Icon::File), relative.display()));
+            }
+
+            if let Some(action) = app.project_compiler.get_selected_action() {
+                preview.push_str(&format!("\nWill execute: {} {}\n", build_system.unwrap_or_default().program(), action.as_just_recipe()));
+            }
+
+            if language == Language::Verilog {
+                if let Some(line) = top_module_line(app, selected_path) {
+                    preview.push_str(&line);
+                }
+                let details = &app.project_compiler.details;
+                let testbench = &details.testbench;
+                if details.testbenches.len() > 1 {
+                    preview.push_str("Testbenches ('t' to pick):\n");
+                    for (name, vcd) in &details.testbenches {
+                        let marker = if name == testbench { "▸" } else { " " };
+                        preview.push_str(&format!(" {} {} → {}\n", marker, name, vcd.as_deref().unwrap_or("no $dumpfile")));
+                    }
+                }
+
+                let dump_depth = match (app.project_compiler.dump_depth, details.dump_depth) {
+                    (Some(depth), _) => format!("{}, set before simulating", verilog::describe_dump_depth(depth)),
+                    (None, Some(depth)) => format!("{} as written in {}", verilog::describe_dump_depth(depth), testbench),
+                    (None, None) => format!("as written in {}", testbench),
+                };
+                if app.project_compiler.skip_dump {
+                    preview.push_str(&format!("Waveform dump: off, compiled with -D{} ('D' to turn on)\n", verilog::NO_DUMP_DEFINE));
+                } else {
+                    preview.push_str(&format!("Dump depth: {}\n", dump_depth));
+                }
+
+                let flags = match (&details.config, &details.config_error) {
+                    (Some(project_config), _) if project_config.iverilog_flags.is_empty() => "none ('f' to set)".to_string(),
+                    (Some(project_config), _) => project_config.flags(),
+                    (None, error) => format!("{} is invalid: {}", project_config::PROJECT_CONFIG_FILE, error.as_deref().unwrap_or_default()),
+                };
+                preview.push_str(&format!("iverilog flags: {}\n", flags));
+                if let Some(project_config) = &details.config {
+                    if !project_config.sources.is_empty() {
+                        let count = details.extra_source_count;
+                        preview.push_str(&format!("Extra sources: {} ({} files)\n", project_config.sources.join(" "), count));
+                    }
+                    if !project_config.include_dirs.is_empty() {
+                        preview.push_str(&format!("Include dirs: {}\n", project_config.include_dirs.join(" ")));
+                    }
+                }
+            }
+
+            if let Some(summary) = history::summary(&app.project_compiler.details.history) {
+                preview.push_str(&format!("History: {}\n", summary));
+            }
+
+            // Build outputs on disk, split by whether `clean` deletes them
+            let artifacts = &app.project_compiler.details.artifacts;
+            if artifacts.is_empty() {
+                preview.push_str("\nArtifacts: none");
+            }
+            for (removed_by_clean, heading) in [(true, "removed by clean"), (false, "not removed by clean")] {
+                let group: Vec<_> = artifacts.iter().filter(|artifact| artifact.removed_by_clean == removed_by_clean).collect();
+                if group.is_empty() {
+                    continue;
+                }
+                let total: u64 = group.iter().map(|artifact| artifact.size).sum();
+                preview.push_str(&format!("\nArtifacts {} ({}):\n", heading, compile_project::format_size(total)));
+                for artifact in group {
+                    let name = artifact.path.file_name().unwrap_or_default().to_string_lossy();
+                    let suffix = if artifact.is_dir { "/" } else { "" };
+                    preview.push_str(&format!(" {}{} ({})\n", name, suffix, compile_project::format_size(artifact.size)));
+                }
+            }
+
+            preview
+        } else {
+            "No Verilog files found in selected project".to_string()
+        }
+    } else {
+        "Select a project to see preview".to_string()
+    };
+
+    let project_notes = app.project_compiler.details.notes.as_ref();
+    let (preview_area, notes_area) = split_for_notes(right_layout[2], project_notes.is_some(), Direction::Vertical);
+
+    let preview_lines = preview_text.lines().count();
+    let preview = Paragraph::new(preview_text.as_str())
+        .style(Style::default().fg(Color::Gray))
+        .scroll((app.preview_scroll.offset(preview_lines, preview_area.height.saturating_sub(2)), 0))
+        .block(Block::default().borders(Borders::ALL).title(scroll_title("Preview", &app.preview_scroll, preview_lines)));
+
+    f.render_widget(projects_widget, main_layout[0]);
+    render_compile_progress(f, app, right_layout[0]);
+    f.render_widget(actions_widget, right_layout[1]);
+    f.render_widget(preview, preview_area);
+    if let (Some(project_notes), Some(notes_area)) = (project_notes, notes_area) {
+        render_notes(f, project_notes, notes_area);
+    }
+}
+
+// Room for the notes panel next to a preview, when the project has notes
+fn split_for_notes(area: ratatui::layout::Rect, has_notes: bool, direction: Direction) -> (ratatui::layout::Rect, Option<ratatui::layout::Rect>) {
+    if !has_notes {
+        return (area, None);
+    }
+    let halves = Layout::default()
+        .direction(direction)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+    (halves[0], Some(halves[1]))
+}
+
+// The end of the notes, where the newest ones are appended
+fn render_notes(f: &mut Frame, project_notes: &str, area: ratatui::layout::Rect) {
+    let lines = wrapped_line_count(project_notes, area.width.saturating_sub(2));
+    let hidden = lines.saturating_sub(area.height.saturating_sub(2) as usize);
+    let notes_widget = Paragraph::new(project_notes)
+        .wrap(Wrap { trim: false })
+        .scroll((hidden as u16, 0))
+        .block(Block::default().borders(Borders::ALL).title(format!("Notes ({})", notes::NOTES_FILE)));
+    f.render_widget(notes_widget, area);
+}
+
+fn render_compile_output_tab(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(4)])
+        .split(area);
+
+    // Latest compiler/simulator output, scrolled to the bottom
+    let mut output_height = layout[1].height.saturating_sub(2) as usize;
+    let results = sim_results::summarize(
+        app.project_compiler.tagged_output(),
+        &app.config.pass_markers,
+        &app.config.fail_markers,
+    );
+    let mut output_lines = Vec::new();
+    if !results.is_empty() {
+        let failed_color = if results.failed > 0 { app.colors().red } else { app.colors().overlay0 };
+        output_lines.push(Line::from(vec![
+            Span::styled(
+                format!("{} {} passed", app.icon(Icon::Found), results.passed),
+                Style::default().fg(app.colors().green.into()).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(", "),
+            Span::styled(
+                format!("{} {} failed", app.icon(Icon::Missing), results.failed),
+                Style::default().fg(failed_color.into()).add_modifier(Modifier::BOLD),
+            ),
+        ]));
+        output_height = output_height.saturating_sub(1);
+    }
+
+    // Simulator output is set apart from the build log with a gutter, and
+    // lines with a fail marker are picked out in red
+    let skipped = app.project_compiler.compilation_output.len().saturating_sub(output_height);
+    output_lines.extend(app.project_compiler.tagged_output().enumerate().skip(skipped).map(|(index, (line, phase))| {
+        match phase {
+            OutputPhase::Build => Line::from(line),
+            OutputPhase::Simulation => {
+                let text = if results.is_failing(index) { app.colors().red } else { app.colors().text };
+                Line::from(vec![
+                    Span::styled("▌ ", Style::default().fg(app.colors().teal.into())),
+                    Span::styled(line, Style::default().fg(text.into())),
+                ])
+            }
+        }
+    }));
+
+    let mut title = match (&app.project_compiler.watch, app.project_compiler.get_selected_project_path().and_then(|path| path.file_name())) {
+        (Some(watch), _) => format!(
+            "Output ({}) - watching, {} run(s), 'w' to stop",
+            watch.project_path.file_name().unwrap_or_default().to_string_lossy(),
+            watch.runs
+        ),
+        (None, Some(name)) => format!("Output ({})", name.to_string_lossy()),
+        (None, None) => "Output".to_string(),
+    };
+    let simulation_lines = app.project_compiler.simulation_line_count();
+    if simulation_lines > 0 {
+        title.push_str(&format!(" | {} line(s) from the simulation", simulation_lines));
+    }
+
+    let output = Paragraph::new(output_lines)
+        .style(Style::default().fg(Color::Gray))
+        .block(Block::default().borders(Borders::ALL).title(title));
+
+    render_compile_progress(f, app, layout[0]);
+    f.render_widget(output, layout[1]);
+}
+
+fn render_compile_diagnostics_tab(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let mut lines = vec![Line::from(Span::styled(
+        "Testbench check",
+        Style::default().fg(app.colors().blue.into()).add_modifier(Modifier::BOLD),
+    ))];
+
+    let warnings = &app.project_compiler.details.testbench_warnings;
+    if warnings.is_empty() {
+        lines.push(Line::from(format!(" {} No port mismatches found", app.icon(Icon::Found))));
+    }
+    for warning in warnings {
+        lines.push(Line::from(Span::styled(format!(" {}", warning), Style::default().fg(app.colors().yellow.into()))));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Last run",
+        Style::default().fg(app.colors().blue.into()).add_modifier(Modifier::BOLD),
+    )));
+
+    let diagnostics = app.project_compiler.output_diagnostics();
+    if diagnostics.is_empty() {
+        lines.push(Line::from(" No errors or warnings"));
+    }
+    for diagnostic in diagnostics {
+        let color = if diagnostic.to_lowercase().contains("error") {
+            app.colors().red
+        } else {
+            app.colors().yellow
+        };
+        lines.push(Line::from(Span::styled(format!(" {}", diagnostic), Style::default().fg(color.into()))));
+    }
+
+    let diagnostics_widget = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title("Diagnostics"));
+
+    f.render_widget(diagnostics_widget, area);
+}
+
+fn render_compile_history_tab(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let Some(project_path) = app.project_compiler.get_selected_project_path() else {
+        let empty = Paragraph::new("Select a project to see its history")
+            .style(Style::default().fg(Color::Gray))
+            .block(Block::default().borders(Borders::ALL).title("History"));
+        f.render_widget(empty, area);
+        return;
+    };
+
+    let title = format!("History: {}", project_label(project_path));
+    let entries = &app.project_compiler.details.history;
+    let Some(summary) = history::summary(entries) else {
+        let empty = Paragraph::new("No runs recorded yet. Compiling the project starts its history.")
+            .style(Style::default().fg(Color::Gray))
+            .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(empty, area);
+        return;
+    };
+
+    let mut lines = vec![Line::from(Span::styled(
+        summary,
+        Style::default().fg(app.colors().yellow.into()).add_modifier(Modifier::BOLD),
+    ))];
+
+    let passed: Vec<_> = entries.iter().filter(|entry| entry.success).collect();
+    if !passed.is_empty() {
+        let average = passed.iter().map(|entry| entry.duration()).sum::<Duration>() / passed.len() as u32;
+        lines.push(Line::from(format!("Average passing run: {:.2}s", average.as_secs_f64())));
+    }
+    lines.push(Line::from(""));
+
+    // Newest first so the most relevant runs stay visible
+    for entry in entries.iter().rev() {
+        let (icon, outcome, color) = if entry.success {
+            (Icon::Found, "passed".to_string(), app.colors().green)
+        } else {
+            let code = entry.exit_code.map(|code| format!("failed ({})", code)).unwrap_or_else(|| "failed".to_string());
+            (Icon::Missing, code, app.colors().red)
+        };
+
+        lines.push(Line::from(vec![
+            Span::raw(format!("{} {}  ", app.icon(icon), entry.timestamp.format("%Y-%m-%d %H:%M:%S"))),
+            Span::raw(format!("{:<10}", entry.action)),
+            Span::styled(format!("{:<12}", outcome), Style::default().fg(color.into())),
+            Span::raw(format!("{:>8.2}s  ", entry.duration().as_secs_f64())),
+            Span::styled(scanner::format_age(entry.timestamp.into()), Style::default().fg(Color::Gray)),
+        ]));
+    }
+
+    let history_widget = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(history_widget, area);
+}
+
+fn render_compile_environment_tab(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(5)])
+        .split(area);
+
+    let project_env = &app.project_compiler.project_env;
+
+    let items: Vec<ListItem> = project_env.vars
+        .iter()
+        .enumerate()
+        .map(|(i, var)| {
+            let marker = if var.enabled { "[x]" } else { "[ ]" };
+            let edited = if var.overridden { " (edited)" } else { "" };
+            let style = if i == project_env.selected_index {
+                Style::default().bg(app.colors().yellow.into()).fg(Color::Black)
+            } else if var.enabled {
+                Style::default()
+            } else {
+                Style::default().fg(Color::Gray)
+            };
+            ListItem::new(format!("{} {}={}{}", marker, var.key, var.value, edited)).style(style)
+        })
+        .collect();
+
+    let title = match app.project_compiler.get_selected_project_path().and_then(|path| path.file_name()) {
+        Some(name) => format!("{} ({})", env_file::ENV_FILE_NAME, name.to_string_lossy()),
+        None => env_file::ENV_FILE_NAME.to_string(),
+    };
+
+    if items.is_empty() {
+        let empty = Paragraph::new(format!(
+            "No variables. Add KEY=VALUE lines to a {} file in the project directory.",
+            env_file::ENV_FILE_NAME
+        ))
+        .style(Style::default().fg(Color::Gray))
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(empty, layout[0]);
+    } else {
+        let list = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(list, layout[0]);
+    }
+
+    let plusargs = match project_env.plusargs() {
+        Some(args) if !args.is_empty() => format!("vvp plusargs: {}", args),
+        _ => format!("Set {} to pass +plusargs to vvp", env_file::PLUSARGS_VAR),
+    };
+    let notes = Paragraph::new(vec![
+        Line::from("Precedence: process environment < .env < overrides made here"),
+        Line::from("Overrides last until the project changes or the list is reloaded"),
+        Line::from(Span::styled(plusargs, Style::default().fg(app.colors().yellow.into()))),
+    ])
+    .wrap(Wrap { trim: false })
+    .block(Block::default().borders(Borders::ALL).title("Notes"));
+    f.render_widget(notes, layout[1]);
+}
+
+fn render_compile_info_tab(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let mut info_text = vec![
+        Line::from(format!("Found {} project(s)", app.project_compiler.project_count())),
+        Line::from(format!("Scan depth: {}", app.project_compiler.scan_options.max_depth)),
+    ];
+
+    if let Some(path) = app.project_compiler.get_selected_project_path() {
+        info_text.push(Line::from(""));
+        info_text.push(Line::from(vec![
+            Span::raw("Selected project: "),
+            Span::styled(path.display().to_string(), Style::default().fg(app.colors().yellow.into())),
+        ]));
+    }
+
+    let info = Paragraph::new(info_text)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title("Project Info"));
+
+    f.render_widget(info, area);
+}
+
+fn render_compile_progress(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let compiler = &app.project_compiler;
+    let title = match compiler.batch.as_ref().and_then(|batch| batch.progress().map(|progress| (progress, batch.total))) {
+        Some(((position, project), total)) => format!("Progress - project {} of {}: {}", position, total, project),
+        None => "Progress".to_string(),
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
+
+    if !compiler.is_compiling {
+        let idle = Paragraph::new("Idle")
+            .style(Style::default().fg(Color::Gray))
+            .block(block);
+        f.render_widget(idle, area);
+        return;
+    }
+
+    let elapsed = compiler.elapsed().map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+
+    let gauge = match (compiler.progress(), compiler.expected_end_time) {
+        (Some(ratio), Some(end_time)) => Gauge::default()
+            .ratio(ratio)
+            .label(format!(
+                "time {} / {} ({:.0}%) - {}s",
+                compiler.latest_sim_time.unwrap_or(0),
+                end_time,
+                ratio * 100.0,
+                elapsed
+            )),
+        _ => {
+            // End time unknown: sweep the bar back and forth to show activity
+            let steps = 20;
+            let position = app.tick_count % (2 * steps);
+            let position = if position < steps { position } else { 2 * steps - position };
+            Gauge::default()
+                .ratio(position as f64 / steps as f64)
+                .label(match compiler.latest_sim_time {
+                    Some(time) => format!("Running... time {} - {}s", time, elapsed),
+                    None => format!("Running... {}s", elapsed),
+                })
+        }
+    };
+
+    f.render_widget(
+        gauge
+            .block(block)
+            .gauge_style(Style::default().fg(app.colors().green.into()).bg(app.colors().surface0.into())),
+        area,
+    );
+}
+
+// List title with the sort order, plus the search query while one is set
+// Pinned projects get a star in place of the folder, left in the row's
+// colours when selected so it stays visible on the highlight
+fn project_icon(app: &App, pinned: bool, selected: bool) -> Span<'static> {
+    if pinned && selected {
+        Span::raw(format!("{} ", app.icon(Icon::Star)))
+    } else if pinned {
+        Span::styled(format!("{} ", app.icon(Icon::Star)), Style::default().fg(app.colors().yellow.into()))
+    } else {
+        Span::raw(format!("{} ", app.icon(Icon::Folder)))
+    }
+}
+
+fn list_title(title: &str, sort_mode: SortMode, search: &ListSearch) -> String {
+    let title = format!("{} (by {})", title, sort_mode.identifier());
+    if search.typing {
+        format!("{} /{}_", title, search.query)
+    } else if search.is_active() {
+        format!("{} /{}", title, search.query)
+    } else {
+        title
+    }
+}
+
+// Size and age shown after each list entry
+fn list_entry_details(entry: &ListEntry) -> String {
+    let age = entry.modified.map(scanner::format_age).unwrap_or_else(|| "unknown".to_string());
+    format!("  {} · {}", compile_project::format_size(entry.size), age)
+}
+
+// Size and age plus the signal count from the header, which tells a huge
+// design apart from a long simulation before either is loaded
+fn vcd_entry_details(entry: &ListEntry, summary: Option<&VcdSummary>) -> String {
+    let age = entry.modified.map(scanner::format_age).unwrap_or_else(|| "unknown".to_string());
+    let signals = match summary.and_then(|summary| summary.signals) {
+        Some(1) => "1 signal".to_string(),
+        Some(count) => format!("{} signals", count),
+        None => "unreadable header".to_string(),
+    };
+    let large = if summary.is_some_and(VcdSummary::is_large) { " · large" } else { "" };
+    format!("  {} · {} · {}{}", compile_project::format_size(entry.size), signals, age, large)
+}
+
+// "Top module: alu" for a project's preview, pointing out a name that isn't
+// the project's own
+fn top_module_line(app: &App, project: &Path) -> Option<String> {
+    let top = app.top_modules.get(project)?;
+    if top == project_label(project) {
+        Some(format!("Top module: {}\n", top))
+    } else {
+        Some(format!("Top module: {} (not named after the project)\n", top))
+    }
+}
+
+// Spans for `label` with the fuzzy-matched characters picked out
+fn highlight_matches(app: &App, label: &str, positions: &[usize], selected: bool) -> Vec<Span<'static>> {
+    let highlight = if selected {
+        Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+    } else {
+        Style::default().fg(app.colors().peach.into()).add_modifier(Modifier::BOLD)
+    };
+
+    let mut spans = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+
+    for (i, c) in label.chars().enumerate() {
+        let matched = positions.contains(&i);
+        if matched != run_matched && !run.is_empty() {
+            let text = std::mem::take(&mut run);
+            spans.push(if run_matched { Span::styled(text, highlight) } else { Span::raw(text) });
+        }
+        run_matched = matched;
+        run.push(c);
+    }
+    if !run.is_empty() {
+        spans.push(if run_matched { Span::styled(run, highlight) } else { Span::raw(run) });
+    }
+
+    spans
+}
+
+fn render_edit_project(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let title = Paragraph::new(format!("{} Edit Verilog Project", app.icon(Icon::Edit)))
+        .style(Style::default().fg(app.colors().blue.into()).add_modifier(Modifier::BOLD))
+        .block(Block::default().borders(Borders::ALL));
+
+    let mut info_text = vec![
+        Line::from(format!("Found {} project(s):", app.project_editor.project_count())),
+    ];
+
+    // Syntax check result for the selected project's main.v
+    let selected_status = app.project_editor
+        .get_selected_project_path()
+        .filter(|_| app.project_editor.selection_visible())
+        .and_then(|path| app.project_editor.syntax_checker.status(path));
+    match selected_status {
+        Some(SyntaxStatus::Clean) => info_text.push(Line::from(Span::styled(
+            "main.v: no syntax errors",
+            Style::default().fg(app.colors().green.into()),
+        ))),
+        Some(SyntaxStatus::Errors(message)) => info_text.push(Line::from(Span::styled(
+            format!("main.v: {}", message),
+            Style::default().fg(app.colors().red.into()),
+        ))),
+        None if app.project_editor.syntax_checker.is_running() => {
+            info_text.push(Line::from(Span::styled("Checking syntax...", Style::default().fg(Color::Gray))));
+        }
+        None => {}
+    }
+
+    let info = Paragraph::new(info_text)
+        .block(Block::default().borders(Borders::ALL).title("Project Info"));
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(4),
+            Constraint::Min(6),
+            Constraint::Length(5),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    // Project list or empty message
+    let visible_projects = app.project_editor.visible_projects();
+    let projects_title = list_title("Projects", app.sort_mode, &app.project_editor.search);
+    let projects_widget = if !visible_projects.is_empty() {
+        let selected_row = visible_projects.iter().position(|(i, _)| *i == app.project_editor.selected_project_index);
+        let rows = app.project_editor.list_window.rows(visible_projects.len(), selected_row, layout[2].height.saturating_sub(2));
+        let project_items: Vec<ListItem> = visible_projects[rows]
+            .iter()
+            .map(|(i, found)| {
+                let entry = &app.project_editor.projects[*i];
+                let project_path = &entry.path;
+                let selected = *i == app.project_editor.selected_project_index;
+
+                let style = if selected {
+                    Style::default().bg(app.colors().yellow.into()).fg(Color::Black)
+                } else {
+                    Style::default()
+                };
+
+                // Show project name with file count
+                let files = app.project_editor.get_project_files(project_path);
+                let mut spans = vec![project_icon(app, app.project_editor.is_pinned(project_path), selected)];
+                match app.project_editor.syntax_checker.status(project_path) {
+                    Some(SyntaxStatus::Clean) => {
+                        spans.push(Span::styled(format!("{} ", app.icon(Icon::Found)), Style::default().fg(app.colors().green.into())));
+                    }
+                    Some(SyntaxStatus::Errors(_)) => {
+                        spans.push(Span::styled(format!("{} ", app.icon(Icon::Missing)), Style::default().fg(app.colors().red.into())));
+                    }
+                    None => {}
+                }
+                spans.extend(highlight_matches(app, &project_label(project_path), &found.positions, selected));
+                spans.push(Span::raw(format!(" ({} files){}", files.len(), list_entry_details(entry))));
+                ListItem::new(Line::from(spans)).style(style)
+            })
+            .collect();
+
+        List::new(project_items)
+            .block(Block::default().title(projects_title).borders(Borders::ALL))
+            .highlight_style(Style::default().bg(app.colors().yellow.into()).fg(Color::Black))
+    } else if app.project_editor.has_projects() {
+        List::new(vec![ListItem::new(format!("No projects match '{}'", app.project_editor.search.query))])
+            .block(Block::default().title(projects_title).borders(Borders::ALL))
+            .style(Style::default().fg(Color::Gray))
+    } else {
+        List::new(vec![ListItem::new("No Verilog or VHDL projects found in current directory")])
+            .block(Block::default().title("Projects").borders(Borders::ALL))
+            .style(Style::default().fg(Color::Gray))
+    };
+
+    // Preview of selected project files
+    let selected_project = app.project_editor.get_selected_project_path().filter(|_| app.project_editor.selection_visible());
+    let preview_text = if let Some(selected_path) = selected_project {
+        let files = app.project_editor.get_project_files(selected_path);
+        if !files.is_empty() {
+            let heading = if app.project_editor.show_all_files { "Will open in editor (all files):" } else { "Will open in editor:" };
+            let mut preview = format!("{}\n{} {}\n", 
+                heading, app.icon(Icon::Folder), selected_path.file_name().unwrap().to_string_lossy());
+
+            for file in &files {
+                if let Some(file_name) = file.file_name() {
+                    let icon = match file.extension().and_then(|ext| ext.to_str()) {
+                        Some("v") => app.icon(Icon::File),
+                        Some(_) => app.icon(Icon::File),
+                        None => app.icon(Icon::Justfile), // justfile has no extension
+                    };
+                    preview.push_str(&format!(" {} {}\n", icon, file_name.to_string_lossy()));
+                }
+            }
+            if let Some(line) = top_module_line(app, selected_path) {
+                preview.push_str(&format!("\n{}", line));
+            }
+            preview
+        } else {
+            "No editable files found in selected project".to_string()
+        }
+    } else {
+        "Select a project to see preview".to_string()
+    };
+
+    let project_notes = app.project_editor.notes.as_ref();
+    let (preview_area, notes_area) = split_for_notes(layout[3], project_notes.is_some(), Direction::Horizontal);
+
+    let preview_lines = preview_text.lines().count();
+    let preview_title = if app.project_editor.show_all_files { "Preview (all files)" } else { "Preview" };
+    let preview = Paragraph::new(preview_text.as_str())
+        .style(Style::default().fg(Color::Gray))
+        .scroll((app.preview_scroll.offset(preview_lines, preview_area.height.saturating_sub(2)), 0))
+        .block(Block::default().borders(Borders::ALL).title(scroll_title(preview_title, &app.preview_scroll, preview_lines)));
+
+    let help = Paragraph::new(help_text(app, &AppMode::EditProject, area))
+        .style(Style::default().fg(Color::Gray))
+        .block(Block::default().borders(Borders::ALL).title("Help"));
+
+    f.render_widget(title, layout[0]);
+    f.render_widget(info, layout[1]);
+    f.render_widget(projects_widget, layout[2]);
+    f.render_widget(preview, preview_area);
+    if let (Some(project_notes), Some(notes_area)) = (project_notes, notes_area) {
+        render_notes(f, project_notes, notes_area);
+    }
+    f.render_widget(help, layout[4]);
+}
+
+fn render_view_waveform(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let title = Paragraph::new(format!("{} View Waveform with External Viewer", app.icon(Icon::Chart)))
+        .style(Style::default().fg(app.colors().mauve.into()).add_modifier(Modifier::BOLD))
+        .block(Block::default().borders(Borders::ALL));
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),  // Title
+            Constraint::Min(8),     // VCD files
+            Constraint::Length(8),  // Viewer options
+            Constraint::Length(3),  // Help
+        ])
+        .split(area);
+
+    // VCD files list
+    let visible_vcd_files = app.visible_vcd_files();
+    let vcd_title = list_title("VCD Files", app.sort_mode, &app.vcd_search);
+    let vcd_widget = if !visible_vcd_files.is_empty() {
+        let selected_row = visible_vcd_files.iter().position(|(i, _)| *i == app.selected_vcd_index);
+        let rows = app.vcd_window.rows(visible_vcd_files.len(), selected_row, layout[1].height.saturating_sub(2));
+        let vcd_items: Vec<ListItem> = visible_vcd_files[rows]
+            .iter()
+            .map(|(i, found)| {
+                let selected = *i == app.selected_vcd_index;
+
+                let style = if selected {
+                    Style::default().bg(app.colors().yellow.into()).fg(Color::Black)
+                } else {
+                    Style::default()
+                };
+
+                let mut spans = vec![Span::raw(format!("{} ", app.icon(Icon::File)))];
+                let entry = &app.vcd_files[*i];
+                spans.extend(highlight_matches(app, &vcd_label(&entry.path), &found.positions, selected));
+                spans.push(Span::raw(vcd_entry_details(entry, app.vcd_summaries.get(&entry.path))));
+                ListItem::new(Line::from(spans)).style(style)
+            })
+            .collect();
+
+        List::new(vcd_items)
+            .block(Block::default().title(vcd_title).borders(Borders::ALL))
+            .highlight_style(Style::default().bg(app.colors().yellow.into()).fg(Color::Black))
+    } else if !app.vcd_files.is_empty() {
+        List::new(vec![ListItem::new(format!("No VCD files match '{}'", app.vcd_search.query))])
+            .block(Block::default().title(vcd_title).borders(Borders::ALL))
+            .style(Style::default().fg(Color::Gray))
+    } else {
+        List::new(vec![ListItem::new("No VCD files found. Run a simulation first!")])
+            .block(Block::default().title("VCD Files").borders(Borders::ALL))
+            .style(Style::default().fg(Color::Gray))
+    };
+
+    // Viewer info
+    let viewer_info = vec![
+        Line::from("Supported Waveform Viewers:"),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled(format!("{} DWFV", app.icon(Icon::Chart)), Style::default().fg(app.colors().green.into()).add_modifier(Modifier::BOLD)),
+            Span::raw(" - Vi-like TUI waveform viewer (Recommended)"),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{} DigiSurf", app.icon(Icon::Justfile)), Style::default().fg(app.colors().blue.into()).add_modifier(Modifier::BOLD)),
+            Span::raw(" - Modern TUI with command interface"),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{} GTKWave", app.icon(Icon::Monitor)), Style::default().fg(app.colors().yellow.into()).add_modifier(Modifier::BOLD)),
+            Span::raw(" - Traditional GUI waveform viewer"),
+        ]),
+    ];
+
+    let viewer_widget = Paragraph::new(viewer_info)
+        .block(Block::default().borders(Borders::ALL).title("Viewer Options"));
+
+    let help = Paragraph::new(help_text(app, &AppMode::ViewWaveform, area))
+        .style(Style::default().fg(Color::Gray))
+        .block(Block::default().borders(Borders::ALL).title("Controls"));
+
+    f.render_widget(title, layout[0]);
+    f.render_widget(vcd_widget, layout[1]);
+    f.render_widget(viewer_widget, layout[2]);
+    f.render_widget(help, layout[3]);
+}
+
+// One signal's row in the built-in waveform chart
+struct WaveLane {
+    base: f64,
+    color: Color,
+    segments: Vec<(u64, u64, String)>,
+    is_bus: bool,
+}
+
+fn render_internal_waveform(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let viewer = &app.waveform_viewer;
+
+    let file_name = viewer.current_file
+        .as_ref()
+        .and_then(|path| path.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let (x_min, x_max, _, _) = viewer.get_chart_bounds();
+    let timescale = viewer.current_vcd
+        .as_ref()
+        .map(|vcd| vcd.timescale.clone())
+        .unwrap_or_default();
+
+    let mut cursor = match (viewer.cursor_a, viewer.cursor_delta()) {
+        (Some(cursor), Some(delta)) => format!(
+            " | A @ {}, now {}{}",
+            waveform_viewer::format_time(cursor as i64, &timescale),
+            if delta > 0 { "+" } else { "" },
+            waveform_viewer::format_time(delta, &timescale)
+        ),
+        _ => String::new(),
+    };
+    if let Some(edge) = viewer.cursor {
+        cursor.push_str(&format!(" | at {}", waveform_viewer::format_time(edge as i64, &timescale)));
+    }
+    let warning_count = viewer.current_vcd.as_ref().map_or(0, |vcd| vcd.warnings.count);
+    if warning_count > 0 {
+        cursor.push_str(&format!(" | {} parse warning{} ('W')", warning_count, if warning_count == 1 { "" } else { "s" }));
+    }
+
+    let title = Paragraph::new(format!("{} {} ({}) - time {} to {}{}", 
+        app.icon(Icon::Chart), file_name, timescale, x_min, x_max, cursor))
+        .style(Style::default().fg(app.colors().mauve.into()).add_modifier(Modifier::BOLD))
+        .block(Block::default().borders(Borders::ALL));
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),  // Title
+            Constraint::Min(8),     // Signals and chart
+            Constraint::Length(3),  // Status
+            Constraint::Length(3),  // Help
+        ])
+        .split(area);
+
+    let body_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+        .split(layout[1]);
+
+    // The value table or parse warnings take the lower part of the chart
+    // column when shown
+    let (chart_area, table_area) = if viewer.show_value_table || viewer.show_parse_warnings {
+        let split = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+            .split(body_layout[1]);
+        (split[0], Some(split[1]))
+    } else {
+        (body_layout[1], None)
+    };
+
+    let chart_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(0), Constraint::Length(1)])
+        .split(chart_area);
+
+    // Signal list, marking signals that belong to the current view
+    let signal_items: Vec<ListItem> = viewer.current_vcd
+        .as_ref()
+        .map(|vcd| {
+            let rows = viewer.signal_window.rows(
+                vcd.signals.len(),
+                Some(viewer.selected_signal_index),
+                body_layout[0].height.saturating_sub(2),
+            );
+            vcd.signals[rows.clone()]
+                .iter()
+                .zip(rows)
+                .map(|(signal, i)| {
+                    let style = if i == viewer.selected_signal_index {
+                        Style::default().bg(app.colors().yellow.into()).fg(Color::Black)
+                    } else {
+                        Style::default()
+                    };
+
+                    let marker = if viewer.is_in_view(signal) { "*" } else { " " };
+                    let value = viewer.format_value(signal, &viewer.get_signal_value_at_time(signal, viewer.current_time()));
+                    // Vectors show whether they are split into bits, which are indented below them
+                    let expander = match (signal.split_from.is_some(), signal.width > 1) {
+                        (true, _) => "   ",
+                        (false, true) if viewer.is_split(signal) => if app.ascii_mode { "v " } else { "▾ " },
+                        (false, true) => if app.ascii_mode { "> " } else { "▸ " },
+                        (false, false) => "",
+                    };
+                    ListItem::new(format!("{} {}{} = {}", marker, expander, signal.hierarchical_name(), value)).style(style)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let signals_title = if viewer.view.signals.is_empty() {
+        "Signals".to_string()
+    } else if viewer.view.name.is_empty() {
+        format!("Signals (view: {} signals)", viewer.view.signals.len())
+    } else {
+        format!("Signals (view: {})", viewer.view.name)
+    };
+
+    let signals_widget = List::new(signal_items)
+        .block(Block::default().title(signals_title).borders(Borders::ALL));
+
+    // Each displayed signal gets its own lane, stacked top to bottom. Single-bit
+    // signals are drawn as high/low lines, buses as a band labelled with the
+    // value of each stable region.
+    let displayed = viewer.get_displayed_signals();
+    let lane_count = displayed.len().max(1);
+    let palette = theme::signal_palette(app.flavor);
+    let signal_color = |signal: &Signal| -> Color { palette[viewer.color_index(signal) % palette.len()].into() };
+    let label_color: Color = app.colors().text.into();
+    // Values with x or z bits are drawn in this colour instead of the lane's
+    let unknown_color: Color = app.colors().red.into();
+
+    // Width of one terminal cell in time units, for fitting labels
+    let plot_width = chart_layout[1].width.saturating_sub(2).max(1) as f64;
+    let cell_time = (x_max - x_min) / plot_width;
+
+    let lanes: Vec<WaveLane> = displayed
+        .iter()
+        .enumerate()
+        .map(|(i, signal)| WaveLane {
+            base: ((lane_count - 1 - i) * 2) as f64,
+            color: signal_color(signal),
+            segments: viewer.visible_segments(signal),
+            is_bus: signal.width > 1,
+        })
+        .collect();
+
+    let chart = Canvas::default()
+        .block(Block::default().title("Waveform").borders(Borders::ALL))
+        .marker(Marker::Braille)
+        .x_bounds([x_min, x_max])
+        .y_bounds([-0.5, (lane_count * 2) as f64 - 0.5])
+        .paint(|ctx| {
+            for WaveLane { base, color, segments, is_bus } in &lanes {
+                let (low, high) = (base + 0.1, base + 0.9);
+
+                for (index, (start, end, value)) in segments.iter().enumerate() {
+                    let (start, end) = (*start as f64, *end as f64);
+                    let color = if waveform_viewer::parse_numeric_value(value).has_unknown { unknown_color } else { *color };
+
+                    if *is_bus {
+                        ctx.draw(&canvas::Line::new(start, low, end, low, color));
+                        ctx.draw(&canvas::Line::new(start, high, end, high, color));
+                        if index > 0 {
+                            ctx.draw(&canvas::Line::new(start, low, start, high, color));
+                        }
+                        continue;
+                    }
+
+                    let level = match value.as_str() {
+                        "1" => high,
+                        "0" => low,
+                        _ => base + 0.5, // x/z sit between the rails
+                    };
+                    ctx.draw(&canvas::Line::new(start, level, end, level, color));
+
+                    if let Some((_, _, next)) = segments.get(index + 1) && next != value {
+                        let next_level = match next.as_str() {
+                            "1" => high,
+                            "0" => low,
+                            _ => base + 0.5,
                         };
+                        ctx.draw(&canvas::Line::new(end, level, end, next_level, color));
+                    }
                 }
             }
-            KeyCode::Down => {
-                if !self.vcd_files.is_empty() {
-                    self.selected_vcd_index = (self.selected_vcd_index + 1) % self.vcd_files.len();
+
+            let cursors = [(viewer.cursor_a, app.colors().yellow), (viewer.cursor, app.colors().sky)];
+            for (cursor, color) in cursors {
+                if let Some(cursor) = cursor.map(|cursor| cursor as f64) && (x_min..=x_max).contains(&cursor) {
+                    ctx.draw(&canvas::Line::new(cursor, -0.5, cursor, (lane_count * 2) as f64 - 0.5, color.into()));
                 }
             }
-            KeyCode::Enter => {
-                self.launch_waveform_viewer();
-            }
-            KeyCode::Char('r') => {
-                // Refresh VCD files
-                self.scan_vcd_files();
-                self.message = format!("Refreshed VCD files. Found {} files", self.vcd_files.len());
-                self.mode = AppMode::MessageDialog;
-            }
-            KeyCode::Char('i') => {
-                // Show install instructions
-                self.message = "Waveform Viewer Installation:\n\n• DWFV (recommended): cargo install dwfv\n• DigiSurf: cargo install digisurf\n• GTKWave: sudo apt install gtkwave\n\nDWFV provides the best terminal experience with vi-like keybindings!".to_string();
-                self.mode = AppMode::MessageDialog;
+
+            // Labels go on top of the lines; skip regions too narrow to hold one
+            for WaveLane { base, segments, is_bus, .. } in &lanes {
+                if !*is_bus {
+                    continue;
+                }
+                for (start, end, value) in segments {
+                    let label = viewer.radix.format(value);
+                    let label_time = label.chars().count() as f64 * cell_time;
+                    let width = (*end - *start) as f64;
+                    if label_time + cell_time > width {
+                        continue;
+                    }
+                    let x = *start as f64 + (width - label_time) / 2.0;
+                    ctx.print(x, base + 0.5, Span::styled(label, Style::default().fg(label_color)));
+                }
             }
-            _ => {}
-        }
+        });
+
+    let status = Paragraph::new(viewer.status_message.clone().unwrap_or_default())
+        .style(Style::default().fg(app.colors().yellow.into()))
+        .block(Block::default().borders(Borders::ALL).title("Status"));
+
+    let help = Paragraph::new(help_text(app, &AppMode::InternalWaveform, area))
+        .style(Style::default().fg(Color::Gray))
+        .block(Block::default().borders(Borders::ALL).title("Controls"));
+
+    f.render_widget(title, layout[0]);
+    f.render_widget(signals_widget, body_layout[0]);
+    // Legend matching each lane's colour to its signal
+    let swatch = if app.ascii_mode { "-- " } else { "━━ " };
+    let legend_spans: Vec<Span> = displayed
+        .iter()
+        .flat_map(|signal| {
+            [
+                Span::styled(swatch, Style::default().fg(signal_color(signal))),
+                Span::raw(format!("{}  ", signal.hierarchical_name())),
+            ]
+        })
+        .collect();
+    let legend_area = ratatui::layout::Rect {
+        x: chart_layout[2].x + 1,
+        width: chart_layout[2].width.saturating_sub(2),
+        ..chart_layout[2]
+    };
+
+    render_time_ruler(f, app, chart_layout[0]);
+    f.render_widget(chart, chart_layout[1]);
+    f.render_widget(Paragraph::new(Line::from(legend_spans)), legend_area);
+    match table_area {
+        Some(table_area) if viewer.show_parse_warnings => render_parse_warnings(f, app, table_area),
+        Some(table_area) => render_value_table(f, app, &displayed, table_area),
+        None => {}
     }
+    f.render_widget(status, layout[2]);
+    f.render_widget(help, layout[3]);
+}
 
-    fn handle_input_dialog_key(&mut self, key: KeyCode) {
-        match key {
-            KeyCode::Esc => {
-                self.input_buffer.clear();
-                self.mode = AppMode::MainMenu;
-            }
-            KeyCode::Enter => {
-                // Handle input submission
-                self.input_buffer.clear();
-                self.mode = AppMode::MainMenu;
-            }
-            KeyCode::Backspace => {
-                self.input_buffer.pop();
-            }
-            KeyCode::Char(c) => {
-                self.input_buffer.push(c);
-            }
-            _ => {}
-        }
+// Exact values of the charted signals at the ruler's tick times across the
+// visible window, one row per signal
+fn render_value_table(f: &mut Frame, app: &App, signals: &[&Signal], area: ratatui::layout::Rect) {
+    let viewer = &app.waveform_viewer;
+    let timescale = viewer.current_vcd.as_ref().map(|vcd| vcd.timescale.as_str()).unwrap_or_default();
+
+    let name_width = signals
+        .iter()
+        .map(|signal| signal.hierarchical_name().chars().count())
+        .max()
+        .unwrap_or(6)
+        .clamp(6, 24) as u16;
+    let inner_width = area.width.saturating_sub(2);
+    let max_columns = (inner_width.saturating_sub(name_width + 1) / (VALUE_COLUMN_WIDTH + 1)).max(1) as usize;
+    let times = viewer.sample_times(max_columns);
+
+    let origin = viewer.time_origin() as i64;
+    let header = Row::new(
+        std::iter::once(Cell::from("signal")).chain(times.iter().map(|time| {
+            let relative = *time as i64 - origin;
+            let sign = if viewer.relative_time && relative > 0 { "+" } else { "" };
+            Cell::from(format!("{}{}", sign, waveform_viewer::format_time(relative, timescale)))
+        })),
+    )
+    .style(Style::default().fg(Color::Gray).add_modifier(Modifier::BOLD));
+
+    let palette = theme::signal_palette(app.flavor);
+    let rows: Vec<Row> = signals
+        .iter()
+        .map(|signal| {
+            let color: Color = palette[viewer.color_index(signal) % palette.len()].into();
+            let mut cells = vec![Cell::from(fit_cell(&signal.hierarchical_name(), name_width as usize)).style(Style::default().fg(color))];
+            cells.extend(times.iter().map(|time| {
+                let value = viewer.get_signal_value_at_time(signal, *time);
+                let style = if waveform_viewer::parse_numeric_value(&value).has_unknown {
+                    Style::default().fg(app.colors().red.into())
+                } else {
+                    Style::default()
+                };
+                Cell::from(fit_cell(&viewer.format_value(signal, &value), VALUE_COLUMN_WIDTH as usize)).style(style)
+            }));
+            Row::new(cells)
+        })
+        .collect();
+
+    let widths = std::iter::once(Constraint::Length(name_width))
+        .chain(times.iter().map(|_| Constraint::Length(VALUE_COLUMN_WIDTH)));
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(Block::default().title(format!("Values ({})", viewer.radix.name())).borders(Borders::ALL));
+    f.render_widget(table, area);
+}
+
+// Lines of the dump the parser skipped, with why
+fn render_parse_warnings(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let Some(warnings) = app.waveform_viewer.current_vcd.as_ref().map(|vcd| &vcd.warnings) else {
+        return;
+    };
+
+    let mut items: Vec<ListItem> = warnings
+        .list
+        .iter()
+        .map(|warning| {
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:>6}  ", warning.line), Style::default().fg(Color::Gray)),
+                Span::styled(fit_cell(&warning.text, 32), Style::default().fg(app.colors().red.into())),
+                Span::raw(format!("  {}", warning.reason)),
+            ]))
+        })
+        .collect();
+    if warnings.count > warnings.list.len() {
+        items.push(ListItem::new(format!("        ...and {} more", warnings.count - warnings.list.len())).style(Style::default().fg(Color::Gray)));
+    }
+    if items.is_empty() {
+        items.push(ListItem::new("No lines were skipped").style(Style::default().fg(app.colors().green.into())));
     }
 
-    fn handle_message_dialog_key(&mut self, key: KeyCode) {
-        match key {
-            KeyCode::Enter | KeyCode::Esc => {
-                self.message.clear();
-                self.mode = AppMode::MainMenu;
-            }
-            _ => {}
-        }
+    let list = List::new(items)
+        .block(Block::default().title(format!("Parse warnings ({})", warnings.count)).borders(Borders::ALL));
+    f.render_widget(list, area);
+}
+
+// Cut `text` to `width` columns, marking that it was cut
+fn fit_cell(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
     }
+    let kept: String = text.chars().take(width.saturating_sub(1)).collect();
+    format!("{}…", kept)
 }
 
-fn ui(f: &mut Frame, app: &App) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Min(0)].as_ref())
-        .split(f.area());
+// Labelled ticks over the chart's plot columns, spaced so labels never touch
+// at any zoom. Times count from cursor A in relative mode.
+fn render_time_ruler(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let viewer = &app.waveform_viewer;
+    let timescale = viewer.current_vcd.as_ref().map(|vcd| vcd.timescale.as_str()).unwrap_or_default();
 
-    match app.mode {
-        AppMode::MainMenu => render_main_menu(f, app, chunks[0]),
-        AppMode::CreateProject => render_create_project(f, app, chunks[0]),
-        AppMode::CompileProject => render_compile_project(f, app, chunks[0]),
-        AppMode::EditProject => render_edit_project(f, app, chunks[0]),
-        AppMode::ViewWaveform => render_view_waveform(f, app, chunks[0]),
-        AppMode::MessageDialog => {
-            render_main_menu(f, app, chunks[0]);
-            render_message_dialog(f, app);
+    // Line up with the inside of the chart's border
+    let area = ratatui::layout::Rect {
+        x: area.x + 1,
+        width: area.width.saturating_sub(2),
+        ..area
+    };
+    let columns = area.width as usize;
+    if columns == 0 {
+        return;
+    }
+
+    let (start, span) = (viewer.time_offset, viewer.visible_time_window.max(1));
+    let end = start + span;
+    let origin = viewer.time_origin();
+    let label = |time: u64| {
+        let time = time as i64 - origin as i64;
+        let sign = if viewer.relative_time && time > 0 { "+" } else { "" };
+        format!("{}{}", sign, waveform_viewer::format_time(time, timescale))
+    };
+    let column = |time: u64| (((time - start) as f64 / span as f64) * (columns - 1) as f64).round() as usize;
+
+    let label_width = label(start).len().max(label(end).len());
+    let step = waveform_viewer::ruler_step(span, columns / (label_width + 2));
+
+    let (line, tick) = if app.ascii_mode { ('-', '+') } else { ('─', '┬') };
+    let mut labels = vec![' '; columns];
+    let mut ticks = vec![line; columns];
+    let mut free_from = 0;
+
+    for time in waveform_viewer::ruler_ticks(start, end, origin, step) {
+        let at = column(time).min(columns - 1);
+        ticks[at] = tick;
+
+        let text = label(time);
+        let begin = at.min(columns.saturating_sub(text.len()));
+        if begin < free_from || begin + text.len() > columns {
+            continue;
+        }
+        for (offset, c) in text.chars().enumerate() {
+            labels[begin + offset] = c;
         }
-        _ => render_main_menu(f, app, chunks[0]),
+        free_from = begin + text.len() + 1;
+    }
+
+    // Cursor A and the edge jumped to are marked on the tick line
+    let mut markers: Vec<(usize, &str, Color)> = [(viewer.cursor_a, "A", app.colors().yellow), (viewer.cursor, "▼", app.colors().sky)]
+        .into_iter()
+        .filter_map(|(cursor, mark, color)| {
+            let cursor = cursor.filter(|cursor| (start..=end).contains(cursor))?;
+            let mark = if app.ascii_mode && mark == "▼" { "v" } else { mark };
+            Some((column(cursor).min(columns - 1), mark, color.into()))
+        })
+        .collect();
+    markers.sort_by_key(|(at, _, _)| *at);
+    markers.dedup_by_key(|(at, _, _)| *at);
+
+    let mut tick_spans = Vec::new();
+    let mut from = 0;
+    for (at, mark, color) in markers {
+        tick_spans.push(Span::raw(ticks[from..at].iter().collect::<String>()));
+        tick_spans.push(Span::styled(mark, Style::default().fg(color).add_modifier(Modifier::BOLD)));
+        from = at + 1;
     }
+    tick_spans.push(Span::raw(ticks[from..].iter().collect::<String>()));
+
+    let ruler = Paragraph::new(vec![
+        Line::from(labels.iter().collect::<String>()),
+        Line::from(tick_spans),
+    ])
+    .style(Style::default().fg(Color::Gray));
+    f.render_widget(ruler, area);
 }
 
-fn render_main_menu(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
-    let title = Paragraph::new("🌊 Hadou - Verilog Project Manager")
-        .style(Style::default().fg(PALETTE.macchiato.colors.teal.into()).add_modifier(Modifier::BOLD))
-        .block(Block::default().borders(Borders::ALL));
+fn render_settings(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let settings = &app.settings_editor;
 
-    let menu_items = vec![
-        "📁 Create New Project",
-        "✏️  Edit Project",
-        "⚙️  Compile Project", 
-        "📊 View Waveform",
-    ];
+    let title = Paragraph::new(format!("{} Settings", app.icon(Icon::Wrench)))
+        .style(Style::default().fg(app.colors().mauve.into()).add_modifier(Modifier::BOLD))
+        .block(Block::default().borders(Borders::ALL));
 
-    let items: Vec<ListItem> = menu_items
+    let items: Vec<ListItem> = SettingField::ALL
         .iter()
         .enumerate()
-        .map(|(i, item)| {
-            let style = if i == app.selected_index {
-                Style::default().bg(PALETTE.macchiato.colors.yellow.into()).fg(Color::Black)
+        .map(|(i, field)| {
+            let selected = i == settings.selected_index;
+            let value = if selected && settings.editing {
+                format!("{}_", settings.edit_buffer)
+            } else {
+                let value = field.value(&settings.draft);
+                if value.is_empty() { "(auto)".to_string() } else { value }
+            };
+
+            let style = if selected {
+                Style::default().bg(app.colors().yellow.into()).fg(Color::Black)
             } else {
                 Style::default()
             };
-            ListItem::new(*item).style(style)
+            ListItem::new(format!("{:<16} {}", field.label(), value)).style(style)
         })
         .collect();
 
-    let menu = List::new(items)
-        .block(Block::default().title("Menu").borders(Borders::ALL))
-        .highlight_style(Style::default().bg(PALETTE.macchiato.colors.yellow.into()).fg(Color::Black));
+    let list = List::new(items)
+        .block(Block::default().title("Configuration").borders(Borders::ALL));
+
+    let field = settings.selected_field();
+    let mut details = vec![Line::from(field.description())];
+    if let Some(status) = &settings.status {
+        details.push(Line::from(Span::styled(status.as_str(), Style::default().fg(app.colors().peach.into()))));
+    }
+    if field == SettingField::Flavor {
+        // The draft flavor's accents as they'll look, or the current ones for auto
+        let flavor = settings.draft.flavor.as_deref().and_then(theme::parse_flavor).unwrap_or(app.flavor);
+        let palette = theme::colors(flavor);
+        details.push(Line::from(""));
+        details.push(Line::from(Span::styled(format!("Preview: {}", flavor), Style::default().add_modifier(Modifier::BOLD))));
+        let background = Style::default().bg(palette.base.into());
+        let mut swatches = Vec::new();
+        for (name, color) in theme::preview_swatches(flavor) {
+            swatches.push(Span::styled("██", background.fg(color.into())));
+            swatches.push(Span::styled(format!(" {}  ", name), background.fg(palette.text.into())));
+        }
+        details.push(Line::from(swatches));
+        details.push(Line::from(Span::styled(
+            "Text on the flavor's background ",
+            background.fg(palette.text.into()),
+        )));
+    }
+    if let Some(path) = Config::config_path() {
+        details.push(Line::from(""));
+        details.push(Line::from(vec![
+            Span::raw("Config file: "),
+            Span::styled(path.display().to_string(), Style::default().fg(app.colors().yellow.into())),
+        ]));
+    }
+
+    let details_widget = Paragraph::new(details)
+        .wrap(Wrap { trim: true })
+        .block(Block::default().borders(Borders::ALL).title("Details"));
 
-    let help = Paragraph::new("Use ↑/↓ to navigate, Enter to select, 'q' or Esc to quit")
+    let help = Paragraph::new(help_text(app, &AppMode::Settings, area))
         .style(Style::default().fg(Color::Gray))
         .block(Block::default().borders(Borders::ALL).title("Help"));
 
@@ -434,55 +4516,51 @@ fn render_main_menu(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),
-            Constraint::Min(8),
+            Constraint::Length(SettingField::ALL.len() as u16 + 2),
+            Constraint::Min(5),
             Constraint::Length(3),
         ])
         .split(area);
 
     f.render_widget(title, layout[0]);
-    f.render_widget(menu, layout[1]);
-    f.render_widget(help, layout[2]);
+    f.render_widget(list, layout[1]);
+    f.render_widget(details_widget, layout[2]);
+    f.render_widget(help, layout[3]);
 }
 
-fn render_create_project(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
-    let title = Paragraph::new("📁 Create New Verilog Project")
-        .style(Style::default().fg(PALETTE.macchiato.colors.green.into()).add_modifier(Modifier::BOLD))
+fn render_module_snippets(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let project = app.project_editor.get_selected_project_name().unwrap_or_default();
+    let title = Paragraph::new(format!("{} Instantiation Templates - {}", app.icon(Icon::File), project))
+        .style(Style::default().fg(app.colors().blue.into()).add_modifier(Modifier::BOLD))
         .block(Block::default().borders(Borders::ALL));
 
-    let current_dir = std::env::current_dir()
-        .map(|p| p.display().to_string())
-        .unwrap_or_else(|_| "Unknown".to_string());
-
-    let info_text = vec![
-        Line::from(vec![
-            Span::raw("Current Directory: "),
-            Span::styled(current_dir, Style::default().fg(PALETTE.macchiato.colors.yellow.into())),
-        ]),
-        Line::from(""),
-        Line::from("Enter Project name (alphanumeric, _ and - allowed):"),
-    ];
-
-    let info = Paragraph::new(info_text)
-        .block(Block::default().borders(Borders::ALL).title("Project Info"));
-
-    let input = Paragraph::new(app.project_creator.project_name.as_str())
-        .style(Style::default().fg(PALETTE.macchiato.colors.yellow.into()))
-        .block(Block::default().borders(Borders::ALL).title("Project Name"));
+    let items: Vec<ListItem> = app.module_snippets
+        .iter()
+        .enumerate()
+        .map(|(i, snippet)| {
+            let style = if i == app.selected_snippet_index {
+                Style::default().bg(app.colors().yellow.into()).fg(Color::Black)
+            } else {
+                Style::default()
+            };
+            let file_name = snippet.file.file_name().unwrap_or_default().to_string_lossy();
+            ListItem::new(format!("{} ({})", snippet.module, file_name)).style(style)
+        })
+        .collect();
 
-    let preview_text = if app.project_creator.project_name.is_empty() {
-        "Enter a Project Name to see preview".to_string()
-    } else {
-        format!(
-            "Will Create:\n📁 {}/\n 📄 main.v (main module)\n 🧪 main_test.v (testbench)\n ⚡ justfile (build automation)",
-            app.project_creator.project_name
-        )
-    };
+    let list = List::new(items)
+        .block(Block::default().title("Modules").borders(Borders::ALL));
 
-    let preview = Paragraph::new(preview_text)
-        .style(Style::default().fg(Color::Gray))
-        .block(Block::default().borders(Borders::ALL).title("Preview"));
+    // Not wrapped or trimmed so the indentation survives a terminal copy
+    let snippet = app.module_snippets
+        .get(app.selected_snippet_index)
+        .map(|snippet| snippet.snippet.as_str())
+        .unwrap_or_default();
+    let preview = Paragraph::new(snippet)
+        .style(Style::default().fg(app.colors().text.into()))
+        .block(Block::default().borders(Borders::ALL).title("Snippet"));
 
-    let help = Paragraph::new("Enter to create a new project, Esc to return to main menu")
+    let help = Paragraph::new(help_text(app, &AppMode::ModuleSnippets, area))
         .style(Style::default().fg(Color::Gray))
         .block(Block::default().borders(Borders::ALL).title("Help"));
 
@@ -490,440 +4568,611 @@ fn render_create_project(f: &mut Frame, app: &App, area: ratatui::layout::Rect)
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),
-            Constraint::Length(6),
-            Constraint::Length(3),
-            Constraint::Min(4),
+            Constraint::Min(6),
             Constraint::Length(3),
         ])
         .split(area);
 
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+        .split(layout[1]);
+
     f.render_widget(title, layout[0]);
-    f.render_widget(info, layout[1]);
-    f.render_widget(input, layout[2]);
-    f.render_widget(preview, layout[3]);
-    f.render_widget(help, layout[4]);
+    f.render_widget(list, columns[0]);
+    f.render_widget(preview, columns[1]);
+    f.render_widget(help, layout[2]);
 }
 
-fn render_compile_project(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
-    let title = Paragraph::new("⚙️  Compile Verilog Project")
-        .style(Style::default().fg(PALETTE.macchiato.colors.red.into()).add_modifier(Modifier::BOLD))
-        .block(Block::default().borders(Borders::ALL));
+fn render_error_detail(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let output = app.error_detail.as_deref().unwrap_or_default();
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)])
+        .split(area);
 
-    let current_dir = std::env::current_dir()
-        .map(|p| p.display().to_string())
-        .unwrap_or_else(|_| "Unknown".to_string());
+    // Unwrapped and untrimmed, so the text is exactly what the tools printed
+    let lines = output.lines().count();
+    let offset = app.error_scroll.offset(lines, layout[0].height.saturating_sub(2));
+    let mut title = scroll_title("Full output of the failed run", &app.error_scroll, lines);
+    if let Some(status) = &app.error_status {
+        title.push_str(&format!(" - {}", status));
+    }
+    let text = Paragraph::new(output)
+        .scroll((offset, 0))
+        .block(
+            Block::default()
+                .title(Span::styled(title, Style::default().fg(app.colors().red.into()).add_modifier(Modifier::BOLD)))
+                .borders(Borders::ALL),
+        );
 
-    let info_text = vec![
-        Line::from(vec![
-            Span::raw("Current Directory: "),
-            Span::styled(current_dir, Style::default().fg(PALETTE.macchiato.colors.yellow.into())),
-        ]),
-        Line::from(""),
-        Line::from(format!("Found {} Verilog project(s):", app.project_compiler.project_count())),
-    ];
+    let help = Paragraph::new(help_text(app, &AppMode::ErrorDetail, area))
+        .style(Style::default().fg(Color::Gray))
+        .block(Block::default().borders(Borders::ALL).title("Help"));
 
-    let info = Paragraph::new(info_text)
-        .block(Block::default().borders(Borders::ALL).title("Project Info"));
+    f.render_widget(text, layout[0]);
+    f.render_widget(help, layout[1]);
+}
 
-    // Create horizontal layout for projects and actions
-    let main_layout = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-        .split(area);
+fn render_references(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let project_path = app.project_editor.get_selected_project_path();
+    let project = app.project_editor.get_selected_project_name().unwrap_or_default();
+    let title = Paragraph::new(format!(
+        "{} References to '{}' - {}",
+        app.icon(Icon::File),
+        app.reference_query,
+        project
+    ))
+    .style(Style::default().fg(app.colors().blue.into()).add_modifier(Modifier::BOLD))
+    .block(Block::default().borders(Borders::ALL));
 
-    let left_layout = Layout::default()
+    let layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),
-            Constraint::Length(6),
-            Constraint::Min(8),
+            Constraint::Min(6),
             Constraint::Length(3),
         ])
-        .split(main_layout[0]);
+        .split(area);
 
-    let right_layout = Layout::default()
+    let rows = app.reference_window.rows(
+        app.references.len(),
+        Some(app.selected_reference_index),
+        layout[1].height.saturating_sub(2),
+    );
+    let items: Vec<ListItem> = app.references[rows.clone()]
+        .iter()
+        .zip(rows)
+        .map(|(reference, i)| {
+            let selected = i == app.selected_reference_index;
+            let location_style = if selected {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(app.colors().peach.into())
+            };
+            let file = project_path
+                .and_then(|project| reference.file.strip_prefix(project).ok())
+                .unwrap_or(&reference.file);
+            let line = Line::from(vec![
+                Span::styled(format!("{}:{}", file.display(), reference.line), location_style),
+                Span::raw(format!(": {}", reference.text)),
+            ]);
+            let style = if selected {
+                Style::default().bg(app.colors().yellow.into()).fg(Color::Black)
+            } else {
+                Style::default()
+            };
+            ListItem::new(line).style(style)
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().title(format!("{} match(es)", app.references.len())).borders(Borders::ALL));
+
+    let help = Paragraph::new(help_text(app, &AppMode::References, area))
+        .style(Style::default().fg(Color::Gray))
+        .block(Block::default().borders(Borders::ALL).title("Help"));
+
+    f.render_widget(title, layout[0]);
+    f.render_widget(list, layout[1]);
+    f.render_widget(help, layout[2]);
+}
+
+fn render_browse_directory(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let browser = &app.dir_browser;
+    let title = Paragraph::new(format!("{} Change Directory - {}", app.icon(Icon::Folder), browser.path.display()))
+        .style(Style::default().fg(app.colors().blue.into()).add_modifier(Modifier::BOLD))
+        .block(Block::default().borders(Borders::ALL));
+
+    let layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),
-            Constraint::Min(8),
-            Constraint::Length(6),
+            Constraint::Min(6),
+            Constraint::Length(4),
         ])
-        .split(main_layout[1]);
-
-    // Projects list
-    let projects_widget = if app.project_compiler.has_projects() {
-        let project_items: Vec<ListItem> = app.project_compiler.projects
-            .iter()
-            .enumerate()
-            .map(|(i, project_path)| {
-                let project_name = project_path
-                    .file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string();
-
-                let style = if i == app.project_compiler.selected_project_index {
-                    Style::default().bg(PALETTE.macchiato.colors.yellow.into()).fg(Color::Black)
-                } else {
-                    Style::default()
-                };
-
-                // Show project name with verilog file count and justfile status
-                let verilog_files = app.project_compiler.get_verilog_files(project_path);
-                let has_justfile = app.project_compiler.has_justfile(project_path);
-                let justfile_indicator = if has_justfile { "⚡" } else { "❌" };
-
-                let display_text = format!("📁 {} ({} .v files) {}", 
-                    project_name, verilog_files.len(), justfile_indicator);
-                ListItem::new(display_text).style(style)
-            })
-            .collect();
+        .split(area);
+    let body = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(layout[1]);
 
-        List::new(project_items)
-            .block(Block::default().title("Projects").borders(Borders::ALL))
-            .highlight_style(Style::default().bg(PALETTE.macchiato.colors.yellow.into()).fg(Color::Black))
+    let rows = browser.list_window.rows(browser.entries.len(), Some(browser.selected), body[0].height.saturating_sub(2));
+    let items: Vec<ListItem> = browser.entries[rows.clone()]
+        .iter()
+        .zip(rows)
+        .map(|(dir, i)| {
+            let selected = i == browser.selected;
+            let mut spans = vec![Span::raw(format!("{} {}", app.icon(Icon::Folder), project_label(dir)))];
+            if dir_browser::is_project(dir) {
+                let tag_style = if selected { Style::default() } else { Style::default().fg(app.colors().green.into()) };
+                spans.push(Span::styled(" (project)", tag_style));
+            }
+            let style = if selected {
+                Style::default().bg(app.colors().yellow.into()).fg(Color::Black)
+            } else {
+                Style::default()
+            };
+            ListItem::new(Line::from(spans)).style(style)
+        })
+        .collect();
+    let list_title = format!(
+        "{} subdirector{}{}",
+        browser.entries.len(),
+        if browser.entries.len() == 1 { "y" } else { "ies" },
+        if browser.show_hidden { ", hidden shown" } else { "" }
+    );
+    let list = if items.is_empty() {
+        List::new(vec![ListItem::new("No subdirectories")]).style(Style::default().fg(Color::Gray))
     } else {
-        List::new(vec![ListItem::new("No Verilog projects found in current directory")])
-            .block(Block::default().title("Projects").borders(Borders::ALL))
-            .style(Style::default().fg(Color::Gray))
+        List::new(items)
     };
 
-    // Actions list
-    let action_items: Vec<ListItem> = app.project_compiler.available_actions
+    // Recent base directories, the current one highlighted
+    let recent: Vec<Line> = app.config.recent_dirs
         .iter()
+        .take(9)
         .enumerate()
-        .map(|(i, action)| {
-            let style = if i == app.project_compiler.selected_action_index {
-                Style::default().bg(PALETTE.macchiato.colors.blue.into()).fg(Color::White)
+        .map(|(i, dir)| {
+            let style = if Path::new(dir) == app.project_compiler.current_directory {
+                Style::default().fg(app.colors().teal.into())
             } else {
                 Style::default()
             };
-
-            let display_text = format!("{} {}", action.icon(), action.description());
-            ListItem::new(display_text).style(style)
+            Line::from(Span::styled(format!("{} {}", i + 1, dir), style))
         })
         .collect();
+    let recent = if recent.is_empty() { vec![Line::from("None yet")] } else { recent };
 
-    let actions_widget = List::new(action_items)
-        .block(Block::default().title("Actions").borders(Borders::ALL))
-        .highlight_style(Style::default().bg(PALETTE.macchiato.colors.blue.into()).fg(Color::White));
+    let help = Paragraph::new(help_text(app, &AppMode::BrowseDirectory, area))
+        .style(Style::default().fg(Color::Gray))
+        .wrap(Wrap { trim: true })
+        .block(Block::default().borders(Borders::ALL).title("Help"));
+
+    f.render_widget(title, layout[0]);
+    f.render_widget(list.block(Block::default().title(list_title).borders(Borders::ALL)), body[0]);
+    f.render_widget(
+        Paragraph::new(recent).wrap(Wrap { trim: false }).block(Block::default().title("Recent").borders(Borders::ALL)),
+        body[1],
+    );
+    f.render_widget(help, layout[2]);
+}
+
+// Centres a popup of the requested size, grown to hold a border and a
+// line of text but never larger than the area it is drawn over
+fn popup_area(area: ratatui::layout::Rect, width: u16, height: u16) -> ratatui::layout::Rect {
+    let width = width.max(20).min(area.width);
+    let height = height.max(5).min(area.height);
+    ratatui::layout::Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+// Bottom row of a popup, used for the key hints
+fn popup_help_area(popup_area: ratatui::layout::Rect) -> ratatui::layout::Rect {
+    ratatui::layout::Rect {
+        y: popup_area.y + popup_area.height.saturating_sub(1),
+        height: popup_area.height.min(1),
+        ..popup_area
+    }
+}
+
+fn render_loading_dialog(f: &mut Frame, app: &App) {
+    let Some(load) = &app.waveform_viewer.loading else {
+        return;
+    };
+
+    let area = f.area();
+    let popup_area = popup_area(area, area.width / 2, 5);
+    f.render_widget(Clear, popup_area);
+
+    let stage = if load.is_parsing() { "Parsing" } else { "Reading" };
+    let gauge = Gauge::default()
+        .ratio(load.ratio())
+        .label(format!(
+            "{} {} ({:.0}%)",
+            stage,
+            compile_project::format_size(load.bytes_done()),
+            load.ratio() * 100.0
+        ))
+        .gauge_style(Style::default().fg(app.colors().green.into()).bg(app.colors().surface0.into()))
+        .block(
+            Block::default()
+                .title(format!("Loading {}", vcd_label(&load.path)))
+                .borders(Borders::ALL)
+                .style(Style::default().bg(Color::Black)),
+        );
+    f.render_widget(gauge, popup_area);
 
-    // Preview of selected project
-    let preview_text = if let Some(selected_path) = app.project_compiler.get_selected_project_path() {
-        let verilog_files = app.project_compiler.get_verilog_files(selected_path);
-        let has_justfile = app.project_compiler.has_justfile(selected_path);
+    let help = Paragraph::new(format!("{} in total, Esc to cancel", compile_project::format_size(load.total)))
+        .style(Style::default().fg(Color::Gray))
+        .alignment(ratatui::layout::Alignment::Center);
+    f.render_widget(help, popup_help_area(popup_area));
+}
 
-        if !verilog_files.is_empty() {
-            let mut preview = format!("Selected Project:\n📁 {}\n", 
-                selected_path.file_name().unwrap().to_string_lossy());
+fn render_web_viewer_dialog(f: &mut Frame, app: &App) {
+    let Some(server) = &app.web_server else {
+        return;
+    };
 
-            preview.push_str(&format!("\nJustfile: {}\n", if has_justfile { "✅ Found" } else { "❌ Missing" }));
+    let area = f.area();
+    let popup_area = popup_area(area, area.width * 2 / 3, 8);
+    f.render_widget(Clear, popup_area);
 
-            preview.push_str("\nVerilog files:\n");
-            for file in verilog_files.iter().take(6) {
-                if let Some(file_name) = file.file_name() {
-                    preview.push_str(&format!(" 📄 {}\n", file_name.to_string_lossy()));
-                }
-            }
-            if verilog_files.len() > 6 {
-                preview.push_str(&format!(" ... and {} more files", verilog_files.len() - 6));
-            }
+    let label = Style::default().fg(Color::Gray);
+    let text = vec![
+        Line::from(vec![
+            Span::styled("Viewer  ", label),
+            Span::styled(server.url(), Style::default().fg(app.colors().teal.into()).add_modifier(Modifier::BOLD)),
+        ]),
+        Line::from(vec![Span::styled("Surfer  ", label), Span::raw(server.surfer_url())]),
+        Line::from(vec![Span::styled("SSH     ", label), Span::raw(server.tunnel_command())]),
+        Line::from(""),
+        Line::from(app.web_status.as_str()),
+        Line::from(Span::styled(format!("{} requests served", server.requests_served()), label)),
+    ];
+    let dialog = Paragraph::new(text)
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .title(format!("Serving {}", vcd_label(&server.vcd_path)))
+                .borders(Borders::ALL)
+                .style(Style::default().bg(Color::Black)),
+        );
+    f.render_widget(dialog, popup_area);
 
-            if let Some(action) = app.project_compiler.get_selected_action() {
-                preview.push_str(&format!("\nWill execute: just {}", action.as_just_recipe()));
-            }
+    let help = Paragraph::new("'o': Open in browser | 'y': Copy URL | Esc: Stop the server")
+        .style(Style::default().fg(Color::Gray))
+        .alignment(ratatui::layout::Alignment::Center);
+    f.render_widget(help, popup_help_area(popup_area));
+}
 
-            preview
-        } else {
-            "No Verilog files found in selected project".to_string()
-        }
-    } else {
-        "Select a project to see preview".to_string()
+fn render_key_help(f: &mut Frame, app: &App) {
+    let Some(screen) = screen_hints(app, &app.key_help_return) else {
+        return;
     };
+    let screen: Vec<Hint> = screen.iter().copied().chain([hints::PALETTE]).collect();
 
-    let preview = Paragraph::new(preview_text)
-        .style(Style::default().fg(Color::Gray))
-        .block(Block::default().borders(Borders::ALL).title("Preview"));
+    let area = f.area();
+    let popup_area = popup_area(area, area.width.saturating_sub(8).min(90), screen.len() as u16 + 2);
+
+    f.render_widget(Clear, popup_area);
+
+    let keys_width = screen.iter().map(|hint| hint.keys.chars().count()).max().unwrap_or(0);
+    let key_style = Style::default().fg(app.colors().yellow.into()).add_modifier(Modifier::BOLD);
+    let rows: Vec<Line> = screen
+        .iter()
+        .map(|hint| {
+            Line::from(vec![
+                Span::styled(format!(" {:<width$}  ", hint.keys, width = keys_width), key_style),
+                Span::raw(hint.action),
+            ])
+        })
+        .collect();
+    let text: String = rows.iter().map(|row| format!("{}\n", row)).collect();
+    let lines = wrapped_line_count(&text, popup_area.width.saturating_sub(2));
+    let offset = app.key_help_scroll.offset(lines, popup_area.height.saturating_sub(2));
+    let list = Paragraph::new(rows)
+        .wrap(Wrap { trim: false })
+        .scroll((offset, 0))
+        .block(
+            Block::default()
+                .title(scroll_title("Keys", &app.key_help_scroll, lines))
+                .borders(Borders::ALL)
+                .style(Style::default().bg(Color::Black)),
+        );
+    f.render_widget(list, popup_area);
 
-    let help_text = if app.project_compiler.has_projects() {
-        "↑/↓ select project, ←/→ select action, Enter to execute, 'r' refresh, 'c' clear output, Esc to return"
+    let help_text = if app.key_help_scroll.overflows(lines) {
+        "↑/↓ PgUp/PgDn scroll, Esc or '?' to close"
     } else {
-        "No projects found. Press 'r' to refresh, Esc to return to main menu"
+        "Esc or '?' to close"
     };
-
     let help = Paragraph::new(help_text)
         .style(Style::default().fg(Color::Gray))
-        .block(Block::default().borders(Borders::ALL).title("Help"));
-
-    // Render left side (title, info, projects, help)
-    f.render_widget(title, left_layout[0]);
-    f.render_widget(info, left_layout[1]);
-    f.render_widget(projects_widget, left_layout[2]);
-    f.render_widget(help, left_layout[3]);
-
-    // Render right side (actions, preview)
-    f.render_widget(actions_widget, right_layout[1]);
-    f.render_widget(preview, right_layout[2]);
+        .alignment(ratatui::layout::Alignment::Center);
+    f.render_widget(help, popup_help_area(popup_area));
 }
 
-fn render_edit_project(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
-    let title = Paragraph::new("✏️  Edit Verilog Project")
-        .style(Style::default().fg(PALETTE.macchiato.colors.blue.into()).add_modifier(Modifier::BOLD))
-        .block(Block::default().borders(Borders::ALL));
+fn render_command_palette(f: &mut Frame, app: &App) {
+    let matches = app.palette.matches();
+    let area = f.area();
+    let popup_area = popup_area(area, area.width.saturating_sub(8).min(90), Command::all().len() as u16 + 5);
 
-    let current_dir = std::env::current_dir()
-        .map(|p| p.display().to_string())
-        .unwrap_or_else(|_| "Unknown".to_string());
+    f.render_widget(Clear, popup_area);
 
-    let info_text = vec![
-        Line::from(vec![
-            Span::raw("Current Directory: "),
-            Span::styled(current_dir, Style::default().fg(PALETTE.macchiato.colors.yellow.into())),
-        ]),
-        Line::from(""),
-        Line::from(format!("Found {} Verilog project(s):", app.project_editor.project_count())),
-    ];
+    let block = Block::default()
+        .title(format!("Commands ({} of {})", matches.len(), Command::all().len()))
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::Black));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
 
-    let info = Paragraph::new(info_text)
-        .block(Block::default().borders(Borders::ALL).title("Project Info"));
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(1)])
+        .split(inner);
 
-    // Project list or empty message
-    let projects_widget = if app.project_editor.has_projects() {
-        let project_items: Vec<ListItem> = app.project_editor.projects
+    let query = Paragraph::new(Line::from(vec![
+        Span::styled(": ", Style::default().fg(Color::Gray)),
+        Span::styled(format!("{}_", app.palette.search.query), Style::default().fg(app.colors().yellow.into())),
+    ]));
+    f.render_widget(query, layout[0]);
+
+    let rows: Vec<ListItem> = if matches.is_empty() {
+        vec![ListItem::new(format!("No commands match '{}'", app.palette.search.query)).style(Style::default().fg(Color::Gray))]
+    } else {
+        let shown = app.palette.window.rows(matches.len(), Some(app.palette.selected), layout[1].height);
+        let name_width = matches.iter().map(|(command, _)| command.name().chars().count()).max().unwrap_or(0);
+        matches[shown.clone()]
             .iter()
-            .enumerate()
-            .map(|(i, project_path)| {
-                let project_name = project_path
-                    .file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string();
-
-                let style = if i == app.project_editor.selected_project_index {
-                    Style::default().bg(PALETTE.macchiato.colors.yellow.into()).fg(Color::Black)
+            .zip(shown)
+            .map(|((command, found), position)| {
+                let selected = position == app.palette.selected;
+                let mut spans = vec![Span::raw(" ")];
+                spans.extend(highlight_matches(app, command.name(), &found.positions, selected));
+                let padding = name_width - command.name().chars().count();
+                spans.push(Span::styled(format!("{}  {}", " ".repeat(padding), command.keys(&app.keymap)), Style::default().fg(Color::DarkGray)));
+                let style = if selected {
+                    Style::default().bg(app.colors().yellow.into()).fg(Color::Black)
                 } else {
                     Style::default()
                 };
-
-                // Show project name with file count
-                let files = app.project_editor.get_project_files(project_path);
-                let display_text = format!("📁 {} ({} files)", project_name, files.len());
-                ListItem::new(display_text).style(style)
+                ListItem::new(Line::from(spans)).style(style)
             })
-            .collect();
-
-        List::new(project_items)
-            .block(Block::default().title("Projects").borders(Borders::ALL))
-            .highlight_style(Style::default().bg(PALETTE.macchiato.colors.yellow.into()).fg(Color::Black))
-    } else {
-        List::new(vec![ListItem::new("No Verilog projects found in current directory")])
-            .block(Block::default().title("Projects").borders(Borders::ALL))
-            .style(Style::default().fg(Color::Gray))
+            .collect()
     };
+    f.render_widget(List::new(rows), layout[1]);
 
-    // Preview of selected project files
-    let preview_text = if let Some(selected_path) = app.project_editor.get_selected_project_path() {
-        let files = app.project_editor.get_project_files(selected_path);
-        if !files.is_empty() {
-            let mut preview = format!("Will open in editor:\n📁 {}\n", 
-                selected_path.file_name().unwrap().to_string_lossy());
+    let help = Paragraph::new(help_text(app, &AppMode::CommandPalette, popup_area))
+        .style(Style::default().fg(Color::Gray))
+        .alignment(ratatui::layout::Alignment::Center);
+    f.render_widget(help, popup_help_area(popup_area));
+}
 
-            for file in files.iter().take(8) { // Show max 8 files to avoid overflow
-                if let Some(file_name) = file.file_name() {
-                    let icon = match file.extension().and_then(|ext| ext.to_str()) {
-                        Some("v") => "📄",
-                        Some(_) => "📄",
-                        None => "⚡", // justfile has no extension
-                    };
-                    preview.push_str(&format!(" {} {}\n", icon, file_name.to_string_lossy()));
-                }
-            }
-            if files.len() > 8 {
-                preview.push_str(&format!(" ... and {} more files", files.len() - 8));
-            }
-            preview
-        } else {
-            "No editable files found in selected project".to_string()
-        }
-    } else {
-        "Select a project to see preview".to_string()
-    };
+fn render_message_dialog(f: &mut Frame, app: &App) {
+    let area = f.area();
+    let popup_area = popup_area(area, area.width / 2, area.height / 3);
 
-    let preview = Paragraph::new(preview_text)
-        .style(Style::default().fg(Color::Gray))
-        .block(Block::default().borders(Borders::ALL).title("Preview"));
+    f.render_widget(Clear, popup_area);
 
-    let help_text = if app.project_editor.has_projects() {
-        "Use ↑/↓ to navigate, Enter to edit project, 'r' to refresh, Esc to return to main menu"
-    } else {
-        "No projects found. Press 'r' to refresh, Esc to return to main menu"
-    };
+    let lines = wrapped_line_count(&app.message, popup_area.width.saturating_sub(2));
+    let offset = app.message_scroll.offset(lines, popup_area.height.saturating_sub(2));
+    let message = Paragraph::new(app.message.as_str())
+        .wrap(Wrap { trim: true })
+        .scroll((offset, 0))
+        .block(
+            Block::default()
+                .title(scroll_title("Message", &app.message_scroll, lines))
+                .borders(Borders::ALL)
+                .style(Style::default().bg(Color::Black)),
+        );
 
+    f.render_widget(message, popup_area);
+
+    let help_area = popup_help_area(popup_area);
+
+    let help_text = match (app.message_scroll.overflows(lines), app.error_detail.is_some()) {
+        (true, true) => "↑/↓ PgUp/PgDn scroll, 'd' full output, Enter/Esc to continue",
+        (true, false) => "↑/↓ PgUp/PgDn scroll, Enter/Esc to continue",
+        (false, true) => "'d' for the full output, Enter or Esc to continue",
+        (false, false) => "Press Enter or Esc to continue",
+    };
     let help = Paragraph::new(help_text)
         .style(Style::default().fg(Color::Gray))
-        .block(Block::default().borders(Borders::ALL).title("Help"));
+        .alignment(ratatui::layout::Alignment::Center);
 
-    let layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(3),
-            Constraint::Length(6),
-            Constraint::Min(6),
-            Constraint::Length(5),
-            Constraint::Length(3),
-        ])
-        .split(area);
+    f.render_widget(help, help_area);
+}
 
-    f.render_widget(title, layout[0]);
-    f.render_widget(info, layout[1]);
-    f.render_widget(projects_widget, layout[2]);
-    f.render_widget(preview, layout[3]);
-    f.render_widget(help, layout[4]);
+// "Preview (3-12 of 40)" while the text is longer than its box. Call after
+// `TextScroll::offset` so the range matches what is drawn.
+fn scroll_title(title: &str, scroll: &TextScroll, lines: usize) -> String {
+    if !scroll.overflows(lines) {
+        return title.to_string();
+    }
+    let shown = scroll.visible(lines);
+    format!("{} ({}-{} of {})", title, shown.start + 1, shown.end, lines)
 }
 
-fn render_view_waveform(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
-    let title = Paragraph::new("📊 View Waveform with External Viewer")
-        .style(Style::default().fg(PALETTE.macchiato.colors.mauve.into()).add_modifier(Modifier::BOLD))
-        .block(Block::default().borders(Borders::ALL));
+// Rows `text` takes up when word-wrapped to `width` columns, the way
+// `Wrap { trim: true }` lays it out
+fn wrapped_line_count(text: &str, width: u16) -> usize {
+    let width = width.max(1) as usize;
+    text.lines()
+        .map(|line| {
+            let mut rows = 1;
+            let mut column = 0;
+            for word in line.split_whitespace() {
+                let len = word.chars().count();
+                if column > 0 && column + 1 + len > width {
+                    rows += 1;
+                    column = 0;
+                }
+                column = if column == 0 { len } else { column + 1 + len };
+                while column > width {
+                    rows += 1;
+                    column -= width;
+                }
+            }
+            rows
+        })
+        .sum()
+}
 
-    let current_dir = std::env::current_dir()
-        .map(|p| p.display().to_string())
-        .unwrap_or_else(|_| "Unknown".to_string());
+fn render_confirm_dialog(f: &mut Frame, app: &App) {
+    let Some(action) = &app.confirm_action else {
+        return;
+    };
 
-    let info_text = vec![
-        Line::from(vec![
-            Span::raw("Current Directory: "),
-            Span::styled(current_dir, Style::default().fg(PALETTE.macchiato.colors.yellow.into())),
-        ]),
+    let area = f.area();
+    let popup_area = popup_area(area, area.width / 2, area.height / 3);
+
+    f.render_widget(Clear, popup_area);
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("{} {}", app.icon(Icon::Missing), action.warning()),
+            Style::default().fg(app.colors().red.into()).add_modifier(Modifier::BOLD),
+        )),
         Line::from(""),
-        Line::from(format!("Found {} VCD file(s):", app.vcd_files.len())),
     ];
+    lines.extend(action.prompt().lines().map(|line| Line::from(line.to_string())));
 
-    let info = Paragraph::new(info_text)
-        .block(Block::default().borders(Borders::ALL).title("VCD Info"));
+    let prompt = Paragraph::new(lines)
+    .wrap(Wrap { trim: true })
+    .block(
+        Block::default()
+            .title(action.title())
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::Black)),
+    );
 
-    // VCD files list
-    let vcd_widget = if !app.vcd_files.is_empty() {
-        let vcd_items: Vec<ListItem> = app.vcd_files
-            .iter()
-            .enumerate()
-            .map(|(i, vcd_path)| {
-                let file_name = vcd_path
-                    .file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string();
-
-                let parent_dir = vcd_path
-                    .parent()
-                    .and_then(|p| p.file_name())
-                    .and_then(|n| n.to_str())
-                    .unwrap_or(".");
-
-                let style = if i == app.selected_vcd_index {
-                    Style::default().bg(PALETTE.macchiato.colors.yellow.into()).fg(Color::Black)
-                } else {
-                    Style::default()
-                };
+    f.render_widget(prompt, popup_area);
 
-                let display_text = if parent_dir == "." {
-                    format!("📄 {}", file_name)
-                } else {
-                    format!("📄 {}/{}", parent_dir, file_name)
-                };
+    let help_area = popup_help_area(popup_area);
 
-                ListItem::new(display_text).style(style)
-            })
-            .collect();
+    let help = Paragraph::new("y/Enter to continue, n/Esc to cancel")
+        .style(Style::default().fg(Color::Gray))
+        .alignment(ratatui::layout::Alignment::Center);
 
-        List::new(vcd_items)
-            .block(Block::default().title("VCD Files").borders(Borders::ALL))
-            .highlight_style(Style::default().bg(PALETTE.macchiato.colors.yellow.into()).fg(Color::Black))
-    } else {
-        List::new(vec![ListItem::new("No VCD files found. Run a simulation first!")])
-            .block(Block::default().title("VCD Files").borders(Borders::ALL))
-            .style(Style::default().fg(Color::Gray))
+    f.render_widget(help, help_area);
+}
+
+fn render_pick_dialog(f: &mut Frame, app: &App) {
+    let Some(action) = &app.pick_action else {
+        return;
     };
 
-    // Viewer info
-    let viewer_info = vec![
-        Line::from("Supported Waveform Viewers:"),
-        Line::from(""),
-        Line::from(vec![
-            Span::styled("📊 DWFV", Style::default().fg(PALETTE.macchiato.colors.green.into()).add_modifier(Modifier::BOLD)),
-            Span::raw(" - Vi-like TUI waveform viewer (Recommended)"),
-        ]),
-        Line::from(vec![
-            Span::styled("⚡ DigiSurf", Style::default().fg(PALETTE.macchiato.colors.blue.into()).add_modifier(Modifier::BOLD)),
-            Span::raw(" - Modern TUI with command interface"),
-        ]),
-        Line::from(vec![
-            Span::styled("🖥️  GTKWave", Style::default().fg(PALETTE.macchiato.colors.yellow.into()).add_modifier(Modifier::BOLD)),
-            Span::raw(" - Traditional GUI waveform viewer"),
-        ]),
-    ];
+    let area = f.area();
+    let popup_area = popup_area(area, area.width / 2, app.pick_choices.len() as u16 + 3);
 
-    let viewer_widget = Paragraph::new(viewer_info)
-        .block(Block::default().borders(Borders::ALL).title("Viewer Options"));
+    f.render_widget(Clear, popup_area);
 
-    let help_text = if !app.vcd_files.is_empty() {
-        "↑/↓: Select VCD file | Enter: Launch viewer | 'r': Refresh | 'i': Install info | Esc: Return"
-    } else {
-        "'r': Refresh files | 'i': Install viewer info | Esc: Return to main menu"
-    };
+    let items: Vec<ListItem> = app.pick_choices
+        .iter()
+        .enumerate()
+        .map(|(i, choice)| {
+            let style = if i == app.selected_pick_index {
+                Style::default().bg(app.colors().yellow.into()).fg(Color::Black)
+            } else {
+                Style::default()
+            };
+            let (icon, label) = match (action, choice) {
+                (PickAction::Recipe { project, .. }, Some(recipe)) => {
+                    (Icon::Justfile, format!("{} {}", BuildSystem::detect(project).unwrap_or_default().program(), recipe))
+                }
+                (PickAction::Recipe { .. }, None) => (Icon::Justfile, String::new()),
+                (PickAction::Viewer, Some(viewer)) if app.config.viewer.as_ref() == Some(viewer) => {
+                    (Icon::Chart, format!("{} (default)", viewer))
+                }
+                (PickAction::Viewer, Some(viewer)) => (Icon::Chart, viewer.clone()),
+                (PickAction::Viewer, None) => (Icon::Chart, "Just open it (first viewer found)".to_string()),
+                (PickAction::Testbench { project }, Some(testbench)) => {
+                    let vcd = testbench::vcd_name(project, testbench).unwrap_or_else(|| "no $dumpfile".to_string());
+                    (Icon::Test, format!("{} → {}", testbench, vcd))
+                }
+                (PickAction::Testbench { .. }, None) => (Icon::Test, String::new()),
+                (PickAction::CompareVcd { .. }, Some(path)) => (Icon::File, vcd_label(Path::new(path))),
+                (PickAction::CompareVcd { .. }, None) => (Icon::File, String::new()),
+                (PickAction::AddFile { project }, Some(label)) => match FileTemplate::parse(label) {
+                    Some(template) => (Icon::File, format!("{} ({})", template.file_name(&project_label(project)), template.description())),
+                    None => (Icon::File, label.clone()),
+                },
+                (PickAction::AddFile { .. }, None) => (Icon::File, String::new()),
+                (PickAction::Archive { project }, Some(extension)) => (Icon::File, format!("{}.{}", project_label(project), extension)),
+                (PickAction::Archive { .. }, None) => (Icon::File, String::new()),
+            };
+            ListItem::new(format!(" {} {}", app.icon(icon), label)).style(style)
+        })
+        .collect();
 
-    let help = Paragraph::new(help_text)
-        .style(Style::default().fg(Color::Gray))
-        .block(Block::default().borders(Borders::ALL).title("Controls"));
+    let list = List::new(items).block(
+        Block::default()
+            .title(action.title())
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::Black)),
+    );
+    f.render_widget(list, popup_area);
 
-    let layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(3),  // Title
-            Constraint::Length(6),  // Info
-            Constraint::Min(8),     // VCD files
-            Constraint::Length(8),  // Viewer options
-            Constraint::Length(3),  // Help
-        ])
-        .split(area);
+    let help_area = popup_help_area(popup_area);
 
-    f.render_widget(title, layout[0]);
-    f.render_widget(info, layout[1]);
-    f.render_widget(vcd_widget, layout[2]);
-    f.render_widget(viewer_widget, layout[3]);
-    f.render_widget(help, layout[4]);
+    let help = Paragraph::new(action.help())
+        .style(Style::default().fg(Color::Gray))
+        .alignment(ratatui::layout::Alignment::Center);
+
+    f.render_widget(help, help_area);
 }
 
-fn render_message_dialog(f: &mut Frame, app: &App) {
-    let area = f.area();
-    let popup_area = ratatui::layout::Rect {
-        x: area.width / 4,
-        y: area.height / 3,
-        width: area.width / 2,
-        height: area.height / 3,
+fn render_input_dialog(f: &mut Frame, app: &App) {
+    let Some(action) = &app.input_action else {
+        return;
     };
 
+    let check = action.check(&app.input_buffer);
+
+    let area = f.area();
+    let popup_area = popup_area(area, area.width / 2, if check.is_some() { 10 } else { 7 });
+
     f.render_widget(Clear, popup_area);
 
-    let message = Paragraph::new(app.message.as_str())
+    let mut text = vec![
+        Line::from(action.prompt()),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("{}_", app.input_buffer),
+            Style::default().fg(app.colors().yellow.into()),
+        )),
+    ];
+    match check {
+        Some(Ok(summary)) => {
+            text.push(Line::from(""));
+            text.push(Line::from(Span::styled(summary, Style::default().fg(app.colors().green.into()))));
+        }
+        Some(Err(error)) => {
+            text.push(Line::from(""));
+            text.push(Line::from(Span::styled(error, Style::default().fg(app.colors().red.into()))));
+        }
+        None => {}
+    }
+
+    let input = Paragraph::new(text)
         .wrap(Wrap { trim: true })
         .block(
             Block::default()
-                .title("Message")
+                .title(action.title())
                 .borders(Borders::ALL)
                 .style(Style::default().bg(Color::Black)),
         );
 
-    f.render_widget(message, popup_area);
+    f.render_widget(input, popup_area);
 
-    let help_area = ratatui::layout::Rect {
-        x: popup_area.x,
-        y: popup_area.y + popup_area.height - 1,
-        width: popup_area.width,
-        height: 1,
-    };
+    let help_area = popup_help_area(popup_area);
 
-    let help = Paragraph::new("Press Enter or Esc to continue")
+    let help = Paragraph::new("Enter to confirm, Esc to cancel")
         .style(Style::default().fg(Color::Gray))
         .alignment(ratatui::layout::Alignment::Center);
 
@@ -931,14 +5180,39 @@ fn render_message_dialog(f: &mut Frame, app: &App) {
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // A directory starts the TUI working there; any other arguments select
+    // a command that runs without it
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let base_dir = cli::tui_directory(&args);
+    if !args.is_empty() && base_dir.is_none() {
+        let config = Config::load();
+        if let Some(reason) = &config.load_error {
+            eprintln!("hadou: {}; using the default settings", reason);
+        }
+        std::process::exit(cli::run(&args, &config));
+    }
+    if let Some(dir) = &base_dir
+        && let Err(e) = std::env::set_current_dir(dir)
+    {
+        eprintln!("hadou: {}: {}", dir.display(), e);
+        std::process::exit(2);
+    }
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
 
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new();
+    let mut config = Config::load();
+    let flavor = theme::resolve_flavor(&mut config);
+
+    let mut app = App::new(config, flavor);
+    if base_dir.is_some() {
+        // Only lost from the recent directories if the config can't be saved
+        let _ = app.remember_base_dir();
+    }
     let res = run_app(&mut terminal, &mut app);
 
     disable_raw_mode()?;
@@ -946,7 +5220,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
@@ -957,6 +5232,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+// Leave the alternate screen and raw mode while `f` runs (e.g. a terminal
+// editor), then restore them and force a full redraw
+fn run_with_terminal_suspended<B: Backend, T>(
+    terminal: &mut Terminal<B>,
+    f: impl FnOnce() -> T
+) -> io::Result<T> {
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, DisableBracketedPaste)?;
+    terminal.show_cursor()?;
+
+    let result = f();
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
+    terminal.clear()?;
+
+    Ok(result)
+}
+
 fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App
@@ -964,12 +5258,32 @@ fn run_app<B: Backend>(
     loop {
         terminal.draw(|f| ui(f, app))?;
 
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press {
-                app.on_key(key.code);
+        // Poll with a timeout so running compilations keep updating the screen
+        if event::poll(EVENT_POLL_INTERVAL)? {
+            match event::read()? {
+                Event::Key(key)
+                    if key.kind == KeyEventKind::Press
+                        && key.code == KeyCode::Char('p')
+                        && key.modifiers.contains(KeyModifiers::CONTROL) =>
+                {
+                    app.open_command_palette()
+                }
+                Event::Key(key) if key.kind == KeyEventKind::Press => app.on_key(key.code),
+                Event::Paste(text) => app.on_paste(&text),
+                // Repaint every cell at the new size on the next pass instead
+                // of diffing against the old buffer, which leaves stray cells
+                Event::Resize(_, _) => terminal.clear()?,
+                _ => {}
             }
         }
 
+        if let Some(launch) = app.pending_editor.take() {
+            let result = run_with_terminal_suspended(terminal, || launch.run())?;
+            app.finish_editor_launch(result);
+        }
+
+        app.tick();
+
         if app.should_quit {
             return Ok(());
         }
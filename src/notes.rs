@@ -0,0 +1,70 @@
+use chrono::Local;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::edit_project::project_label;
+use crate::error::HadouError;
+
+pub const NOTES_FILE: &str = ".hadou-notes.md";
+
+pub fn notes_path(project: &Path) -> PathBuf {
+    project.join(NOTES_FILE)
+}
+
+// The project's notes, or None when it has none worth showing
+pub fn load(project: &Path) -> Option<String> {
+    fs::read_to_string(notes_path(project))
+        .ok()
+        .map(|notes| notes.trim_end().to_string())
+        .filter(|notes| !notes.trim().is_empty())
+}
+
+// Add `note` as a timestamped bullet at the end of the project's notes,
+// starting the file with a heading the first time
+pub fn append(project: &Path, note: &str) -> Result<PathBuf, HadouError> {
+    let note = note.trim();
+    if note.is_empty() {
+        return Err(HadouError::Other("The note is empty".to_string()));
+    }
+
+    let path = notes_path(project);
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+    let mut entry = String::new();
+    if existing.trim().is_empty() {
+        entry.push_str(&format!("# Notes for {}\n\n", project_label(project)));
+    } else if !existing.ends_with('\n') {
+        entry.push('\n');
+    }
+    entry.push_str(&format!("- {} {}\n", Local::now().format("%Y-%m-%d %H:%M"), note));
+    file.write_all(entry.as_bytes())?;
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notes_are_appended_under_a_heading() {
+        let dir = std::env::temp_dir().join(format!("hadou-notes-{}", std::process::id())).join("alu");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        assert_eq!(load(&dir), None);
+        assert!(append(&dir, "   ").is_err());
+        append(&dir, "carry is wrong for a=255").unwrap();
+        append(&dir, " pin 12 is the reset ").unwrap();
+
+        let notes = load(&dir).unwrap();
+        let lines: Vec<&str> = notes.lines().collect();
+        assert_eq!(lines[0], "# Notes for alu");
+        assert_eq!(lines.len(), 4);
+        assert!(lines[2].starts_with("- ") && lines[2].ends_with(" carry is wrong for a=255"));
+        assert!(lines[3].ends_with(" pin 12 is the reset"));
+        fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,181 @@
+// The ':' command palette: the main actions of every screen in one list,
+// searched the same way as the project lists, so none of them needs its
+// screen's key remembered
+
+use crate::compile_project::CompileAction;
+use crate::fuzzy::{FuzzyMatch, ListSearch};
+use crate::keymap::{Action, Keymap};
+use crate::list_window::ListWindow;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    CreateProject,
+    QuickCreate,
+    AdoptDirectory,
+    EditProjects,
+    OpenInEditor,
+    CompileProjects,
+    // Picks the action on the compile screen and runs it on the selected
+    // project straight away
+    Run(CompileAction),
+    RerunLast,
+    ViewWaveforms,
+    ChangeDirectory,
+    Settings,
+    Refresh,
+    ShowKeys,
+    Quit,
+}
+
+impl Command {
+    pub fn all() -> Vec<Command> {
+        vec![
+            Command::CreateProject,
+            Command::QuickCreate,
+            Command::AdoptDirectory,
+            Command::EditProjects,
+            Command::OpenInEditor,
+            Command::CompileProjects,
+            Command::Run(CompileAction::CompileOnly),
+            Command::Run(CompileAction::CompileAndSimulate),
+            Command::Run(CompileAction::CompileSimulateAndView),
+            Command::Run(CompileAction::CompileSimulateAndInspect),
+            Command::Run(CompileAction::Clean),
+            Command::RerunLast,
+            Command::ViewWaveforms,
+            Command::ChangeDirectory,
+            Command::Settings,
+            Command::Refresh,
+            Command::ShowKeys,
+            Command::Quit,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::CreateProject => "Create a new project",
+            Command::QuickCreate => "Quick-create a project from a spec",
+            Command::AdoptDirectory => "Adopt the current directory as a project",
+            Command::EditProjects => "Edit projects",
+            Command::OpenInEditor => "Open the selected project in the editor",
+            Command::CompileProjects => "Compile projects",
+            Command::Run(CompileAction::CompileOnly) => "Compile the selected project",
+            Command::Run(CompileAction::CompileAndSimulate) => "Simulate the selected project",
+            Command::Run(CompileAction::CompileSimulateAndView) => "Simulate the selected project and open the waveform viewer",
+            Command::Run(CompileAction::CompileSimulateAndInspect) => "Simulate the selected project into the built-in viewer",
+            Command::Run(CompileAction::Clean) => "Clean the selected project's build outputs",
+            Command::Run(CompileAction::Info) => "Show the selected project's build info",
+            Command::Run(CompileAction::Custom(_)) => "Run a recipe on the selected project",
+            Command::RerunLast => "Re-run the last compilation",
+            Command::ViewWaveforms => "View waveforms",
+            Command::ChangeDirectory => "Change directory",
+            Command::Settings => "Settings",
+            Command::Refresh => "Refresh projects, VCD files and tools",
+            Command::ShowKeys => "Show every key of the current screen",
+            Command::Quit => "Quit Hadou",
+        }
+    }
+
+    // Where the command is also bound, shown next to it as a reminder. The
+    // shared keys are read from `keymap` so rebound ones show up; F5, '?'
+    // and the main menu letters can't be rebound.
+    pub fn keys(&self, keymap: &Keymap) -> String {
+        match self {
+            Command::CreateProject | Command::EditProjects | Command::CompileProjects => "main menu".to_string(),
+            Command::ViewWaveforms | Command::ChangeDirectory | Command::Settings => "main menu".to_string(),
+            Command::QuickCreate => "'n' on the main menu".to_string(),
+            Command::AdoptDirectory => "'a' on the main menu".to_string(),
+            Command::OpenInEditor => format!("{} on the edit screen", keymap.keys_for(Action::Select)),
+            Command::Run(_) => format!("{} on the compile screen", keymap.keys_for(Action::Select)),
+            Command::RerunLast => "F5".to_string(),
+            Command::Refresh => keymap.keys_for(Action::Refresh),
+            Command::ShowKeys => "'?'".to_string(),
+            Command::Quit => format!("{} on the main menu", keymap.keys_for(Action::Quit)),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CommandPalette {
+    pub search: ListSearch,
+    // Position in `matches()`, not in `Command::all()`
+    pub selected: usize,
+    pub window: ListWindow,
+}
+
+impl CommandPalette {
+    pub fn open(&mut self) {
+        self.search.query.clear();
+        self.search.start();
+        self.selected = 0;
+    }
+
+    // Commands matching the query, best match first
+    pub fn matches(&self) -> Vec<(Command, FuzzyMatch)> {
+        let commands = Command::all();
+        self.search
+            .filter(commands.iter().map(Command::name))
+            .into_iter()
+            .map(|(i, found)| (commands[i].clone(), found))
+            .collect()
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.search.query.push(c);
+        self.selected = 0;
+    }
+
+    pub fn pop(&mut self) {
+        self.search.query.pop();
+        self.selected = 0;
+    }
+
+    pub fn move_selection(&mut self, forward: bool) {
+        let count = self.matches().len();
+        if count == 0 {
+            return;
+        }
+        self.selected = if forward {
+            (self.selected + 1) % count
+        } else {
+            (self.selected + count - 1) % count
+        };
+    }
+
+    pub fn selected_command(&self) -> Option<Command> {
+        self.matches().into_iter().nth(self.selected).map(|(command, _)| command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typing_narrows_the_commands() {
+        let mut palette = CommandPalette::default();
+        palette.open();
+        assert_eq!(palette.matches().len(), Command::all().len());
+        assert_eq!(palette.selected_command(), Some(Command::CreateProject));
+
+        for c in "sim".chars() {
+            palette.push(c);
+        }
+        assert_eq!(palette.selected_command(), Some(Command::Run(CompileAction::CompileAndSimulate)));
+        palette.move_selection(true);
+        assert!(matches!(palette.selected_command(), Some(Command::Run(_))));
+        palette.move_selection(false);
+        palette.move_selection(false);
+        assert_eq!(palette.selected, palette.matches().len() - 1);
+
+        let mut keys = std::collections::HashMap::new();
+        keys.insert("refresh".to_string(), vec!["F6".to_string()]);
+        let keymap = Keymap::from_config(&keys).unwrap();
+        assert_eq!(Command::Refresh.keys(&keymap), "F6");
+        assert_eq!(Command::Quit.keys(&Keymap::default()), "'q' on the main menu");
+
+        palette.push('q');
+        assert!(palette.matches().is_empty());
+        assert_eq!(palette.selected_command(), None);
+    }
+}
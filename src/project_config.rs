@@ -0,0 +1,330 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::build_system::{self, BuildSystem};
+use crate::error::HadouError;
+use crate::safe_write;
+use crate::scanner::{self, ScanOptions};
+
+pub const PROJECT_CONFIG_FILE: &str = ".hadou.toml";
+
+// Generated Justfiles pass this variable's value to iverilog ahead of the sources
+pub const IVERILOG_FLAGS_VAR: &str = "HADOU_IVERILOG_FLAGS";
+
+// Line of a generated Justfile holding the flags used when the variable is unset
+const JUSTFILE_FLAGS_PREFIX: &str = "IVERILOG_FLAGS := ";
+
+// A generated Makefile's equivalent, which the environment variable overrides
+const MAKEFILE_FLAGS_PREFIX: &str = "HADOU_IVERILOG_FLAGS ?=";
+
+// Generated build files compile the files (or globs) in this variable's
+// value after main.v
+pub const EXTRA_SOURCES_VAR: &str = "HADOU_EXTRA_SOURCES";
+
+const JUSTFILE_SOURCES_PREFIX: &str = "EXTRA_SOURCES := ";
+
+const MAKEFILE_SOURCES_PREFIX: &str = "HADOU_EXTRA_SOURCES ?=";
+
+// Deepest directory below the project a source pattern is matched in
+const MAX_SOURCE_DEPTH: usize = 16;
+
+// Settings kept in the project's own .hadou.toml so they travel with it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    // Extra iverilog arguments such as -g2012, -Wall, -Iinclude or -DSIM
+    pub iverilog_flags: Vec<String>,
+    // Further sources compiled with main.v, relative to the project: files
+    // or globs such as rtl/*.v
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+    // Directories `include files are searched in, passed to iverilog as -I
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include_dirs: Vec<String>,
+    // Set by adopting a directory of loose sources, which lists the directory
+    // Hadou was started in as a project of its own
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub adopted: bool,
+    // Testbench simulated instead of main_test.v, picked on the compile screen
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub testbench: Option<String>,
+}
+
+impl ProjectConfig {
+    pub fn path(project_path: &Path) -> PathBuf {
+        project_path.join(PROJECT_CONFIG_FILE)
+    }
+
+    // A project without a .hadou.toml uses the defaults
+    pub fn load(project_path: &Path) -> Result<Self, HadouError> {
+        let path = Self::path(project_path);
+        match fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content).map_err(|e| HadouError::Other(format!("{}: {}", path.display(), e))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, project_path: &Path) -> Result<Option<PathBuf>, HadouError> {
+        safe_write::write_with_backup(&Self::path(project_path), &toml::to_string_pretty(self)?)
+    }
+
+    pub fn flags(&self) -> String {
+        self.iverilog_flags.join(" ")
+    }
+
+    // Everything passed to iverilog ahead of the sources: -I for each
+    // include directory, then the flags
+    pub fn compiler_flag_list(&self) -> Vec<String> {
+        self.include_dirs
+            .iter()
+            .map(|dir| format!("-I{}", dir))
+            .chain(self.iverilog_flags.iter().cloned())
+            .collect()
+    }
+
+    pub fn compiler_flags(&self) -> String {
+        self.compiler_flag_list().join(" ")
+    }
+
+    // The sources and include directories as typed into the compile
+    // screen's dialog, e.g. `rtl/*.v -I include`
+    pub fn sources_input(&self) -> String {
+        self.sources
+            .iter()
+            .cloned()
+            .chain(self.include_dirs.iter().map(|dir| format!("-I {}", dir)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    // Files below the project the extra source patterns match
+    pub fn extra_source_files(&self, project_path: &Path) -> Vec<PathBuf> {
+        if self.sources.is_empty() {
+            return Vec::new();
+        }
+        let patterns: Vec<&str> = self.sources.iter().map(|source| source.trim_start_matches("./")).collect();
+        let options = ScanOptions { max_depth: MAX_SOURCE_DEPTH, ..ScanOptions::default() };
+        options.find_files(project_path, |path| {
+            let Ok(relative) = path.strip_prefix(project_path) else {
+                return false;
+            };
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            patterns.iter().any(|pattern| scanner::glob_match(pattern, &relative))
+        })
+    }
+}
+
+pub fn parse_flags(input: &str) -> Vec<String> {
+    input.split_whitespace().map(str::to_string).collect()
+}
+
+// Source patterns and `-I dir` (or `-Idir`) include directories, in the
+// form `sources_input` writes them
+pub fn parse_sources(input: &str) -> (Vec<String>, Vec<String>) {
+    let mut sources = Vec::new();
+    let mut include_dirs = Vec::new();
+    let mut words = input.split_whitespace();
+    while let Some(word) = words.next() {
+        match word.strip_prefix("-I") {
+            Some("") => include_dirs.extend(words.next().map(str::to_string)),
+            Some(dir) => include_dirs.push(dir.to_string()),
+            None => sources.push(word.to_string()),
+        }
+    }
+    (sources, include_dirs)
+}
+
+pub fn justfile_sources_line(sources: &str) -> String {
+    format!("{}env_var_or_default(\"{}\", {})", JUSTFILE_SOURCES_PREFIX, EXTRA_SOURCES_VAR, just_string(sources))
+}
+
+pub fn makefile_sources_line(sources: &str) -> String {
+    if sources.is_empty() {
+        MAKEFILE_SOURCES_PREFIX.to_string()
+    } else {
+        format!("{} {}", MAKEFILE_SOURCES_PREFIX, sources)
+    }
+}
+
+pub fn makefile_flags_line(flags: &str) -> String {
+    if flags.is_empty() {
+        MAKEFILE_FLAGS_PREFIX.to_string()
+    } else {
+        format!("{} {}", MAKEFILE_FLAGS_PREFIX, flags)
+    }
+}
+
+// Quote `value` as a just string literal
+pub fn just_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// Outcome of changing a project's iverilog flags or sources
+#[derive(Debug, Default)]
+pub struct FlagsUpdate {
+    // False when the Justfile or Makefile doesn't read the settings (a
+    // custom one, or generated before they existed), which then only sees
+    // them through HADOU_IVERILOG_FLAGS and HADOU_EXTRA_SOURCES
+    pub build_file_updated: bool,
+    // Previous versions of the files that were rewritten
+    pub backups: Vec<PathBuf>,
+}
+
+// Save the flags and write them into the project's Justfile or Makefile when
+// Hadou generated it
+pub fn set_iverilog_flags(project_path: &Path, flags: Vec<String>) -> Result<FlagsUpdate, HadouError> {
+    let mut config = ProjectConfig::load(project_path)?;
+    config.iverilog_flags = flags;
+    save_to_build_file(project_path, &config)
+}
+
+// Save the extra sources and include directories, and write them into the
+// project's Justfile or Makefile when Hadou generated it
+pub fn set_sources(project_path: &Path, sources: Vec<String>, include_dirs: Vec<String>) -> Result<FlagsUpdate, HadouError> {
+    let mut config = ProjectConfig::load(project_path)?;
+    config.sources = sources;
+    config.include_dirs = include_dirs;
+    save_to_build_file(project_path, &config)
+}
+
+fn save_to_build_file(project_path: &Path, config: &ProjectConfig) -> Result<FlagsUpdate, HadouError> {
+    let mut update = FlagsUpdate {
+        backups: config.save(project_path)?.into_iter().collect(),
+        ..FlagsUpdate::default()
+    };
+
+    let Some((system, build_file)) = build_system::find_build_file(project_path) else {
+        return Ok(update);
+    };
+    let content = fs::read_to_string(&build_file)?;
+    let Some(updated) = with_build_settings(&content, system, config) else {
+        return Ok(update);
+    };
+
+    update.build_file_updated = config.sources.is_empty() || updated.contains(EXTRA_SOURCES_VAR);
+    if updated != content {
+        update.backups.extend(safe_write::write_with_backup(&build_file, &updated)?);
+    }
+    Ok(update)
+}
+
+// A generated build file with its defaults for the flags and extra sources
+// set from `config`, or None for a build file that doesn't read the flags
+pub fn with_build_settings(content: &str, system: BuildSystem, config: &ProjectConfig) -> Option<String> {
+    let flags = config.compiler_flags();
+    let sources = config.sources.join(" ");
+    let (flags_prefix, flags_line, sources_prefix, sources_line) = match system {
+        BuildSystem::Just => {
+            if !content.contains(&format!("\"{}\"", IVERILOG_FLAGS_VAR)) {
+                return None;
+            }
+            (
+                JUSTFILE_FLAGS_PREFIX,
+                format!("{}env_var_or_default(\"{}\", {})", JUSTFILE_FLAGS_PREFIX, IVERILOG_FLAGS_VAR, just_string(&flags)),
+                JUSTFILE_SOURCES_PREFIX,
+                justfile_sources_line(&sources),
+            )
+        }
+        BuildSystem::Make => (MAKEFILE_FLAGS_PREFIX, makefile_flags_line(&flags), MAKEFILE_SOURCES_PREFIX, makefile_sources_line(&sources)),
+    };
+
+    let mut lines: Vec<&str> = content.lines().collect();
+    let flags_index = lines.iter().position(|line| line.starts_with(flags_prefix))?;
+    lines[flags_index] = &flags_line;
+    if let Some(line) = lines.iter_mut().find(|line| line.starts_with(sources_prefix)) {
+        *line = &sources_line;
+    }
+
+    let mut updated = lines.join("\n");
+    if content.ends_with('\n') {
+        updated.push('\n');
+    }
+    Some(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("hadou-project-config-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn missing_file_means_no_flags() {
+        let dir = project("missing");
+        assert_eq!(ProjectConfig::load(&dir).unwrap(), ProjectConfig::default());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn generated_justfile_gets_the_flags() {
+        let dir = project("generated");
+        fs::write(dir.join("Justfile"), crate::create_new_project::generate_justfile("demo", "iverilog", "")).unwrap();
+
+        let update = set_iverilog_flags(&dir, parse_flags(" -g2012  -Wall -DNAME=\"x\" ")).unwrap();
+        assert!(update.build_file_updated);
+        assert_eq!(update.backups.len(), 1);
+
+        let justfile = fs::read_to_string(dir.join("Justfile")).unwrap();
+        assert!(justfile.contains(r#"IVERILOG_FLAGS := env_var_or_default("HADOU_IVERILOG_FLAGS", "-g2012 -Wall -DNAME=\"x\"")"#));
+        assert_eq!(ProjectConfig::load(&dir).unwrap().iverilog_flags, vec!["-g2012", "-Wall", "-DNAME=\"x\""]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn generated_makefile_gets_the_flags() {
+        let dir = project("makefile");
+        fs::write(dir.join("Makefile"), crate::create_new_project::generate_makefile("demo", "iverilog", "-Wall")).unwrap();
+
+        assert!(set_iverilog_flags(&dir, parse_flags("-g2012")).unwrap().build_file_updated);
+        let makefile = fs::read_to_string(dir.join("Makefile")).unwrap();
+        assert!(makefile.contains("\nHADOU_IVERILOG_FLAGS ?= -g2012\n"));
+        assert!(makefile.contains("\tiverilog $(HADOU_IVERILOG_FLAGS) -o"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn extra_sources_and_include_dirs_reach_the_justfile() {
+        let dir = project("sources");
+        fs::create_dir_all(dir.join("rtl/sub")).unwrap();
+        fs::write(dir.join("rtl/fifo.v"), "module fifo; endmodule\n").unwrap();
+        fs::write(dir.join("rtl/sub/uart.v"), "module uart; endmodule\n").unwrap();
+        fs::write(dir.join("rtl/notes.txt"), "not a source\n").unwrap();
+        fs::write(dir.join("Justfile"), crate::create_new_project::generate_justfile("demo", "iverilog", "")).unwrap();
+        set_iverilog_flags(&dir, vec!["-g2012".to_string()]).unwrap();
+
+        let (sources, include_dirs) = parse_sources("rtl/*.v ./rtl/sub/uart.v -I include -Icommon");
+        assert_eq!(include_dirs, vec!["include", "common"]);
+        assert!(set_sources(&dir, sources, include_dirs).unwrap().build_file_updated);
+
+        let config = ProjectConfig::load(&dir).unwrap();
+        assert_eq!(config.compiler_flags(), "-Iinclude -Icommon -g2012");
+        assert_eq!(config.sources_input(), "rtl/*.v ./rtl/sub/uart.v -I include -I common");
+        assert_eq!(config.extra_source_files(&dir), vec![dir.join("rtl/fifo.v"), dir.join("rtl/sub/uart.v")]);
+
+        let justfile = fs::read_to_string(dir.join("Justfile")).unwrap();
+        assert!(justfile.contains(r#"IVERILOG_FLAGS := env_var_or_default("HADOU_IVERILOG_FLAGS", "-Iinclude -Icommon -g2012")"#));
+        assert!(justfile.contains(r#"EXTRA_SOURCES := env_var_or_default("HADOU_EXTRA_SOURCES", "rtl/*.v ./rtl/sub/uart.v")"#));
+        assert!(justfile.contains("{{SRC_FILE}} {{EXTRA_SOURCES}} {{TEST_FILE}}"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn custom_justfile_is_left_alone() {
+        let dir = project("custom");
+        let custom = "compile:\n    iverilog -o out.vvp main.v\n";
+        fs::write(dir.join("Justfile"), custom).unwrap();
+
+        assert!(!set_iverilog_flags(&dir, vec!["-g2012".to_string()]).unwrap().build_file_updated);
+        assert_eq!(fs::read_to_string(dir.join("Justfile")).unwrap(), custom);
+        assert!(dir.join(PROJECT_CONFIG_FILE).exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,134 @@
+use chrono::Local;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::error::HadouError;
+
+// Backups older than the newest few of a file are removed as new ones are made
+pub const KEPT_BACKUPS: usize = 5;
+
+// Write `content` to `path`, first copying whatever is there to
+// `<name>.bak.<timestamp>`. Returns the backup when one was made. Leaves the
+// file untouched when it already holds `content`.
+pub fn write_with_backup(path: &Path, content: &str) -> Result<Option<PathBuf>, HadouError> {
+    let existing = match fs::read(path) {
+        Ok(existing) => existing,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            fs::write(path, content)?;
+            return Ok(None);
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if existing == content.as_bytes() {
+        return Ok(None);
+    }
+
+    let backup = backup_path(path)?;
+    fs::write(&backup, &existing)?;
+    fs::write(path, content)?;
+    prune_backups(path);
+    Ok(Some(backup))
+}
+
+fn file_name(path: &Path) -> Result<String, HadouError> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string)
+        .ok_or_else(|| HadouError::Other(format!("Invalid file name: {}", path.display())))
+}
+
+// A name no other backup has, even when two are made within the same second
+fn backup_path(path: &Path) -> Result<PathBuf, HadouError> {
+    let stamp = Local::now().format("%Y%m%d-%H%M%S");
+    let name = file_name(path)?;
+    let mut candidate = path.with_file_name(format!("{}.bak.{}", name, stamp));
+    let mut counter = 1;
+    while candidate.exists() {
+        candidate = path.with_file_name(format!("{}.bak.{}-{}", name, stamp, counter));
+        counter += 1;
+    }
+    Ok(candidate)
+}
+
+// Backups of `path`, oldest first
+pub fn backups(path: &Path) -> Vec<PathBuf> {
+    let (Ok(name), Some(dir)) = (file_name(path), path.parent()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.bak.", name);
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut found: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_str().is_some_and(|file| file.starts_with(&prefix)))
+        .map(|entry| {
+            let modified = entry.metadata().and_then(|meta| meta.modified()).unwrap_or(std::time::UNIX_EPOCH);
+            (modified, entry.path())
+        })
+        .collect();
+    found.sort();
+    found.into_iter().map(|(_, path)| path).collect()
+}
+
+// Failing to remove an old backup is not worth failing the write over
+fn prune_backups(path: &Path) {
+    let backups = backups(path);
+    let excess = backups.len().saturating_sub(KEPT_BACKUPS);
+    for old in &backups[..excess] {
+        let _ = fs::remove_file(old);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("hadou-safe-write-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn new_and_unchanged_files_get_no_backup() {
+        let dir = dir("unchanged");
+        let path = dir.join("main_test.v");
+        assert_eq!(write_with_backup(&path, "module t;\n").unwrap(), None);
+        assert_eq!(write_with_backup(&path, "module t;\n").unwrap(), None);
+        assert!(backups(&path).is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn overwriting_keeps_the_old_content() {
+        let dir = dir("overwrite");
+        let path = dir.join("Justfile");
+        fs::write(&path, "hand edited\n").unwrap();
+
+        let backup = write_with_backup(&path, "generated\n").unwrap().unwrap();
+        assert!(backup.file_name().unwrap().to_str().unwrap().starts_with("Justfile.bak."));
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "hand edited\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "generated\n");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn old_backups_are_pruned() {
+        let dir = dir("prune");
+        let path = dir.join("main.v");
+        for i in 0..=KEPT_BACKUPS + 2 {
+            write_with_backup(&path, &format!("version {}\n", i)).unwrap();
+        }
+        let kept = backups(&path);
+        assert_eq!(kept.len(), KEPT_BACKUPS);
+        let contents: Vec<String> = kept.iter().map(|backup| fs::read_to_string(backup).unwrap()).collect();
+        assert!(contents.contains(&format!("version {}\n", KEPT_BACKUPS + 1)));
+        assert!(!contents.contains(&"version 0\n".to_string()));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
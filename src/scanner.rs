@@ -0,0 +1,410 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::config::Config;
+
+pub const IGNORE_FILE_NAME: &str = ".hadouignore";
+
+// Order of the project and VCD lists, shared by every screen showing one
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SortMode {
+    #[default]
+    Name,
+    Modified,
+    Size,
+}
+
+impl SortMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "name" => Some(SortMode::Name),
+            "modified" => Some(SortMode::Modified),
+            "size" => Some(SortMode::Size),
+            _ => None,
+        }
+    }
+
+    pub fn identifier(&self) -> &'static str {
+        match self {
+            SortMode::Name => "name",
+            SortMode::Modified => "modified",
+            SortMode::Size => "size",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            SortMode::Name => SortMode::Modified,
+            SortMode::Modified => SortMode::Size,
+            SortMode::Size => SortMode::Name,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        config.sort.as_deref().and_then(Self::parse).unwrap_or_default()
+    }
+
+    // Names ascend; newest and largest entries come first
+    pub fn sort(&self, entries: &mut [ListEntry]) {
+        match self {
+            SortMode::Name => entries.sort_by(|a, b| {
+                a.path.file_name().cmp(&b.path.file_name()).then_with(|| a.path.cmp(&b.path))
+            }),
+            SortMode::Modified => entries.sort_by_key(|entry| Reverse(entry.modified)),
+            SortMode::Size => entries.sort_by_key(|entry| Reverse(entry.size)),
+        }
+    }
+}
+
+// Move pinned entries to the front, keeping the sort order within both groups
+pub fn pin_to_top(entries: &mut [ListEntry], pinned: &HashSet<PathBuf>) {
+    entries.sort_by_key(|entry| !pinned.contains(&entry.path));
+}
+
+// A scanned file or project directory with the metadata the lists sort by
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListEntry {
+    pub path: PathBuf,
+    // For directories, the newest file directly inside them
+    pub modified: Option<SystemTime>,
+    // For directories, the total size of everything below them
+    pub size: u64,
+}
+
+impl ListEntry {
+    pub fn read(path: PathBuf) -> Self {
+        let (modified, size) = if path.is_dir() {
+            (latest_modified(&path), dir_size(&path))
+        } else {
+            let metadata = fs::metadata(&path).ok();
+            (
+                metadata.as_ref().and_then(|metadata| metadata.modified().ok()),
+                metadata.map(|metadata| metadata.len()).unwrap_or(0),
+            )
+        };
+
+        Self { path, modified, size }
+    }
+}
+
+fn latest_modified(dir: &Path) -> Option<SystemTime> {
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .filter_map(|metadata| metadata.modified().ok())
+        .max()
+}
+
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map(|metadata| metadata.len()).unwrap_or(0),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+// Short relative age such as "5m ago", falling back to the date for old files
+pub fn format_age(modified: SystemTime) -> String {
+    let Ok(age) = SystemTime::now().duration_since(modified) else {
+        return "just now".to_string();
+    };
+
+    match age.as_secs() {
+        0..60 => "just now".to_string(),
+        seconds @ 60..3600 => format!("{}m ago", seconds / 60),
+        seconds @ 3600..86400 => format!("{}h ago", seconds / 3600),
+        seconds @ 86400..2592000 => format!("{}d ago", seconds / 86400),
+        _ => chrono::DateTime::<chrono::Local>::from(modified).format("%Y-%m-%d").to_string(),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    // How many directory levels below the base directory to descend
+    pub max_depth: usize,
+    pub ignore_patterns: Vec<String>,
+    // Descend into symlinked directories, each real directory at most once
+    pub follow_symlinks: bool,
+}
+
+impl ScanOptions {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_depth: config.scan_depth.max(1),
+            ignore_patterns: config.ignore.clone(),
+            follow_symlinks: config.follow_symlinks,
+        }
+    }
+
+    // Directories below `root` (not including it) for which `predicate` holds
+    pub fn find_dirs<F>(&self, root: &Path, predicate: F) -> Vec<PathBuf>
+    where
+        F: Fn(&Path) -> bool,
+    {
+        let mut dirs = Vec::new();
+        self.walk(root, |dir, depth, _| {
+            if depth > 0 && predicate(dir) {
+                dirs.push(dir.to_path_buf());
+            }
+        });
+        dirs
+    }
+
+    // Files in `root` and the directories below it for which `predicate` holds
+    pub fn find_files<F>(&self, root: &Path, predicate: F) -> Vec<PathBuf>
+    where
+        F: Fn(&Path) -> bool,
+    {
+        let mut files = Vec::new();
+        self.walk(root, |dir, _, rules| {
+            if let Ok(entries) = fs::read_dir(dir) {
+                for entry in entries.flatten() {
+                    let path = entry.path();
+                    if path.is_file() && !is_ignored(rules, &path) && predicate(&path) {
+                        files.push(path);
+                    }
+                }
+            }
+        });
+        files
+    }
+
+    fn walk<F>(&self, root: &Path, mut visit: F)
+    where
+        F: FnMut(&Path, usize, &[IgnoreRules]),
+    {
+        let rules = vec![IgnoreRules::new(root, self.ignore_patterns.clone())];
+        let mut visited = HashSet::new();
+        self.walk_dir(root, 0, &rules, &mut visited, &mut visit);
+    }
+
+    // `visited` holds the canonical path of every directory walked so far, so
+    // a symlink back up the tree (or to a directory already seen through
+    // another link) ends the walk there instead of looping
+    fn walk_dir<F>(&self, dir: &Path, depth: usize, rules: &[IgnoreRules], visited: &mut HashSet<PathBuf>, visit: &mut F)
+    where
+        F: FnMut(&Path, usize, &[IgnoreRules]),
+    {
+        if !visited.insert(fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf())) {
+            return;
+        }
+
+        // A .hadouignore applies to everything below the directory it lives in
+        let mut rules = rules.to_vec();
+        if let Some(local_rules) = IgnoreRules::from_ignore_file(dir) {
+            rules.push(local_rules);
+        }
+
+        visit(dir, depth, &rules);
+
+        if depth >= self.max_depth {
+            return;
+        }
+
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+
+        let mut subdirs: Vec<PathBuf> = entries
+            .flatten()
+            .filter(|entry| self.follow_symlinks || entry.file_type().is_ok_and(|file_type| !file_type.is_symlink()))
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .filter(|path| !is_ignored(&rules, path))
+            .collect();
+        subdirs.sort();
+
+        for subdir in subdirs {
+            self.walk_dir(&subdir, depth + 1, &rules, visited, visit);
+        }
+    }
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self::from_config(&Config::default())
+    }
+}
+
+fn is_ignored(rules: &[IgnoreRules], path: &Path) -> bool {
+    rules.iter().any(|rule| rule.is_ignored(path))
+}
+
+#[derive(Debug, Clone)]
+pub struct IgnoreRules {
+    base: PathBuf,
+    patterns: Vec<String>,
+}
+
+impl IgnoreRules {
+    pub fn new(base: &Path, patterns: Vec<String>) -> Self {
+        Self {
+            base: base.to_path_buf(),
+            patterns,
+        }
+    }
+
+    pub fn from_ignore_file(dir: &Path) -> Option<Self> {
+        let content = fs::read_to_string(dir.join(IGNORE_FILE_NAME)).ok()?;
+        let patterns = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(String::from)
+            .collect();
+
+        Some(Self::new(dir, patterns))
+    }
+
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.base) else {
+            return false;
+        };
+
+        let relative = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        self.patterns.iter().any(|pattern| {
+            let pattern = pattern.trim_end_matches('/');
+            if pattern.contains('/') {
+                glob_match(pattern.trim_start_matches('/'), &relative)
+            } else {
+                // Bare names like `target` match at any depth, as in .gitignore
+                glob_match(&format!("**/{}", pattern), &relative)
+            }
+        })
+    }
+}
+
+// Match a `/`-separated path against a glob where `**` spans any number of
+// path segments and `*`/`?` match within a single segment
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern_segments: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let path_segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match_segments(&pattern_segments, &path_segments)
+}
+
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| match_segments(rest, &path[skip..])),
+        Some((segment, rest)) => match path.split_first() {
+            Some((name, path_rest)) => match_segment(segment, name) && match_segments(rest, path_rest),
+            None => false,
+        },
+    }
+}
+
+fn match_segment(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    match_chars(&pattern, &name)
+}
+
+fn match_chars(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| match_chars(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && match_chars(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && match_chars(rest, &name[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_directories_are_skipped_or_walked_once() {
+        let root = std::env::temp_dir().join(format!("hadou-scan-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("alu")).unwrap();
+        fs::create_dir_all(root.join("shared/uart")).unwrap();
+        fs::write(root.join("alu/main.v"), "").unwrap();
+        fs::write(root.join("shared/uart/main.v"), "").unwrap();
+        // A loop back to the base directory and a second way into alu
+        std::os::unix::fs::symlink(&root, root.join("alu/loop")).unwrap();
+        std::os::unix::fs::symlink(root.join("shared/uart"), root.join("uart")).unwrap();
+
+        let mut options = ScanOptions { max_depth: 64, ..ScanOptions::default() };
+        let is_project = |dir: &Path| dir.join("main.v").is_file();
+        assert_eq!(options.find_dirs(&root, is_project), vec![root.join("alu"), root.join("shared/uart")]);
+
+        options.follow_symlinks = true;
+        let projects = options.find_dirs(&root, is_project);
+        assert_eq!(projects.len(), 2);
+        assert_eq!(options.find_files(&root, |path| path.ends_with("main.v")).len(), 2);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn pinned_entries_come_first_in_sort_order() {
+        let entry = |name: &str, size| ListEntry { path: PathBuf::from(name), modified: None, size };
+        let mut entries = vec![entry("a", 1), entry("b", 4), entry("c", 2), entry("d", 3)];
+        let pinned: HashSet<PathBuf> = ["a", "c"].into_iter().map(PathBuf::from).collect();
+
+        SortMode::Size.sort(&mut entries);
+        pin_to_top(&mut entries, &pinned);
+        let names: Vec<&Path> = entries.iter().map(|entry| entry.path.as_path()).collect();
+        assert_eq!(names, ["c", "a", "b", "d"].map(Path::new));
+    }
+
+    #[test]
+    fn ignore_patterns_match_like_gitignore() {
+        let base = Path::new("/work");
+        let rules = IgnoreRules::new(base, vec!["**/target".to_string(), "*.vcd".to_string(), "/build".to_string(), "sim/*.log".to_string()]);
+        let ignored = |path: &str| rules.is_ignored(&base.join(path));
+
+        assert!(ignored("target"));
+        assert!(ignored("alu/target"));
+        assert!(!ignored("alu/target_tb.v"));
+
+        // Bare patterns match at any depth
+        assert!(ignored("dump.vcd"));
+        assert!(ignored("alu/waves/dump.vcd"));
+        assert!(!ignored("alu/dump.vcd.gz"));
+
+        // Patterns with a slash are anchored to the directory of the rules
+        assert!(ignored("build"));
+        assert!(!ignored("alu/build"));
+        assert!(ignored("sim/run.log"));
+        assert!(!ignored("alu/sim/run.log"));
+
+        assert!(!rules.is_ignored(Path::new("/elsewhere/dump.vcd")));
+    }
+
+    #[test]
+    fn hadouignore_applies_below_its_own_directory() {
+        let root = std::env::temp_dir().join(format!("hadou-ignore-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for project in ["alu", "boards/uart", "boards/spi", "boards/old/spi"] {
+            fs::create_dir_all(root.join(project)).unwrap();
+            fs::write(root.join(project).join("main.v"), "").unwrap();
+        }
+        fs::write(root.join("boards/.hadouignore"), "# retired designs\n/old\nspi\n").unwrap();
+
+        let options = ScanOptions { max_depth: 8, ignore_patterns: Vec::new(), follow_symlinks: false };
+        let projects = options.find_dirs(&root, |dir| dir.join("main.v").is_file());
+        assert_eq!(projects, vec![root.join("alu"), root.join("boards/uart")]);
+
+        let sources = options.find_files(&root, |path| path.ends_with("main.v"));
+        assert_eq!(sources.len(), 2);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
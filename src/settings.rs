@@ -0,0 +1,304 @@
+use catppuccin::FlavorName;
+use std::process::Command;
+
+use crate::config::Config;
+use crate::create_new_project::ProjectCreator;
+use crate::error::HadouError;
+use crate::scanner::SortMode;
+use crate::theme;
+
+const MAX_SCAN_DEPTH: usize = 16;
+
+const FLAVORS: [FlavorName; 4] = [
+    FlavorName::Latte,
+    FlavorName::Frappe,
+    FlavorName::Macchiato,
+    FlavorName::Mocha,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SettingField {
+    Editor,
+    EditorKind,
+    EditorOpen,
+    OpenAfterCreate,
+    NamePrefix,
+    Simulator,
+    Viewer,
+    ViewerPicker,
+    ParseWarnings,
+    Flavor,
+    ScanDepth,
+    FollowSymlinks,
+    SimulationTimeout,
+    Sort,
+    SyntaxCheck,
+    Watch,
+    AsciiMode,
+}
+
+impl SettingField {
+    pub const ALL: [SettingField; 17] = [
+        SettingField::Editor,
+        SettingField::EditorKind,
+        SettingField::EditorOpen,
+        SettingField::OpenAfterCreate,
+        SettingField::NamePrefix,
+        SettingField::Simulator,
+        SettingField::Viewer,
+        SettingField::ViewerPicker,
+        SettingField::ParseWarnings,
+        SettingField::Flavor,
+        SettingField::ScanDepth,
+        SettingField::FollowSymlinks,
+        SettingField::SimulationTimeout,
+        SettingField::Sort,
+        SettingField::SyntaxCheck,
+        SettingField::Watch,
+        SettingField::AsciiMode,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SettingField::Editor => "Editor",
+            SettingField::EditorKind => "Editor kind",
+            SettingField::EditorOpen => "Open projects as",
+            SettingField::OpenAfterCreate => "Open when created",
+            SettingField::NamePrefix => "Auto name prefix",
+            SettingField::Simulator => "Simulator",
+            SettingField::Viewer => "Waveform viewer",
+            SettingField::ViewerPicker => "Ask for viewer",
+            SettingField::ParseWarnings => "VCD parse warnings",
+            SettingField::Flavor => "Flavor",
+            SettingField::ScanDepth => "Scan depth",
+            SettingField::FollowSymlinks => "Follow symlinks",
+            SettingField::SimulationTimeout => "Sim timeout (s)",
+            SettingField::Sort => "List order",
+            SettingField::SyntaxCheck => "Syntax check",
+            SettingField::Watch => "Auto refresh",
+            SettingField::AsciiMode => "ASCII mode",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            SettingField::Editor => "Command used to open projects (empty uses $EDITOR)",
+            SettingField::EditorKind => "terminal editors take over the screen until closed, gui editors run alongside Hadou",
+            SettingField::EditorOpen => "workspace opens the project folder, files passes each project file (auto picks workspace for VS Code, Zed, Helix and Sublime)",
+            SettingField::OpenAfterCreate => "Open a new project in the editor right after creating it (the create screen can change this per project)",
+            SettingField::NamePrefix => "F2 on the create screen names the project with this and the next free number, e.g. proj_001",
+            SettingField::Simulator => "iverilog-compatible compiler used in generated Justfiles",
+            SettingField::Viewer => "External viewer opened by Enter on a VCD file (empty asks, or tries dwfv, digisurf, gtkwave)",
+            SettingField::ViewerPicker => "Ask which installed viewer to open when no waveform viewer is set",
+            SettingField::ParseWarnings => "List the lines the built-in viewer skips while loading a VCD ('W' in the viewer); off keeps large loads fast",
+            SettingField::Flavor => "Catppuccin colour flavor",
+            SettingField::ScanDepth => "Directory levels searched for projects and VCD files",
+            SettingField::FollowSymlinks => "Also search symlinked directories, each real directory once (off skips them, so a link can't make a scan loop)",
+            SettingField::SimulationTimeout => "Seconds a simulation may run before it is stopped (0 never stops it; per-project values go under [project_timeouts] in config.toml)",
+            SettingField::Sort => "Order of the project and VCD lists (also changed with 's' on those screens)",
+            SettingField::SyntaxCheck => "Check each project's main.v in the background on the edit screen",
+            SettingField::Watch => "Watch the working directory and update the lists when files appear or disappear (can be slow on large trees)",
+            SettingField::AsciiMode => "Replace emoji icons with plain ASCII labels",
+        }
+    }
+
+    // Text fields are edited inline; the others change in place on Enter
+    pub fn is_text(&self) -> bool {
+        matches!(self, SettingField::Editor | SettingField::NamePrefix | SettingField::Simulator | SettingField::Viewer | SettingField::ScanDepth | SettingField::SimulationTimeout)
+    }
+
+    pub fn value(&self, config: &Config) -> String {
+        match self {
+            SettingField::Editor => config.editor.clone().unwrap_or_default(),
+            SettingField::EditorKind => config.editor_kind.clone().unwrap_or_else(|| "auto".to_string()),
+            SettingField::EditorOpen => config.editor_open.clone().unwrap_or_else(|| "auto".to_string()),
+            SettingField::NamePrefix => config.name_prefix.clone(),
+            SettingField::Simulator => config.simulator.clone().unwrap_or_default(),
+            SettingField::Viewer => config.viewer.clone().unwrap_or_default(),
+            SettingField::Flavor => config.flavor.clone().unwrap_or_else(|| "auto".to_string()),
+            SettingField::ScanDepth => config.scan_depth.to_string(),
+            SettingField::FollowSymlinks => if config.follow_symlinks { "on" } else { "off" }.to_string(),
+            SettingField::SimulationTimeout => config.simulation_timeout.to_string(),
+            SettingField::Sort => SortMode::from_config(config).identifier().to_string(),
+            SettingField::OpenAfterCreate => if config.open_after_create { "on" } else { "off" }.to_string(),
+            SettingField::SyntaxCheck => if config.syntax_check { "on" } else { "off" }.to_string(),
+            SettingField::Watch => if config.watch { "on" } else { "off" }.to_string(),
+            SettingField::ViewerPicker => if config.viewer_picker { "on" } else { "off" }.to_string(),
+            SettingField::ParseWarnings => if config.vcd_parse_warnings { "on" } else { "off" }.to_string(),
+            SettingField::AsciiMode => if config.ascii_mode { "on" } else { "off" }.to_string(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SettingsEditor {
+    // Working copy of the config; only written out on save
+    pub draft: Config,
+    pub selected_index: usize,
+    pub editing: bool,
+    pub edit_buffer: String,
+    pub status: Option<String>,
+}
+
+impl SettingsEditor {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            draft: config.clone(),
+            selected_index: 0,
+            editing: false,
+            edit_buffer: String::new(),
+            status: None,
+        }
+    }
+
+    pub fn reset(&mut self, config: &Config) {
+        *self = Self::new(config);
+    }
+
+    pub fn selected_field(&self) -> SettingField {
+        SettingField::ALL[self.selected_index]
+    }
+
+    pub fn move_selection_up(&mut self) {
+        self.selected_index = if self.selected_index == 0 {
+            SettingField::ALL.len() - 1
+        } else {
+            self.selected_index - 1
+        };
+    }
+
+    pub fn move_selection_down(&mut self) {
+        self.selected_index = (self.selected_index + 1) % SettingField::ALL.len();
+    }
+
+    // Start editing a text field, or toggle/cycle the other kinds
+    pub fn activate(&mut self) {
+        self.status = None;
+        let field = self.selected_field();
+
+        match field {
+            SettingField::Flavor => self.cycle_flavor(),
+            SettingField::EditorKind => {
+                self.draft.editor_kind = match self.draft.editor_kind.as_deref() {
+                    None => Some("terminal".to_string()),
+                    Some("terminal") => Some("gui".to_string()),
+                    Some(_) => None,
+                };
+            }
+            SettingField::EditorOpen => {
+                self.draft.editor_open = match self.draft.editor_open.as_deref() {
+                    None => Some("workspace".to_string()),
+                    Some("workspace") => Some("files".to_string()),
+                    Some(_) => None,
+                };
+            }
+            SettingField::Sort => {
+                let next = SortMode::from_config(&self.draft).next();
+                self.draft.sort = Some(next.identifier().to_string());
+            }
+            SettingField::OpenAfterCreate => self.draft.open_after_create = !self.draft.open_after_create,
+            SettingField::SyntaxCheck => self.draft.syntax_check = !self.draft.syntax_check,
+            SettingField::Watch => self.draft.watch = !self.draft.watch,
+            SettingField::ViewerPicker => self.draft.viewer_picker = !self.draft.viewer_picker,
+            SettingField::ParseWarnings => self.draft.vcd_parse_warnings = !self.draft.vcd_parse_warnings,
+            SettingField::FollowSymlinks => self.draft.follow_symlinks = !self.draft.follow_symlinks,
+            SettingField::AsciiMode => self.draft.ascii_mode = !self.draft.ascii_mode,
+            _ => {
+                self.edit_buffer = field.value(&self.draft);
+                self.editing = true;
+            }
+        }
+    }
+
+    fn cycle_flavor(&mut self) {
+        let current = self.draft.flavor.as_deref().and_then(theme::parse_flavor);
+        let next = match current.and_then(|flavor| FLAVORS.iter().position(|f| *f == flavor)) {
+            Some(index) => FLAVORS[(index + 1) % FLAVORS.len()],
+            None => FLAVORS[0],
+        };
+        self.draft.flavor = Some(next.identifier().to_string());
+    }
+
+    pub fn cancel_edit(&mut self) {
+        self.editing = false;
+        self.edit_buffer.clear();
+        self.status = None;
+    }
+
+    // Validate the edit buffer and store it in the draft. On failure the
+    // field stays in edit mode so the value can be corrected.
+    pub fn commit_edit(&mut self) {
+        let input = self.edit_buffer.trim().to_string();
+        let field = self.selected_field();
+
+        match Self::apply_text(&mut self.draft, field, &input) {
+            Ok(()) => {
+                self.editing = false;
+                self.edit_buffer.clear();
+                self.status = None;
+            }
+            Err(e) => self.status = Some(e),
+        }
+    }
+
+    fn apply_text(config: &mut Config, field: SettingField, input: &str) -> Result<(), String> {
+        let command = || -> Result<Option<String>, String> {
+            if input.is_empty() {
+                return Ok(None);
+            }
+            if !command_exists(input) {
+                return Err(format!("Command '{}' not found", input));
+            }
+            Ok(Some(input.to_string()))
+        };
+
+        match field {
+            SettingField::Editor => config.editor = command()?,
+            SettingField::NamePrefix => {
+                ProjectCreator::check_project_name(&format!("{}001", input)).map_err(|e| e.to_string())?;
+                config.name_prefix = input.to_string();
+            }
+            SettingField::Simulator => config.simulator = command()?,
+            SettingField::Viewer => config.viewer = command()?,
+            SettingField::ScanDepth => {
+                let depth = input
+                    .parse::<usize>()
+                    .map_err(|_| "Scan depth must be a whole number".to_string())?;
+                if depth == 0 || depth > MAX_SCAN_DEPTH {
+                    return Err(format!("Scan depth must be between 1 and {}", MAX_SCAN_DEPTH));
+                }
+                config.scan_depth = depth;
+            }
+            SettingField::SimulationTimeout => {
+                config.simulation_timeout = input
+                    .parse::<u64>()
+                    .map_err(|_| "Simulation timeout must be a whole number of seconds".to_string())?;
+            }
+            SettingField::EditorKind | SettingField::EditorOpen | SettingField::OpenAfterCreate | SettingField::Flavor | SettingField::Sort | SettingField::SyntaxCheck | SettingField::Watch | SettingField::ViewerPicker | SettingField::ParseWarnings | SettingField::FollowSymlinks | SettingField::AsciiMode => {}
+        }
+
+        Ok(())
+    }
+
+    pub fn save(&self) -> Result<(), HadouError> {
+        self.draft.save()
+    }
+}
+
+pub fn command_exists(command: &str) -> bool {
+    Command::new("which")
+        .arg(command)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or_else(|_| {
+            if cfg!(target_os = "windows") {
+                Command::new("where")
+                    .arg(command)
+                    .output()
+                    .map(|output| output.status.success())
+                    .unwrap_or(false)
+            } else {
+                false
+            }
+        })
+}
@@ -0,0 +1,81 @@
+// Pass/fail tally of a simulation, read from the lines a testbench prints
+// ("PASS", "FAIL", "ASSERTION FAILED", ...) so a run's verdict can be seen
+// without reading through its $display output
+
+use crate::compile_project::OutputPhase;
+
+pub fn default_pass_markers() -> Vec<String> {
+    vec!["PASS".to_string()]
+}
+
+pub fn default_fail_markers() -> Vec<String> {
+    vec!["FAIL".to_string(), "ASSERTION FAILED".to_string(), "ERROR".to_string()]
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+    pub passed: usize,
+    pub failed: usize,
+    // Indices of the failing lines in the output they were counted from
+    pub failing: Vec<usize>,
+}
+
+impl Summary {
+    pub fn is_empty(&self) -> bool {
+        self.passed == 0 && self.failed == 0
+    }
+
+    pub fn is_failing(&self, index: usize) -> bool {
+        self.failing.binary_search(&index).is_ok()
+    }
+}
+
+// Count the simulation lines containing one of the markers. Markers are
+// matched case-sensitively so "PASS" doesn't pick up "bypass", and a line
+// with both kinds counts as failed.
+pub fn summarize<'a>(
+    output: impl Iterator<Item = (&'a str, OutputPhase)>,
+    pass_markers: &[String],
+    fail_markers: &[String],
+) -> Summary {
+    let has_marker = |line: &str, markers: &[String]| markers.iter().any(|marker| !marker.is_empty() && line.contains(marker.as_str()));
+
+    let mut summary = Summary::default();
+    for (index, (line, phase)) in output.enumerate() {
+        if phase != OutputPhase::Simulation {
+            continue;
+        }
+        if has_marker(line, fail_markers) {
+            summary.failed += 1;
+            summary.failing.push(index);
+        } else if has_marker(line, pass_markers) {
+            summary.passed += 1;
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_markers_in_simulation_lines_only() {
+        let output = [
+            ("Compiling main.v - FAIL here is not the testbench", OutputPhase::Build),
+            ("test 1: PASS", OutputPhase::Simulation),
+            ("bypass mux selected", OutputPhase::Simulation),
+            ("test 2: PASS", OutputPhase::Simulation),
+            ("ASSERTION FAILED: sum != 3", OutputPhase::Simulation),
+            ("test 3: FAIL (expected PASS)", OutputPhase::Simulation),
+        ];
+
+        let summary = summarize(output.into_iter(), &default_pass_markers(), &default_fail_markers());
+        assert_eq!(summary, Summary { passed: 2, failed: 2, failing: vec![4, 5] });
+        assert!(summary.is_failing(5));
+        assert!(!summary.is_failing(1));
+
+        let custom = summarize(output.into_iter(), &["ok".to_string()], &["sum".to_string()]);
+        assert_eq!(custom, Summary { passed: 0, failed: 1, failing: vec![4] });
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::SystemTime;
+
+const DESIGN_FILE: &str = "main.v";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxStatus {
+    Clean,
+    // First line the compiler reported
+    Errors(String),
+}
+
+#[derive(Debug)]
+struct CheckResult {
+    project: PathBuf,
+    modified: SystemTime,
+    // None when the compiler could not be run at all
+    status: Option<SyntaxStatus>,
+}
+
+// Runs `iverilog -t null` on each project's main.v on a background thread.
+// Results are cached by the file's modification time so unchanged designs are
+// not checked again, and a new batch cancels whatever is still queued.
+#[derive(Debug, Default)]
+pub struct SyntaxChecker {
+    cache: HashMap<PathBuf, (SystemTime, SyntaxStatus)>,
+    receiver: Option<Receiver<CheckResult>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl SyntaxChecker {
+    pub fn start(&mut self, projects: &[PathBuf], compiler: &str) {
+        self.cancel();
+
+        let pending: Vec<(PathBuf, SystemTime)> = projects
+            .iter()
+            .filter_map(|project| {
+                let modified = design_modified(project)?;
+                match self.cache.get(project) {
+                    Some((checked, _)) if *checked == modified => None,
+                    _ => Some((project.clone(), modified)),
+                }
+            })
+            .collect();
+
+        if pending.is_empty() {
+            return;
+        }
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        self.cancelled = Arc::clone(&cancelled);
+        self.receiver = Some(receiver);
+
+        let compiler = compiler.to_string();
+        thread::spawn(move || {
+            for (project, modified) in pending {
+                if cancelled.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                let status = check_design(&compiler, &project);
+                if sender.send(CheckResult { project, modified, status }).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    // Stop after the check in progress; results already cached are kept
+    pub fn cancel(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.receiver = None;
+    }
+
+    // Move finished checks into the cache. Called on every UI tick.
+    pub fn poll(&mut self) {
+        let Some(receiver) = &self.receiver else {
+            return;
+        };
+
+        loop {
+            match receiver.try_recv() {
+                Ok(result) => {
+                    if let Some(status) = result.status {
+                        self.cache.insert(result.project, (result.modified, status));
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.receiver = None;
+                    break;
+                }
+            }
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    // Result for the project's current main.v, if it has been checked since
+    // it last changed
+    pub fn status(&self, project: &Path) -> Option<&SyntaxStatus> {
+        let (checked, status) = self.cache.get(project)?;
+        (design_modified(project) == Some(*checked)).then_some(status)
+    }
+}
+
+fn design_modified(project: &Path) -> Option<SystemTime> {
+    fs::metadata(project.join(DESIGN_FILE)).ok()?.modified().ok()
+}
+
+fn check_design(compiler: &str, project: &Path) -> Option<SyntaxStatus> {
+    let output = Command::new(compiler)
+        .current_dir(project)
+        .args(["-t", "null", DESIGN_FILE])
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+
+    if output.status.success() {
+        return Some(SyntaxStatus::Clean);
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let message = stderr
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{} exited with code {}", compiler, output.status.code().unwrap_or(-1)));
+
+    Some(SyntaxStatus::Errors(message))
+}
@@ -0,0 +1,254 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::build_system::{self, BuildSystem};
+use crate::edit_project::{justfile_project_name, project_label};
+use crate::error::HadouError;
+use crate::language::Language;
+use crate::project_config::ProjectConfig;
+use crate::safe_write;
+use crate::verilog;
+
+// Build file variables Hadou-generated Justfiles and Makefiles read the
+// testbench and its VCD from
+const TEST_FILE_VAR: &str = "TEST_FILE";
+const VCD_FILE_VAR: &str = "VCD_FILE";
+
+// main_test.v, other_test.v and tb_other.v all count as testbenches
+pub fn is_testbench_name(name: &str) -> bool {
+    let Some(stem) = name.strip_suffix(".v") else {
+        return false;
+    };
+    stem == "main_test" || stem.ends_with("_test") || stem.starts_with("tb_")
+}
+
+// Verilog testbenches directly inside the project: main_test.v first, then
+// the rest by name
+pub fn find_testbenches(project: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(project) else {
+        return Vec::new();
+    };
+
+    let default = Language::Verilog.testbench_file();
+    let mut testbenches: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .filter(|name| is_testbench_name(name))
+        .collect();
+    testbenches.sort_by_key(|name| (name != default, name.clone()));
+    testbenches
+}
+
+// The testbench runs simulate: the one picked for the project while it
+// still exists, otherwise main_test.v
+pub fn selected(project: &Path) -> String {
+    ProjectConfig::load(project)
+        .ok()
+        .and_then(|config| config.testbench)
+        .filter(|testbench| project.join(testbench).is_file())
+        .unwrap_or_else(|| Language::Verilog.testbench_file().to_string())
+}
+
+pub fn selected_path(project: &Path) -> PathBuf {
+    project.join(selected(project))
+}
+
+// Remember `testbench` for the project's runs; main_test.v needs no entry
+pub fn select(project: &Path, testbench: &str) -> Result<Option<PathBuf>, HadouError> {
+    let mut config = ProjectConfig::load(project)?;
+    config.testbench = Some(testbench.to_string()).filter(|testbench| testbench != Language::Verilog.testbench_file());
+    config.save(project)
+}
+
+// The VCD a testbench writes, as named by its $dumpfile call
+pub fn vcd_name(project: &Path, testbench: &str) -> Option<String> {
+    fs::read_to_string(project.join(testbench)).ok().and_then(|source| verilog::dumpfile_name(&source))
+}
+
+// The VCD a simulation of the project writes: the one the selected
+// testbench's $dumpfile names, otherwise <project>.vcd as generated build
+// files assume
+pub fn vcd_file(project: &Path) -> String {
+    vcd_name(project, &selected(project)).unwrap_or_else(|| format!("{}.vcd", build_project_name(project)))
+}
+
+// The PROJECT_NAME the build file sets, or the directory name without one
+pub fn build_project_name(project: &Path) -> String {
+    build_system::find_build_file(project)
+        .and_then(|(_, path)| fs::read_to_string(path).ok())
+        .and_then(|content| justfile_project_name(&content))
+        .unwrap_or_else(|| project_label(project))
+}
+
+// `VAR=value` arguments passing a picked testbench to the build file. Only
+// variables the build file defines are passed, since just refuses to
+// override one it doesn't have. For main_test.v only a VCD name differing
+// from the build file's is passed, so `view` opens what was dumped.
+pub fn build_overrides(project: &Path) -> Vec<String> {
+    let testbench = selected(project);
+    let Some(content) = build_system::find_build_file(project).and_then(|(_, path)| fs::read_to_string(path).ok()) else {
+        return Vec::new();
+    };
+
+    let mut overrides = Vec::new();
+    let picked = testbench != Language::Verilog.testbench_file();
+    if picked && defines_variable(&content, TEST_FILE_VAR) {
+        overrides.push(format!("{}={}", TEST_FILE_VAR, testbench));
+    }
+    if let Some(vcd) = vcd_name(project, &testbench)
+        .filter(|vcd| defines_variable(&content, VCD_FILE_VAR) && (picked || build_file_vcd(&content).as_ref() != Some(vcd)))
+    {
+        overrides.push(format!("{}={}", VCD_FILE_VAR, vcd));
+    }
+    overrides
+}
+
+// The VCD_FILE a Justfile or Makefile sets, for the forms Hadou generates:
+// a literal name, `PROJECT_NAME + ".vcd"` or `$(PROJECT_NAME).vcd`
+fn build_file_vcd(build_file: &str) -> Option<String> {
+    let value = build_file.lines().find_map(|line| {
+        let rest = line.strip_prefix(VCD_FILE_VAR)?.trim_start();
+        rest.strip_prefix(":=").or_else(|| rest.strip_prefix('=')).map(str::trim)
+    })?;
+
+    if value == "PROJECT_NAME + \".vcd\"" || value == "$(PROJECT_NAME).vcd" {
+        return justfile_project_name(build_file).map(|name| format!("{}.vcd", name));
+    }
+    let literal = value.trim_matches('"');
+    (!literal.is_empty() && !literal.contains(['"', '$', '+', ' '])).then(|| literal.to_string())
+}
+
+// A generated Justfile dumping to `vcd` instead of <project>.vcd
+pub fn with_vcd_file(justfile: &str, vcd: &str) -> String {
+    justfile
+        .lines()
+        .map(|line| match line.strip_prefix(VCD_FILE_VAR) {
+            Some(rest) if rest.trim_start().starts_with(":=") => format!("{} := \"{}\"", VCD_FILE_VAR, vcd),
+            _ => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        + "\n"
+}
+
+// Warning for a picked testbench the build file has no way of being given
+pub fn override_warning(project: &Path, system: BuildSystem) -> Option<String> {
+    let testbench = selected(project);
+    if testbench == Language::Verilog.testbench_file() {
+        return None;
+    }
+    let build_file = build_system::find_build_file(project)?.1;
+    let content = fs::read_to_string(build_file).ok()?;
+    (!defines_variable(&content, TEST_FILE_VAR)).then(|| {
+        format!(
+            "warning: the {} has no {} variable, so it builds its own sources instead of {}",
+            system.file_name(),
+            TEST_FILE_VAR,
+            testbench
+        )
+    })
+}
+
+// `NAME :=` (just) or `NAME =`, `NAME ?=`, `NAME :=` (make) at the start of a line
+fn defines_variable(build_file: &str, name: &str) -> bool {
+    build_file.lines().any(|line| {
+        line.strip_prefix(name)
+            .map(str::trim_start)
+            .is_some_and(|rest| rest.starts_with('=') || rest.starts_with(":=") || rest.starts_with("?="))
+    })
+}
+
+// Before simulating a testbench other than main_test.v, point its $dumpfile
+// at <testbench>.vcd when it would otherwise overwrite the VCD of another
+// testbench (usually because it started out as a copy of main_test.v).
+// Returns a line for the output describing the change.
+pub fn separate_dumpfile(project: &Path) -> Result<Option<String>, HadouError> {
+    let testbench = selected(project);
+    if testbench == Language::Verilog.testbench_file() {
+        return Ok(None);
+    }
+    let path = project.join(&testbench);
+    let Ok(source) = fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    let Some(vcd) = verilog::dumpfile_name(&source) else {
+        return Ok(None);
+    };
+
+    let shared = find_testbenches(project)
+        .iter()
+        .filter(|other| **other != testbench)
+        .any(|other| vcd_name(project, other).as_deref() == Some(vcd.as_str()));
+    if !shared {
+        return Ok(None);
+    }
+
+    let own = format!("{}.vcd", testbench.trim_end_matches(".v"));
+    let Some(updated) = verilog::set_dumpfile_name(&source, &own) else {
+        return Ok(None);
+    };
+    let backup = safe_write::write_with_backup(&path, &updated)?;
+    let mut note = format!("{} dumped to {} like another testbench; it now writes {}", testbench, vcd, own);
+    if let Some(backup) = backup {
+        note.push_str(&format!(" (previous version saved to {})", backup.display()));
+    }
+    Ok(Some(note))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picked_testbench_is_passed_to_the_build_and_gets_its_own_vcd() {
+        let dir = std::env::temp_dir().join(format!("hadou-testbench-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let testbench = "module main_test;\n  initial begin\n    // $dumpfile(\"old.vcd\");\n    $dumpfile(\"alu.vcd\");\n  end\nendmodule\n";
+        fs::write(dir.join("main.v"), "module alu; endmodule\n").unwrap();
+        fs::write(dir.join("main_test.v"), testbench).unwrap();
+        fs::write(dir.join("edge_test.v"), testbench).unwrap();
+        fs::write(dir.join("tb_reset.v"), "module tb_reset; endmodule\n").unwrap();
+        fs::write(dir.join("Justfile"), "PROJECT_NAME := \"alu\"\nTEST_FILE := \"main_test.v\"\nVCD_FILE := PROJECT_NAME + \".vcd\"\n").unwrap();
+
+        assert_eq!(find_testbenches(&dir), vec!["main_test.v", "edge_test.v", "tb_reset.v"]);
+        assert!(build_overrides(&dir).is_empty());
+
+        select(&dir, "edge_test.v").unwrap();
+        assert_eq!(selected(&dir), "edge_test.v");
+        assert_eq!(build_overrides(&dir), vec!["TEST_FILE=edge_test.v", "VCD_FILE=alu.vcd"]);
+
+        assert!(separate_dumpfile(&dir).unwrap().is_some());
+        assert_eq!(vcd_name(&dir, "edge_test.v").as_deref(), Some("edge_test.vcd"));
+        assert!(fs::read_to_string(dir.join("edge_test.v")).unwrap().contains("// $dumpfile(\"old.vcd\")"));
+        assert_eq!(build_overrides(&dir)[1], "VCD_FILE=edge_test.vcd");
+        assert!(separate_dumpfile(&dir).unwrap().is_none());
+
+        select(&dir, "main_test.v").unwrap();
+        assert_eq!(ProjectConfig::load(&dir).unwrap().testbench, None);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn vcd_named_by_the_testbench_reaches_the_build_file() {
+        let dir = std::env::temp_dir().join(format!("hadou-vcd-name-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let justfile = crate::create_new_project::generate_justfile("counter", "iverilog", "");
+        fs::write(dir.join("Justfile"), &justfile).unwrap();
+        assert_eq!(vcd_file(&dir), "counter.vcd");
+
+        fs::write(dir.join("main_test.v"), "module main_test;\n  initial $dumpfile(\"counter.vcd\");\nendmodule\n").unwrap();
+        assert!(build_overrides(&dir).is_empty());
+
+        fs::write(dir.join("main_test.v"), "module main_test;\n  initial $dumpfile(\"sim.vcd\");\nendmodule\n").unwrap();
+        assert_eq!(vcd_file(&dir), "sim.vcd");
+        assert_eq!(build_overrides(&dir), vec!["VCD_FILE=sim.vcd"]);
+
+        fs::write(dir.join("Justfile"), with_vcd_file(&justfile, "sim.vcd")).unwrap();
+        assert!(fs::read_to_string(dir.join("Justfile")).unwrap().contains("VCD_FILE := \"sim.vcd\"\n"));
+        assert!(build_overrides(&dir).is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,220 @@
+use catppuccin::{Color, FlavorColors, FlavorName, PALETTE};
+use std::env;
+
+use crate::config::Config;
+
+pub const DEFAULT_FLAVOR: FlavorName = FlavorName::Macchiato;
+
+// How long to wait for the terminal to answer the background colour query
+#[cfg(unix)]
+const OSC_QUERY_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Background {
+    Light,
+    Dark,
+}
+
+pub fn colors(flavor: FlavorName) -> &'static FlavorColors {
+    match flavor {
+        FlavorName::Latte => &PALETTE.latte.colors,
+        FlavorName::Frappe => &PALETTE.frappe.colors,
+        FlavorName::Macchiato => &PALETTE.macchiato.colors,
+        FlavorName::Mocha => &PALETTE.mocha.colors,
+    }
+}
+
+// Accents handed out to waveform signals in turn. Red and yellow are left
+// out since the chart uses them for unknown values and the cursor. Each
+// flavor's accents are tuned to its own background, so all stay readable.
+pub fn signal_palette(flavor: FlavorName) -> [Color; 10] {
+    let colors = colors(flavor);
+    [
+        colors.green,
+        colors.sky,
+        colors.peach,
+        colors.mauve,
+        colors.pink,
+        colors.teal,
+        colors.blue,
+        colors.flamingo,
+        colors.lavender,
+        colors.sapphire,
+    ]
+}
+
+// Accents shown side by side in the settings preview of a flavor
+pub fn preview_swatches(flavor: FlavorName) -> [(&'static str, Color); 6] {
+    let colors = colors(flavor);
+    [
+        ("teal", colors.teal),
+        ("green", colors.green),
+        ("red", colors.red),
+        ("blue", colors.blue),
+        ("mauve", colors.mauve),
+        ("yellow", colors.yellow),
+    ]
+}
+
+pub fn parse_flavor(name: &str) -> Option<FlavorName> {
+    name.trim().to_lowercase().parse().ok()
+}
+
+// A flavor stored in the config always wins. Otherwise the terminal background
+// is probed and a conclusive result is saved so the probe only runs once.
+// Must be called after raw mode is enabled so the terminal reply can be read.
+pub fn resolve_flavor(config: &mut Config) -> FlavorName {
+    if let Some(flavor) = config.flavor.as_deref().and_then(parse_flavor) {
+        return flavor;
+    }
+
+    match detect_background() {
+        Some(background) => {
+            let flavor = match background {
+                Background::Light => FlavorName::Latte,
+                Background::Dark => FlavorName::Macchiato,
+            };
+            config.flavor = Some(flavor.identifier().to_string());
+            let _ = config.save();
+            flavor
+        }
+        None => DEFAULT_FLAVOR,
+    }
+}
+
+pub fn detect_background() -> Option<Background> {
+    if let Ok(colorfgbg) = env::var("COLORFGBG") && let Some(background) = parse_colorfgbg(&colorfgbg) {
+        return Some(background);
+    }
+
+    query_terminal_background()
+}
+
+// COLORFGBG looks like "15;0" or "15;default;0", the last field being the
+// background's ANSI colour index
+fn parse_colorfgbg(value: &str) -> Option<Background> {
+    let index = value.rsplit(';').next()?.trim().parse::<u8>().ok()?;
+    match index {
+        7 | 9..=15 => Some(Background::Light),
+        0..=6 | 8 => Some(Background::Dark),
+        _ => None,
+    }
+}
+
+#[cfg(unix)]
+fn query_terminal_background() -> Option<Background> {
+    use std::fs::OpenOptions;
+    use std::io::{Read, Write};
+    use std::os::unix::io::AsRawFd;
+    use std::time::Instant;
+
+    let mut tty = OpenOptions::new().read(true).write(true).open("/dev/tty").ok()?;
+
+    // Follow the OSC 11 query with a device attributes request. Every terminal
+    // answers the latter, so its reply tells us when to stop waiting instead of
+    // always sitting out the full timeout on terminals without OSC 11 support.
+    tty.write_all(b"\x1b]11;?\x1b\\\x1b[c").ok()?;
+    tty.flush().ok()?;
+
+    let deadline = Instant::now() + OSC_QUERY_TIMEOUT;
+    let mut response = Vec::new();
+    let mut buffer = [0u8; 64];
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+
+        let mut pollfd = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ready = unsafe { libc::poll(&mut pollfd, 1, remaining.as_millis() as libc::c_int) };
+        if ready <= 0 {
+            break;
+        }
+
+        let read = tty.read(&mut buffer).ok()?;
+        if read == 0 {
+            break;
+        }
+        response.extend_from_slice(&buffer[..read]);
+
+        if has_device_attributes_reply(&response) {
+            break;
+        }
+    }
+
+    parse_osc11_response(&String::from_utf8_lossy(&response))
+}
+
+#[cfg(not(unix))]
+fn query_terminal_background() -> Option<Background> {
+    None
+}
+
+#[cfg(unix)]
+fn has_device_attributes_reply(response: &[u8]) -> bool {
+    response
+        .windows(3)
+        .position(|window| window == b"\x1b[?")
+        .map(|start| response[start..].contains(&b'c'))
+        .unwrap_or(false)
+}
+
+// Reply format: ESC ] 11 ; rgb:RRRR/GGGG/BBBB (BEL | ESC \)
+#[cfg(unix)]
+fn parse_osc11_response(response: &str) -> Option<Background> {
+    let start = response.find("rgb:")? + 4;
+    let rgb: String = response[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_hexdigit() || *c == '/')
+        .collect();
+
+    let channels: Vec<f64> = rgb
+        .split('/')
+        .filter_map(|component| {
+            if component.is_empty() || component.len() > 4 {
+                return None;
+            }
+            let max = (16u32.pow(component.len() as u32) - 1) as f64;
+            u32::from_str_radix(component, 16).ok().map(|value| value as f64 / max)
+        })
+        .collect();
+
+    if channels.len() != 3 {
+        return None;
+    }
+
+    let luminance = 0.2126 * channels[0] + 0.7152 * channels[1] + 0.0722 * channels[2];
+    if luminance > 0.5 {
+        Some(Background::Light)
+    } else {
+        Some(Background::Dark)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colorfgbg_uses_the_background_index() {
+        assert_eq!(parse_colorfgbg("15;0"), Some(Background::Dark));
+        assert_eq!(parse_colorfgbg("0;15"), Some(Background::Light));
+        assert_eq!(parse_colorfgbg("0;default;7"), Some(Background::Light));
+        assert_eq!(parse_colorfgbg("default;default"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn osc11_replies_are_read_with_either_terminator() {
+        assert_eq!(parse_osc11_response("\x1b]11;rgb:ffff/ffff/ffff\x07"), Some(Background::Light));
+        assert_eq!(parse_osc11_response("\x1b]11;rgb:1e1e/1e1e/2e2e\x1b\\"), Some(Background::Dark));
+        assert_eq!(parse_osc11_response("\x1b]11;rgb:ee/ee/ee\x1b\\\x1b[?62;c"), Some(Background::Light));
+        assert_eq!(parse_osc11_response("\x1b]11;rgb:24/27/3a\x07"), Some(Background::Dark));
+        assert_eq!(parse_osc11_response("\x1b[?62;c"), None);
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use crate::error::HadouError;
+use crate::waveform_viewer::{format_time, Signal, VcdData};
+
+// How timestamps count when comparing two dumps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiffMode {
+    // Every value change has to happen at the same time in both
+    #[default]
+    Strict,
+    // Only the order of each signal's values has to match, so a run that is
+    // the other one delayed (a changed clock phase, say) still compares equal
+    Sequence,
+}
+
+impl DiffMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            DiffMode::Strict => "strict timing",
+            DiffMode::Sequence => "sequence only",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            DiffMode::Strict => DiffMode::Sequence,
+            DiffMode::Sequence => DiffMode::Strict,
+        }
+    }
+}
+
+// The n-th value change of a signal in each dump. None on one side when
+// that dump has fewer changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    pub signal: String,
+    pub change: usize,
+    pub first: Option<(u64, String)>,
+    pub second: Option<(u64, String)>,
+}
+
+impl Mismatch {
+    // Earlier of the two times, used to find the first mismatch overall
+    fn time(&self) -> u64 {
+        self.first.iter().chain(&self.second).map(|(time, _)| *time).min().unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DiffReport {
+    pub mode: DiffMode,
+    pub timescales: (String, String),
+    pub signals_compared: usize,
+    pub only_in_first: Vec<String>,
+    pub only_in_second: Vec<String>,
+    // Changes to a different value, or a change one dump doesn't have
+    pub first_value_mismatch: Option<Mismatch>,
+    // The same value reached at a different time
+    pub first_timing_mismatch: Option<Mismatch>,
+    // How much later the second dump changes, when every matching change
+    // after time 0 moved by the same amount
+    pub timing_offset: Option<i64>,
+}
+
+impl DiffReport {
+    pub fn is_equivalent(&self) -> bool {
+        let same_signals = self.only_in_first.is_empty() && self.only_in_second.is_empty();
+        let timing_counts = self.mode == DiffMode::Strict && self.first_timing_mismatch.is_some();
+        same_signals && self.first_value_mismatch.is_none() && !timing_counts
+    }
+
+    pub fn verdict(&self) -> String {
+        if !self.is_equivalent() {
+            return "different".to_string();
+        }
+        match (self.first_timing_mismatch.is_some(), self.timing_offset) {
+            (false, _) => "identical".to_string(),
+            (true, Some(offset)) => format!("equivalent sequence, timing offset {}", format_time(offset, &self.timescales.1)),
+            (true, None) => "equivalent sequence, timing differs".to_string(),
+        }
+    }
+
+    // Lines for the message dialog, `names` being how to refer to the files
+    pub fn describe(&self, names: (&str, &str)) -> String {
+        let mut lines = vec![
+            format!("{} vs {} ({})", names.0, names.1, self.mode.label()),
+            format!("Result: {}", self.verdict()),
+            format!("Signals compared: {}", self.signals_compared),
+        ];
+        if self.timescales.0 != self.timescales.1 {
+            lines.push(format!(
+                "Timescales differ ({} vs {}); times are compared as written",
+                self.timescales.0, self.timescales.1
+            ));
+        }
+        for (name, only) in [(names.0, &self.only_in_first), (names.1, &self.only_in_second)] {
+            if !only.is_empty() {
+                lines.push(format!("Only in {}: {}", name, only.join(", ")));
+            }
+        }
+
+        lines.push(String::new());
+        lines.push(match &self.first_value_mismatch {
+            Some(mismatch) => format!("First value mismatch: {}", self.describe_mismatch(mismatch)),
+            None => "First value mismatch: none".to_string(),
+        });
+        lines.push(match &self.first_timing_mismatch {
+            Some(mismatch) => format!("First timing mismatch: {}", self.describe_mismatch(mismatch)),
+            None => "First timing mismatch: none".to_string(),
+        });
+        lines.join("\n")
+    }
+
+    fn describe_mismatch(&self, mismatch: &Mismatch) -> String {
+        let side = |change: &Option<(u64, String)>, timescale: &str| match change {
+            Some((time, value)) => format!("{} at {}", value, format_time(*time as i64, timescale)),
+            None => "no change".to_string(),
+        };
+        format!(
+            "{}, change {}: {} vs {}",
+            mismatch.signal,
+            mismatch.change + 1,
+            side(&mismatch.first, &self.timescales.0),
+            side(&mismatch.second, &self.timescales.1)
+        )
+    }
+}
+
+pub fn compare(first: &Path, second: &Path, mode: DiffMode) -> Result<DiffReport, HadouError> {
+    Ok(compare_data(&VcdData::from_path(first)?, &VcdData::from_path(second)?, mode))
+}
+
+// Signals are matched by hierarchical name and compared change by change
+pub fn compare_data(first: &VcdData, second: &VcdData, mode: DiffMode) -> DiffReport {
+    let first_signals = by_name(first);
+    let second_signals = by_name(second);
+    let first_names: BTreeSet<&String> = first_signals.keys().collect();
+    let second_names: BTreeSet<&String> = second_signals.keys().collect();
+
+    let mut value_mismatches = Vec::new();
+    let mut timing_mismatches = Vec::new();
+    let mut offsets = BTreeSet::new();
+
+    for name in first_names.intersection(&second_names) {
+        let a = changes(first_signals[*name]);
+        let b = changes(second_signals[*name]);
+
+        for index in 0..a.len().max(b.len()) {
+            let (x, y) = (a.get(index), b.get(index));
+            let mismatch = || Mismatch { signal: (*name).clone(), change: index, first: x.cloned(), second: y.cloned() };
+            match (x, y) {
+                (Some((time_a, value_a)), Some((time_b, value_b))) if value_a == value_b => {
+                    if time_a != time_b {
+                        timing_mismatches.push(mismatch());
+                    }
+                    // Initial values are dumped at 0 whatever the delay
+                    if *time_a != 0 || *time_b != 0 {
+                        offsets.insert(*time_b as i64 - *time_a as i64);
+                    }
+                }
+                _ => {
+                    // Everything after the first different value is out of step
+                    value_mismatches.push(mismatch());
+                    break;
+                }
+            }
+        }
+    }
+
+    let only = |names: &BTreeSet<&String>, other: &BTreeSet<&String>| names.difference(other).map(|name| (*name).clone()).collect();
+    let earliest = |mismatches: Vec<Mismatch>| mismatches.into_iter().min_by_key(|mismatch| (mismatch.time(), mismatch.signal.clone()));
+
+    DiffReport {
+        mode,
+        timescales: (first.timescale.clone(), second.timescale.clone()),
+        signals_compared: first_names.intersection(&second_names).count(),
+        only_in_first: only(&first_names, &second_names),
+        only_in_second: only(&second_names, &first_names),
+        first_value_mismatch: earliest(value_mismatches),
+        first_timing_mismatch: earliest(timing_mismatches),
+        timing_offset: (offsets.len() == 1).then(|| offsets.into_iter().next().unwrap_or_default()),
+    }
+}
+
+fn by_name(data: &VcdData) -> HashMap<String, &Signal> {
+    data.signals.iter().map(|signal| (signal.hierarchical_name(), signal)).collect()
+}
+
+// A signal's values without the repeats $dumpall and the like write
+fn changes(signal: &Signal) -> Vec<(u64, String)> {
+    let mut changes: Vec<(u64, String)> = Vec::new();
+    for (time, value) in &signal.values {
+        if changes.last().is_none_or(|(_, last)| last != value) {
+            changes.push((*time, value.clone()));
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dump(clock: &[u64], data: &[(u64, &str)]) -> VcdData {
+        let mut vcd = String::from("$timescale 1ns $end\n$scope module tb $end\n$var wire 1 ! clk $end\n$var wire 4 \" data $end\n$upscope $end\n$enddefinitions $end\n#0\n0!\nb0 \"\n");
+        let mut events: Vec<(u64, String)> = clock.iter().enumerate().map(|(i, time)| (*time, format!("{}!", (i + 1) % 2))).collect();
+        events.extend(data.iter().map(|(time, value)| (*time, format!("b{} \"", value))));
+        events.sort();
+        for (time, change) in events {
+            vcd.push_str(&format!("#{}\n{}\n", time, change));
+        }
+        vcd.parse().unwrap()
+    }
+
+    #[test]
+    fn delayed_run_is_an_equivalent_sequence_with_an_offset() {
+        let reference = dump(&[5, 10, 15], &[(10, "1"), (20, "11")]);
+        let delayed = dump(&[8, 13, 18], &[(13, "1"), (23, "11")]);
+
+        let strict = compare_data(&reference, &delayed, DiffMode::Strict);
+        assert_eq!(strict.verdict(), "different");
+        assert!(strict.first_value_mismatch.is_none());
+        assert_eq!(strict.first_timing_mismatch.as_ref().unwrap().signal, "tb.clk");
+
+        let sequence = compare_data(&reference, &delayed, DiffMode::Sequence);
+        assert_eq!(sequence.verdict(), "equivalent sequence, timing offset 3ns");
+        assert_eq!(compare_data(&reference, &reference, DiffMode::Strict).verdict(), "identical");
+
+        let broken = dump(&[8, 13, 18], &[(13, "1"), (23, "10")]);
+        let report = compare_data(&reference, &broken, DiffMode::Sequence);
+        assert_eq!(report.verdict(), "different");
+        let mismatch = report.first_value_mismatch.as_ref().unwrap();
+        assert_eq!((mismatch.signal.as_str(), mismatch.change), ("tb.data", 2));
+        assert_eq!(report.first_timing_mismatch.as_ref().unwrap().first, Some((5, "1".to_string())));
+        assert!(report.describe(("a.vcd", "b.vcd")).contains("tb.data, change 3: 11 at 20ns vs 10 at 23ns"));
+    }
+}
@@ -0,0 +1,845 @@
+// Lightweight helpers for working with Verilog source text. These are
+// deliberately not a full parser; they only need to understand enough of the
+// lexical structure (comments, strings, identifiers) to edit files safely.
+
+use std::ops::Range;
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '$'
+}
+
+// A simple (non-escaped) identifier such as a module or port name
+pub fn is_identifier(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars.next().is_some_and(is_identifier_start) && chars.all(is_identifier_char)
+}
+
+// Whether `name` appears in `text` as a whole identifier rather than as part
+// of a longer one
+pub fn contains_identifier(text: &str, name: &str) -> bool {
+    !name.is_empty() && text.match_indices(name).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + name.len()..].chars().next();
+        !before.is_some_and(is_identifier_char) && !after.is_some_and(is_identifier_char)
+    })
+}
+
+// Replace whole identifiers according to `renames`, leaving comments untouched.
+// String literals are only rewritten when their entire contents match one of
+// `string_renames` (e.g. the `$dumpfile("<name>.vcd")` argument).
+pub fn rename_identifiers(source: &str, renames: &[(String, String)], string_renames: &[(String, String)]) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let mut output = String::with_capacity(source.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        // Line comment
+        if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                output.push(chars[i]);
+                i += 1;
+            }
+            continue;
+        }
+
+        // Block comment
+        if c == '/' && chars.get(i + 1) == Some(&'*') {
+            output.push_str("/*");
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                output.push(chars[i]);
+                i += 1;
+            }
+            if i < chars.len() {
+                output.push_str("*/");
+                i += 2;
+            }
+            continue;
+        }
+
+        // String literal
+        if c == '"' {
+            let start = i + 1;
+            i += 1;
+            while i < chars.len() && chars[i] != '"' && chars[i] != '\n' {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            let end = i.min(chars.len());
+            let contents: String = chars[start..end].iter().collect();
+            let replaced = string_renames
+                .iter()
+                .find(|(old, _)| *old == contents)
+                .map(|(_, new)| new.clone())
+                .unwrap_or(contents);
+
+            output.push('"');
+            output.push_str(&replaced);
+            if i < chars.len() && chars[i] == '"' {
+                output.push('"');
+                i += 1;
+            }
+            continue;
+        }
+
+        // Identifier (system tasks like $display are skipped as a whole)
+        if is_identifier_start(c) || c == '$' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && is_identifier_char(chars[i]) {
+                i += 1;
+            }
+            let identifier: String = chars[start..i].iter().collect();
+            let replaced = renames
+                .iter()
+                .find(|(old, _)| *old == identifier)
+                .map(|(_, new)| new.as_str())
+                .unwrap_or(&identifier);
+            output.push_str(replaced);
+            continue;
+        }
+
+        // Numbers such as 8'hFF must not be mistaken for identifiers
+        if c.is_ascii_digit() || c == '\'' {
+            while i < chars.len() && (is_identifier_char(chars[i]) || chars[i] == '\'') {
+                output.push(chars[i]);
+                i += 1;
+            }
+            continue;
+        }
+
+        output.push(c);
+        i += 1;
+    }
+
+    output
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Number(String),
+    Symbol(char),
+}
+
+// Replace // and /* */ comments with spaces. Line breaks are kept and every
+// other character stays at the same position as in the original source.
+pub fn strip_comments(source: &str) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let mut output = String::with_capacity(source.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                output.push(' ');
+                i += 1;
+            }
+        } else if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+            let start = i;
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i = (i + 2).min(chars.len());
+            for c in &chars[start..i] {
+                output.push(if *c == '\n' { '\n' } else { ' ' });
+            }
+        } else if chars[i] == '"' {
+            // Keep strings intact so a "//" inside one isn't treated as a comment
+            output.push('"');
+            i += 1;
+            while i < chars.len() && chars[i] != '"' && chars[i] != '\n' {
+                if chars[i] == '\\' && i + 1 < chars.len() {
+                    output.push(chars[i]);
+                    i += 1;
+                }
+                output.push(chars[i]);
+                i += 1;
+            }
+            if i < chars.len() && chars[i] == '"' {
+                output.push('"');
+                i += 1;
+            }
+        } else {
+            output.push(chars[i]);
+            i += 1;
+        }
+    }
+
+    output
+}
+
+fn tokenize(source: &str) -> Vec<Token> {
+    tokenize_with_offsets(source).into_iter().map(|(token, _)| token).collect()
+}
+
+// Tokens paired with the byte offset where each one starts in `source`
+fn tokenize_with_offsets(source: &str) -> Vec<(Token, usize)> {
+    // Stripping keeps the character count, so character positions map
+    // straight back onto the original text
+    let chars: Vec<char> = strip_comments(source).chars().collect();
+    let byte_offsets: Vec<usize> = source.char_indices().map(|(offset, _)| offset).collect();
+    let offset_of = |i: usize| byte_offsets.get(i).copied().unwrap_or(source.len());
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '"' {
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            i += 1;
+        } else if is_identifier_start(c) || c == '$' || c == '`' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && is_identifier_char(chars[i]) {
+                i += 1;
+            }
+            tokens.push((Token::Word(chars[start..i].iter().collect()), offset_of(start)));
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.' || chars[i] == '_' || chars[i] == '\'') {
+                i += 1;
+            }
+            tokens.push((Token::Number(chars[start..i].iter().collect()), offset_of(start)));
+        } else {
+            tokens.push((Token::Symbol(c), offset_of(i)));
+            i += 1;
+        }
+    }
+
+    tokens
+}
+
+fn delay_value(number: &str) -> Option<u64> {
+    let digits: String = number
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.' || *c == '_')
+        .filter(|c| *c != '_')
+        .collect();
+    digits.parse::<f64>().ok().map(|value| value.round() as u64)
+}
+
+// Rough estimate of when a testbench finishes: the largest sum of `#<delay>`
+// statements across `initial` blocks that call $finish. Blocks containing
+// `forever` never finish on their own and are ignored.
+pub fn estimate_simulation_end(source: &str) -> Option<u64> {
+    let tokens = tokenize(source);
+    let mut estimate: Option<u64> = None;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if tokens[i] != Token::Word("initial".to_string()) {
+            i += 1;
+            continue;
+        }
+
+        // Find the extent of the block: begin/end (or fork/join) pairs, or a
+        // single statement terminated by ';'
+        let start = i + 1;
+        let mut end = start;
+        let mut depth = 0usize;
+        while end < tokens.len() {
+            match &tokens[end] {
+                Token::Word(word) if word == "begin" || word == "fork" => depth += 1,
+                Token::Word(word) if word == "end" || word.starts_with("join") => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                Token::Symbol(';') if depth == 0 => break,
+                _ => {}
+            }
+            end += 1;
+        }
+
+        let block = &tokens[start..end.min(tokens.len())];
+        let finishes = block.contains(&Token::Word("$finish".to_string()));
+        let loops_forever = block.contains(&Token::Word("forever".to_string()));
+
+        if finishes && !loops_forever {
+            let mut total = 0u64;
+            for (j, token) in block.iter().enumerate() {
+                if *token != Token::Symbol('#') {
+                    continue;
+                }
+                let delay = match (block.get(j + 1), block.get(j + 2)) {
+                    (Some(Token::Number(number)), _) => delay_value(number),
+                    (Some(Token::Symbol('(')), Some(Token::Number(number))) => delay_value(number),
+                    _ => None,
+                };
+                total += delay.unwrap_or(0);
+            }
+            estimate = Some(estimate.map_or(total, |current| current.max(total)));
+        }
+
+        i = end + 1;
+    }
+
+    estimate.filter(|total| *total > 0)
+}
+
+// Extract a simulation time from output such as "Time=120", "time: 120" or
+// "TIME = 120 ns"
+pub fn parse_simulation_time(line: &str) -> Option<u64> {
+    let lower = line.to_lowercase();
+    let mut search_from = 0;
+
+    while let Some(position) = lower[search_from..].find("time") {
+        let after = search_from + position + 4;
+        let rest = lower[after..].trim_start();
+
+        if let Some(rest) = rest.strip_prefix('=').or_else(|| rest.strip_prefix(':')) {
+            let digits: String = rest.trim_start().chars().take_while(|c| c.is_ascii_digit()).collect();
+            if let Ok(time) = digits.parse::<u64>() {
+                return Some(time);
+            }
+        }
+
+        search_from = after;
+    }
+
+    None
+}
+
+// Byte ranges of the depth argument of every `$dumpvars(<depth>, ...)` call.
+// The depth is how many levels below each listed scope get dumped: 1 is the
+// scope's own signals only, 2 adds the instances directly inside it, and 0
+// means no limit.
+fn dump_depth_ranges(source: &str) -> Vec<Range<usize>> {
+    let tokens = tokenize_with_offsets(source);
+    tokens
+        .windows(4)
+        .filter_map(|window| match window {
+            [
+                (Token::Word(call), _),
+                (Token::Symbol('('), _),
+                (Token::Number(depth), offset),
+                (Token::Symbol(',' | ')'), _),
+            ] if call == "$dumpvars" && depth.chars().all(|c| c.is_ascii_digit()) => Some(*offset..*offset + depth.len()),
+            _ => None,
+        })
+        .collect()
+}
+
+// Depth of the first $dumpvars call that gives one
+pub fn dump_depth(source: &str) -> Option<u32> {
+    dump_depth_ranges(source).first().and_then(|range| source[range.clone()].parse().ok())
+}
+
+// The source with every $dumpvars depth set to `depth`, or None when there
+// is no $dumpvars call with a depth to change
+pub fn set_dump_depth(source: &str, depth: u32) -> Option<String> {
+    let ranges = dump_depth_ranges(source);
+    if ranges.is_empty() {
+        return None;
+    }
+
+    let mut updated = String::with_capacity(source.len());
+    let mut copied = 0;
+    for range in ranges {
+        updated.push_str(&source[copied..range.start]);
+        updated.push_str(&depth.to_string());
+        copied = range.end;
+    }
+    updated.push_str(&source[copied..]);
+    Some(updated)
+}
+
+// Byte range of the file name in the first `$dumpfile("...")` call outside
+// comments, quotes excluded
+fn dumpfile_range(source: &str) -> Option<Range<usize>> {
+    let offset = tokenize_with_offsets(source)
+        .into_iter()
+        .find_map(|(token, offset)| matches!(&token, Token::Word(call) if call == "$dumpfile").then_some(offset))?;
+
+    let call = &source[offset + "$dumpfile".len()..];
+    let argument = call.trim_start().strip_prefix('(')?.trim_start();
+    let name = argument.strip_prefix('"')?;
+    let start = source.len() - name.len();
+    let end = start + name.find('"')?;
+    Some(start..end)
+}
+
+// File the testbench's $dumpfile call writes the VCD to
+pub fn dumpfile_name(source: &str) -> Option<String> {
+    dumpfile_range(source).map(|range| source[range].to_string()).filter(|name| !name.is_empty())
+}
+
+// The source with the $dumpfile call writing to `name` instead, or None when
+// there is no $dumpfile call to change
+pub fn set_dumpfile_name(source: &str, name: &str) -> Option<String> {
+    let range = dumpfile_range(source)?;
+    Some(format!("{}{}{}", &source[..range.start], name, &source[range.end..]))
+}
+
+// Macro the compile screen's fast mode defines; generated testbenches only
+// dump a VCD when it is not set
+pub const NO_DUMP_DEFINE: &str = "NO_DUMP";
+
+// Whether the source has an `ifndef NO_DUMP guard for the fast mode to turn
+// its dump off with
+pub fn has_dump_guard(source: &str) -> bool {
+    tokenize(source).windows(2).any(|window| {
+        matches!(window, [Token::Word(directive), Token::Word(name)] if directive == "`ifndef" && name == NO_DUMP_DEFINE)
+    })
+}
+
+pub fn describe_dump_depth(depth: u32) -> String {
+    match depth {
+        0 => "0 (all levels)".to_string(),
+        1 => "1 (testbench signals only)".to_string(),
+        2 => "2 (testbench and the top module)".to_string(),
+        depth => format!("{} (testbench and {} levels of the design)", depth, depth - 1),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PortDirection {
+    Input,
+    Output,
+    Inout,
+}
+
+impl PortDirection {
+    fn parse(word: &str) -> Option<Self> {
+        match word {
+            "input" => Some(PortDirection::Input),
+            "output" => Some(PortDirection::Output),
+            "inout" => Some(PortDirection::Inout),
+            _ => None,
+        }
+    }
+
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            PortDirection::Input => "input",
+            PortDirection::Output => "output",
+            PortDirection::Inout => "inout",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Port {
+    pub name: String,
+    // Ports whose direction is never declared are treated as inputs
+    pub direction: PortDirection,
+    // Range as written, e.g. `[WIDTH-1:0]`; `None` for single-bit ports
+    pub width: Option<String>,
+    // Unpacked dimensions written after the name, e.g. `[0:3]`
+    pub dimensions: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleDecl {
+    pub name: String,
+    // Overridable parameters with their default values as written, from the
+    // header first and then the body
+    pub parameters: Vec<(String, String)>,
+    pub ports: Vec<Port>,
+}
+
+impl ModuleDecl {
+    pub fn has_port(&self, name: &str) -> bool {
+        self.ports.iter().any(|port| port.name == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PortConnections {
+    // `.port(signal)` style; `None` marks an explicitly empty `.port()`
+    Named(Vec<(String, Option<String>)>),
+    Positional(usize),
+    // `.*` connects everything implicitly
+    Wildcard,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instance {
+    pub module: String,
+    pub name: String,
+    pub connections: PortConnections,
+    // Byte range from the module name to the closing `;`
+    pub span: (usize, usize),
+    // Byte offset of the instance name, just after any parameter overrides
+    pub name_offset: usize,
+}
+
+// Index just past the group opened at `open` (which must be `(`), or the end
+// of the token list if it is never closed
+fn skip_parens(tokens: &[Token], open: usize) -> usize {
+    let mut depth = 0usize;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match token {
+            Token::Symbol('(') => depth += 1,
+            Token::Symbol(')') => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+// Split the tokens inside a parenthesised group at top-level commas
+fn split_group(tokens: &[Token], open: usize) -> Vec<&[Token]> {
+    let close = skip_parens(tokens, open).saturating_sub(1).max(open + 1);
+    split_top_level(&tokens[(open + 1).min(close)..close])
+}
+
+// Split at the commas that are not nested in brackets
+fn split_top_level(inner: &[Token]) -> Vec<&[Token]> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (i, token) in inner.iter().enumerate() {
+        match token {
+            Token::Symbol('(') | Token::Symbol('[') | Token::Symbol('{') => depth += 1,
+            Token::Symbol(')') | Token::Symbol(']') | Token::Symbol('}') => depth = depth.saturating_sub(1),
+            Token::Symbol(',') if depth == 0 => {
+                parts.push(&inner[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < inner.len() {
+        parts.push(&inner[start..]);
+    }
+
+    parts
+}
+
+// Module declarations with their ports. Handles both ANSI headers
+// (`input wire [7:0] a, b`) and the older name-only lists, whose directions
+// and widths are then taken from declarations in the module body.
+pub fn parse_modules(source: &str) -> Vec<ModuleDecl> {
+    let tokens = tokenize(source);
+    let mut modules = Vec::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if tokens[i] != Token::Word("module".to_string()) {
+            i += 1;
+            continue;
+        }
+
+        let Some(Token::Word(name)) = tokens.get(i + 1) else {
+            i += 1;
+            continue;
+        };
+
+        let mut j = i + 2;
+        let mut parameters = Vec::new();
+        // Parameter list: #( ... )
+        if tokens.get(j) == Some(&Token::Symbol('#')) && tokens.get(j + 1) == Some(&Token::Symbol('(')) {
+            parameters.extend(split_group(&tokens, j + 1).into_iter().filter_map(parse_parameter));
+            j = skip_parens(&tokens, j + 1);
+        }
+
+        let mut ports = Vec::new();
+        if tokens.get(j) == Some(&Token::Symbol('(')) {
+            // In ANSI headers a direction and range carry over to the
+            // following names until the next direction keyword
+            let mut direction = None;
+            let mut width = None;
+            for part in split_group(&tokens, j) {
+                let declaration = parse_declaration(part);
+                if declaration.direction.is_some() {
+                    direction = declaration.direction;
+                    width = declaration.width;
+                } else if declaration.width.is_some() {
+                    width = declaration.width;
+                }
+                let dimensions = declaration.dimensions.into_iter().last().flatten();
+                if let Some(name) = declaration.names.into_iter().last() {
+                    ports.push(Port {
+                        name,
+                        // Filled in below for non-ANSI headers
+                        direction: direction.unwrap_or(PortDirection::Input),
+                        width: width.clone(),
+                        dimensions,
+                    });
+                }
+            }
+            j = skip_parens(&tokens, j);
+        }
+
+        let body_end = tokens[j.min(tokens.len())..]
+            .iter()
+            .position(|token| *token == Token::Word("endmodule".to_string()))
+            .map(|position| j + position)
+            .unwrap_or(tokens.len());
+
+        // Non-ANSI style: `input [7:0] a, b;` statements in the body
+        let mut k = j;
+        while k < body_end {
+            let statement_end = tokens[k..body_end]
+                .iter()
+                .position(|token| *token == Token::Symbol(';'))
+                .map(|position| k + position)
+                .unwrap_or(body_end);
+            let statement = &tokens[k..statement_end];
+            if statement.first() == Some(&Token::Word("parameter".to_string())) {
+                for parameter in split_top_level(statement).into_iter().filter_map(parse_parameter) {
+                    if !parameters.iter().any(|(existing, _)| *existing == parameter.0) {
+                        parameters.push(parameter);
+                    }
+                }
+            }
+
+            let declaration = parse_declaration(statement);
+            if let Some(direction) = declaration.direction {
+                for (declared, dimensions) in declaration.names.iter().zip(&declaration.dimensions) {
+                    if let Some(port) = ports.iter_mut().find(|port| port.name == *declared) {
+                        port.direction = direction;
+                        port.width = declaration.width.clone();
+                        port.dimensions = dimensions.clone();
+                    }
+                }
+            }
+            k = statement_end + 1;
+        }
+
+        modules.push(ModuleDecl {
+            name: name.clone(),
+            parameters,
+            ports,
+        });
+        i = body_end + 1;
+    }
+
+    modules
+}
+
+// `NAME = value` from a parameter list or declaration
+fn parse_parameter(part: &[Token]) -> Option<(String, String)> {
+    let equals = part.iter().position(|token| *token == Token::Symbol('='))?;
+    let Some(Token::Word(parameter)) = part[..equals].last() else {
+        return None;
+    };
+    let value = part[equals + 1..].iter().map(token_text).collect::<String>();
+    Some((parameter.clone(), value))
+}
+
+struct Declaration {
+    direction: Option<PortDirection>,
+    width: Option<String>,
+    names: Vec<String>,
+    // Unpacked dimensions following each of `names`
+    dimensions: Vec<Option<String>>,
+}
+
+const DECLARATION_KEYWORDS: [&str; 10] = [
+    "wire", "reg", "logic", "signed", "unsigned", "integer", "tri", "var", "parameter", "localparam",
+];
+
+// Pieces of a declaration like `output reg [7:0] q, r = 0`. Everything after
+// an `=` is an initialiser, so only names before it are collected. Ranges
+// before the names are the (packed) width, ranges after a name its unpacked
+// dimensions; several in a row are kept together, e.g. `[3:0][7:0]`.
+fn parse_declaration(tokens: &[Token]) -> Declaration {
+    let mut declaration = Declaration {
+        direction: None,
+        width: None,
+        names: Vec::new(),
+        dimensions: Vec::new(),
+    };
+    let mut depth = 0usize;
+    let mut range = String::new();
+    let mut in_initialiser = false;
+
+    for token in tokens {
+        match token {
+            Token::Symbol('[') => {
+                depth += 1;
+                if depth == 1 {
+                    range.clear();
+                }
+                range.push('[');
+            }
+            Token::Symbol(']') => {
+                depth = depth.saturating_sub(1);
+                range.push(']');
+                if depth == 0 && !in_initialiser {
+                    let target = match declaration.dimensions.last_mut() {
+                        Some(dimensions) => dimensions,
+                        None => &mut declaration.width,
+                    };
+                    target.get_or_insert_with(String::new).push_str(&range);
+                }
+            }
+            _ if depth > 0 => range.push_str(&token_text(token)),
+            Token::Symbol(',') => in_initialiser = false,
+            Token::Symbol('=') => in_initialiser = true,
+            Token::Word(word) if !in_initialiser => {
+                if let Some(direction) = PortDirection::parse(word) {
+                    declaration.direction = Some(direction);
+                } else if !DECLARATION_KEYWORDS.contains(&word.as_str()) {
+                    declaration.names.push(word.clone());
+                    declaration.dimensions.push(None);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    declaration
+}
+
+fn token_text(token: &Token) -> String {
+    match token {
+        Token::Word(word) | Token::Number(word) => word.clone(),
+        Token::Symbol(c) => c.to_string(),
+    }
+}
+
+// Names of the regs, wires and other variables declared in `source`
+pub fn declared_signals(source: &str) -> Vec<String> {
+    let tokens = tokenize(source);
+    let mut names = Vec::new();
+
+    for statement in tokens.split(|token| *token == Token::Symbol(';')) {
+        let declares = matches!(
+            statement.first(),
+            Some(Token::Word(word)) if DECLARATION_KEYWORDS.contains(&word.as_str()) || PortDirection::parse(word).is_some()
+        );
+        if declares {
+            names.extend(parse_declaration(statement).names);
+        }
+    }
+
+    names
+}
+
+// Instances of any of `module_names` in `source`
+pub fn find_instances(source: &str, module_names: &[String]) -> Vec<Instance> {
+    let (tokens, offsets): (Vec<Token>, Vec<usize>) = tokenize_with_offsets(source).into_iter().unzip();
+    let mut instances = Vec::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        let Token::Word(module) = token else {
+            continue;
+        };
+        if !module_names.contains(module) {
+            continue;
+        }
+        // Skip the module's own declaration
+        if i > 0 && tokens[i - 1] == Token::Word("module".to_string()) {
+            continue;
+        }
+
+        let mut j = i + 1;
+        if tokens.get(j) == Some(&Token::Symbol('#')) && tokens.get(j + 1) == Some(&Token::Symbol('(')) {
+            j = skip_parens(&tokens, j + 1);
+        }
+
+        let Some(Token::Word(name)) = tokens.get(j) else {
+            continue;
+        };
+        if tokens.get(j + 1) != Some(&Token::Symbol('(')) {
+            continue;
+        }
+
+        let parts: Vec<&[Token]> = split_group(&tokens, j + 1)
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect();
+
+        let connections = if parts.iter().any(|part| part.first() == Some(&Token::Symbol('.')) && part.get(1) == Some(&Token::Symbol('*'))) {
+            PortConnections::Wildcard
+        } else if parts.iter().all(|part| part.first() == Some(&Token::Symbol('.'))) && !parts.is_empty() {
+            let named = parts
+                .iter()
+                .filter_map(|part| match (part.get(1), part.get(2)) {
+                    (Some(Token::Word(port)), Some(Token::Symbol('('))) => {
+                        let signal = part[3..]
+                            .iter()
+                            .take_while(|token| **token != Token::Symbol(')'))
+                            .map(token_text)
+                            .collect::<String>();
+                        Some((port.clone(), Some(signal).filter(|signal| !signal.is_empty())))
+                    }
+                    (Some(Token::Word(port)), _) => Some((port.clone(), None)),
+                    _ => None,
+                })
+                .collect();
+            PortConnections::Named(named)
+        } else {
+            PortConnections::Positional(parts.len())
+        };
+
+        // Up to and including the `;` after the connection list
+        let close = skip_parens(&tokens, j + 1);
+        let end = match tokens.get(close) {
+            Some(Token::Symbol(';')) => offsets[close] + 1,
+            _ => offsets.get(close).copied().unwrap_or(source.len()),
+        };
+
+        instances.push(Instance {
+            module: module.clone(),
+            name: name.clone(),
+            connections,
+            span: (offsets[i], end),
+            name_offset: offsets[j],
+        });
+    }
+
+    instances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renames_whole_identifiers_outside_comments() {
+        let source = "\
+// alu is the project's top module
+module alu_test;
+  /* alu */ reg alu_en;
+  alu uut (.en(alu_en));
+  initial $dumpfile(\"alu.vcd\");
+  initial $display(\"alu.vcd done\");
+endmodule
+";
+        let renames = [("alu".to_string(), "core".to_string()), ("alu_test".to_string(), "core_test".to_string())];
+        let string_renames = [("alu.vcd".to_string(), "core.vcd".to_string())];
+
+        assert_eq!(
+            rename_identifiers(source, &renames, &string_renames),
+            "\
+// alu is the project's top module
+module core_test;
+  /* alu */ reg alu_en;
+  core uut (.en(alu_en));
+  initial $dumpfile(\"core.vcd\");
+  initial $display(\"alu.vcd done\");
+endmodule
+"
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use crate::error::HadouError;
+use crate::language::Language;
+use crate::scanner::{IgnoreRules, ScanOptions, IGNORE_FILE_NAME};
+use crate::waveform_viewer;
+
+// Changes are collected until things have been quiet for this long, so a
+// build writing dozens of files causes one re-scan instead of dozens
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+// Which lists need scanning again after a burst of filesystem changes
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WatchChange {
+    pub projects: bool,
+    pub vcd_files: bool,
+}
+
+impl WatchChange {
+    fn is_empty(&self) -> bool {
+        !self.projects && !self.vcd_files
+    }
+
+    fn merge(&mut self, other: WatchChange) {
+        self.projects |= other.projects;
+        self.vcd_files |= other.vcd_files;
+    }
+}
+
+// Watches the base directory for files and projects appearing or going away.
+// notify delivers events on its own thread; they are only queued there and
+// turned into re-scans by `poll` on the UI thread.
+pub struct DirectoryWatcher {
+    // Kept alive for as long as events are wanted
+    _watcher: RecommendedWatcher,
+    receiver: Receiver<notify::Result<Event>>,
+    root: PathBuf,
+    pub scan_options: ScanOptions,
+    pending: WatchChange,
+    last_event: Option<Instant>,
+}
+
+impl std::fmt::Debug for DirectoryWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DirectoryWatcher")
+            .field("root", &self.root)
+            .field("pending", &self.pending)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DirectoryWatcher {
+    pub fn new(root: &Path, scan_options: ScanOptions) -> Result<Self, HadouError> {
+        let root = root.canonicalize()?;
+        let (sender, receiver) = mpsc::channel();
+
+        // Symlinked directories are watched only when scans go into them
+        let config = notify::Config::default().with_follow_symlinks(scan_options.follow_symlinks);
+        let mut watcher = RecommendedWatcher::new(
+            move |event| {
+                let _ = sender.send(event);
+            },
+            config,
+        )?;
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+
+        Ok(Self {
+            _watcher: watcher,
+            receiver,
+            root,
+            scan_options,
+            pending: WatchChange::default(),
+            last_event: None,
+        })
+    }
+
+    // Returns the accumulated change once no new events have arrived for
+    // the debounce interval
+    pub fn poll(&mut self) -> Option<WatchChange> {
+        for event in self.receiver.try_iter().flatten() {
+            let change = self.classify(&event);
+            if !change.is_empty() {
+                self.pending.merge(change);
+                self.last_event = Some(Instant::now());
+            }
+        }
+
+        let quiet = self.last_event.is_some_and(|last| last.elapsed() >= DEBOUNCE);
+        if !quiet {
+            return None;
+        }
+
+        self.last_event = None;
+        Some(std::mem::take(&mut self.pending))
+    }
+
+    fn classify(&self, event: &Event) -> WatchChange {
+        // Content changes don't add or remove list entries
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(_))) {
+            return WatchChange::default();
+        }
+
+        let ignore = IgnoreRules::new(&self.root, self.scan_options.ignore_patterns.clone());
+        let mut change = WatchChange::default();
+
+        for path in &event.paths {
+            let Ok(relative) = path.strip_prefix(&self.root) else {
+                continue;
+            };
+
+            // Files directly inside the deepest scanned directories still count
+            if relative.components().count() > self.scan_options.max_depth + 1 || ignore.is_ignored(path) {
+                continue;
+            }
+
+            let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+
+            if file_name == IGNORE_FILE_NAME {
+                change.projects = true;
+                change.vcd_files = true;
+            } else if waveform_viewer::is_vcd_file(path) {
+                change.vcd_files = true;
+            } else if path.is_dir() || path.extension().is_none() || Language::of_file(path).is_some() {
+                // Removed paths can't be checked, so anything without an
+                // extension may have been a project directory or Justfile
+                change.projects = true;
+            }
+        }
+
+        change
+    }
+}
+
+// Watches a single project for edits to its HDL sources, for re-running
+// a compile action on every save
+pub struct SourceWatcher {
+    _watcher: RecommendedWatcher,
+    receiver: Receiver<notify::Result<Event>>,
+    last_event: Option<Instant>,
+}
+
+impl std::fmt::Debug for SourceWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SourceWatcher")
+            .field("last_event", &self.last_event)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SourceWatcher {
+    pub fn new(project: &Path) -> Result<Self, HadouError> {
+        let (sender, receiver) = mpsc::channel();
+
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = sender.send(event);
+        })?;
+        watcher.watch(project, RecursiveMode::Recursive)?;
+
+        Ok(Self {
+            _watcher: watcher,
+            receiver,
+            last_event: None,
+        })
+    }
+
+    // True once a burst of source changes has settled
+    pub fn poll(&mut self) -> bool {
+        for event in self.receiver.try_iter().flatten() {
+            let touches_sources = !matches!(event.kind, EventKind::Access(_))
+                && event.paths.iter().any(|path| is_source_file(path));
+            if touches_sources {
+                self.last_event = Some(Instant::now());
+            }
+        }
+
+        let quiet = self.last_event.is_some_and(|last| last.elapsed() >= DEBOUNCE);
+        if quiet {
+            self.last_event = None;
+        }
+        quiet
+    }
+}
+
+// Design sources, plus the Verilog headers they may include
+fn is_source_file(path: &Path) -> bool {
+    Language::of_file(path).is_some() || matches!(path.extension().and_then(|ext| ext.to_str()), Some("vh" | "svh"))
+}
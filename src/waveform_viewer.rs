@@ -1,14 +1,544 @@
-use std::fs;
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use crate::error::HadouError;
+use crate::list_window::ListWindow;
+use crate::scanner::ScanOptions;
+
+// Smallest number of time units the chart will show
+const MIN_TIME_WINDOW: u64 = 10;
+
+// Dumps at least this big (once decompressed) take long enough to load that
+// the built-in viewer asks first
+pub const LARGE_VCD_BYTES: u64 = 100 * 1024 * 1024;
+
+// How much a background load reads between progress updates
+const LOAD_CHUNK_BYTES: usize = 256 * 1024;
+
+// Lines parsed between progress updates and checks for cancellation
+const LOAD_CHECK_LINES: usize = 4096;
+
+// Skipped lines kept for the parse warnings panel; the rest are only counted
+const MAX_PARSE_WARNINGS: usize = 500;
+
+// Bus values are shown in hex; values with unknown or high-Z bits are shown
+// as `x`/`z` when uniform and as raw binary otherwise
+pub fn format_bus_value(value: &str) -> String {
+    let value = value.trim_start_matches(['b', 'B']);
+
+    if !value.is_empty() && value.chars().all(|c| c == '0' || c == '1') {
+        let trimmed = value.trim_start_matches('0');
+        if trimmed.len() <= 128 {
+            return format!("0x{:X}", u128::from_str_radix(trimmed, 2).unwrap_or(0));
+        }
+        return value.to_string();
+    }
+
+    for state in ['x', 'z'] {
+        if !value.is_empty() && value.chars().all(|c| c.eq_ignore_ascii_case(&state)) {
+            return state.to_string();
+        }
+    }
+
+    value.to_string()
+}
+
+// How bus values are written in the signal list, on the chart and in the
+// value table
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Radix {
+    #[default]
+    Hex,
+    Decimal,
+    Binary,
+}
+
+impl Radix {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Radix::Hex => "hex",
+            Radix::Decimal => "decimal",
+            Radix::Binary => "binary",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Radix::Hex => Radix::Decimal,
+            Radix::Decimal => Radix::Binary,
+            Radix::Binary => Radix::Hex,
+        }
+    }
+
+    // Values with x or z bits are written the way `format_bus_value` writes
+    // them in every radix, as are reals
+    pub fn format(&self, value: &str) -> String {
+        let bits = value.trim_start_matches(['b', 'B']);
+        let known = !bits.is_empty() && bits.chars().all(|c| c == '0' || c == '1');
+        match self {
+            Radix::Hex => format_bus_value(value),
+            Radix::Decimal if known && bits.trim_start_matches('0').len() <= 128 => {
+                u128::from_str_radix(bits, 2).unwrap_or(0).to_string()
+            }
+            Radix::Binary if bits.chars().all(|c| matches!(c, '0' | '1' | 'x' | 'X' | 'z' | 'Z')) && !bits.is_empty() => {
+                bits.to_ascii_lowercase()
+            }
+            _ => format_bus_value(value),
+        }
+    }
+}
+
+// A value searched for with the viewer's find. Numbers are read in the
+// current radix unless written with 0x, 0d or 0b, or Verilog style (8'h3f).
+// Binary and hex targets may contain x and z digits, which have to match
+// exactly.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueTarget {
+    Number(u128),
+    Bits(String),
+    Real(f64),
+}
+
+impl ValueTarget {
+    pub fn parse(text: &str, radix: Radix) -> Option<Self> {
+        let text = text.trim().replace('_', "").to_ascii_lowercase();
+        let (radix, digits) = if let Some((_, based)) = text.split_once('\'') {
+            let radix = match based.chars().next()? {
+                'h' => Radix::Hex,
+                'd' => Radix::Decimal,
+                'b' => Radix::Binary,
+                _ => return None,
+            };
+            (radix, &based[1..])
+        } else if let Some(digits) = text.strip_prefix("0x") {
+            (Radix::Hex, digits)
+        } else if let Some(digits) = text.strip_prefix("0b") {
+            (Radix::Binary, digits)
+        } else if let Some(digits) = text.strip_prefix("0d") {
+            (Radix::Decimal, digits)
+        } else {
+            (radix, text.as_str())
+        };
+        if digits.is_empty() {
+            return None;
+        }
+
+        let bits: String = match radix {
+            Radix::Decimal if digits == "x" || digits == "z" => digits.to_string(),
+            Radix::Decimal if digits.contains('.') => return digits.parse().ok().map(ValueTarget::Real),
+            Radix::Decimal => return digits.parse().ok().map(ValueTarget::Number),
+            Radix::Binary if digits.chars().all(|c| matches!(c, '0' | '1' | 'x' | 'z')) => digits.to_string(),
+            Radix::Binary => return None,
+            Radix::Hex => {
+                let mut bits = String::new();
+                for digit in digits.chars() {
+                    match digit {
+                        'x' | 'z' => bits.extend([digit; 4]),
+                        _ => bits.push_str(&format!("{:04b}", digit.to_digit(16)?)),
+                    }
+                }
+                bits
+            }
+        };
+
+        if bits.contains(['x', 'z']) {
+            return Some(ValueTarget::Bits(bits));
+        }
+        bits_to_number(&bits).map(ValueTarget::Number)
+    }
+
+    // How the search dialog confirms what it will look for
+    pub fn describe(&self) -> String {
+        match self {
+            ValueTarget::Number(number) => format!("{} (0x{:x})", number, number),
+            ValueTarget::Bits(bits) => format!("bits {}", bits),
+            ValueTarget::Real(real) => real.to_string(),
+        }
+    }
+
+    pub fn matches(&self, value: &str) -> bool {
+        let bits = value.trim_start_matches(['b', 'B']);
+        let is_bits = !bits.is_empty() && bits.chars().all(|c| matches!(c, '0' | '1' | 'x' | 'X' | 'z' | 'Z'));
+        match self {
+            ValueTarget::Number(target) if is_bits => bits_to_number(bits) == Some(*target),
+            ValueTarget::Number(target) => value.parse::<f64>().ok() == Some(*target as f64),
+            // Compared bit by bit, padded the way VCD pads short values
+            ValueTarget::Bits(target) if is_bits => {
+                (0..bits.len().max(target.len())).all(|index| bit_of_value(bits, index) == bit_of_value(target, index))
+            }
+            ValueTarget::Bits(_) => false,
+            ValueTarget::Real(target) => value.parse::<f64>().ok() == Some(*target),
+        }
+    }
+}
+
+// Known bits as a number, when they fit
+fn bits_to_number(bits: &str) -> Option<u128> {
+    let significant = bits.trim_start_matches('0');
+    u128::from_str_radix(if significant.is_empty() { "0" } else { significant }, 2).ok()
+}
+
+// Numeric reading of a VCD value. Unknown (`x`) and high-Z (`z`) bits count
+// as 0 towards the magnitude and are flagged so the chart can mark the value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumericValue {
+    pub magnitude: f64,
+    pub has_unknown: bool,
+}
+
+pub fn parse_numeric_value(value: &str) -> NumericValue {
+    let bits = value.trim_start_matches(['b', 'B']);
+
+    if !bits.is_empty() && bits.chars().all(|c| matches!(c, '0' | '1' | 'x' | 'X' | 'z' | 'Z')) {
+        // Accumulate as a float so buses wider than 64 bits don't overflow
+        let magnitude = bits.chars().fold(0.0, |acc, c| acc * 2.0 + if c == '1' { 1.0 } else { 0.0 });
+        let has_unknown = bits.chars().any(|c| !matches!(c, '0' | '1'));
+        return NumericValue { magnitude, has_unknown };
+    }
+
+    // Real values are written as `r1.25`
+    let magnitude = value.trim_start_matches(['r', 'R']).parse::<f64>().unwrap_or(0.0);
+    NumericValue { magnitude, has_unknown: false }
+}
+
+// One bit of a vector value, counting from the right. Values shorter than
+// the vector are padded the way VCD pads them: with x or z when the leftmost
+// bit is x or z, otherwise with 0. None for values that aren't bits (reals).
+pub fn bit_of_value(value: &str, index: usize) -> Option<char> {
+    let bits = value.trim_start_matches(['b', 'B']);
+    if bits.is_empty() || !bits.chars().all(|c| matches!(c, '0' | '1' | 'x' | 'X' | 'z' | 'Z')) {
+        return None;
+    }
+
+    let pad = bits.chars().next().filter(|c| matches!(c, 'x' | 'X' | 'z' | 'Z')).unwrap_or('0');
+    let bit = bits.chars().rev().nth(index).unwrap_or(pad);
+    Some(bit.to_ascii_lowercase())
+}
+
+// A timescale such as "10 ns" or "1ps" split into its multiplier and unit
+fn split_timescale(timescale: &str) -> (u64, &str) {
+    let timescale = timescale.trim();
+    let digits = timescale.find(|c: char| !c.is_ascii_digit()).unwrap_or(timescale.len());
+    let multiplier = timescale[..digits].parse().unwrap_or(1);
+    (multiplier, timescale[digits..].trim())
+}
+
+// A time in timescale units as the ruler labels it, e.g. 25 at "10 ns" is
+// "250ns". Negative times come from measuring relative to a cursor.
+pub fn format_time(time: i64, timescale: &str) -> String {
+    let (multiplier, unit) = split_timescale(timescale);
+    format!("{}{}", time.saturating_mul(multiplier as i64), unit)
+}
+
+// Distance between ruler ticks: the smallest 1, 2 or 5 times a power of ten
+// that puts no more than `max_ticks` ticks across `span`
+pub fn ruler_step(span: u64, max_ticks: usize) -> u64 {
+    let max_ticks = max_ticks.max(1) as u64;
+    let mut power = 1u64;
+
+    loop {
+        for factor in [1, 2, 5] {
+            let step = power.saturating_mul(factor);
+            if span / step < max_ticks || step == u64::MAX {
+                return step;
+            }
+        }
+        power = power.saturating_mul(10);
+    }
+}
+
+// Ruler ticks from `start` to `end`, at whole steps away from `origin`
+pub fn ruler_ticks(start: u64, end: u64, origin: u64, step: u64) -> Vec<u64> {
+    let step = step.max(1);
+    let offset = origin % step;
+    let first = match start.checked_sub(offset) {
+        Some(aligned) => aligned.div_ceil(step) * step + offset,
+        None => offset,
+    };
+
+    let mut ticks = Vec::new();
+    let mut tick = first;
+    while tick <= end {
+        ticks.push(tick);
+        match tick.checked_add(step) {
+            Some(next) => tick = next,
+            None => break,
+        }
+    }
+    ticks
+}
+
+// External viewers Hadou knows how to start, in the order they are tried
+pub const EXTERNAL_VIEWERS: [&str; 3] = ["dwfv", "digisurf", "gtkwave"];
+
+pub fn external_viewer_command(viewer: &str, file: &Path) -> Command {
+    let mut command = Command::new(viewer);
+    if viewer == "digisurf" {
+        command.arg("-f");
+    }
+    command.arg(file);
+    command
+}
+
+// Both plain `.vcd` files and gzipped `.vcd.gz` files are picked up
+pub fn is_vcd_file(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+    name.ends_with(".vcd") || name.ends_with(".vcd.gz")
+}
+
+pub fn is_compressed(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("gz"))
+}
+
+// Read a VCD file, decompressing it first if it is gzipped
+pub fn read_vcd_file(path: &Path) -> Result<String, HadouError> {
+    if !is_compressed(path) {
+        return Ok(fs::read_to_string(path)?);
+    }
+
+    let mut content = String::new();
+    open_gzip(path)?
+        .read_to_string(&mut content)
+        .map_err(|e| gzip_error(path, e))?;
+    Ok(content)
+}
+
+// read_vcd_file in chunks, reporting each one to `progress` and stopping
+// with HadouError::Cancelled once it returns false
+fn read_vcd_file_with_progress<F>(path: &Path, mut progress: F) -> Result<String, HadouError>
+where
+    F: FnMut(u64) -> bool,
+{
+    let compressed = is_compressed(path);
+    let mut reader: Box<dyn Read> = if compressed {
+        Box::new(open_gzip(path)?)
+    } else {
+        Box::new(File::open(path)?)
+    };
+
+    let mut content = Vec::new();
+    let mut chunk = vec![0u8; LOAD_CHUNK_BYTES];
+    loop {
+        let read = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) if compressed => return Err(gzip_error(path, e)),
+            Err(e) => return Err(e.into()),
+        };
+        content.extend_from_slice(&chunk[..read]);
+        if !progress(content.len() as u64) {
+            return Err(HadouError::Cancelled);
+        }
+    }
+
+    String::from_utf8(content)
+        .map_err(|_| HadouError::VcdParse(format!("{} is not a text file (invalid UTF-8)", path.display())))
+}
+
+// Number of `$var` declarations, counted from the header alone so it stays
+// quick however many value changes follow `$enddefinitions`
+pub fn count_signals(path: &Path) -> Result<usize, HadouError> {
+    let reader: Box<dyn Read> = if is_compressed(path) {
+        Box::new(open_gzip(path)?)
+    } else {
+        Box::new(File::open(path)?)
+    };
+
+    let mut count = 0;
+    for line in BufReader::new(reader).split(b'\n') {
+        let line = line.map_err(|e| if is_compressed(path) { gzip_error(path, e) } else { e.into() })?;
+        for token in String::from_utf8_lossy(&line).split_whitespace() {
+            match token {
+                "$var" => count += 1,
+                "$enddefinitions" => return Ok(count),
+                _ => {}
+            }
+        }
+    }
+
+    Err(HadouError::VcdParse(format!("{}: missing $enddefinitions", path.display())))
+}
+
+// Bytes the parser will have to read. Gzip records the original size (modulo
+// 4 GiB) in its last four bytes, so compressed files aren't unpacked to find out.
+pub fn loaded_size(path: &Path) -> u64 {
+    let on_disk = fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0);
+    if !is_compressed(path) {
+        return on_disk;
+    }
+
+    let mut trailer = [0u8; 4];
+    let read_trailer = File::open(path).and_then(|mut file| {
+        file.seek(SeekFrom::End(-4))?;
+        file.read_exact(&mut trailer)
+    });
+    match read_trailer {
+        Ok(()) => u64::from(u32::from_le_bytes(trailer)).max(on_disk),
+        Err(_) => on_disk,
+    }
+}
+
+// What the waveform list shows about a file without loading it
+#[derive(Debug, Clone, PartialEq)]
+pub struct VcdSummary {
+    // None when the header can't be read
+    pub signals: Option<usize>,
+    pub loaded_size: u64,
+}
+
+impl VcdSummary {
+    pub fn read(path: &Path) -> Self {
+        Self {
+            signals: count_signals(path).ok(),
+            loaded_size: loaded_size(path),
+        }
+    }
+
+    pub fn is_large(&self) -> bool {
+        self.loaded_size >= LARGE_VCD_BYTES
+    }
+}
+
+// External viewers don't all understand gzip, so hand them a decompressed
+// copy in the temp directory instead
+pub fn decompress_to_temp(path: &Path) -> Result<PathBuf, HadouError> {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let target = env::temp_dir().join(format!("hadou-{}-{}", std::process::id(), stem));
+
+    let mut decoder = open_gzip(path)?;
+    let mut output = File::create(&target)?;
+    if let Err(e) = io::copy(&mut decoder, &mut output) {
+        let _ = fs::remove_file(&target);
+        return Err(gzip_error(path, e));
+    }
+
+    Ok(target)
+}
+
+fn open_gzip(path: &Path) -> Result<GzDecoder<File>, HadouError> {
+    // Check the magic bytes up front so a mislabelled file isn't reported as truncated
+    let mut magic = [0u8; 2];
+    let is_gzip = File::open(path)?.read_exact(&mut magic).is_ok() && magic == [0x1f, 0x8b];
+    if !is_gzip {
+        return Err(HadouError::VcdParse(format!("{} is not a gzip-compressed file", path.display())));
+    }
+
+    Ok(GzDecoder::new(File::open(path)?))
+}
+
+fn gzip_error(path: &Path, error: io::Error) -> HadouError {
+    HadouError::VcdParse(match error.kind() {
+        io::ErrorKind::UnexpectedEof => format!("{} is truncated (unexpected end of gzip data)", path.display()),
+        io::ErrorKind::InvalidData | io::ErrorKind::InvalidInput => {
+            format!("{} is not a valid gzip-compressed VCD file: {}", path.display(), error)
+        }
+        _ => format!("Failed to decompress {}: {}", path.display(), error),
+    })
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Signal {
     pub name: String,
+    pub scope: String, // Dot-separated enclosing scopes, e.g. "top_test.uut"
     pub identifier: String,
     pub width: usize,
-    pub values: Vec<(u64, String)>, // (timestamp, value)
-    pub chart_data: Vec<(f64, f64)>, // (time, numeric_value) for chart rendering
+    // Bit range from the declaration, e.g. [7:0]. Equal for a single bit
+    // select such as data [3].
+    pub msb: Option<i64>,
+    pub lsb: Option<i64>,
+    pub values: Vec<(u64, String)>, // (timestamp, value), in time order
+    // Hierarchical name of the vector this bit was split out of
+    pub split_from: Option<String>,
+}
+
+impl Signal {
+    // Includes the bit range so the individually dumped bits of a vector
+    // stay distinct, e.g. "top_test.uut.data_out[7:0]"
+    pub fn hierarchical_name(&self) -> String {
+        let range = match (self.msb, self.lsb) {
+            (Some(msb), Some(lsb)) if msb == lsb => format!("[{}]", msb),
+            (Some(msb), Some(lsb)) => format!("[{}:{}]", msb, lsb),
+            _ => String::new(),
+        };
+
+        if self.scope.is_empty() {
+            format!("{}{}", self.name, range)
+        } else {
+            format!("{}.{}{}", self.scope, self.name, range)
+        }
+    }
+
+    // One 1-bit signal per bit, most significant first, named after the
+    // declared bit numbers (data_out[7] down to data_out[0])
+    pub fn split_bits(&self) -> Vec<Signal> {
+        let parent = self.hierarchical_name();
+
+        (0..self.width)
+            .rev()
+            .map(|index| {
+                let bit = match (self.msb, self.lsb) {
+                    (Some(msb), Some(lsb)) if msb < lsb => lsb - index as i64,
+                    (_, Some(lsb)) => lsb + index as i64,
+                    _ => index as i64,
+                };
+
+                let mut values: Vec<(u64, String)> = Vec::new();
+                for (time, value) in &self.values {
+                    let Some(bit) = bit_of_value(value, index) else {
+                        continue;
+                    };
+                    // Keep only the changes of this bit
+                    if values.last().is_none_or(|(_, last)| *last != bit.to_string()) {
+                        values.push((*time, bit.to_string()));
+                    }
+                }
+
+                Signal {
+                    name: self.name.clone(),
+                    scope: self.scope.clone(),
+                    // Distinct from the vector's so each bit gets a colour of its own
+                    identifier: format!("{} {}", self.identifier, bit),
+                    width: 1,
+                    msb: Some(bit),
+                    lsb: Some(bit),
+                    values,
+                    split_from: Some(parent.clone()),
+                }
+            })
+            .collect()
+    }
+
+    // False when the declared width disagrees with the bit range
+    fn width_matches_range(&self) -> bool {
+        match (self.msb, self.lsb) {
+            (Some(msb), Some(lsb)) => msb.abs_diff(lsb) + 1 == self.width as u64,
+            _ => true,
+        }
+    }
+}
+
+// A named, ordered selection of signals saved next to a VCD file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SignalView {
+    pub name: String,
+    pub signals: Vec<String>, // Hierarchical signal names in display order
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SavedViews {
+    views: Vec<SignalView>,
 }
 
 #[derive(Debug, Clone)]
@@ -16,32 +546,402 @@ pub struct VcdData {
     pub timescale: String,
     pub signals: Vec<Signal>,
     pub max_time: u64,
+    // Lines the parser skipped; only collected when asked for
+    pub warnings: ParseWarnings,
+}
+
+// A line of the dump the parser couldn't make sense of and skipped
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseWarning {
+    pub line: usize,
+    pub text: String,
+    pub reason: String,
+}
+
+// Skipped lines, recorded only when `enabled` so a normal load of a large
+// dump doesn't pay for copying them. The first MAX_PARSE_WARNINGS are kept;
+// `count` has them all.
+#[derive(Debug, Clone, Default)]
+pub struct ParseWarnings {
+    pub enabled: bool,
+    pub list: Vec<ParseWarning>,
+    pub count: usize,
+}
+
+impl ParseWarnings {
+    fn add(&mut self, line: usize, text: &str, reason: impl Into<String>) {
+        if !self.enabled {
+            return;
+        }
+        self.count += 1;
+        if self.list.len() < MAX_PARSE_WARNINGS {
+            self.list.push(ParseWarning { line, text: text.to_string(), reason: reason.into() });
+        }
+    }
+}
+
+// Parsing doesn't depend on any viewer state, so the same VcdData can be
+// built wherever a file's contents are needed
+impl VcdData {
+    pub fn from_path(path: &Path) -> Result<Self, HadouError> {
+        read_vcd_file(path)?.parse()
+    }
+}
+
+// Counters a background load updates and the UI reads
+#[derive(Debug, Default)]
+struct LoadProgress {
+    read: AtomicU64,
+    parsed: AtomicU64,
+    // Size of the text being parsed, known once it has all been read
+    parse_total: AtomicU64,
+    cancelled: AtomicBool,
+}
+
+// A VCD file being read and parsed on a background thread, so the UI keeps
+// drawing (and can cancel) while a large dump loads
+#[derive(Debug)]
+pub struct VcdLoad {
+    pub path: PathBuf,
+    // Bytes to read, as far as loaded_size can tell before reading
+    pub total: u64,
+    progress: Arc<LoadProgress>,
+    receiver: Receiver<Result<VcdData, HadouError>>,
+}
+
+impl VcdLoad {
+    pub fn start(path: &Path, collect_warnings: bool) -> Self {
+        let progress = Arc::new(LoadProgress::default());
+        let (sender, receiver) = mpsc::channel();
+
+        let shared = Arc::clone(&progress);
+        let file = path.to_path_buf();
+        thread::spawn(move || {
+            let result = read_vcd_file_with_progress(&file, |read| {
+                shared.read.store(read, Ordering::Relaxed);
+                !shared.cancelled.load(Ordering::Relaxed)
+            })
+            .and_then(|content| {
+                shared.parse_total.store(content.len() as u64, Ordering::Relaxed);
+                parse_vcd(&content, collect_warnings, |parsed| {
+                    shared.parsed.store(parsed, Ordering::Relaxed);
+                    !shared.cancelled.load(Ordering::Relaxed)
+                })
+            });
+            let _ = sender.send(result);
+        });
+
+        Self {
+            path: path.to_path_buf(),
+            total: loaded_size(path),
+            progress,
+            receiver,
+        }
+    }
+
+    // The parse result once the thread is done
+    pub fn poll(&self) -> Option<Result<VcdData, HadouError>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(HadouError::VcdParse(format!("Loading {} stopped unexpectedly", self.path.display())))),
+        }
+    }
+
+    // The thread stops at its next progress update
+    pub fn cancel(&self) {
+        self.progress.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_parsing(&self) -> bool {
+        self.progress.parse_total.load(Ordering::Relaxed) > 0
+    }
+
+    // Bytes read, or parsed once reading is done
+    pub fn bytes_done(&self) -> u64 {
+        if self.is_parsing() {
+            self.progress.parsed.load(Ordering::Relaxed)
+        } else {
+            self.progress.read.load(Ordering::Relaxed)
+        }
+    }
+
+    // Reading and parsing each count for half of the work
+    pub fn ratio(&self) -> f64 {
+        let fraction = |done: u64, total: u64| (done as f64 / total.max(1) as f64).min(1.0);
+        if self.is_parsing() {
+            0.5 + fraction(self.progress.parsed.load(Ordering::Relaxed), self.progress.parse_total.load(Ordering::Relaxed)) / 2.0
+        } else {
+            fraction(self.progress.read.load(Ordering::Relaxed), self.total) / 2.0
+        }
+    }
+}
+
+impl FromStr for VcdData {
+    type Err = HadouError;
+
+    fn from_str(content: &str) -> Result<Self, Self::Err> {
+        parse_vcd(content, false, |_| true)
+    }
+}
+
+// Parse `content`, telling `progress` how many bytes are done every few
+// thousand lines and stopping with HadouError::Cancelled once it returns false.
+// With `collect_warnings` the lines it skips are recorded.
+fn parse_vcd<F>(content: &str, collect_warnings: bool, mut progress: F) -> Result<VcdData, HadouError>
+where
+    F: FnMut(u64) -> bool,
+{
+    let mut timescale = String::from("1ns");
+    let mut signals = Vec::new();
+    let mut signal_map: HashMap<String, usize> = HashMap::new();
+    let mut current_time = 0u64;
+    let mut max_time = 0u64;
+    let mut in_definitions = true;
+    let mut has_definitions = false;
+    let mut scopes: Vec<String> = Vec::new();
+    let mut in_text_block = false;
+    let mut offset = 0u64;
+    let mut warnings = ParseWarnings { enabled: collect_warnings, ..ParseWarnings::default() };
+
+    for (number, line) in content.lines().enumerate() {
+        offset += line.len() as u64 + 1;
+        if number % LOAD_CHECK_LINES == 0 && !progress(offset) {
+            return Err(HadouError::Cancelled);
+        }
+        let line = line.trim();
+        let closes_block = line.split_whitespace().any(|token| token == "$end");
+
+        // $comment, $date and $version hold free-form text that may span
+        // several lines and contain anything, including '#' or 'b' prefixed
+        // lines. They can also appear after $enddefinitions.
+        if in_text_block {
+            in_text_block = !closes_block;
+            continue;
+        }
+
+        if line.starts_with("$comment") || line.starts_with("$date") || line.starts_with("$version") {
+            in_text_block = !closes_block;
+            continue;
+        }
+
+        if line.starts_with("$timescale") {
+            // Either "$timescale 1ns $end" or the value on the following line
+            let inline = line.trim_start_matches("$timescale").trim_end_matches("$end").trim();
+            if !inline.is_empty() {
+                timescale = inline.to_string();
+            } else if let Some(next_line) = content.lines().skip_while(|l| !l.contains("$timescale")).nth(1) {
+                timescale = next_line.trim().trim_end_matches("$end").trim().to_string();
+            }
+        }
+
+        if line.starts_with("$scope") {
+            // $scope module name $end
+            if let Some(name) = line.split_whitespace().nth(2) {
+                scopes.push(name.to_string());
+            }
+            continue;
+        }
+
+        if line.starts_with("$upscope") {
+            scopes.pop();
+            continue;
+        }
+
+        if line.starts_with("$var") {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 5 {
+                warnings.add(number + 1, line, "$var needs a type, width, identifier and name");
+            } else {
+                let width = parts[2].parse::<usize>().unwrap_or_else(|_| {
+                    warnings.add(number + 1, line, format!("width '{}' is not a number, taken as 1", parts[2]));
+                    1
+                });
+                let identifier = parts[3].to_string();
+                let reference = parts[4..].join(" ");
+                let (name, range) = split_bit_range(reference.trim_end_matches("$end"));
+
+                let signal = Signal {
+                    name,
+                    scope: scopes.join("."),
+                    identifier: identifier.clone(),
+                    width,
+                    msb: range.map(|(msb, _)| msb),
+                    lsb: range.map(|(_, lsb)| lsb),
+                    values: Vec::new(),
+                    split_from: None,
+                };
+
+                // Aliases of one net share an identifier, but only the last
+                // declaration gets the values
+                if let Some(&earlier) = signal_map.get(&identifier) {
+                    let earlier: &Signal = &signals[earlier];
+                    warnings.add(
+                        number + 1,
+                        line,
+                        format!("identifier '{}' is also {}, which will show no changes", identifier, earlier.hierarchical_name()),
+                    );
+                }
+                signal_map.insert(identifier, signals.len());
+                signals.push(signal);
+            }
+        }
+
+        if line.starts_with("$enddefinitions") {
+            in_definitions = false;
+            has_definitions = true;
+        }
+
+        if !in_definitions && !line.is_empty() && !line.starts_with("$") {
+            if let Some(time) = line.strip_prefix('#') {
+                let time = time.parse::<u64>().map_err(|_| {
+                    HadouError::VcdParse(format!("Line {}: invalid timestamp '{}'", number + 1, line))
+                })?;
+                current_time = time;
+                max_time = max_time.max(time);
+            } else {
+                // Vectors (b/B) and reals (r/R) put a space between the
+                // value and the identifier; scalars don't
+                let (value, identifier) = if let Some(vector) = line.strip_prefix(['b', 'B', 'r', 'R']) {
+                    let parts: Vec<&str> = vector.split_whitespace().collect();
+                    if parts.len() >= 2 {
+                        (parts[0].to_string(), parts[1].to_string())
+                    } else {
+                        warnings.add(number + 1, line, "vector or real value without an identifier");
+                        continue;
+                    }
+                } else if line.len() >= 2 && line.is_char_boundary(1) {
+                    (line[0..1].to_string(), line[1..].to_string())
+                } else {
+                    warnings.add(number + 1, line, "not a value change");
+                    continue;
+                };
+
+                match signal_map.get(&identifier) {
+                    Some(&signal_idx) => signals[signal_idx].values.push((current_time, value.clone())),
+                    None => warnings.add(number + 1, line, format!("no $var declares identifier '{}'", identifier)),
+                }
+            }
+        }
+    }
+
+    if !progress(content.len() as u64) {
+        return Err(HadouError::Cancelled);
+    }
+
+    if !has_definitions {
+        return Err(HadouError::VcdParse("No $enddefinitions found; not a VCD file or its header is incomplete".to_string()));
+    }
+
+    Ok(VcdData {
+        timescale,
+        signals,
+        max_time,
+        warnings,
+    })
+}
+
+// Times at which `signal` takes a different value, leaving out its initial
+// value and repeats such as those $dumpall writes
+pub fn edge_times(signal: &Signal) -> Vec<u64> {
+    signal.values
+        .windows(2)
+        .filter(|pair| pair[0].1 != pair[1].1)
+        .map(|pair| pair[1].0)
+        .collect()
+}
+
+// Splits a $var reference such as "data_out [7:0]", "data_out[7:0]" or
+// "data [3]" into the name and its msb/lsb. Anything that isn't a numeric
+// range is left as part of the name.
+fn split_bit_range(reference: &str) -> (String, Option<(i64, i64)>) {
+    let reference = reference.trim();
+    let Some(open) = reference.rfind('[').filter(|_| reference.ends_with(']')) else {
+        return (reference.to_string(), None);
+    };
+
+    let inner = &reference[open + 1..reference.len() - 1];
+    let range = match inner.split_once(':') {
+        Some((msb, lsb)) => msb.trim().parse().ok().zip(lsb.trim().parse().ok()),
+        None => inner.trim().parse().ok().map(|bit| (bit, bit)),
+    };
+
+    let name = reference[..open].trim_end();
+    match range {
+        Some(range) if !name.is_empty() => (name.to_string(), Some(range)),
+        _ => (reference.to_string(), None),
+    }
 }
 
 #[derive(Debug)]
 pub struct WaveformViewer {
     pub vcd_files: Vec<PathBuf>,
     pub selected_file_index: usize,
+    // Only replaced once a load has parsed the whole file
     pub current_vcd: Option<VcdData>,
+    pub loading: Option<VcdLoad>,
     pub selected_signal_index: usize,
+    pub signal_window: ListWindow,
     pub time_offset: u64,
     pub time_scale: f64,
     pub current_directory: PathBuf,
     pub visible_time_window: u64, // How many time units to show
+    pub scan_options: ScanOptions,
+    pub current_file: Option<PathBuf>,
+    pub saved_views: Vec<SignalView>,
+    pub active_view_index: usize,
+    pub view: SignalView, // View being displayed/edited; empty shows signals around the selection
+    pub status_message: Option<String>,
+    // Marker placed at the current time with 'c', for measuring from
+    pub cursor_a: Option<u64>,
+    // Edge the view last jumped to, which is the current time while set;
+    // scrolling puts the current time back at the left edge
+    pub cursor: Option<u64>,
+    // Label the ruler with times relative to cursor A instead of absolute ones
+    pub relative_time: bool,
+    pub radix: Radix,
+    // Table of exact values under the chart
+    pub show_value_table: bool,
+    // Record skipped lines while loading (the vcd_parse_warnings setting)
+    pub collect_warnings: bool,
+    // Panel listing them in place of the value table
+    pub show_parse_warnings: bool,
+    // Value the selected signal was last searched for, as typed and as read
+    pub value_search: Option<(String, ValueTarget)>,
+    // Palette slot of every signal, keyed by identifier so a signal keeps
+    // its colour however the chart is scrolled, zoomed or filtered
+    signal_colors: HashMap<String, usize>,
 }
 
 impl WaveformViewer {
-    pub fn new() -> Self {
+    pub fn new(scan_options: ScanOptions) -> Self {
         let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
         let mut viewer = Self {
             vcd_files: Vec::new(),
             selected_file_index: 0,
             current_vcd: None,
+            loading: None,
             selected_signal_index: 0,
+            signal_window: ListWindow::default(),
             time_offset: 0,
             time_scale: 1.0,
             current_directory: current_dir,
             visible_time_window: 100,
+            scan_options,
+            current_file: None,
+            saved_views: Vec::new(),
+            active_view_index: 0,
+            view: SignalView::default(),
+            status_message: None,
+            cursor_a: None,
+            cursor: None,
+            relative_time: false,
+            radix: Radix::Hex,
+            show_value_table: false,
+            collect_warnings: false,
+            show_parse_warnings: false,
+            value_search: None,
+            signal_colors: HashMap::new(),
         };
         
         viewer.scan_for_vcd_files();
@@ -49,42 +949,9 @@ impl WaveformViewer {
     }
 
     pub fn scan_for_vcd_files(&mut self) {
-        self.vcd_files.clear();
         self.selected_file_index = 0;
 
-        if let Ok(entries) = fs::read_dir(&self.current_directory) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_file() {
-                    if let Some(extension) = path.extension() {
-                        if extension == "vcd" {
-                            self.vcd_files.push(path);
-                        }
-                    }
-                }
-            }
-        }
-
-        // Also check subdirectories for VCD files
-        if let Ok(entries) = fs::read_dir(&self.current_directory) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_dir() {
-                    if let Ok(sub_entries) = fs::read_dir(&path) {
-                        for sub_entry in sub_entries.flatten() {
-                            let sub_path = sub_entry.path();
-                            if sub_path.is_file() {
-                                if let Some(extension) = sub_path.extension() {
-                                    if extension == "vcd" {
-                                        self.vcd_files.push(sub_path);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        self.vcd_files = self.scan_options.find_files(&self.current_directory, is_vcd_file);
 
         self.vcd_files.sort_by(|a, b| {
             a.file_name()
@@ -93,163 +960,259 @@ impl WaveformViewer {
         });
     }
 
-    pub fn load_vcd_file(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        if self.vcd_files.is_empty() {
-            return Err("No VCD files found".into());
+    pub fn load_vcd_file(&mut self) -> Result<(), HadouError> {
+        if self.vcd_files.is_empty() {
+            return Err(HadouError::NoVcdFiles);
+        }
+
+        if self.selected_file_index >= self.vcd_files.len() {
+            return Err(HadouError::NoSelection("file"));
+        }
+
+        let vcd_path = self.vcd_files[self.selected_file_index].clone();
+        self.load_vcd_path(&vcd_path)
+    }
+
+    pub fn load_vcd_path(&mut self, vcd_path: &Path) -> Result<(), HadouError> {
+        let vcd_data = VcdData::from_path(vcd_path)?;
+        self.show_vcd(vcd_path, vcd_data);
+        Ok(())
+    }
+
+    // Load `vcd_path` in the background, abandoning any load still running
+    pub fn start_loading(&mut self, vcd_path: &Path) {
+        self.cancel_loading();
+        self.loading = Some(VcdLoad::start(vcd_path, self.collect_warnings));
+    }
+
+    pub fn cancel_loading(&mut self) {
+        if let Some(load) = self.loading.take() {
+            load.cancel();
+        }
+    }
+
+    // Show the file once its load finishes. Called on every UI tick; Some
+    // with the file when the load ended this time, successfully or not.
+    pub fn poll_loading(&mut self) -> Option<(PathBuf, Result<(), HadouError>)> {
+        let result = self.loading.as_ref()?.poll()?;
+        let load = self.loading.take()?;
+        let result = result.map(|vcd_data| self.show_vcd(&load.path, vcd_data));
+        Some((load.path, result))
+    }
+
+    fn show_vcd(&mut self, vcd_path: &Path, vcd_data: VcdData) {
+        self.show_parse_warnings &= vcd_data.warnings.enabled;
+        self.current_vcd = Some(vcd_data);
+        self.current_file = Some(vcd_path.to_path_buf());
+        self.selected_signal_index = 0;
+        self.time_offset = 0;
+        self.status_message = None;
+        self.cursor_a = None;
+        self.cursor = None;
+        self.relative_time = false;
+        self.signal_colors.clear();
+        self.assign_colors();
+        
+        // Set initial visible window based on max time
+        self.visible_time_window = (self.max_time() / 10).max(100).min(self.max_time_window());
+
+        // Restore the most recently saved signal view, if any
+        self.saved_views = self.read_saved_views(vcd_path);
+        self.active_view_index = 0;
+        self.view = SignalView::default();
+        if !self.saved_views.is_empty() {
+            self.apply_saved_view(0);
+        }
+
+        // The declared width is what the value changes follow, so it is kept
+        let mismatched: Vec<String> = self.current_vcd
+            .iter()
+            .flat_map(|vcd| &vcd.signals)
+            .filter(|signal| !signal.width_matches_range())
+            .map(|signal| format!("{} (width {})", signal.hierarchical_name(), signal.width))
+            .collect();
+        if !mismatched.is_empty() {
+            self.status_message = Some(format!("Width does not match the bit range: {}", mismatched.join(", ")));
+        }
+    }
+
+    fn view_file_path(vcd_path: &Path) -> PathBuf {
+        let mut file_name = vcd_path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(".hadou-view.json");
+        vcd_path.with_file_name(file_name)
+    }
+
+    fn read_saved_views(&self, vcd_path: &Path) -> Vec<SignalView> {
+        fs::read_to_string(Self::view_file_path(vcd_path))
+            .ok()
+            .and_then(|content| serde_json::from_str::<SavedViews>(&content).ok())
+            .map(|saved| saved.views)
+            .unwrap_or_default()
+    }
+
+    pub fn apply_saved_view(&mut self, index: usize) {
+        let Some(saved) = self.saved_views.get(index).cloned() else {
+            return;
+        };
+
+        let available: Vec<String> = self.current_vcd
+            .as_ref()
+            .map(|vcd| vcd.signals.iter().map(Signal::hierarchical_name).collect())
+            .unwrap_or_default();
+
+        let (present, missing): (Vec<String>, Vec<String>) = saved.signals
+            .into_iter()
+            .partition(|name| available.contains(name));
+
+        self.active_view_index = index;
+        self.view = SignalView {
+            name: saved.name.clone(),
+            signals: present,
+        };
+
+        self.status_message = Some(if missing.is_empty() {
+            format!("Loaded view '{}'", saved.name)
+        } else {
+            format!("Loaded view '{}'; missing signals: {}", saved.name, missing.join(", "))
+        });
+    }
+
+    pub fn next_saved_view(&mut self) {
+        if self.saved_views.is_empty() {
+            self.status_message = Some("No saved views for this file".to_string());
+            return;
         }
 
-        if self.selected_file_index >= self.vcd_files.len() {
-            return Err("Invalid file selection".into());
-        }
+        let next = (self.active_view_index + 1) % self.saved_views.len();
+        self.apply_saved_view(next);
+    }
 
-        let vcd_path = &self.vcd_files[self.selected_file_index];
-        let vcd_data = self.parse_vcd_file(vcd_path)?;
-        
-        self.current_vcd = Some(vcd_data);
-        self.selected_signal_index = 0;
-        self.time_offset = 0;
-        
-        // Set initial visible window based on max time
-        if let Some(vcd) = &self.current_vcd {
-            self.visible_time_window = (vcd.max_time / 10).max(100);
+    pub fn save_view(&mut self, name: &str) -> Result<PathBuf, HadouError> {
+        let vcd_path = self.current_file.clone().ok_or_else(|| HadouError::Other("No VCD file loaded".to_string()))?;
+
+        if self.view.signals.is_empty() {
+            return Err(HadouError::Other("The current view has no signals. Press 'a' to add the selected signal".to_string()));
         }
-        
-        Ok(())
-    }
 
-    fn parse_vcd_file(&self, path: &Path) -> Result<VcdData, Box<dyn std::error::Error>> {
-        let content = fs::read_to_string(path)?;
-        let mut timescale = String::from("1ns");
-        let mut signals = Vec::new();
-        let mut signal_map: HashMap<String, usize> = HashMap::new();
-        let mut current_time = 0u64;
-        let mut max_time = 0u64;
-        let mut in_definitions = true;
+        self.view.name = name.to_string();
 
-        for line in content.lines() {
-            let line = line.trim();
+        // Most recently saved view goes first so it is restored on the next load
+        self.saved_views.retain(|view| view.name != name);
+        self.saved_views.insert(0, self.view.clone());
+        self.active_view_index = 0;
 
-            if line.starts_with("$timescale") {
-                if let Some(next_line) = content.lines().skip_while(|l| !l.contains("$timescale")).nth(1) {
-                    timescale = next_line.trim().to_string();
-                }
-            }
+        let path = Self::view_file_path(&vcd_path);
+        let saved = SavedViews { views: self.saved_views.clone() };
+        fs::write(&path, serde_json::to_string_pretty(&saved)?)?;
 
-            if line.starts_with("$var") {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 5 {
-                    let width = parts[2].parse::<usize>().unwrap_or(1);
-                    let identifier = parts[3].to_string();
-                    let name = parts[4..].join(" ").trim_end_matches(" $end").to_string();
-
-                    let signal = Signal {
-                        name: name.clone(),
-                        identifier: identifier.clone(),
-                        width,
-                        values: Vec::new(),
-                        chart_data: Vec::new(),
-                    };
+        self.status_message = Some(format!("Saved view '{}'", name));
+        Ok(path)
+    }
 
-                    signal_map.insert(identifier, signals.len());
-                    signals.push(signal);
-                }
-            }
+    pub fn is_split(&self, signal: &Signal) -> bool {
+        let name = signal.hierarchical_name();
+        self.current_vcd
+            .iter()
+            .flat_map(|vcd| &vcd.signals)
+            .any(|bit| bit.split_from.as_ref() == Some(&name))
+    }
+
+    // Expand the selected vector into one row per bit just below it, or
+    // collapse it again. On a bit row this collapses the vector it came from.
+    pub fn toggle_bit_split(&mut self) {
+        let Some(selected) = self.get_selected_signal().cloned() else {
+            return;
+        };
 
-            if line.starts_with("$enddefinitions") {
-                in_definitions = false;
+        let parent = match &selected.split_from {
+            Some(parent) => parent.clone(),
+            None if selected.width > 1 => selected.hierarchical_name(),
+            None => {
+                self.status_message = Some(format!("{} is a single bit", selected.hierarchical_name()));
+                return;
             }
+        };
+        let expanded = self.is_split(&selected) || selected.split_from.is_some();
 
-            if !in_definitions && !line.is_empty() && !line.starts_with("$") {
-                if line.starts_with('#') {
-                    if let Ok(time) = line[1..].parse::<u64>() {
-                        current_time = time;
-                        if time > max_time {
-                            max_time = time;
-                        }
-                    }
-                } else {
-                    let (value, identifier) = if line.starts_with('b') {
-                        let parts: Vec<&str> = line[1..].split_whitespace().collect();
-                        if parts.len() >= 2 {
-                            (parts[0].to_string(), parts[1].to_string())
-                        } else {
-                            continue;
-                        }
-                    } else if line.len() >= 2 {
-                        (line[0..1].to_string(), line[1..].to_string())
-                    } else {
-                        continue;
-                    };
+        let Some(vcd) = self.current_vcd.as_mut() else {
+            return;
+        };
+        let Some(index) = vcd.signals.iter().position(|signal| signal.split_from.is_none() && signal.hierarchical_name() == parent) else {
+            return;
+        };
 
-                    if let Some(&signal_idx) = signal_map.get(&identifier) {
-                        signals[signal_idx].values.push((current_time, value.clone()));
-                    }
-                }
-            }
+        if expanded {
+            vcd.signals.retain(|signal| signal.split_from.as_ref() != Some(&parent));
+        } else {
+            let bits = vcd.signals[index].split_bits();
+            vcd.signals.splice(index + 1..index + 1, bits);
         }
+        self.selected_signal_index = index;
+        self.assign_colors();
+    }
 
-        // Generate chart data for each signal
-        for signal in &mut signals {
-            self.generate_chart_data(signal, max_time);
+    // Give signals without a colour the next palette slot, in list order so
+    // neighbouring signals differ
+    fn assign_colors(&mut self) {
+        for signal in self.current_vcd.iter().flat_map(|vcd| &vcd.signals) {
+            let next = self.signal_colors.len();
+            self.signal_colors.entry(signal.identifier.clone()).or_insert(next);
         }
+    }
 
-        Ok(VcdData {
-            timescale,
-            signals,
-            max_time,
-        })
+    pub fn color_index(&self, signal: &Signal) -> usize {
+        self.signal_colors.get(&signal.identifier).copied().unwrap_or(0)
     }
 
-    fn generate_chart_data(&self, signal: &mut Signal, max_time: u64) {
-        signal.chart_data.clear();
-        
-        if signal.values.is_empty() {
+    pub fn toggle_selected_in_view(&mut self) {
+        let Some(name) = self.get_selected_signal().map(Signal::hierarchical_name) else {
             return;
+        };
+
+        if let Some(position) = self.view.signals.iter().position(|signal| *signal == name) {
+            self.view.signals.remove(position);
+        } else {
+            self.view.signals.push(name);
         }
+    }
 
-        let mut current_value = 0.0;
-        let mut value_index = 0;
-        
-        // Sample the signal at regular intervals
-        let sample_interval = (max_time as f64 / 1000.0).max(1.0) as u64; // Sample at most 1000 points
-        
-        for time in (0..=max_time).step_by(sample_interval as usize) {
-            // Find the current value at this time
-            while value_index < signal.values.len() && signal.values[value_index].0 <= time {
-                current_value = self.value_to_numeric(&signal.values[value_index].1, signal.width);
-                value_index += 1;
+    pub fn move_selected_in_view(&mut self, earlier: bool) {
+        let Some(name) = self.get_selected_signal().map(Signal::hierarchical_name) else {
+            return;
+        };
+
+        if let Some(position) = self.view.signals.iter().position(|signal| *signal == name) {
+            if earlier && position > 0 {
+                self.view.signals.swap(position, position - 1);
+            } else if !earlier && position + 1 < self.view.signals.len() {
+                self.view.signals.swap(position, position + 1);
             }
-            
-            // For multi-bit signals, normalize to 0-1 range based on signal width
-            let normalized_value = if signal.width > 1 {
-                current_value / ((1u64 << signal.width.min(32)) as f64 - 1.0)
-            } else {
-                current_value
-            };
-            
-            signal.chart_data.push((time as f64, normalized_value));
         }
-        
-        // Reset value_index for next signal
-    }
-
-    fn value_to_numeric(&self, value: &str, width: usize) -> f64 {
-        match value {
-            "0" => 0.0,
-            "1" => 1.0,
-            "x" | "X" => 0.5, // Unknown state - middle value
-            "z" | "Z" => 0.25, // High-Z state - quarter value
-            _ => {
-                // Multi-bit value - try to parse as binary or decimal
-                if value.chars().all(|c| c == '0' || c == '1') {
-                    // Binary string
-                    u64::from_str_radix(value, 2).unwrap_or(0) as f64
-                } else {
-                    // Try decimal
-                    value.parse::<u64>().unwrap_or(0) as f64
-                }
-            }
+    }
+
+    pub fn is_in_view(&self, signal: &Signal) -> bool {
+        self.view.signals.contains(&signal.hierarchical_name())
+    }
+
+    pub fn clear_view(&mut self) {
+        self.view = SignalView::default();
+    }
+
+    // Signals drawn on the chart: the active view in its saved order, or the
+    // neighbourhood of the selected signal when no view is set up
+    pub fn get_displayed_signals(&self) -> Vec<&Signal> {
+        let Some(vcd) = &self.current_vcd else {
+            return Vec::new();
+        };
+
+        if self.view.signals.is_empty() {
+            return self.get_visible_signals();
         }
+
+        self.view.signals
+            .iter()
+            .filter_map(|name| vcd.signals.iter().find(|signal| signal.hierarchical_name() == *name))
+            .collect()
     }
 
     pub fn get_visible_signals(&self) -> Vec<&Signal> {
@@ -290,72 +1253,286 @@ impl WaveformViewer {
     }
 
     pub fn move_signal_selection_up(&mut self) {
-        if let Some(vcd) = &self.current_vcd {
-            if !vcd.signals.is_empty() {
-                self.selected_signal_index = if self.selected_signal_index == 0 {
-                    vcd.signals.len() - 1
-                } else {
-                    self.selected_signal_index - 1
-                };
-            }
+        if let Some(vcd) = &self.current_vcd && !vcd.signals.is_empty() {
+            self.selected_signal_index = if self.selected_signal_index == 0 {
+                vcd.signals.len() - 1
+            } else {
+                self.selected_signal_index - 1
+            };
         }
     }
 
     pub fn move_signal_selection_down(&mut self) {
-        if let Some(vcd) = &self.current_vcd {
-            if !vcd.signals.is_empty() {
-                self.selected_signal_index = (self.selected_signal_index + 1) % vcd.signals.len();
+        if let Some(vcd) = &self.current_vcd && !vcd.signals.is_empty() {
+            self.selected_signal_index = (self.selected_signal_index + 1) % vcd.signals.len();
+        }
+    }
+
+    fn max_time(&self) -> u64 {
+        self.current_vcd.as_ref().map(|vcd| vcd.max_time).unwrap_or(0)
+    }
+
+    // The window can grow to cover the whole trace, but never shrinks below
+    // the minimum, even for empty or single-timestamp dumps
+    fn max_time_window(&self) -> u64 {
+        self.max_time().max(MIN_TIME_WINDOW)
+    }
+
+    fn scroll_amount(&self) -> u64 {
+        (self.visible_time_window / 10).max(1)
+    }
+
+    fn clamp_time_offset(&mut self) {
+        let max_offset = self.max_time().saturating_sub(self.visible_time_window);
+        self.time_offset = self.time_offset.min(max_offset);
+    }
+
+    // Where the signal list reads its values: the edge jumped to, or else the
+    // left edge of the chart
+    pub fn current_time(&self) -> u64 {
+        self.cursor.unwrap_or(self.time_offset)
+    }
+
+    // Drop cursor A at the current time, or lift it when it is already there
+    pub fn toggle_cursor_a(&mut self) {
+        if self.cursor_a == Some(self.current_time()) {
+            self.cursor_a = None;
+            self.relative_time = false;
+        } else {
+            self.cursor_a = Some(self.current_time());
+        }
+    }
+
+    // Move the current time to the selected signal's next (or previous) value
+    // change and centre the window on it, wrapping around at either end
+    pub fn jump_to_edge(&mut self, forward: bool) {
+        let Some(signal) = self.get_selected_signal() else {
+            return;
+        };
+        let edges = edge_times(signal);
+        let name = signal.hierarchical_name();
+        let (Some(&first), Some(&last)) = (edges.first(), edges.last()) else {
+            self.status_message = Some(format!("{} never changes", name));
+            return;
+        };
+
+        let now = self.current_time();
+        let found = if forward {
+            edges.iter().copied().find(|&time| time > now)
+        } else {
+            edges.iter().copied().rev().find(|&time| time < now)
+        };
+        let target = match found {
+            Some(time) => {
+                self.status_message = None;
+                time
+            }
+            None if forward => {
+                self.status_message = Some(format!("Wrapped around to the first change of {}", name));
+                first
+            }
+            None => {
+                self.status_message = Some(format!("Wrapped around to the last change of {}", name));
+                last
+            }
+        };
+
+        self.cursor = Some(target);
+        self.center_on(target);
+    }
+
+    // Search the selected signal for `text`, read as a ValueTarget
+    pub fn find_value(&mut self, text: &str) {
+        match ValueTarget::parse(text, self.radix) {
+            Some(target) => {
+                self.value_search = Some((text.trim().to_string(), target));
+                self.find_next_value(true);
+            }
+            None => self.status_message = Some(format!("'{}' is not a {} value", text.trim(), self.radix.name())),
+        }
+    }
+
+    // Jump to the next (or previous) time the selected signal changes to the
+    // searched value, wrapping around at the ends
+    pub fn find_next_value(&mut self, forward: bool) {
+        let (Some((text, target)), Some(signal)) = (&self.value_search, self.get_selected_signal()) else {
+            return;
+        };
+        let name = signal.hierarchical_name();
+        let text = text.clone();
+        let mut matches: Vec<u64> = Vec::new();
+        let mut was_matching = false;
+        for (time, value) in &signal.values {
+            let matching = target.matches(value);
+            if matching && !was_matching && matches.last() != Some(time) {
+                matches.push(*time);
             }
+            was_matching = matching;
+        }
+        if matches.is_empty() {
+            self.status_message = Some(format!("No match: {} is never {}", name, text));
+            return;
+        }
+
+        // Before the first jump the left edge itself counts
+        let now = self.current_time();
+        let found = if forward {
+            matches.iter().position(|&time| time > now || (self.cursor.is_none() && time == now))
+        } else {
+            matches.iter().rposition(|&time| time < now)
+        };
+        let (index, wrapped) = match found {
+            Some(index) => (index, ""),
+            None if forward => (0, ", wrapped around"),
+            None => (matches.len() - 1, ", wrapped around"),
+        };
+        let time = matches[index];
+        let timescale = self.current_vcd.as_ref().map(|vcd| vcd.timescale.as_str()).unwrap_or_default();
+        self.status_message = Some(format!(
+            "{} = {} at {} (match {} of {}{})",
+            name,
+            text,
+            format_time(time as i64, timescale),
+            index + 1,
+            matches.len(),
+            wrapped
+        ));
+        self.cursor = Some(time);
+        self.center_on(time);
+    }
+
+    fn center_on(&mut self, time: u64) {
+        self.time_offset = time.saturating_sub(self.visible_time_window / 2);
+        self.clamp_time_offset();
+    }
+
+    pub fn toggle_relative_time(&mut self) {
+        if self.cursor_a.is_none() {
+            self.status_message = Some("Place cursor A with 'c' to measure time from it".to_string());
+            return;
+        }
+        self.relative_time = !self.relative_time;
+    }
+
+    // Time the ruler counts from
+    pub fn time_origin(&self) -> u64 {
+        match self.cursor_a {
+            Some(cursor) if self.relative_time => cursor,
+            _ => 0,
         }
     }
 
+    // Current time minus cursor A
+    pub fn cursor_delta(&self) -> Option<i64> {
+        self.cursor_a.map(|cursor| self.current_time() as i64 - cursor as i64)
+    }
+
+    // Zooming keeps the edge jumped to in the middle of the window
     pub fn zoom_in(&mut self) {
-        self.visible_time_window = (self.visible_time_window as f64 * 0.7) as u64;
-        if self.visible_time_window < 10 {
-            self.visible_time_window = 10;
+        self.visible_time_window = ((self.visible_time_window as f64 * 0.7) as u64).max(MIN_TIME_WINDOW);
+        self.clamp_time_offset();
+        if let Some(cursor) = self.cursor {
+            self.center_on(cursor);
         }
     }
 
     pub fn zoom_out(&mut self) {
-        if let Some(vcd) = &self.current_vcd {
-            self.visible_time_window = (self.visible_time_window as f64 * 1.4) as u64;
-            if self.visible_time_window > vcd.max_time {
-                self.visible_time_window = vcd.max_time;
-            }
+        self.visible_time_window = ((self.visible_time_window as f64 * 1.4) as u64)
+            .clamp(MIN_TIME_WINDOW, self.max_time_window());
+        self.clamp_time_offset();
+        if let Some(cursor) = self.cursor {
+            self.center_on(cursor);
         }
     }
 
     pub fn scroll_left(&mut self) {
-        let scroll_amount = self.visible_time_window / 10;
-        if self.time_offset > scroll_amount {
-            self.time_offset -= scroll_amount;
-        } else {
-            self.time_offset = 0;
-        }
+        self.cursor = None;
+        self.time_offset = self.time_offset.saturating_sub(self.scroll_amount());
     }
 
     pub fn scroll_right(&mut self) {
-        if let Some(vcd) = &self.current_vcd {
-            let scroll_amount = self.visible_time_window / 10;
-            self.time_offset += scroll_amount;
-            if self.time_offset + self.visible_time_window > vcd.max_time {
-                self.time_offset = vcd.max_time.saturating_sub(self.visible_time_window);
+        self.cursor = None;
+        self.time_offset += self.scroll_amount();
+        self.clamp_time_offset();
+    }
+
+    // Stable regions of `signal` that overlap the visible window, clipped to it,
+    // as (start, end, value). Built from the raw value changes on every render,
+    // so zooming in always shows exact edges. Only the changes inside the
+    // window are visited, found by binary search since `values` is in time order.
+    pub fn visible_segments(&self, signal: &Signal) -> Vec<(u64, u64, String)> {
+        let window_start = self.time_offset;
+        let window_end = self.time_offset + self.visible_time_window;
+        let max_time = self.max_time();
+        let mut segments = Vec::new();
+
+        // The last change before the window is still in effect at its start
+        let first = signal.values.partition_point(|(time, _)| *time < window_start).saturating_sub(1);
+
+        for (i, (time, value)) in signal.values.iter().enumerate().skip(first) {
+            if *time > window_end {
+                break;
+            }
+            let end = signal.values.get(i + 1).map(|(next, _)| *next).unwrap_or(max_time.max(*time));
+            if end == *time && i + 1 < signal.values.len() {
+                continue; // Superseded at the same timestamp
             }
+            if end < window_start {
+                continue;
+            }
+            segments.push(((*time).max(window_start), end.min(window_end), value.clone()));
         }
+
+        segments
     }
 
     pub fn get_signal_value_at_time(&self, signal: &Signal, time: u64) -> String {
-        let mut current_value = String::from("x");
-        
-        for (t, v) in &signal.values {
-            if *t <= time {
-                current_value = v.clone();
-            } else {
-                break;
-            }
+        let changed = signal.values.partition_point(|(t, _)| *t <= time);
+
+        match changed.checked_sub(1) {
+            Some(index) => signal.values[index].1.clone(),
+            None => String::from("x"),
         }
-        
-        current_value
+    }
+
+    // A value of `signal` as the list, chart and table show it: bits of
+    // single-bit signals as they are, buses in the selected radix
+    pub fn format_value(&self, signal: &Signal, value: &str) -> String {
+        if signal.width > 1 {
+            self.radix.format(value)
+        } else {
+            value.to_string()
+        }
+    }
+
+    // Show or hide the lines the parser skipped. Loads only record them with
+    // the vcd_parse_warnings setting on.
+    pub fn toggle_parse_warnings(&mut self) {
+        let Some(vcd) = &self.current_vcd else {
+            return;
+        };
+        if !vcd.warnings.enabled {
+            self.status_message = Some("Turn on 'VCD parse warnings' in Settings and reload the file to see skipped lines".to_string());
+            return;
+        }
+        self.show_parse_warnings = !self.show_parse_warnings;
+        if self.show_parse_warnings && vcd.warnings.count == 0 {
+            self.status_message = Some("Every line of the file was parsed".to_string());
+        }
+    }
+
+    pub fn cycle_radix(&mut self) {
+        self.radix = self.radix.next();
+        self.status_message = Some(format!("Showing bus values in {}", self.radix.name()));
+    }
+
+    // Times the value table samples, at most `max_columns` of them: the
+    // ruler's ticks over the visible window, so columns line up with it
+    pub fn sample_times(&self, max_columns: usize) -> Vec<u64> {
+        let start = self.time_offset;
+        let span = self.visible_time_window.max(1);
+        let step = ruler_step(span, max_columns);
+        ruler_ticks(start, start + span, self.time_origin(), step)
     }
 
     pub fn has_vcd_files(&self) -> bool {
@@ -392,6 +1569,419 @@ impl WaveformViewer {
 
 impl Default for WaveformViewer {
     fn default() -> Self {
-        Self::new()
+        Self::new(ScanOptions::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> VcdData {
+        content.parse().expect("valid VCD")
+    }
+
+    fn signal<'a>(vcd: &'a VcdData, hierarchical_name: &str) -> &'a Signal {
+        vcd.signals
+            .iter()
+            .find(|signal| signal.hierarchical_name() == hierarchical_name)
+            .unwrap_or_else(|| panic!("no signal {}", hierarchical_name))
+    }
+
+    const HEADER: &str = "$date today $end
+$version Icarus Verilog $end
+$timescale 1ps $end
+$scope module top_test $end
+$var reg 1 ! clk $end
+$var wire 8 \" data [7:0] $end
+$var real 1 # temp $end
+$scope module uut $end
+$var wire 1 $ ready $end
+$upscope $end
+$upscope $end
+$enddefinitions $end
+";
+
+    #[test]
+    fn radix_formats_known_bits_only() {
+        assert_eq!(Radix::Hex.format("b101010"), "0x2A");
+        assert_eq!(Radix::Decimal.format("b101010"), "42");
+        assert_eq!(Radix::Binary.format("b0010X"), "0010x");
+        assert_eq!(Radix::Decimal.format("bxxxx"), "x");
+        assert_eq!(Radix::Decimal.format("r1.5"), "r1.5");
+    }
+
+    #[test]
+    fn partly_unknown_buses_keep_their_known_bits() {
+        let value = |magnitude, has_unknown| NumericValue { magnitude, has_unknown };
+        assert_eq!(parse_numeric_value("10x1"), value(9.0, true));
+        assert_eq!(parse_numeric_value("b1z0"), value(4.0, true));
+        assert_eq!(parse_numeric_value("bxxxx"), value(0.0, true));
+        assert_eq!(parse_numeric_value("ZZ"), value(0.0, true));
+        assert_eq!(parse_numeric_value("b1010"), value(10.0, false));
+        assert_eq!(parse_numeric_value("r1.5"), value(1.5, false));
+    }
+
+    #[test]
+    fn edge_jumps_skip_repeats_and_wrap() {
+        let vcd = parse(&format!("{}#0\n0!\n#10\n1!\n#15\n1!\n#20\n0!\n#400\n1!\n", HEADER));
+        assert_eq!(edge_times(signal(&vcd, "top_test.clk")), vec![10, 20, 400]);
+
+        let mut viewer = WaveformViewer {
+            current_vcd: Some(vcd),
+            visible_time_window: 100,
+            ..WaveformViewer::new(ScanOptions::default())
+        };
+        viewer.jump_to_edge(true);
+        assert_eq!((viewer.cursor, viewer.time_offset), (Some(10), 0));
+        viewer.jump_to_edge(true);
+        viewer.jump_to_edge(true);
+        // Centred on the edge, as far as the end of the trace allows
+        assert_eq!((viewer.cursor, viewer.time_offset), (Some(400), 300));
+        viewer.jump_to_edge(true);
+        assert_eq!(viewer.cursor, Some(10));
+        assert!(viewer.status_message.is_some());
+        viewer.jump_to_edge(false);
+        assert_eq!(viewer.cursor, Some(400));
+
+        viewer.scroll_left();
+        assert_eq!((viewer.cursor, viewer.current_time()), (None, 290));
+    }
+
+    #[test]
+    fn value_search_reads_the_radix_and_steps_through_matches() {
+        assert_eq!(ValueTarget::parse("1f", Radix::Hex), Some(ValueTarget::Number(31)));
+        assert_eq!(ValueTarget::parse("8'b0001_1111", Radix::Decimal), Some(ValueTarget::Number(31)));
+        assert_eq!(ValueTarget::parse("0x1f", Radix::Binary), Some(ValueTarget::Number(31)));
+        assert_eq!(ValueTarget::parse("1f", Radix::Decimal), None);
+        assert!(ValueTarget::parse("x", Radix::Hex).unwrap().matches("bxxxxxxxx"));
+        assert!(ValueTarget::parse("0b1z", Radix::Hex).unwrap().matches("b0001z"));
+        assert!(!ValueTarget::parse("3", Radix::Hex).unwrap().matches("bx11"));
+
+        let vcd = parse(&format!("{}#0\nb11 \"\n#10\nb101 \"\n#20\nb11 \"\n#30\nb0011 \"\n#40\nb11 \"\n", HEADER));
+        let mut viewer = WaveformViewer {
+            current_vcd: Some(vcd),
+            selected_signal_index: 1,
+            visible_time_window: 20,
+            ..WaveformViewer::new(ScanOptions::default())
+        };
+        viewer.find_value("3");
+        assert_eq!(viewer.cursor, Some(0));
+        viewer.find_next_value(true);
+        // 20 and 30 are the same value, so 30 is no new match
+        assert_eq!(viewer.cursor, Some(20));
+        assert_eq!(viewer.status_message.as_deref(), Some("top_test.data[7:0] = 3 at 20ps (match 2 of 2)"));
+        viewer.find_next_value(true);
+        assert_eq!(viewer.cursor, Some(0));
+        assert!(viewer.status_message.as_ref().unwrap().ends_with("wrapped around)"));
+
+        viewer.find_value("ff");
+        assert_eq!(viewer.status_message.as_deref(), Some("No match: top_test.data[7:0] is never ff"));
+        assert_eq!(viewer.cursor, Some(0));
+    }
+
+    #[test]
+    fn value_table_samples_on_ruler_ticks() {
+        let mut viewer = WaveformViewer {
+            time_offset: 20,
+            visible_time_window: 100,
+            ..WaveformViewer::new(ScanOptions::default())
+        };
+        assert_eq!(viewer.sample_times(6), vec![20, 40, 60, 80, 100, 120]);
+        viewer.cursor_a = Some(25);
+        viewer.relative_time = true;
+        assert_eq!(viewer.sample_times(6), vec![25, 45, 65, 85, 105]);
+    }
+
+    #[test]
+    fn zoom_and_scroll_survive_empty_and_single_timestamp_dumps() {
+        for content in [HEADER.to_string(), format!("{}#5\n1!\n", HEADER)] {
+            let mut viewer = WaveformViewer {
+                current_vcd: Some(parse(&content)),
+                ..WaveformViewer::new(ScanOptions::default())
+            };
+            for _ in 0..20 {
+                viewer.zoom_in();
+            }
+            assert_eq!(viewer.visible_time_window, MIN_TIME_WINDOW);
+            for _ in 0..20 {
+                viewer.zoom_out();
+                viewer.scroll_right();
+            }
+            assert_eq!(viewer.visible_time_window, MIN_TIME_WINDOW);
+            assert_eq!(viewer.max_time_window(), MIN_TIME_WINDOW);
+            assert_eq!(viewer.time_offset, 0);
+        }
+    }
+
+    #[test]
+    fn var_ranges_are_split_and_checked_against_the_width() {
+        let vcd = parse(
+            "$scope module top $end
+$var wire 8 ! data [7:0] $end
+$var wire 1 \" clk $end
+$var wire 4 # up[0:3] $end
+$var wire 1 $ bit [3] $end
+$var wire 3 % bad [7:0] $end
+$upscope $end
+$enddefinitions $end
+",
+        );
+        let range = |name: &str| {
+            let signal = signal(&vcd, name);
+            (signal.msb, signal.lsb, signal.width_matches_range())
+        };
+
+        assert_eq!(range("top.data[7:0]"), (Some(7), Some(0), true));
+        assert_eq!(range("top.clk"), (None, None, true));
+        assert_eq!(range("top.up[0:3]"), (Some(0), Some(3), true));
+        assert_eq!(range("top.bit[3]"), (Some(3), Some(3), true));
+        assert_eq!(range("top.bad[7:0]"), (Some(7), Some(0), false));
+        assert_eq!(signal(&vcd, "top.bad[7:0]").width, 3);
+
+        assert_eq!(split_bit_range("mem [i]"), ("mem [i]".to_string(), None));
+        assert_eq!(split_bit_range("[3:0]"), ("[3:0]".to_string(), None));
+    }
+
+    #[test]
+    fn scalar_changes_follow_timestamps() {
+        let vcd = parse(&format!("{}#0\n$dumpvars\n0!\nx$\n$end\n#5\n1!\n#10\n0!\n1$\n", HEADER));
+
+        assert_eq!(signal(&vcd, "top_test.clk").values, vec![
+            (0, "0".to_string()),
+            (5, "1".to_string()),
+            (10, "0".to_string()),
+        ]);
+        assert_eq!(signal(&vcd, "top_test.uut.ready").values, vec![(0, "x".to_string()), (10, "1".to_string())]);
+        assert_eq!(vcd.max_time, 10);
+    }
+
+    #[test]
+    fn vector_values_keep_their_bits() {
+        let vcd = parse(&format!("{}#0\nb0 \"\n#20\nb10100101 \"\n#30\nBzzzzzzzz \"\n", HEADER));
+        let data = signal(&vcd, "top_test.data[7:0]");
+
+        assert_eq!(data.width, 8);
+        assert_eq!((data.msb, data.lsb), (Some(7), Some(0)));
+        assert_eq!(data.values, vec![
+            (0, "0".to_string()),
+            (20, "10100101".to_string()),
+            (30, "zzzzzzzz".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn real_values_are_parsed() {
+        let vcd = parse(&format!("{}#0\nr0 #\n#7\nr1.5e3 #\n", HEADER));
+
+        assert_eq!(signal(&vcd, "top_test.temp").values, vec![(0, "0".to_string()), (7, "1.5e3".to_string())]);
+    }
+
+    #[test]
+    fn scopes_nest_and_unwind() {
+        let header = HEADER.replace(
+            "$enddefinitions",
+            "$scope module later $end\n$var wire 1 % done $end\n$upscope $end\n$enddefinitions",
+        );
+        let vcd = parse(&header);
+
+        let scopes: Vec<&str> = vcd.signals.iter().map(|signal| signal.scope.as_str()).collect();
+        assert_eq!(scopes, vec!["top_test", "top_test", "top_test", "top_test.uut", "later"]);
+    }
+
+    #[test]
+    fn header_values_are_read() {
+        let vcd = parse(HEADER);
+        assert_eq!(vcd.timescale, "1ps");
+        assert_eq!(vcd.signals.len(), 4);
+        assert_eq!(vcd.max_time, 0);
+
+        let split = parse("$timescale\n  10 ns\n$end\n$enddefinitions $end\n");
+        assert_eq!(split.timescale, "10 ns");
+    }
+
+    #[test]
+    fn comments_may_contain_value_like_lines() {
+        let vcd = parse(
+            "$comment
+#1 is not a timestamp
+b1010 ! is not a value change
+$end
+$date
+  #2 also in a block
+$end
+$version
+  b0 ! Icarus
+$end
+$timescale 1ns $end
+$scope module top $end
+$var reg 1 ! clk $end
+$var wire 4 \" data [3:0] $end
+$upscope $end
+$enddefinitions $end
+#0
+0!
+b0 \"
+$comment
+#99
+1!
+b1111 \"
+$end
+#4
+1!
+$comment single line b11 \" $end
+",
+        );
+
+        assert_eq!(vcd.timescale, "1ns");
+        assert_eq!(vcd.signals.len(), 2);
+        assert_eq!(signal(&vcd, "top.clk").values, vec![(0, "0".to_string()), (4, "1".to_string())]);
+        assert_eq!(signal(&vcd, "top.data[3:0]").values, vec![(0, "0".to_string())]);
+        assert_eq!(vcd.max_time, 4);
+    }
+
+    #[test]
+    fn unknown_identifiers_are_ignored() {
+        let vcd = parse(&format!("{}#0\n1?\nb1 ?\n", HEADER));
+        assert!(vcd.signals.iter().all(|signal| signal.values.is_empty()));
+    }
+
+    #[test]
+    fn skipped_lines_are_recorded_only_when_asked() {
+        let content = format!("{}$var wire x % bad $end\n#0\n1?\nb1\n1!\n", HEADER.replace("$enddefinitions", "$var wire 1 ! clk_alias $end\n$enddefinitions"));
+        assert_eq!(parse(&content).warnings.count, 0);
+
+        let warnings = parse_vcd(&content, true, |_| true).unwrap().warnings;
+        let reasons: Vec<(usize, &str)> = warnings.list.iter().map(|warning| (warning.line, warning.reason.as_str())).collect();
+        assert_eq!(warnings.count, 4);
+        assert!(reasons[0].1.contains("is also top_test.clk"));
+        assert_eq!(reasons[1], (14, "width 'x' is not a number, taken as 1"));
+        assert_eq!(reasons[2], (16, "no $var declares identifier '?'"));
+        assert_eq!(reasons[3], (17, "vector or real value without an identifier"));
+        assert_eq!(warnings.list[3].text, "b1");
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        assert!(matches!("".parse::<VcdData>(), Err(HadouError::VcdParse(_))));
+        assert!(matches!("this is not a waveform".parse::<VcdData>(), Err(HadouError::VcdParse(_))));
+        assert!(matches!(
+            format!("{}$var wire 1 ! a $end\n", "$scope module t $end\n").parse::<VcdData>(),
+            Err(HadouError::VcdParse(_))
+        ));
+
+        let Err(HadouError::VcdParse(message)) = format!("{}#0\n#ten\n", HEADER).parse::<VcdData>() else {
+            panic!("bad timestamp accepted");
+        };
+        assert!(message.contains("Line 14"), "{}", message);
+    }
+
+    #[test]
+    fn times_are_labelled_in_the_timescale() {
+        assert_eq!(format_time(25, "10 ns"), "250ns");
+        assert_eq!(format_time(-3, "1ps"), "-3ps");
+        assert_eq!(format_time(7, ""), "7");
+    }
+
+    #[test]
+    fn ruler_steps_grow_with_the_span() {
+        assert_eq!(ruler_step(10, 20), 1);
+        assert_eq!(ruler_step(100, 10), 20);
+        assert_eq!(ruler_step(1000, 10), 200);
+        assert_eq!(ruler_step(5000, 4), 2000);
+        assert_eq!(ruler_step(u64::MAX, 1), u64::MAX);
+        assert_eq!(ruler_step(50, 0), 100);
+    }
+
+    #[test]
+    fn ruler_ticks_align_to_the_origin() {
+        assert_eq!(ruler_ticks(0, 50, 0, 20), vec![0, 20, 40]);
+        assert_eq!(ruler_ticks(15, 60, 0, 20), vec![20, 40, 60]);
+        assert_eq!(ruler_ticks(15, 60, 33, 20), vec![33, 53]);
+        assert_eq!(ruler_ticks(0, 30, 45, 20), vec![5, 25]);
+    }
+
+    #[test]
+    fn short_values_are_padded_like_vcd() {
+        assert_eq!(bit_of_value("b101", 0), Some('1'));
+        assert_eq!(bit_of_value("101", 1), Some('0'));
+        assert_eq!(bit_of_value("b1", 5), Some('0'));
+        assert_eq!(bit_of_value("x1", 5), Some('x'));
+        assert_eq!(bit_of_value("Z", 3), Some('z'));
+        assert_eq!(bit_of_value("r1.5", 0), None);
+    }
+
+    #[test]
+    fn vectors_split_into_bit_signals() {
+        let vcd = parse(&format!("{}#0\nb0 \"\n#5\nb11 \"\n#9\nbx1 \"\n", HEADER));
+        let bits = signal(&vcd, "top_test.data[7:0]").split_bits();
+
+        let names: Vec<String> = bits.iter().map(Signal::hierarchical_name).collect();
+        assert_eq!(names.first().map(String::as_str), Some("top_test.data[7]"));
+        assert_eq!(names.last().map(String::as_str), Some("top_test.data[0]"));
+        assert!(bits.iter().all(|bit| bit.width == 1 && bit.split_from.as_deref() == Some("top_test.data[7:0]")));
+
+        // Only changes of the bit itself are kept
+        assert_eq!(bits[7].values, vec![(0, "0".to_string()), (5, "1".to_string())]);
+        assert_eq!(bits[6].values, vec![(0, "0".to_string()), (5, "1".to_string()), (9, "x".to_string())]);
+        assert_eq!(bits[0].values, vec![(0, "0".to_string()), (9, "x".to_string())]);
+    }
+
+    #[test]
+    fn from_path_reads_plain_files() {
+        let path = env::temp_dir().join(format!("hadou-test-{}.vcd", std::process::id()));
+        fs::write(&path, format!("{}#3\n1!\n", HEADER)).unwrap();
+
+        let vcd = VcdData::from_path(&path);
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(signal(&vcd.unwrap(), "top_test.clk").values, vec![(3, "1".to_string())]);
+        assert!(matches!(VcdData::from_path(&path), Err(HadouError::Io(_))));
+    }
+
+    #[test]
+    fn background_load_finishes_or_cancels() {
+        let path = env::temp_dir().join(format!("hadou-test-load-{}.vcd", std::process::id()));
+        fs::write(&path, format!("{}#3\n1!\n", HEADER)).unwrap();
+
+        let load = VcdLoad::start(&path, false);
+        let result = loop {
+            if let Some(result) = load.poll() {
+                break result;
+            }
+            thread::sleep(std::time::Duration::from_millis(5));
+        };
+        let _ = fs::remove_file(&path);
+        assert_eq!(signal(&result.unwrap(), "top_test.clk").values, vec![(3, "1".to_string())]);
+        assert_eq!(load.ratio(), 1.0);
+
+        let content = format!("{}#3\n1!\n", HEADER);
+        assert!(matches!(parse_vcd(&content, false, |_| false), Err(HadouError::Cancelled)));
+    }
+
+    #[test]
+    fn header_scan_counts_signals_and_sizes() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let plain = env::temp_dir().join(format!("hadou-count-{}.vcd", std::process::id()));
+        let compressed = plain.with_extension("vcd.gz");
+        // A value change that looks like a declaration must not be counted
+        let content = format!("{}#0\n$var\n", HEADER);
+        fs::write(&plain, &content).unwrap();
+        let mut encoder = GzEncoder::new(File::create(&compressed).unwrap(), Compression::default());
+        encoder.write_all(content.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let counts = (count_signals(&plain).ok(), count_signals(&compressed).ok());
+        let sizes = (loaded_size(&plain), loaded_size(&compressed));
+        fs::write(&plain, "$var wire 1 ! a $end\n").unwrap();
+        let unterminated = count_signals(&plain);
+        let _ = fs::remove_file(&plain);
+        let _ = fs::remove_file(&compressed);
+
+        assert_eq!(counts, (Some(4), Some(4)));
+        assert_eq!(sizes, (content.len() as u64, content.len() as u64));
+        assert!(matches!(unterminated, Err(HadouError::VcdParse(_))));
     }
 }
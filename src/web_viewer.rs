@@ -0,0 +1,324 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::error::HadouError;
+use crate::waveform_viewer;
+
+// Page that fetches the VCD and draws it, with a link to Surfer's web build
+const VIEWER_PAGE: &str = include_str!("web_viewer.html");
+
+const SURFER_URL: &str = "https://app.surfer-project.org/";
+
+// How often the accept loop checks whether it has been stopped
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Serves one VCD file and the viewer page on localhost until stopped (or
+// dropped), so a browser on this machine, or on the other end of an SSH
+// tunnel, can show the waveform
+pub struct WebServer {
+    pub vcd_path: PathBuf,
+    pub port: u16,
+    // Name the VCD is served under, e.g. /counter.vcd
+    file_route: String,
+    requests: Arc<AtomicUsize>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for WebServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebServer")
+            .field("vcd_path", &self.vcd_path)
+            .field("port", &self.port)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WebServer {
+    // Listen on a free port picked by the OS
+    pub fn start(vcd_path: &Path) -> Result<Self, HadouError> {
+        if !vcd_path.is_file() {
+            return Err(HadouError::Other(format!("{} no longer exists", vcd_path.display())));
+        }
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        listener.set_nonblocking(true)?;
+        let port = listener.local_addr()?.port();
+
+        let file_route = format!("/{}", served_file_name(vcd_path));
+        let requests = Arc::new(AtomicUsize::new(0));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let site = Site { vcd_path: vcd_path.to_path_buf(), file_route: file_route.clone(), port };
+            let requests = Arc::clone(&requests);
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || serve(listener, site, requests, stop))
+        };
+
+        Ok(Self { vcd_path: vcd_path.to_path_buf(), port, file_route, requests, stop, handle: Some(handle) })
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://127.0.0.1:{}/", self.port)
+    }
+
+    // The same file opened in Surfer's web build, which fetches it from here
+    pub fn surfer_url(&self) -> String {
+        format!("{}?load_url=http://127.0.0.1:{}{}", SURFER_URL, self.port, self.file_route)
+    }
+
+    // Command forwarding the port from the user's own machine when Hadou
+    // runs on a remote host
+    pub fn tunnel_command(&self) -> String {
+        format!("ssh -L {}:127.0.0.1:{} <host>", self.port, self.port)
+    }
+
+    pub fn requests_served(&self) -> usize {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for WebServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// Over SSH a browser started here would open on the wrong machine, if at all
+pub fn is_remote_session() -> bool {
+    ["SSH_CONNECTION", "SSH_TTY"].iter().any(|var| env::var_os(var).is_some_and(|value| !value.is_empty()))
+}
+
+pub fn open_in_browser(url: &str) -> Result<(), HadouError> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+
+    command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| HadouError::Other(format!("Could not open a browser: {}", e)))?;
+    Ok(())
+}
+
+// Last path component without characters that would need escaping in a URL,
+// and without .gz since the browser decompresses it
+fn served_file_name(vcd_path: &Path) -> String {
+    let name = vcd_path.file_name().unwrap_or_default().to_string_lossy();
+    let name = name.strip_suffix(".gz").unwrap_or(&name);
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect();
+    if name.is_empty() { "waveform.vcd".to_string() } else { name }
+}
+
+struct Site {
+    vcd_path: PathBuf,
+    file_route: String,
+    port: u16,
+}
+
+impl Site {
+    // Only requests addressed to the loopback name the server was opened
+    // under, so a page whose domain was rebound to 127.0.0.1 can't read it
+    fn allows_host(&self, host: Option<&str>) -> bool {
+        host.is_some_and(|host| {
+            [format!("127.0.0.1:{}", self.port), format!("localhost:{}", self.port)]
+                .iter()
+                .any(|allowed| host.eq_ignore_ascii_case(allowed))
+        })
+    }
+}
+
+fn serve(listener: TcpListener, site: Site, requests: Arc<AtomicUsize>, stop: Arc<AtomicBool>) {
+    let site = Arc::new(site);
+    while !stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                requests.fetch_add(1, Ordering::Relaxed);
+                let site = Arc::clone(&site);
+                // A large VCD shouldn't hold up the page or other requests
+                thread::spawn(move || {
+                    let _ = handle_connection(stream, &site);
+                });
+            }
+            // Nothing waiting (or a failed accept): check `stop` again shortly
+            Err(_) => thread::sleep(POLL_INTERVAL),
+        }
+    }
+}
+
+fn handle_connection(stream: TcpStream, site: &Site) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Only Host is needed, but every header is read so the client sees a
+    // clean close
+    let mut host = None;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        if let Some((name, value)) = header.split_once(':')
+            && name.trim().eq_ignore_ascii_case("host")
+        {
+            host = Some(value.trim().to_string());
+        }
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    let path = target.split('?').next().unwrap_or_default();
+
+    let mut stream = stream;
+    if !site.allows_host(host.as_deref()) {
+        let body = "Forbidden";
+        write_head(&mut stream, "403 Forbidden", "text/plain", body.len() as u64, None, false)?;
+        stream.write_all(body.as_bytes())?;
+        return stream.flush();
+    }
+
+    match (method, path) {
+        ("GET" | "HEAD", "/" | "/index.html") => {
+            let page = viewer_page(&site.vcd_path, &site.file_route);
+            write_head(&mut stream, "200 OK", "text/html; charset=utf-8", page.len() as u64, None, false)?;
+            if method == "GET" {
+                stream.write_all(page.as_bytes())?;
+            }
+        }
+        ("GET" | "HEAD", path) if path == site.file_route => {
+            let mut file = File::open(&site.vcd_path)?;
+            let length = file.metadata()?.len();
+            // Gzipped dumps go out as they are for the browser to unpack
+            let encoding = waveform_viewer::is_compressed(&site.vcd_path).then_some("gzip");
+            write_head(&mut stream, "200 OK", "text/plain; charset=utf-8", length, encoding, true)?;
+            if method == "GET" {
+                io::copy(&mut file, &mut stream)?;
+            }
+        }
+        ("OPTIONS", _) => write_head(&mut stream, "204 No Content", "text/plain", 0, None, true)?,
+        _ => {
+            let body = "Not found";
+            write_head(&mut stream, "404 Not Found", "text/plain", body.len() as u64, None, false)?;
+            stream.write_all(body.as_bytes())?;
+        }
+    }
+    stream.flush()
+}
+
+// `for_surfer` marks responses Surfer's web build fetches from its own
+// origin. Only that origin is let in, so other pages open in the browser
+// can't read the waveform.
+fn write_head(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    length: u64,
+    encoding: Option<&str>,
+    for_surfer: bool,
+) -> io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n",
+        status, content_type, length
+    );
+    if let Some(encoding) = encoding {
+        head.push_str(&format!("Content-Encoding: {}\r\n", encoding));
+    }
+    if for_surfer {
+        head.push_str(&format!("Access-Control-Allow-Origin: {}\r\n", SURFER_URL.trim_end_matches('/')));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())
+}
+
+fn viewer_page(vcd_path: &Path, file_route: &str) -> String {
+    let title = html_escape(&vcd_path.file_name().unwrap_or_default().to_string_lossy());
+    VIEWER_PAGE
+        .replace("{{TITLE}}", &title)
+        .replace("{{FILE}}", file_route)
+        .replace("{{SURFER}}", SURFER_URL)
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn request(port: u16, host: &str, path: &str) -> String {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\n{}\r\n", path, host).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    fn get(port: u16, path: &str) -> String {
+        request(port, &format!("Host: localhost:{}\r\n", port), path)
+    }
+
+    #[test]
+    fn serves_the_page_and_the_file_until_stopped() {
+        let dir = std::env::temp_dir().join(format!("hadou-web-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let vcd = dir.join("my counter.vcd");
+        std::fs::write(&vcd, "$timescale 1ns $end\n$enddefinitions $end\n#0\n").unwrap();
+
+        let mut server = WebServer::start(&vcd).unwrap();
+        assert!(server.surfer_url().ends_with(&format!("127.0.0.1:{}/my_counter.vcd", server.port)));
+
+        let page = get(server.port, "/");
+        assert!(page.starts_with("HTTP/1.1 200 OK"));
+        assert!(page.contains("my counter.vcd") && page.contains("\"/my_counter.vcd\""));
+        assert!(!page.contains("Access-Control-Allow-Origin"));
+
+        let file = get(server.port, "/my_counter.vcd");
+        assert!(file.contains("Access-Control-Allow-Origin: https://app.surfer-project.org\r\n"));
+        assert!(file.ends_with("$enddefinitions $end\n#0\n"));
+        assert!(get(server.port, "/../secret").starts_with("HTTP/1.1 404"));
+        assert!(request(server.port, &format!("host: 127.0.0.1:{}\r\n", server.port), "/").starts_with("HTTP/1.1 200 OK"));
+
+        // A rebound domain still names itself in Host, and gets nothing
+        for host in [format!("Host: attacker.example:{}\r\n", server.port), "Host: localhost\r\n".to_string(), String::new()] {
+            let response = request(server.port, &host, "/my_counter.vcd");
+            assert!(response.starts_with("HTTP/1.1 403 Forbidden") && !response.contains("#0"), "{:?}", host);
+        }
+        assert_eq!(server.requests_served(), 7);
+
+        let port = server.port;
+        server.stop();
+        assert!(TcpStream::connect((Ipv4Addr::LOCALHOST, port)).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}